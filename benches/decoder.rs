@@ -0,0 +1,123 @@
+//! The incremental decoder against the two decoders it replaced, which
+//! `legacy` keeps for this comparison, and against a baseline that runs
+//! today's parser from the start of the buffer on every read.
+
+mod legacy;
+
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use simple_redis::{BulkString, RespArray, RespDecoder, RespEncode, RespError, RespFrame};
+
+/// Bytes per read, roughly one TCP segment.
+const CHUNK: usize = 1460;
+
+/// The baselines are quadratic in the input size, so they stop at 1 MiB.
+const BASELINE_MAX: usize = 1 << 20;
+
+type LegacyDecode = fn(&mut BytesMut) -> Result<RespFrame, RespError>;
+
+/// Many small `SET` requests back to back, as a pipelining client sends them.
+fn pipelined_batch(size: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(size + 64);
+    let mut i = 0;
+    while out.len() < size {
+        let key = format!("key:{}", i);
+        let frame: RespFrame = RespArray::new(Some(vec![
+            BulkString::new(Some("SET")).into(),
+            BulkString::new(Some(key)).into(),
+            BulkString::new(Some("0123456789abcdef")).into(),
+        ]))
+        .into();
+        out.extend_from_slice(&frame.encode());
+        i += 1;
+    }
+    out
+}
+
+/// One array holding `size` bytes worth of 16 byte bulk strings.
+fn single_frame(size: usize) -> Vec<u8> {
+    let frames = (0..size / 23)
+        .map(|_| BulkString::new(Some("0123456789abcdef")).into())
+        .collect::<Vec<RespFrame>>();
+    RespFrame::from(RespArray::new(Some(frames))).encode()
+}
+
+fn feed(decoder: &mut RespDecoder, input: &[u8]) -> usize {
+    let mut buf = BytesMut::new();
+    let mut frames = 0;
+    for chunk in input.chunks(CHUNK) {
+        buf.extend_from_slice(chunk);
+        while decoder.decode(&mut buf).unwrap().is_some() {
+            frames += 1;
+        }
+    }
+    frames
+}
+
+/// `feed` with a new decoder for every call, so nothing parsed before a
+/// read is kept.
+fn feed_restarting(input: &[u8]) -> usize {
+    let mut buf = BytesMut::new();
+    let mut frames = 0;
+    for chunk in input.chunks(CHUNK) {
+        buf.extend_from_slice(chunk);
+        while RespDecoder::new().decode(&mut buf).unwrap().is_some() {
+            frames += 1;
+        }
+    }
+    frames
+}
+
+/// `feed` for the old decoders, which report a partial frame as an error.
+fn feed_legacy(decode: LegacyDecode, input: &[u8]) -> usize {
+    let mut buf = BytesMut::new();
+    let mut frames = 0;
+    for chunk in input.chunks(CHUNK) {
+        buf.extend_from_slice(chunk);
+        loop {
+            match decode(&mut buf) {
+                Ok(_) => frames += 1,
+                Err(RespError::NotComplete) => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+    frames
+}
+
+fn bench_decoder(c: &mut Criterion) {
+    for (name, make) in [
+        ("pipelined", pipelined_batch as fn(usize) -> Vec<u8>),
+        ("single_frame", single_frame),
+    ] {
+        let mut group = c.benchmark_group(name);
+        group.sample_size(10);
+        for size in [256 << 10, 1 << 20, 4 << 20, 16 << 20] {
+            let input = make(size);
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            group.bench_with_input(BenchmarkId::new("incremental", size), &input, |b, input| {
+                b.iter(|| feed(&mut RespDecoder::new(), input))
+            });
+            if size > BASELINE_MAX {
+                continue;
+            }
+            group.bench_with_input(BenchmarkId::new("restart", size), &input, |b, input| {
+                b.iter(|| feed_restarting(input))
+            });
+            let frames = feed(&mut RespDecoder::new(), &input);
+            for (id, decode) in [
+                ("winnow", legacy::winnow::decode as LegacyDecode),
+                ("scan", legacy::scan::decode),
+            ] {
+                assert_eq!(feed_legacy(decode, &input), frames, "{} decoder", id);
+                group.bench_with_input(BenchmarkId::new(id, size), &input, |b, input| {
+                    b.iter(|| feed_legacy(decode, input))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_decoder);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion};
+use simple_redis::{Backend, BulkString, Config};
+
+/// Tracks bytes currently allocated so the benchmark can report how much
+/// memory each small hash or set takes.
+struct CountingAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const KEYS: usize = 100_000;
+
+/// A backend with the default thresholds, or with every collection stored
+/// as a hash table as it was before the compact encodings.
+fn backend(compact: bool) -> Backend {
+    let mut config = Config::default();
+    if !compact {
+        let zero = |name: &str| (name.to_string(), "0".to_string());
+        config
+            .set(&[
+                zero("hash-max-listpack-entries"),
+                zero("set-max-intset-entries"),
+            ])
+            .unwrap();
+    }
+    Backend::with_config(config)
+}
+
+fn fill_hashes(backend: &Backend) {
+    for i in 0..KEYS {
+        backend
+            .hmset(
+                Bytes::from(format!("hash:{}", i)),
+                vec![
+                    Bytes::from("name"),
+                    Bytes::from("email"),
+                    Bytes::from("visits"),
+                ],
+                vec![
+                    BulkString::new(Some(format!("user {}", i))).into(),
+                    BulkString::new(Some(format!("user{}@example.com", i))).into(),
+                    BulkString::new(Some(i.to_string())).into(),
+                ],
+            )
+            .unwrap();
+    }
+}
+
+fn fill_sets(backend: &Backend) {
+    for i in 0..KEYS {
+        let members = (0..5).map(|n| Bytes::from((i * 5 + n).to_string()));
+        backend
+            .sadd(Bytes::from(format!("set:{}", i)), members.collect())
+            .unwrap();
+    }
+}
+
+/// Bytes per key left allocated by `fill`.
+fn bytes_per_key(compact: bool, fill: fn(&Backend)) -> usize {
+    let before = LIVE.load(Ordering::Relaxed);
+    let backend = backend(compact);
+    fill(&backend);
+    let used = LIVE.load(Ordering::Relaxed) - before;
+    drop(backend);
+    used / KEYS
+}
+
+fn bench_encodings(c: &mut Criterion) {
+    // memory report, outside the timing loops
+    for (name, fill) in [
+        ("3-field hash", fill_hashes as fn(&Backend)),
+        ("5-member integer set", fill_sets),
+    ] {
+        println!(
+            "{}: hashtable {} bytes/key, compact {} bytes/key",
+            name,
+            bytes_per_key(false, fill),
+            bytes_per_key(true, fill)
+        );
+    }
+
+    for compact in [false, true] {
+        let backend = backend(compact);
+        fill_hashes(&backend);
+        fill_sets(&backend);
+        let encoding = if compact { "compact" } else { "hashtable" };
+        c.bench_function(&format!("hget {}", encoding), |b| {
+            b.iter(|| backend.hget(b"hash:42", b"visits"))
+        });
+        c.bench_function(&format!("sismember {}", encoding), |b| {
+            b.iter(|| backend.sismember(b"set:42", b"213"))
+        });
+    }
+}
+
+criterion_group!(benches, bench_encodings);
+criterion_main!(benches);
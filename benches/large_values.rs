@@ -0,0 +1,87 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, Criterion};
+use simple_redis::{
+    cmd::{Command, CommandExecutor},
+    network::RespFrameCodec,
+    Backend, RespFrame,
+};
+use tokio_util::codec::Decoder;
+
+/// Counts bytes handed out by the allocator so the benchmark can report how
+/// much each GET/SET round trip copies.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const VALUE_SIZE: usize = 1024 * 1024;
+
+fn set_request() -> Vec<u8> {
+    let mut req = format!("*3\r\n$3\r\nset\r\n$3\r\nkey\r\n${}\r\n", VALUE_SIZE).into_bytes();
+    req.resize(req.len() + VALUE_SIZE, b'x');
+    req.extend_from_slice(b"\r\n");
+    req
+}
+
+fn execute(codec: &mut RespFrameCodec, buf: &mut BytesMut, backend: &Backend) -> RespFrame {
+    let frame = codec.decode(buf).unwrap().unwrap();
+    let cmd = Command::try_from(frame).unwrap();
+    cmd.execute(backend)
+}
+
+fn bench_large_values(c: &mut Criterion) {
+    let backend = Backend::new();
+    let set = set_request();
+    let get = b"*2\r\n$3\r\nget\r\n$3\r\nkey\r\n".to_vec();
+    let mut codec = RespFrameCodec::default();
+
+    // allocation report for a single round trip, outside the timing loop
+    let mut buf = BytesMut::from(&set[..]);
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    execute(&mut codec, &mut buf, &backend);
+    let after_set = ALLOCATED.load(Ordering::Relaxed);
+    let mut buf = BytesMut::from(&get[..]);
+    execute(&mut codec, &mut buf, &backend);
+    let after_get = ALLOCATED.load(Ordering::Relaxed);
+    println!(
+        "1 MiB SET allocated {} bytes, GET allocated {} bytes",
+        after_set - before,
+        after_get - after_set
+    );
+
+    c.bench_function("set 1MiB", |b| {
+        b.iter_batched(
+            || BytesMut::from(&set[..]),
+            |mut buf| execute(&mut codec, &mut buf, &backend),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("get 1MiB", |b| {
+        b.iter_batched(
+            || BytesMut::from(&get[..]),
+            |mut buf| execute(&mut codec, &mut buf, &backend),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_large_values);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+//! The two decoders `RespDecoder` replaced, cut down to the arrays and bulk
+//! strings the decoder bench feeds them. Both find the end of a frame by
+//! walking the buffer from its start, so a frame that arrives over many
+//! reads is walked again on every one of them.
+
+pub mod scan;
+pub mod winnow;
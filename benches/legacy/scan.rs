@@ -0,0 +1,122 @@
+//! The hand-written `RespDecode` impls of `RespArray` and `BulkString`.
+//! The originals indexed past the end of the buffer when the last bulk
+//! string was only partly received; here that reports `NotComplete`
+//! instead, so the decoder can be fed in chunks at all.
+
+use bytes::BytesMut;
+use simple_redis::{BulkString, RespArray, RespError, RespFrame};
+
+const CRLF_LEN: usize = 2;
+
+pub fn decode(buf: &mut BytesMut) -> Result<RespFrame, RespError> {
+    match buf.first() {
+        Some(b'*') => Ok(decode_array(buf)?.into()),
+        Some(b'$') => Ok(decode_bulk_string(buf)?.into()),
+        Some(val) => Err(RespError::InvalidFrameType(format!(
+            "unknown frame type: {:?}",
+            val
+        ))),
+        None => Err(RespError::NotComplete),
+    }
+}
+
+fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+    match buf.first() {
+        Some(b'*') => array_length(buf),
+        Some(b'$') => bulk_string_length(buf),
+        Some(_) => Err(RespError::InvalidFrameType(format!(
+            "unknown frame type: {:?}",
+            buf
+        ))),
+        None => Err(RespError::NotComplete),
+    }
+}
+
+fn decode_array(buf: &mut BytesMut) -> Result<RespArray, RespError> {
+    let expect_length = array_length(buf)?;
+    if expect_length > buf.len() {
+        return Err(RespError::NotComplete);
+    }
+    let mut data = buf.split_to(expect_length);
+
+    let (end, frame_count) = parse_aggregate_length(&data)?;
+    if frame_count < -1 {
+        return Err(RespError::InvalidFrameLength(frame_count));
+    }
+    if frame_count == -1 {
+        return Ok(RespArray::new(None::<Vec<_>>));
+    }
+    let mut frames = vec![];
+    let mut tmp_buf = data.split_off(end + CRLF_LEN);
+    for _ in 0..frame_count {
+        frames.push(decode(&mut tmp_buf)?);
+    }
+    Ok(RespArray::new(Some(frames)))
+}
+
+fn array_length(buf: &[u8]) -> Result<usize, RespError> {
+    let (end, length) = parse_aggregate_length(buf)?;
+    if length < 0 {
+        return Ok(end + CRLF_LEN);
+    }
+    let mut cur_index = end + CRLF_LEN;
+    for _ in 0..length {
+        let rest = buf.get(cur_index..).ok_or(RespError::NotComplete)?;
+        cur_index += expect_length(rest)?;
+    }
+    Ok(cur_index)
+}
+
+fn decode_bulk_string(buf: &mut BytesMut) -> Result<BulkString, RespError> {
+    let expect_length = bulk_string_length(buf)?;
+    if expect_length > buf.len() {
+        return Err(RespError::NotComplete);
+    }
+    let data = buf.split_to(expect_length).freeze();
+
+    let (end, content_length) = parse_aggregate_length(&data)?;
+    if content_length < -1 {
+        return Err(RespError::InvalidFrameLength(content_length));
+    }
+    if content_length == -1 {
+        return Ok(BulkString::new(None::<Vec<u8>>));
+    }
+
+    let content_begin = end + CRLF_LEN;
+    let active_length = content_begin + content_length as usize + CRLF_LEN;
+    if !data.ends_with(b"\r\n") || active_length != expect_length {
+        return Err(RespError::InvalidFrameData(format!("{:?}", data)));
+    }
+    Ok(data
+        .slice(content_begin..content_begin + content_length as usize)
+        .into())
+}
+
+fn bulk_string_length(buf: &[u8]) -> Result<usize, RespError> {
+    let (end, length) = parse_aggregate_length(buf)?;
+    if length < 0 {
+        return Ok(end + CRLF_LEN);
+    }
+    Ok(length as usize + CRLF_LEN * 2 + end)
+}
+
+fn find_crlf(buf: &[u8], nth_crlf: usize) -> Option<usize> {
+    let mut cur_times = 0;
+    for i in 0..buf.len() - 1 {
+        if buf[i] == b'\r' && buf[i + 1] == b'\n' {
+            cur_times += 1;
+        }
+        if cur_times == nth_crlf {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn parse_aggregate_length(buf: &[u8]) -> Result<(usize, isize), RespError> {
+    if buf.len() < 3 {
+        return Err(RespError::NotComplete);
+    }
+    let end = find_crlf(buf, 1).ok_or(RespError::NotComplete)?;
+    Ok((end, String::from_utf8(buf[1..end].into())?.parse()?))
+}
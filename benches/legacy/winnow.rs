@@ -0,0 +1,199 @@
+//! The winnow parser from `resp2`, limited to arrays and bulk strings.
+//! `decode` first walks the buffer to find where the next frame ends and
+//! then parses it. The original also printed every length it read, which
+//! made up most of the time quoted for it when `RespDecoder` was added;
+//! that is left out here.
+
+// kept as it was written against winnow 0.6.20
+#![allow(deprecated)]
+
+use bytes::{Bytes, BytesMut};
+use simple_redis::{BulkString, RespArray, RespError, RespFrame};
+use winnow::{
+    combinator::{dispatch, fail, terminated},
+    error::{ContextError, ErrMode, ErrorKind, FromExternalError, Needed},
+    stream::Stateful,
+    token::{any, take, take_until},
+    PResult, Parser,
+};
+
+const CRLF: &[u8] = b"\r\n";
+
+/// Parser input that remembers the `Bytes` it borrows from, so payloads can
+/// be handed out as zero-copy slices of the read buffer.
+type Input<'i> = Stateful<&'i [u8], &'i Bytes>;
+
+/// `RespDecodeV2::decode`: measure the next frame, split it off the buffer
+/// and parse it.
+pub fn decode(buf: &mut BytesMut) -> Result<RespFrame, RespError> {
+    let len = parse_frame_length(buf)?;
+    let buff = buf.split_to(len).freeze();
+    parse_frame_bytes(&buff)
+        .map(|(frame, _)| frame)
+        .map_err(|_| RespError::ParseError)
+}
+
+fn parse_frame_bytes(origin: &Bytes) -> PResult<(RespFrame, usize)> {
+    let mut input = Input {
+        input: &origin[..],
+        state: origin,
+    };
+    let frame = parse_value(&mut input)?;
+    Ok((frame, origin.len() - input.input.len()))
+}
+
+fn parse_value(input: &mut Input) -> PResult<RespFrame> {
+    dispatch! { any;
+        b'$' => parse_bulk_string.map(RespFrame::BulkString),
+        b'*' => parse_array.map(RespFrame::Array),
+        _ => fail::<_,_,_>,
+    }
+    .parse_next(input)
+}
+
+fn parse_frame_length(input: &[u8]) -> Result<usize, RespError> {
+    let target = &mut (&*input);
+    match parse_frame_len(target) {
+        Ok(_) => {
+            let start = input.as_ptr() as usize;
+            let end = (*target).as_ptr() as usize;
+            Ok(end - start)
+        }
+        Err(RespError::InvalidFrame(_)) => Err(RespError::InvalidFrame(
+            String::from_utf8_lossy(input).to_string(),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+fn parse_frame_len(input: &mut &[u8]) -> Result<(), RespError> {
+    if input.is_empty() {
+        return Err(RespError::NotComplete);
+    }
+    dispatch! { any;
+        b'$' => parse_bulk_string_len,
+        b'*' => parse_array_len,
+        _ => fail::<_,_,_>,
+    }
+    .parse_next(input)
+    .map_err(|e| match e {
+        ErrMode::Incomplete(_) => RespError::NotComplete,
+        _ => RespError::InvalidFrame(e.to_string()),
+    })
+}
+
+fn parse_bulk_string(input: &mut Input) -> PResult<BulkString> {
+    Ok(match parse_blob(input)? {
+        Some(data) => data.into(),
+        None => BulkString::new(None::<Vec<u8>>),
+    })
+}
+
+/// Read a length-prefixed payload (`<len>\r\n<bytes>\r\n`) strictly by its
+/// declared length. A length of `-1` is the RESP2 null and yields `None`.
+fn parse_blob(input: &mut Input) -> PResult<Option<Bytes>> {
+    let len = parse_len(&mut input.input)?;
+    if len < -1 {
+        return Err(ErrMode::Cut(ContextError::from_external_error(
+            input,
+            ErrorKind::Fail,
+            RespError::InvalidFrameLength(len),
+        )));
+    }
+    if len == -1 {
+        return Ok(None);
+    }
+    let data = terminated(take(len as usize), CRLF).parse_next(input)?;
+    Ok(Some(input.state.slice_ref(data)))
+}
+
+fn parse_len(input: &mut &[u8]) -> PResult<isize> {
+    let len_str = parse_str.parse_next(input)?;
+    len_str
+        .parse::<isize>()
+        .map_err(|e| ErrMode::Cut(ContextError::from_external_error(input, ErrorKind::Fail, e)))
+}
+
+fn parse_array(input: &mut Input) -> PResult<RespArray> {
+    let len = parse_len(&mut input.input)?;
+    if len < -1 {
+        return Err(ErrMode::Cut(ContextError::from_external_error(
+            input,
+            ErrorKind::Fail,
+            RespError::InvalidFrameLength(len),
+        )));
+    }
+    if len == -1 {
+        return Ok(RespArray::new(None::<Vec<_>>));
+    }
+    let mut frames = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        frames.push(parse_value.parse_next(input).map_err(|_| {
+            ErrMode::Cut(ContextError::from_external_error(
+                input,
+                ErrorKind::Fail,
+                RespError::InvalidFrameData("".to_string()),
+            ))
+        })?);
+    }
+    Ok(RespArray::new(Some(frames)))
+}
+
+fn parse_str(input: &mut &[u8]) -> PResult<String> {
+    terminated(take_until(0.., CRLF), CRLF)
+        .parse_to::<String>()
+        .parse_next(input)
+}
+
+fn parse_bulk_string_len(input: &mut &[u8]) -> PResult<()> {
+    let len_str = parse_str
+        .parse_next(input)
+        .map_err(|_: ErrMode<ContextError>| ErrMode::Incomplete(Needed::Unknown))?;
+
+    let len = len_str
+        .parse::<isize>()
+        .map_err(|e| ErrMode::Cut(ContextError::from_external_error(input, ErrorKind::Fail, e)))?;
+    if len < -1 {
+        return Err(ErrMode::Cut(ContextError::from_external_error(
+            input,
+            ErrorKind::Fail,
+            RespError::InvalidFrameLength(len),
+        )));
+    }
+    if len == -1 {
+        return Ok(());
+    }
+
+    terminated(take(len as usize), CRLF)
+        .parse_next(input)
+        .map_err(|_e: ErrMode<ContextError>| ErrMode::Incomplete(Needed::new(len as usize)))?;
+    Ok(())
+}
+
+fn parse_array_len(input: &mut &[u8]) -> PResult<()> {
+    let len_str = parse_str
+        .parse_next(input)
+        .map_err(|_: ErrMode<ContextError>| ErrMode::Incomplete(Needed::Unknown))?;
+
+    let len = len_str
+        .parse::<isize>()
+        .map_err(|e| ErrMode::Cut(ContextError::from_external_error(input, ErrorKind::Fail, e)))?;
+    if len < -1 {
+        return Err(ErrMode::Cut(ContextError::from_external_error(
+            input,
+            ErrorKind::Fail,
+            RespError::InvalidFrameLength(len),
+        )));
+    }
+    if len == -1 {
+        return Ok(());
+    }
+
+    for _ in 0..len {
+        parse_frame_len(input).map_err(|e| match e {
+            RespError::NotComplete => ErrMode::Incomplete(Needed::Unknown),
+            _ => ErrMode::Cut(ContextError::from_external_error(input, ErrorKind::Fail, e)),
+        })?;
+    }
+    Ok(())
+}
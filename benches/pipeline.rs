@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use simple_redis::{network, Backend, ProtocolLimits};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    runtime::Runtime,
+};
+
+const COMMANDS: usize = 10_000;
+
+/// `COMMANDS` SETs, sent `depth` at a time before waiting for the replies.
+async fn run(stream: &mut TcpStream, request: &[u8], depth: usize) {
+    let reply_len = b"+OK\r\n".len() * depth;
+    let mut batch = Vec::with_capacity(request.len() * depth);
+    for _ in 0..depth {
+        batch.extend_from_slice(request);
+    }
+    let mut replies = vec![0; reply_len];
+    for _ in 0..COMMANDS / depth {
+        stream.write_all(&batch).await.unwrap();
+        stream.read_exact(&mut replies).await.unwrap();
+    }
+}
+
+fn bench_pipeline(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut stream = rt.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let backend = Backend::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(network::stream_handler(
+                    stream,
+                    backend.clone(),
+                    ProtocolLimits::default(),
+                ));
+            }
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        stream.set_nodelay(true).unwrap();
+        stream
+    });
+    let request = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+
+    let mut group = c.benchmark_group("pipeline");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(COMMANDS as u64));
+    for depth in [1, 16, 256] {
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
+            b.iter(|| rt.block_on(run(&mut stream, request, depth)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pipeline);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+use std::fmt;
+
+use super::AofError;
+use crate::{config::quote_arg, network::split_inline_args};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// The compacted dataset, as an RDB file or a list of commands.
+    Base,
+    /// Commands appended after the base was written.
+    Incr,
+    /// Replaced by a rewrite and waiting to be deleted.
+    History,
+}
+
+impl FileKind {
+    fn code(self) -> &'static str {
+        match self {
+            FileKind::Base => "b",
+            FileKind::Incr => "i",
+            FileKind::History => "h",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AofFile {
+    pub name: String,
+    pub seq: u64,
+    pub kind: FileKind,
+}
+
+/// The Redis 7 multi-part AOF manifest: one line per file, in the form
+/// `file <name> seq <n> type <b|i|h>`. Loading replays the base and then
+/// the incremental files in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub base: Option<AofFile>,
+    pub incrs: Vec<AofFile>,
+    pub history: Vec<AofFile>,
+}
+
+fn invalid(reason: &str) -> AofError {
+    AofError::BadManifest(reason.to_string())
+}
+
+impl Manifest {
+    pub fn parse(content: &str) -> Result<Manifest, AofError> {
+        let mut manifest = Manifest::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let args =
+                split_inline_args(line.as_bytes()).ok_or_else(|| invalid("unbalanced quotes"))?;
+            if args.len() % 2 != 0 {
+                return Err(invalid("the number of arguments is odd"));
+            }
+            let (mut name, mut seq, mut kind) = (None, None, None);
+            for pair in args.chunks(2) {
+                let value = String::from_utf8_lossy(&pair[1]).into_owned();
+                match pair[0].as_slice() {
+                    b"file" => name = Some(value),
+                    b"seq" => seq = Some(value.parse().map_err(|_| invalid("bad seq"))?),
+                    b"type" => {
+                        kind = Some(match value.as_str() {
+                            "b" => FileKind::Base,
+                            "i" => FileKind::Incr,
+                            "h" => FileKind::History,
+                            _ => return Err(invalid("unknown file type")),
+                        })
+                    }
+                    // unknown keys are skipped so newer manifests still load
+                    _ => {}
+                }
+            }
+            let (Some(name), Some(seq), Some(kind)) = (name, seq, kind) else {
+                return Err(invalid("missing file, seq or type"));
+            };
+            let file = AofFile { name, seq, kind };
+            match kind {
+                FileKind::Base if manifest.base.is_some() => {
+                    return Err(invalid("found duplicate base file information"))
+                }
+                FileKind::Base => manifest.base = Some(file),
+                FileKind::Incr => {
+                    if manifest.incrs.last().is_some_and(|last| last.seq >= seq) {
+                        return Err(invalid("found a non-monotonic sequence number"));
+                    }
+                    manifest.incrs.push(file);
+                }
+                FileKind::History => manifest.history.push(file),
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Add a new, empty incremental file after the existing ones.
+    pub fn add_incr(&mut self, prefix: &str) -> AofFile {
+        let seq = self.incrs.last().map_or(1, |last| last.seq + 1);
+        let file = AofFile {
+            name: format!("{}.{}.incr.aof", prefix, seq),
+            seq,
+            kind: FileKind::Incr,
+        };
+        self.incrs.push(file.clone());
+        file
+    }
+
+    /// The file a rewrite writes the next base to.
+    pub fn next_base(&self, prefix: &str) -> AofFile {
+        let seq = self.base.as_ref().map_or(1, |base| base.seq + 1);
+        AofFile {
+            name: format!("{}.{}.base.rdb", prefix, seq),
+            seq,
+            kind: FileKind::Base,
+        }
+    }
+
+    /// Install a rewritten base that covers every incremental file before
+    /// `first_incr`. The files it replaces become history.
+    pub fn install_base(&mut self, base: AofFile, first_incr: u64) {
+        let replaced = self.base.replace(base).into_iter();
+        let (covered, kept): (Vec<_>, Vec<_>) =
+            self.incrs.drain(..).partition(|incr| incr.seq < first_incr);
+        self.incrs = kept;
+        self.history
+            .extend(replaced.chain(covered).map(|file| AofFile {
+                kind: FileKind::History,
+                ..file
+            }));
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = self.base.iter().chain(&self.history).chain(&self.incrs);
+        for file in files {
+            writeln!(
+                f,
+                "file {} seq {} type {}",
+                quote_arg(&file.name),
+                file.seq,
+                file.kind.code()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redis_manifest() {
+        let content = "file appendonly.aof.3.base.rdb seq 3 type b\n\
+                       file appendonly.aof.2.incr.aof seq 2 type h\n\
+                       file appendonly.aof.5.incr.aof seq 5 type i\n\
+                       file appendonly.aof.6.incr.aof seq 6 type i\n";
+        let manifest = Manifest::parse(content).unwrap();
+        assert_eq!(manifest.base.as_ref().unwrap().seq, 3);
+        assert_eq!(manifest.incrs.len(), 2);
+        assert_eq!(manifest.history.len(), 1);
+        assert_eq!(manifest.to_string(), content);
+
+        for bad in [
+            "file a seq 1\n",
+            "file a seq 1 type x\n",
+            "file a seq 2 type i\nfile b seq 1 type i\n",
+            "file a seq 1 type b\nfile b seq 2 type b\n",
+        ] {
+            assert!(Manifest::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_install_base() {
+        let mut manifest = Manifest::default();
+        manifest.add_incr("a.aof");
+        let first = manifest.add_incr("a.aof");
+        assert_eq!(first.name, "a.aof.2.incr.aof");
+
+        let base = manifest.next_base("a.aof");
+        manifest.install_base(base, 2);
+        assert_eq!(
+            manifest.to_string(),
+            "file a.aof.1.base.rdb seq 1 type b\n\
+             file a.aof.1.incr.aof seq 1 type h\n\
+             file a.aof.2.incr.aof seq 2 type i\n"
+        );
+
+        let spaced = Manifest {
+            base: Some(AofFile {
+                name: "my file".to_string(),
+                seq: 1,
+                kind: FileKind::Base,
+            }),
+            ..Manifest::default()
+        };
+        assert_eq!(Manifest::parse(&spaced.to_string()).unwrap(), spaced);
+    }
+}
@@ -0,0 +1,611 @@
+//! The append only file: every write command is logged in RESP format and
+//! replayed on startup. Files follow Redis 7's multi-part layout, a base
+//! plus incremental files listed in a manifest inside `appenddirname`.
+
+mod manifest;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    backend::now_ms,
+    cmd::{Command, CommandExecutor},
+    rdb::{self, write_snapshot, RdbError},
+    Backend, RespDecoder, Snapshot,
+};
+
+pub use manifest::{AofFile, FileKind, Manifest};
+
+/// How often the incremental file is flushed to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendFsync {
+    /// After every write command, before the reply is sent.
+    Always,
+    /// Once per second from a background task.
+    EverySec,
+    /// Whenever the operating system decides to.
+    No,
+}
+
+#[derive(Error, Debug)]
+pub enum AofError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Rdb(#[from] RdbError),
+    #[error("Invalid AOF manifest file format: {0}")]
+    BadManifest(String),
+    #[error("Bad file format reading the append only file {0}")]
+    BadFormat(String),
+    #[error("Unexpected end of file reading the append only file {0}")]
+    Truncated(String),
+    #[error("Unknown command '{1}' reading the append only file {0}")]
+    UnknownCommand(String, String),
+    #[error("Background append only file rewriting already in progress")]
+    RewriteInProgress,
+}
+
+/// AOF bookkeeping owned by the backend.
+#[derive(Debug, Default)]
+pub struct Aof {
+    state: Mutex<AofState>,
+    rewrite_in_progress: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct AofState {
+    /// The incremental file being appended to, open while AOF is on.
+    incr: Option<File>,
+    /// Where the files live, fixed once the manifest has been read since
+    /// `dir` may change at runtime.
+    dir: Option<PathBuf>,
+    prefix: String,
+    manifest: Manifest,
+    /// Whether `incr` has writes that were not fsynced yet.
+    unsynced: bool,
+}
+
+fn manifest_name(prefix: &str) -> String {
+    format!("{}.manifest", prefix)
+}
+
+impl AofState {
+    /// Read the manifest the first time the files are needed.
+    fn init(&mut self, backend: &Backend) -> Result<&Path, AofError> {
+        if self.dir.is_none() {
+            let config = backend.config();
+            let dir = config.aof_dir();
+            self.prefix = config.aof_file_name().to_string();
+            self.manifest = match fs::read_to_string(dir.join(manifest_name(&self.prefix))) {
+                Ok(content) => Manifest::parse(&content)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Manifest::default(),
+                Err(e) => return Err(e.into()),
+            };
+            self.dir = Some(dir);
+        }
+        Ok(self.dir.as_deref().expect("initialized above"))
+    }
+
+    fn dir(&self) -> &Path {
+        self.dir.as_deref().expect("AOF state not initialized")
+    }
+
+    /// Replace the manifest on disk through a temporary file.
+    fn persist_manifest(&self) -> io::Result<()> {
+        let name = manifest_name(&self.prefix);
+        let tmp = self.dir().join(format!("temp-{}", name));
+        let mut file = File::create(&tmp)?;
+        file.write_all(self.manifest.to_string().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir().join(name))
+    }
+
+    /// Start appending to a new incremental file and return its sequence
+    /// number. The previous one is fsynced first so it is complete on disk.
+    fn open_new_incr(&mut self) -> Result<u64, AofError> {
+        fs::create_dir_all(self.dir())?;
+        let mut manifest = self.manifest.clone();
+        let incr = manifest.add_incr(&self.prefix);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir().join(&incr.name))?;
+        if let Some(previous) = &self.incr {
+            previous.sync_data()?;
+        }
+        let old = std::mem::replace(&mut self.manifest, manifest);
+        if let Err(e) = self.persist_manifest() {
+            self.manifest = old;
+            let _ = fs::remove_file(self.dir().join(&incr.name));
+            return Err(e.into());
+        }
+        self.incr = Some(file);
+        self.unsynced = false;
+        Ok(incr.seq)
+    }
+
+    /// Delete the files a rewrite replaced. They stay listed until then, so
+    /// a crash in between leaves them to be cleaned up on the next start.
+    fn delete_history(&mut self) {
+        if self.manifest.history.is_empty() {
+            return;
+        }
+        for file in self.manifest.history.drain(..) {
+            let path = self.dir.as_deref().expect("initialized").join(&file.name);
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    warn!("Can't delete AOF history file {}: {}", path.display(), e);
+                }
+            }
+        }
+        if let Err(e) = self.persist_manifest() {
+            warn!("Can't update the AOF manifest: {}", e);
+        }
+    }
+}
+
+impl Aof {
+    fn state(&self) -> MutexGuard<'_, AofState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.state().incr.is_some()
+    }
+
+    pub fn rewrite_in_progress(&self) -> bool {
+        self.rewrite_in_progress.load(Ordering::Acquire)
+    }
+
+    /// Log an encoded write command. Does nothing while AOF is off.
+    pub fn append(&self, data: &[u8], fsync: AppendFsync) {
+        let mut state = self.state();
+        let Some(file) = state.incr.as_mut() else {
+            return;
+        };
+        let result = file.write_all(data).and_then(|()| match fsync {
+            AppendFsync::Always => file.sync_data(),
+            _ => Ok(()),
+        });
+        match result {
+            Ok(()) => state.unsynced = fsync != AppendFsync::Always,
+            Err(e) => warn!("Error writing to the AOF file: {}", e),
+        }
+    }
+
+    /// Flush pending writes to disk, e.g. before exiting.
+    pub fn fsync(&self) -> io::Result<()> {
+        let mut state = self.state();
+        if let Some(file) = &state.incr {
+            file.sync_data()?;
+        }
+        state.unsynced = false;
+        Ok(())
+    }
+
+    fn begin_rewrite(&self) -> Result<(), AofError> {
+        self.rewrite_in_progress
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| AofError::RewriteInProgress)
+    }
+}
+
+/// Switch to a new incremental file and copy the dataset in one step, so
+/// every write is either in the copy or in a file the new base keeps.
+/// Returns the copy and the first incremental file it does not cover.
+fn switch_and_snapshot(backend: &Backend, enable: bool) -> Result<(Snapshot, u64), AofError> {
+    let (snapshot, first_incr) = backend.snapshot_and(|| {
+        let mut state = backend.aof().state();
+        state.init(backend)?;
+        if enable || state.incr.is_some() {
+            state.open_new_incr()
+        } else {
+            // nothing is appended while AOF is off, the base covers it all
+            Ok(u64::MAX)
+        }
+    });
+    Ok((snapshot, first_incr?))
+}
+
+/// Write `snapshot` as the next base and retire the files it replaces.
+fn install_base(backend: &Backend, snapshot: &Snapshot, first_incr: u64) -> Result<(), AofError> {
+    let (dir, base) = {
+        let state = backend.aof().state();
+        (
+            state.dir().to_path_buf(),
+            state.manifest.next_base(&state.prefix),
+        )
+    };
+    let tmp = dir.join(format!("temp-rewriteaof-bg-{}.aof", std::process::id()));
+    let result = (|| {
+        let checksum = backend.config().rdb_checksum();
+        fs::create_dir_all(&dir)?;
+        let file = File::create(&tmp)?;
+        let mut out = write_snapshot(
+            snapshot,
+            BufWriter::new(file),
+            checksum,
+            true,
+            now_ms() / 1000,
+        )?;
+        out.flush()?;
+        out.get_ref().sync_all()?;
+        fs::rename(&tmp, dir.join(&base.name))
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+
+    let mut state = backend.aof().state();
+    let previous = state.manifest.clone();
+    state.manifest.install_base(base, first_incr);
+    if let Err(e) = state.persist_manifest() {
+        state.manifest = previous;
+        return Err(e.into());
+    }
+    state.delete_history();
+    Ok(())
+}
+
+/// Compact the AOF into a new base file, as `BGREWRITEAOF` does. Writes
+/// go to a new incremental file from now on; the base is written from a
+/// copy of the dataset on a background thread.
+pub fn bgrewrite(backend: &Backend) -> Result<(), AofError> {
+    rewrite(backend, false)
+}
+
+fn rewrite(backend: &Backend, enable: bool) -> Result<(), AofError> {
+    let aof = backend.aof();
+    aof.begin_rewrite()?;
+    let (snapshot, first_incr) = match switch_and_snapshot(backend, enable) {
+        Ok(switched) => switched,
+        Err(e) => {
+            aof.rewrite_in_progress.store(false, Ordering::Release);
+            return Err(e);
+        }
+    };
+    let cloned = backend.clone();
+    let spawned = thread::Builder::new()
+        .name("bgrewriteaof".to_string())
+        .spawn(move || {
+            match install_base(&cloned, &snapshot, first_incr) {
+                Ok(()) => info!("Background AOF rewrite terminated with success"),
+                Err(e) => warn!("Background AOF rewrite error: {}", e),
+            }
+            cloned
+                .aof()
+                .rewrite_in_progress
+                .store(false, Ordering::Release);
+        });
+    if let Err(e) = spawned {
+        aof.rewrite_in_progress.store(false, Ordering::Release);
+        return Err(e.into());
+    }
+    info!("Background append only file rewriting started");
+    Ok(())
+}
+
+/// Turn AOF on at runtime. The files on disk may be stale, so a rewrite
+/// captures the current dataset, as after `CONFIG SET appendonly yes`.
+pub fn start(backend: &Backend) -> Result<(), AofError> {
+    if backend.aof().enabled() {
+        return Ok(());
+    }
+    rewrite(backend, true)
+}
+
+/// Turn AOF on at startup, after `load`. Appends continue in the last
+/// incremental file; without any files a base is written first.
+pub fn resume(backend: &Backend) -> Result<(), AofError> {
+    let mut state = backend.aof().state();
+    state.init(backend)?;
+    state.delete_history();
+    if state.manifest.base.is_none() && state.manifest.incrs.is_empty() {
+        drop(state);
+        return start(backend);
+    }
+    match state.manifest.incrs.last() {
+        Some(last) => {
+            let path = state.dir().join(&last.name);
+            state.incr = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        None => {
+            state.open_new_incr()?;
+        }
+    }
+    Ok(())
+}
+
+/// Stop logging writes, as after `CONFIG SET appendonly no`.
+pub fn stop(backend: &Backend) -> io::Result<()> {
+    let aof = backend.aof();
+    let result = aof.fsync();
+    aof.state().incr = None;
+    result
+}
+
+/// Load the dataset from the files listed in the manifest. Returns `None`
+/// when there is no manifest, i.e. AOF was never enabled in this `dir`.
+pub fn load(backend: &Backend) -> Result<Option<usize>, AofError> {
+    let start = Instant::now();
+    let mut state = backend.aof().state();
+    let dir = state.init(backend)?.to_path_buf();
+    if !dir.join(manifest_name(&state.prefix)).exists() {
+        return Ok(None);
+    }
+    let manifest = state.manifest.clone();
+    drop(state);
+
+    let allow_truncated = backend.config().aof_load_truncated();
+    let mut loaded = 0;
+    if let Some(base) = &manifest.base {
+        loaded += load_file(backend, &dir.join(&base.name), false)?;
+    }
+    for (i, incr) in manifest.incrs.iter().enumerate() {
+        let last = i + 1 == manifest.incrs.len();
+        loaded += load_file(backend, &dir.join(&incr.name), last && allow_truncated)?;
+    }
+    info!(
+        "DB loaded from append only file: {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(Some(loaded))
+}
+
+/// Load one AOF file: an RDB base, or commands to replay. Returns the
+/// number of keys or commands read.
+fn load_file(backend: &Backend, path: &Path, allow_truncated: bool) -> Result<usize, AofError> {
+    let mut file = File::open(path)?;
+    let mut signature = [0; 5];
+    let is_rdb = file.read_exact(&mut signature).is_ok() && &signature == b"REDIS";
+    if is_rdb {
+        return Ok(rdb::load(backend, path)?);
+    }
+    replay(backend, path, allow_truncated)
+}
+
+/// Execute the commands in `path`. A command cut short at the end of the
+/// file is dropped, and the file truncated after the last complete one,
+/// when `allow_truncated` is set.
+fn replay(backend: &Backend, path: &Path, allow_truncated: bool) -> Result<usize, AofError> {
+    let name = path.display().to_string();
+    let mut input = BufReader::new(File::open(path)?);
+    let mut decoder = RespDecoder::new();
+    let mut buf = BytesMut::new();
+    let mut chunk = vec![0; 64 * 1024];
+    let (mut valid, mut commands) = (0, 0);
+    loop {
+        let read = input.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+        loop {
+            let before = buf.len();
+            let frame = match decoder.decode(&mut buf) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(_) => return Err(AofError::BadFormat(name)),
+            };
+            valid += (before - buf.len()) as u64;
+            let cmd = Command::try_from(frame.clone()).map_err(|_| {
+                let spec = crate::cmd::command_spec(&frame);
+                let cmd = crate::cmd::request_summary(&frame, spec).map(|(name, _)| name);
+                AofError::UnknownCommand(name.clone(), cmd.unwrap_or_default())
+            })?;
+            cmd.execute(backend);
+            commands += 1;
+        }
+    }
+    if !buf.is_empty() {
+        if !allow_truncated {
+            return Err(AofError::Truncated(name));
+        }
+        warn!(
+            "!!! Warning: short read while loading the AOF file {}!!!",
+            name
+        );
+        warn!("!!! Truncating the AOF {} at offset {} !!!", name, valid);
+        OpenOptions::new().write(true).open(path)?.set_len(valid)?;
+        warn!(
+            "AOF {} loaded anyway because aof-load-truncated is enabled",
+            name
+        );
+    }
+    Ok(commands)
+}
+
+/// Flush the incremental file once per second under `appendfsync
+/// everysec`, off the async runtime since fsync can block for a while.
+pub async fn fsync_cron(backend: Backend) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+        if backend.config().appendfsync() != AppendFsync::EverySec {
+            continue;
+        }
+        let file = {
+            let mut state = backend.aof().state();
+            if !state.unsynced {
+                continue;
+            }
+            state.unsynced = false;
+            state.incr.as_ref().and_then(|file| file.try_clone().ok())
+        };
+        if let Some(file) = file {
+            let result = tokio::task::spawn_blocking(move || file.sync_data()).await;
+            if let Ok(Err(e)) = result {
+                warn!("Can't fsync the AOF file: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, Config, RespArray, RespFrame};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("simple-redis-aof-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn backend_in(dir: &Path) -> Backend {
+        let mut config = Config::default();
+        config
+            .set(&[
+                ("dir".to_string(), dir.to_str().unwrap().to_string()),
+                ("appendonly".to_string(), "yes".to_string()),
+                ("appendfsync".to_string(), "always".to_string()),
+            ])
+            .unwrap();
+        Backend::with_config(config)
+    }
+
+    fn command(args: &[&str]) -> RespFrame {
+        RespArray::new(Some(
+            args.iter()
+                .map(|arg| BulkString::new(Some(*arg)).into())
+                .collect::<Vec<RespFrame>>(),
+        ))
+        .into()
+    }
+
+    fn write(backend: &Backend, args: &[&str]) {
+        let frame = command(args);
+        Command::try_from(frame.clone()).unwrap().execute(backend);
+        backend.propagate(&frame);
+    }
+
+    fn wait_for_rewrite(backend: &Backend) {
+        while backend.aof().rewrite_in_progress() {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn bulk(value: &str) -> Option<RespFrame> {
+        Some(BulkString::new(Some(value)).into())
+    }
+
+    #[test]
+    fn test_append_and_replay() {
+        let dir = temp_dir("replay");
+        let backend = backend_in(&dir);
+        assert_eq!(load(&backend).unwrap(), None);
+        resume(&backend).unwrap();
+        wait_for_rewrite(&backend);
+        write(&backend, &["set", "k", "v"]);
+        write(&backend, &["hset", "h", "f", "1"]);
+        write(&backend, &["sadd", "s", "a", "b"]);
+
+        let aof_dir = dir.join("appendonlydir");
+        let manifest = fs::read_to_string(aof_dir.join("appendonly.aof.manifest")).unwrap();
+        assert_eq!(
+            manifest,
+            "file appendonly.aof.1.base.rdb seq 1 type b\n\
+             file appendonly.aof.1.incr.aof seq 1 type i\n"
+        );
+        let incr = fs::read(aof_dir.join("appendonly.aof.1.incr.aof")).unwrap();
+        assert!(incr.starts_with(b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n"));
+
+        let restored = backend_in(&dir);
+        assert_eq!(load(&restored).unwrap(), Some(3));
+        assert_eq!(restored.get(b"k").unwrap(), bulk("v"));
+        assert_eq!(restored.hget(b"h", b"f").unwrap(), bulk("1"));
+        assert_eq!(restored.sismember(b"s", b"b").unwrap(), 1.into());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_tail() {
+        let dir = temp_dir("truncated");
+        let backend = backend_in(&dir);
+        resume(&backend).unwrap();
+        wait_for_rewrite(&backend);
+        write(&backend, &["set", "a", "1"]);
+        write(&backend, &["set", "b", "2"]);
+        let path = dir.join("appendonlydir/appendonly.aof.1.incr.aof");
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        let mut strict = backend_in(&dir).config().clone();
+        strict
+            .set(&[("aof-load-truncated".to_string(), "no".to_string())])
+            .unwrap();
+        let strict = Backend::with_config(strict);
+        assert!(matches!(load(&strict), Err(AofError::Truncated(_))));
+
+        let restored = backend_in(&dir);
+        assert_eq!(load(&restored).unwrap(), Some(1));
+        assert_eq!(restored.get(b"a").unwrap(), bulk("1"));
+        assert_eq!(restored.get(b"b").unwrap(), None);
+        // the partial command was cut off, new writes follow the good ones
+        assert_eq!(fs::metadata(&path).unwrap().len(), 27);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let dir = temp_dir("rewrite");
+        let backend = backend_in(&dir);
+        resume(&backend).unwrap();
+        wait_for_rewrite(&backend);
+        for i in 0..10 {
+            write(&backend, &["set", "k", &i.to_string()]);
+        }
+        bgrewrite(&backend).unwrap();
+        write(&backend, &["set", "after", "yes"]);
+        wait_for_rewrite(&backend);
+
+        let aof_dir = dir.join("appendonlydir");
+        let manifest = fs::read_to_string(aof_dir.join("appendonly.aof.manifest")).unwrap();
+        assert_eq!(
+            manifest,
+            "file appendonly.aof.2.base.rdb seq 2 type b\n\
+             file appendonly.aof.2.incr.aof seq 2 type i\n"
+        );
+        let mut files = fs::read_dir(&aof_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "appendonly.aof.2.base.rdb",
+                "appendonly.aof.2.incr.aof",
+                "appendonly.aof.manifest"
+            ]
+        );
+
+        let restored = backend_in(&dir);
+        load(&restored).unwrap();
+        assert_eq!(restored.get(b"k").unwrap(), bulk("9"));
+        assert_eq!(restored.get(b"after").unwrap(), bulk("yes"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
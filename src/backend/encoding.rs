@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+
+use super::{
+    intset::{self, IntSet},
+    listpack::Listpack,
+};
+use crate::{BulkString, RespEncode, RespFrame};
+
+/// Rough cost of a value's header, whatever its encoding.
+pub(super) const VALUE_OVERHEAD: usize = 16;
+
+/// Rough cost of one field or member of a hash table beyond its bytes: the
+/// table slot and the headers of its strings.
+const TABLE_ENTRY_OVERHEAD: usize = 32;
+
+/// When small hashes and sets outgrow their compact encodings, from the
+/// `hash-max-listpack-*` and `set-max-intset-entries` parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodingLimits {
+    pub hash_max_listpack_entries: usize,
+    pub hash_max_listpack_value: usize,
+    pub set_max_intset_entries: usize,
+}
+
+impl Default for EncodingLimits {
+    fn default() -> Self {
+        EncodingLimits {
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            set_max_intset_entries: 512,
+        }
+    }
+}
+
+/// Stored values are always bulk strings since every command argument is
+/// one; anything else has no RDB representation.
+pub(super) fn frame_bytes(frame: &RespFrame) -> Option<Bytes> {
+    match frame {
+        RespFrame::BulkString(BulkString(Some(data))) => Some(data.clone()),
+        _ => None,
+    }
+}
+
+/// Longest string Redis allocates together with its header.
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+/// How Redis would encode a string value: `int` for integers written the
+/// canonical way, `embstr` for short strings and `raw` for the rest.
+pub(super) fn string_encoding(frame: &RespFrame) -> &'static str {
+    match frame {
+        RespFrame::BulkString(BulkString(Some(data))) if intset::parse_member(data).is_some() => {
+            "int"
+        }
+        RespFrame::BulkString(BulkString(Some(data))) if data.len() <= EMBSTR_SIZE_LIMIT => {
+            "embstr"
+        }
+        _ => "raw",
+    }
+}
+
+/// Approximate memory a stored string value takes.
+pub(super) fn frame_usage(frame: &RespFrame) -> usize {
+    VALUE_OVERHEAD + frame_len(frame)
+}
+
+fn frame_len(frame: &RespFrame) -> usize {
+    match frame {
+        RespFrame::BulkString(BulkString(Some(data))) => data.len(),
+        frame => frame.encoded_len(),
+    }
+}
+
+fn field_usage(field: &[u8], value: &RespFrame) -> usize {
+    TABLE_ENTRY_OVERHEAD + field.len() + frame_len(value)
+}
+
+/// A hash, kept as a listpack of field and value pairs until it has more
+/// than `hash-max-listpack-entries` fields or a field or value longer than
+/// `hash-max-listpack-value`. It never converts back. The table needs no
+/// locking of its own: the entry guard of the keyspace map is exclusive.
+#[derive(Debug)]
+pub(super) enum Hash {
+    Listpack(Listpack),
+    Table {
+        table: HashMap<Bytes, RespFrame>,
+        /// Approximate memory of the fields and values.
+        bytes: usize,
+    },
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Hash::Listpack(Listpack::default())
+    }
+}
+
+impl Hash {
+    pub fn from_pairs(pairs: Vec<(Bytes, Bytes)>, limits: &EncodingLimits) -> Self {
+        let compact = pairs.len() <= limits.hash_max_listpack_entries
+            && pairs.iter().all(|(field, value)| {
+                field.len() <= limits.hash_max_listpack_value
+                    && value.len() <= limits.hash_max_listpack_value
+            });
+        if compact {
+            let mut lp = Listpack::default();
+            for (field, value) in pairs {
+                lp.set_pair(&field, &value);
+            }
+            Hash::Listpack(lp)
+        } else {
+            let mut hash = Hash::Table {
+                table: HashMap::with_capacity(pairs.len()),
+                bytes: 0,
+            };
+            for (field, value) in pairs {
+                hash.insert_table(field, BulkString::new(Some(value)).into());
+            }
+            hash
+        }
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Hash::Listpack(_) => "listpack",
+            Hash::Table { .. } => "hashtable",
+        }
+    }
+
+    /// Approximate memory the hash takes.
+    pub fn memory_usage(&self) -> usize {
+        VALUE_OVERHEAD
+            + match self {
+                Hash::Listpack(lp) => lp.bytes(),
+                Hash::Table { bytes, .. } => *bytes,
+            }
+    }
+
+    pub fn get(&self, field: &[u8]) -> Option<RespFrame> {
+        match self {
+            Hash::Listpack(lp) => lp
+                .get_pair(field)
+                .map(|value| BulkString::new(Some(value.to_vec())).into()),
+            Hash::Table { table, .. } => table.get(field).cloned(),
+        }
+    }
+
+    /// Set `field`, converting to a hash table first if it would no longer
+    /// fit the limits. Returns whether the field is new.
+    pub fn insert(&mut self, field: Bytes, value: RespFrame, limits: &EncodingLimits) -> bool {
+        if let Hash::Listpack(lp) = self {
+            let fits = |data: &[u8]| data.len() <= limits.hash_max_listpack_value;
+            if let Some(data) = frame_bytes(&value).filter(|data| fits(&field) && fits(data)) {
+                if lp.get_pair(&field).is_some() || lp.len() / 2 < limits.hash_max_listpack_entries
+                {
+                    return lp.set_pair(&field, &data);
+                }
+            }
+            self.convert();
+        }
+        self.insert_table(field, value)
+    }
+
+    fn insert_table(&mut self, field: Bytes, value: RespFrame) -> bool {
+        let Hash::Table { table, bytes } = self else {
+            unreachable!("converted above");
+        };
+        *bytes += field_usage(&field, &value);
+        match table.insert(field.clone(), value) {
+            Some(old) => {
+                *bytes -= field_usage(&field, &old);
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn convert(&mut self) {
+        if let Hash::Listpack(lp) = self {
+            let mut entries = lp.iter();
+            let mut hash = Hash::Table {
+                table: HashMap::with_capacity(lp.len() / 2),
+                bytes: 0,
+            };
+            while let (Some(field), Some(value)) = (entries.next(), entries.next()) {
+                hash.insert_table(
+                    Bytes::copy_from_slice(field),
+                    BulkString::new(Some(value.to_vec())).into(),
+                );
+            }
+            *self = hash;
+        }
+    }
+
+    /// Every field and value; values that are not bulk strings are skipped.
+    pub fn pairs(&self) -> Vec<(Bytes, Bytes)> {
+        match self {
+            Hash::Listpack(lp) => {
+                let mut entries = lp.iter().map(Bytes::copy_from_slice);
+                let mut pairs = Vec::with_capacity(lp.len() / 2);
+                while let (Some(field), Some(value)) = (entries.next(), entries.next()) {
+                    pairs.push((field, value));
+                }
+                pairs
+            }
+            Hash::Table { table, .. } => table
+                .iter()
+                .filter_map(|(field, value)| Some((field.clone(), frame_bytes(value)?)))
+                .collect(),
+        }
+    }
+}
+
+/// A set, kept as an intset while every member is an integer and there are
+/// at most `set-max-intset-entries` of them. It never converts back.
+#[derive(Debug)]
+pub(super) enum Set {
+    IntSet(IntSet),
+    Table {
+        table: HashSet<Bytes>,
+        /// Approximate memory of the members.
+        bytes: usize,
+    },
+}
+
+impl Default for Set {
+    fn default() -> Self {
+        Set::IntSet(IntSet::default())
+    }
+}
+
+impl Set {
+    pub fn from_members(members: Vec<Bytes>, limits: &EncodingLimits) -> Self {
+        let mut set = Set::default();
+        if members.len() > limits.set_max_intset_entries {
+            set.convert(members.len());
+        }
+        for member in members {
+            set.insert(member, limits);
+        }
+        set
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Set::IntSet(_) => "intset",
+            Set::Table { .. } => "hashtable",
+        }
+    }
+
+    /// Approximate memory the set takes.
+    pub fn memory_usage(&self) -> usize {
+        VALUE_OVERHEAD
+            + match self {
+                Set::IntSet(set) => set.bytes(),
+                Set::Table { bytes, .. } => *bytes,
+            }
+    }
+
+    pub fn contains(&self, member: &[u8]) -> bool {
+        match self {
+            Set::IntSet(set) => intset::parse_member(member).is_some_and(|n| set.contains(n)),
+            Set::Table { table, .. } => table.contains(member),
+        }
+    }
+
+    /// Add `member`, converting to a hash table first if it is not an
+    /// integer or the set would grow past the limit. Returns whether it was
+    /// missing.
+    pub fn insert(&mut self, member: Bytes, limits: &EncodingLimits) -> bool {
+        if let Set::IntSet(set) = self {
+            if let Some(n) = intset::parse_member(&member) {
+                if set.contains(n) {
+                    return false;
+                }
+                if set.len() < limits.set_max_intset_entries {
+                    return set.insert(n);
+                }
+            }
+            let len = set.len() + 1;
+            self.convert(len);
+        }
+        self.insert_table(member)
+    }
+
+    fn insert_table(&mut self, member: Bytes) -> bool {
+        let Set::Table { table, bytes } = self else {
+            unreachable!("converted above");
+        };
+        let usage = TABLE_ENTRY_OVERHEAD + member.len();
+        let new = table.insert(member);
+        if new {
+            *bytes += usage;
+        }
+        new
+    }
+
+    fn convert(&mut self, capacity: usize) {
+        if let Set::IntSet(set) = self {
+            let members = set.iter().collect::<Vec<_>>();
+            *self = Set::Table {
+                table: HashSet::with_capacity(capacity),
+                bytes: 0,
+            };
+            for n in members {
+                self.insert_table(Bytes::from(n.to_string()));
+            }
+        }
+    }
+
+    pub fn members(&self) -> Vec<Bytes> {
+        match self {
+            Set::IntSet(set) => set.iter().map(|n| Bytes::from(n.to_string())).collect(),
+            Set::Table { table, .. } => table.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(entries: usize, value: usize) -> EncodingLimits {
+        EncodingLimits {
+            hash_max_listpack_entries: entries,
+            hash_max_listpack_value: value,
+            set_max_intset_entries: entries,
+        }
+    }
+
+    fn bulk(value: &str) -> RespFrame {
+        BulkString::new(Some(value)).into()
+    }
+
+    #[test]
+    fn test_hash_conversion() {
+        let limits = limits(2, 4);
+        let mut hash = Hash::default();
+        assert!(hash.insert(Bytes::from("a"), bulk("1"), &limits));
+        assert!(hash.insert(Bytes::from("b"), bulk("2"), &limits));
+        assert!(!hash.insert(Bytes::from("b"), bulk("3"), &limits));
+        assert_eq!(hash.encoding(), "listpack");
+        assert_eq!(hash.get(b"b"), Some(bulk("3")));
+
+        // a third field is over the limit
+        assert!(hash.insert(Bytes::from("c"), bulk("4"), &limits));
+        assert_eq!(hash.encoding(), "hashtable");
+        let mut pairs = hash.pairs();
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            vec![
+                (Bytes::from("a"), Bytes::from("1")),
+                (Bytes::from("b"), Bytes::from("3")),
+                (Bytes::from("c"), Bytes::from("4")),
+            ]
+        );
+
+        let mut hash = Hash::default();
+        hash.insert(Bytes::from("a"), bulk("too long"), &limits);
+        assert_eq!(hash.encoding(), "hashtable");
+        assert_eq!(hash.get(b"a"), Some(bulk("too long")));
+
+        let pairs = vec![(Bytes::from("f"), Bytes::from("v"))];
+        assert_eq!(
+            Hash::from_pairs(pairs.clone(), &limits).encoding(),
+            "listpack"
+        );
+        assert_eq!(
+            Hash::from_pairs(pairs, &self::limits(0, 4)).encoding(),
+            "hashtable"
+        );
+    }
+
+    #[test]
+    fn test_set_conversion() {
+        let limits = limits(2, 0);
+        let mut set = Set::default();
+        assert!(set.insert(Bytes::from("10"), &limits));
+        assert!(set.insert(Bytes::from("-1"), &limits));
+        assert!(!set.insert(Bytes::from("10"), &limits));
+        assert_eq!(set.encoding(), "intset");
+        assert!(set.contains(b"-1"));
+        assert!(!set.contains(b"010"));
+
+        assert!(set.insert(Bytes::from("3"), &limits));
+        assert_eq!(set.encoding(), "hashtable");
+        let mut members = set.members();
+        members.sort();
+        assert_eq!(members, vec!["-1", "10", "3"]);
+
+        let mut set = Set::default();
+        set.insert(Bytes::from("a"), &limits);
+        assert_eq!(set.encoding(), "hashtable");
+        assert!(set.contains(b"a"));
+
+        let members = vec![Bytes::from("1"), Bytes::from("x")];
+        assert_eq!(Set::from_members(members, &limits).encoding(), "hashtable");
+        let members = vec![Bytes::from("1"), Bytes::from("2")];
+        assert_eq!(Set::from_members(members, &limits).encoding(), "intset");
+    }
+
+    #[test]
+    fn test_string_encoding() {
+        assert_eq!(string_encoding(&bulk("12345")), "int");
+        assert_eq!(string_encoding(&bulk("-7")), "int");
+        assert_eq!(string_encoding(&bulk("007")), "embstr");
+        assert_eq!(string_encoding(&bulk("+7")), "embstr");
+        assert_eq!(string_encoding(&bulk(&"a".repeat(44))), "embstr");
+        assert_eq!(string_encoding(&bulk(&"a".repeat(45))), "raw");
+    }
+}
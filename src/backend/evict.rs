@@ -0,0 +1,628 @@
+//! Memory accounting and eviction. Every key's footprint is estimated as it
+//! is written, and when the total passes `maxmemory` keys are evicted by
+//! sampling a few and dropping the best candidate, like Redis's
+//! approximated LRU, LFU and TTL policies.
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hasher, RandomState},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use bytes::Bytes;
+
+use super::{encoding::frame_usage, now_ms, BackendInner, NotifyFlags};
+
+/// Rough cost of a key beyond its name and value: its slots in the value
+/// maps and the key index.
+pub(super) const KEY_OVERHEAD: usize = 48;
+
+/// Approximate memory a key holding a value of `value_usage` bytes takes.
+pub(super) fn key_usage(key: &[u8], value_usage: usize) -> usize {
+    KEY_OVERHEAD + key.len() + value_usage
+}
+
+/// `maxmemory-policy`: which keys are evicted once `maxmemory` is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    NoEviction,
+    AllKeysLru,
+    VolatileLru,
+    AllKeysLfu,
+    VolatileLfu,
+    AllKeysRandom,
+    VolatileRandom,
+    VolatileTtl,
+}
+
+impl EvictionPolicy {
+    pub const NAMES: &'static [&'static str] = &[
+        "volatile-lru",
+        "volatile-lfu",
+        "volatile-random",
+        "volatile-ttl",
+        "allkeys-lru",
+        "allkeys-lfu",
+        "allkeys-random",
+        "noeviction",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "noeviction" => EvictionPolicy::NoEviction,
+            "allkeys-lru" => EvictionPolicy::AllKeysLru,
+            "volatile-lru" => EvictionPolicy::VolatileLru,
+            "allkeys-lfu" => EvictionPolicy::AllKeysLfu,
+            "volatile-lfu" => EvictionPolicy::VolatileLfu,
+            "allkeys-random" => EvictionPolicy::AllKeysRandom,
+            "volatile-random" => EvictionPolicy::VolatileRandom,
+            "volatile-ttl" => EvictionPolicy::VolatileTtl,
+            _ => return None,
+        })
+    }
+
+    /// Whether keys are chosen by access frequency, which is then what
+    /// `OBJECT FREQ` reports instead of the idle time.
+    pub fn lfu(self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu
+        )
+    }
+
+    /// Whether only keys with an expiry time may be evicted.
+    fn volatile(self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::VolatileLru
+                | EvictionPolicy::VolatileLfu
+                | EvictionPolicy::VolatileRandom
+                | EvictionPolicy::VolatileTtl
+        )
+    }
+}
+
+/// The `maxmemory*` parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct EvictionConfig {
+    /// Limit of the estimated dataset size in bytes; 0 for none.
+    pub maxmemory: usize,
+    pub policy: EvictionPolicy,
+    /// Keys sampled per eviction, from `maxmemory-samples`.
+    pub samples: usize,
+}
+
+/// `lfu-log-factor` and `lfu-decay-time`, which shape the access counters.
+#[derive(Debug, Clone, Copy)]
+pub struct LfuConfig {
+    pub log_factor: u64,
+    /// Idle minutes that take one off a counter; 0 never decays.
+    pub decay_time: u64,
+}
+
+/// The counter a new key starts with, so it is not evicted before it has
+/// a chance to be used.
+pub const LFU_INIT_VAL: u8 = 5;
+
+/// When a key was last used and how often, for the LRU and LFU policies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Access {
+    /// Unix milliseconds of the last read or write.
+    pub at: i64,
+    /// Logarithmic access counter, like Redis's LFU counter.
+    pub counter: u8,
+}
+
+impl Access {
+    pub fn new(now: i64) -> Self {
+        Access {
+            at: now,
+            counter: LFU_INIT_VAL,
+        }
+    }
+
+    /// The counter decayed by the minutes since the last access.
+    pub fn decayed_counter(&self, now: i64, lfu: &LfuConfig) -> u8 {
+        if lfu.decay_time == 0 {
+            return self.counter;
+        }
+        let periods = (now - self.at).max(0) as u64 / 60_000 / lfu.decay_time;
+        self.counter.saturating_sub(periods.min(255) as u8)
+    }
+
+    /// Record an access: decay the counter, then increment it with a
+    /// probability that shrinks as it grows, like Redis's `LFULogIncr`.
+    fn hit(&mut self, now: i64, lfu: &LfuConfig) {
+        let mut counter = self.decayed_counter(now, lfu);
+        if counter < u8::MAX {
+            let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+            let p = 1.0 / (base * lfu.log_factor as f64 + 1.0);
+            if random_f64() < p {
+                counter += 1;
+            }
+        }
+        self.counter = counter;
+        self.at = now;
+    }
+}
+
+static RANDOM_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn random() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(RANDOM_SEQ.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+fn random_f64() -> f64 {
+    (random() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+const INDEX_SHARDS: usize = 64;
+
+/// Every key with its access record. Keys are kept in vectors so a random
+/// one can be picked in constant time, which the maps can't do.
+#[derive(Debug)]
+pub(super) struct KeyIndex {
+    hasher: RandomState,
+    shards: Vec<Mutex<IndexShard>>,
+}
+
+#[derive(Debug, Default)]
+struct IndexShard {
+    keys: Vec<(Bytes, Access)>,
+    positions: HashMap<Bytes, usize>,
+}
+
+impl Default for KeyIndex {
+    fn default() -> Self {
+        KeyIndex {
+            hasher: RandomState::new(),
+            shards: (0..INDEX_SHARDS).map(|_| Mutex::default()).collect(),
+        }
+    }
+}
+
+impl KeyIndex {
+    fn shard(&self, key: &[u8]) -> std::sync::MutexGuard<'_, IndexShard> {
+        let index = self.hasher.hash_one(key) as usize % INDEX_SHARDS;
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record a write to `key`, adding it if it is new.
+    pub fn write(&self, key: &Bytes, lfu: &LfuConfig) {
+        let now = now_ms();
+        let mut shard = self.shard(key);
+        match shard.positions.get(key) {
+            Some(&i) => shard.keys[i].1.hit(now, lfu),
+            None => {
+                let i = shard.keys.len();
+                shard.keys.push((key.clone(), Access::new(now)));
+                shard.positions.insert(key.clone(), i);
+            }
+        }
+    }
+
+    /// Record a read of `key`, if it exists.
+    pub fn touch(&self, key: &[u8], lfu: &LfuConfig) {
+        let now = now_ms();
+        let mut shard = self.shard(key);
+        if let Some(&i) = shard.positions.get(key) {
+            shard.keys[i].1.hit(now, lfu);
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Access> {
+        let shard = self.shard(key);
+        shard.positions.get(key).map(|&i| shard.keys[i].1)
+    }
+
+    /// Overwrite the access record of `key`, if it exists.
+    pub fn set(&self, key: &[u8], access: Access) {
+        let mut shard = self.shard(key);
+        if let Some(&i) = shard.positions.get(key) {
+            shard.keys[i].1 = access;
+        }
+    }
+
+    pub fn remove(&self, key: &[u8]) {
+        let mut shard = self.shard(key);
+        if let Some(i) = shard.positions.remove(key) {
+            shard.keys.swap_remove(i);
+            if let Some((moved, _)) = shard.keys.get(i).cloned() {
+                shard.positions.insert(moved, i);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            *shard.lock().unwrap_or_else(PoisonError::into_inner) = IndexShard::default();
+        }
+    }
+
+    /// A random key and its access record; `None` if there are no keys.
+    pub fn random_key(&self) -> Option<(Bytes, Access)> {
+        let start = random() as usize;
+        (0..INDEX_SHARDS).find_map(|offset| {
+            let shard = self.shards[(start + offset) % INDEX_SHARDS]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if shard.keys.is_empty() {
+                return None;
+            }
+            Some(shard.keys[random() as usize % shard.keys.len()].clone())
+        })
+    }
+}
+
+/// Candidates kept between evictions, best last, like Redis's eviction
+/// pool: each round only samples a few keys, and the pool remembers good
+/// candidates from earlier rounds.
+const POOL_SIZE: usize = 16;
+
+/// Random draws per wanted key when only keys with an expiry time qualify.
+const VOLATILE_DRAWS: usize = 10;
+
+#[derive(Debug, Default)]
+pub(super) struct EvictionPool(Mutex<Vec<(u64, Bytes)>>);
+
+impl BackendInner {
+    /// Estimated size of the dataset in bytes.
+    pub fn used_memory(&self) -> usize {
+        self.used_memory.load(Ordering::Relaxed)
+    }
+
+    pub fn evicted_keys(&self) -> u64 {
+        self.evicted_keys.load(Ordering::Relaxed)
+    }
+
+    pub fn expired_keys(&self) -> u64 {
+        self.expired_keys.load(Ordering::Relaxed)
+    }
+
+    /// The largest `used_memory` has been since startup or the last
+    /// `CONFIG RESETSTAT`.
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory.load(Ordering::Relaxed)
+    }
+
+    /// `CONFIG RESETSTAT`: zero the counters and restart the peak from the
+    /// current usage.
+    pub fn reset_stats(&self) {
+        self.evicted_keys.store(0, Ordering::Relaxed);
+        self.expired_keys.store(0, Ordering::Relaxed);
+        self.peak_memory
+            .store(self.used_memory(), Ordering::Relaxed);
+    }
+
+    /// Per-key overhead included in `used_memory`, as opposed to the bytes
+    /// of the keys and values themselves.
+    pub fn memory_overhead(&self) -> usize {
+        self.key_count() * KEY_OVERHEAD
+    }
+
+    /// How much memory `key` and its value take, as `MEMORY USAGE` reports
+    /// it. Doesn't count as an access.
+    pub fn memory_usage(&self, key: &[u8]) -> Option<usize> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        let usage = if let Some(value) = self.map.get(key) {
+            frame_usage(&value)
+        } else if let Some(hash) = self.hmap.get(key) {
+            hash.memory_usage()
+        } else {
+            self.hset.get(key)?.memory_usage()
+        };
+        Some(key_usage(key, usage))
+    }
+
+    /// When `key` was last used and how often, without counting as an
+    /// access.
+    pub fn access(&self, key: &[u8]) -> Option<Access> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        self.keys.get(key)
+    }
+
+    /// Overwrite the access record of `key`, as `RESTORE` does with
+    /// `IDLETIME` or `FREQ`.
+    pub fn set_access(&self, key: &[u8], access: Access) {
+        self.keys.set(key, access);
+    }
+
+    pub(super) fn grow(&self, bytes: usize) {
+        let used = self.used_memory.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak_memory.fetch_max(used, Ordering::Relaxed);
+    }
+
+    /// Saturates at zero, so an estimate that drifted low can't wrap around
+    /// and make every write look over `maxmemory`.
+    pub(super) fn shrink(&self, bytes: usize) {
+        let _ = self
+            .used_memory
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+
+    /// Evict keys until the dataset fits `maxmemory`, following
+    /// `maxmemory-policy`. Returns false if it still doesn't fit, so
+    /// commands that would grow it can be refused.
+    pub fn evict_if_needed(&self) -> bool {
+        let (eviction, lfu) = {
+            let config = self.config();
+            (config.eviction(), config.lfu())
+        };
+        if eviction.maxmemory == 0 {
+            return true;
+        }
+        while self.used_memory() > eviction.maxmemory {
+            let Some(key) = self.eviction_candidate(&eviction, &lfu) else {
+                return false;
+            };
+            // a command may be resizing the same key
+            let deleted = {
+                let _lock = self.key_locks.lock(&key);
+                self.delete(&key)
+            };
+            if deleted {
+                self.dirty.fetch_add(1, Ordering::Relaxed);
+                self.evicted_keys.fetch_add(1, Ordering::Relaxed);
+                self.notify(NotifyFlags::EVICTED, "evicted", &key);
+                self.propagate_deletion(&key);
+            }
+        }
+        true
+    }
+
+    fn eviction_candidate(&self, eviction: &EvictionConfig, lfu: &LfuConfig) -> Option<Bytes> {
+        let policy = eviction.policy;
+        let samples = self.sample_keys(eviction.samples, policy.volatile());
+        let now = now_ms();
+        let score = |key: &Bytes, access: &Access| match policy {
+            EvictionPolicy::AllKeysLru | EvictionPolicy::VolatileLru => (now - access.at) as u64,
+            EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu => {
+                (u8::MAX - access.decayed_counter(now, lfu)) as u64
+            }
+            EvictionPolicy::VolatileTtl => {
+                self.expires.get(key).map_or(0, |at| u64::MAX - *at as u64)
+            }
+            _ => 0,
+        };
+        match policy {
+            EvictionPolicy::NoEviction => None,
+            EvictionPolicy::AllKeysRandom | EvictionPolicy::VolatileRandom => {
+                samples.into_iter().next().map(|(key, _)| key)
+            }
+            _ => {
+                let mut pool = self
+                    .eviction_pool
+                    .0
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                for (key, access) in samples {
+                    let score = score(&key, &access);
+                    pool.retain(|(_, pooled)| *pooled != key);
+                    let at = pool.partition_point(|(pooled, _)| *pooled <= score);
+                    if pool.len() < POOL_SIZE || at > 0 {
+                        pool.insert(at, (score, key));
+                        if pool.len() > POOL_SIZE {
+                            pool.remove(0);
+                        }
+                    }
+                }
+                // pooled keys may have been deleted since they were sampled
+                while let Some((_, key)) = pool.pop() {
+                    let live = self.keys.get(&key).is_some()
+                        && (!policy.volatile() || self.expires.contains_key(&key));
+                    if live {
+                        return Some(key);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Up to `count` random keys, only keys with an expiry time if
+    /// `volatile`. Keys may repeat.
+    pub(super) fn sample_keys(&self, count: usize, volatile: bool) -> Vec<(Bytes, Access)> {
+        let draws = if volatile {
+            count * VOLATILE_DRAWS
+        } else {
+            count
+        };
+        (0..draws)
+            .filter_map(|_| self.keys.random_key())
+            .filter(|(key, _)| !volatile || self.expires.contains_key(key))
+            .take(count)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Backend, BulkString, Config, Entry, Value};
+
+    use super::*;
+
+    fn backend(params: &[(&str, &str)]) -> Backend {
+        let mut config = Config::default();
+        let params = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        config.set(&params).unwrap();
+        Backend::with_config(config)
+    }
+
+    fn set(backend: &Backend, key: &str) {
+        backend.set(
+            Bytes::from(key.to_string()),
+            BulkString::new(Some("v")).into(),
+        );
+    }
+
+    #[test]
+    fn test_memory_accounting() {
+        let backend = backend(&[("hash-max-listpack-entries", "2")]);
+        set(&backend, "s");
+        let one = backend.used_memory();
+        assert_eq!(one, key_usage(b"s", 17));
+        set(&backend, "s");
+        assert_eq!(backend.used_memory(), one);
+
+        let fields = (0..3)
+            .map(|i| Bytes::from(format!("f{}", i)))
+            .collect::<Vec<_>>();
+        let values = (0..3).map(|_| BulkString::new(Some("v")).into()).collect();
+        backend.hmset(Bytes::from("h"), fields, values).unwrap();
+        let members = vec![Bytes::from("1"), Bytes::from("a")];
+        backend.sadd(Bytes::from("set"), members).unwrap();
+        assert!(backend.used_memory() > one);
+
+        for key in ["s", "h", "set"] {
+            assert!(backend.remove(key.as_bytes()));
+        }
+        assert_eq!(backend.used_memory(), 0);
+        backend.shrink(10);
+        assert_eq!(backend.used_memory(), 0);
+    }
+
+    #[test]
+    fn test_evict_lru() {
+        let backend = backend(&[("maxmemory-policy", "allkeys-lru")]);
+        for i in 0..10 {
+            set(&backend, &format!("key:{}", i));
+        }
+        assert!(backend.evict_if_needed());
+        let limit = backend.used_memory() / 2;
+        backend
+            .config_mut()
+            .set(&[("maxmemory".to_string(), limit.to_string())])
+            .unwrap();
+        assert!(backend.evict_if_needed());
+        assert!(backend.used_memory() <= limit);
+        assert_eq!(backend.evicted_keys(), 5);
+    }
+
+    #[test]
+    fn test_noeviction_and_volatile_ttl() {
+        // sample enough that the choice is not left to chance
+        let backend = backend(&[("maxmemory", "1"), ("maxmemory-samples", "64")]);
+        set(&backend, "a");
+        assert!(!backend.evict_if_needed());
+        assert!(backend.exists(b"a"));
+
+        // only keys with a TTL qualify, soonest expiry first
+        backend
+            .config_mut()
+            .set(&[("maxmemory-policy".to_string(), "volatile-ttl".to_string())])
+            .unwrap();
+        let later = now_ms() + 60_000;
+        for (key, at) in [("b", later), ("c", later + 1000)] {
+            backend.insert_entry(Entry {
+                key: Bytes::from(key),
+                value: Value::String(Bytes::from("v")),
+                expire_at: Some(at),
+            });
+        }
+        let limit = backend.used_memory() - 1;
+        backend
+            .config_mut()
+            .set(&[("maxmemory".to_string(), limit.to_string())])
+            .unwrap();
+        assert!(backend.evict_if_needed());
+        assert!(!backend.exists(b"b"));
+        assert!(backend.exists(b"a") && backend.exists(b"c"));
+        // "a" has no TTL, so once the volatile keys are gone nothing is left
+        backend
+            .config_mut()
+            .set(&[("maxmemory".to_string(), "1".to_string())])
+            .unwrap();
+        assert!(!backend.evict_if_needed());
+        assert!(backend.exists(b"a"));
+    }
+
+    #[test]
+    fn test_eviction_propagates_del() {
+        let dir = std::env::temp_dir().join(format!("simple-redis-evict-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let backend = backend(&[
+            ("dir", dir.to_str().unwrap()),
+            ("appendonly", "yes"),
+            ("appendfsync", "always"),
+            ("maxmemory-policy", "allkeys-random"),
+        ]);
+        crate::aof::resume(&backend).unwrap();
+        while backend.aof().rewrite_in_progress() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        // an attached replica starts the replication backlog
+        crate::replication::psync(&backend, &crate::Client::new(), "?", -1).unwrap();
+        set(&backend, "k");
+        let offset = backend.replication().offset();
+        backend
+            .config_mut()
+            .set(&[("maxmemory".to_string(), "1".to_string())])
+            .unwrap();
+        assert!(backend.evict_if_needed());
+
+        let del = b"*2\r\n$3\r\nDEL\r\n$1\r\nk\r\n";
+        assert_eq!(backend.replication().offset(), offset + del.len() as i64);
+        let incr = std::fs::read(dir.join("appendonlydir/appendonly.aof.1.incr.aof")).unwrap();
+        assert!(incr.ends_with(del));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_key_index() {
+        let index = KeyIndex::default();
+        let lfu = LfuConfig {
+            log_factor: 10,
+            decay_time: 1,
+        };
+        for key in ["a", "b", "c"] {
+            index.write(&Bytes::from(key), &lfu);
+        }
+        index.remove(b"a");
+        index.remove(b"missing");
+        assert!(index.get(b"a").is_none());
+        assert_eq!(index.get(b"c").unwrap().counter, LFU_INIT_VAL);
+        for _ in 0..10 {
+            let (key, _) = index.random_key().unwrap();
+            assert!(key == "b" || key == "c");
+        }
+        index.clear();
+        assert!(index.random_key().is_none());
+    }
+
+    #[test]
+    fn test_lfu_counter() {
+        let lfu = LfuConfig {
+            log_factor: 0,
+            decay_time: 1,
+        };
+        let mut access = Access::new(0);
+        // with a log factor of 0 every hit counts
+        access.hit(0, &lfu);
+        access.hit(0, &lfu);
+        assert_eq!(access.counter, LFU_INIT_VAL + 2);
+        // one decrement per idle minute
+        assert_eq!(access.decayed_counter(3 * 60_000, &lfu), LFU_INIT_VAL - 1);
+        let never = LfuConfig {
+            log_factor: 0,
+            decay_time: 0,
+        };
+        assert_eq!(access.decayed_counter(i64::MAX, &never), LFU_INIT_VAL + 2);
+    }
+}
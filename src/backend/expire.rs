@@ -0,0 +1,162 @@
+//! Key expiry. A key is deleted when it is accessed after its expiry time,
+//! and a background cycle samples keys with an expiry time so those that
+//! are never accessed again go too, like Redis's `activeExpireCycle`.
+
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use super::{now_ms, Backend, BackendInner, NotifyFlags};
+
+/// How often the active expire cycle runs, Redis's default `hz` of 10.
+const ACTIVE_EXPIRE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Keys with an expiry time sampled per round of the cycle.
+const ACTIVE_EXPIRE_KEYS_PER_LOOP: usize = 20;
+
+/// Time a cycle may take, so it can't hold up clients for long.
+const ACTIVE_EXPIRE_BUDGET: Duration = Duration::from_millis(25);
+
+impl BackendInner {
+    /// Whether `key` has an expiry time that has passed.
+    fn is_expired(&self, key: &[u8]) -> bool {
+        self.expires.get(key).is_some_and(|at| *at <= now_ms())
+    }
+
+    /// Delete `key` if its expiry time has passed, returning whether it
+    /// has, i.e. whether the key reads as missing.
+    pub(super) fn expire_if_needed(&self, key: &[u8]) -> bool {
+        if !self.is_expired(key) {
+            return false;
+        }
+        let _lock = self.key_locks.lock(key);
+        self.expire_locked(key)
+    }
+
+    /// [`Self::expire_if_needed`] for callers that hold the lock of `key`.
+    /// The expiry is checked again under the lock, as a write may have
+    /// stored a new value and cleared it since.
+    ///
+    /// A replica leaves the key in place until the master's `DEL` arrives,
+    /// like Redis, so its dataset only changes through the replication
+    /// stream. The `DEL` sent on a master doesn't need the write guard: it
+    /// is harmless where the key is already gone.
+    pub(super) fn expire_locked(&self, key: &[u8]) -> bool {
+        if !self.is_expired(key) {
+            return false;
+        }
+        if self.replication.is_replica() {
+            return true;
+        }
+        if self.delete(key) {
+            self.expired_keys.fetch_add(1, Ordering::Relaxed);
+            self.notify(NotifyFlags::EXPIRED, "expired", key);
+            self.propagate_deletion(key);
+        }
+        true
+    }
+
+    /// Sample keys with an expiry time and delete those past it, repeating
+    /// while more than a quarter of a sample was due. Returns the number of
+    /// keys deleted.
+    pub fn active_expire_cycle(&self) -> usize {
+        let start = Instant::now();
+        let mut expired = 0;
+        loop {
+            let sample = self.sample_keys(ACTIVE_EXPIRE_KEYS_PER_LOOP, true);
+            let due = sample
+                .iter()
+                .filter(|(key, _)| self.expire_if_needed(key))
+                .count();
+            expired += due;
+            if due * 4 <= sample.len() || start.elapsed() > ACTIVE_EXPIRE_BUDGET {
+                return expired;
+            }
+        }
+    }
+}
+
+/// Run the active expire cycle every `ACTIVE_EXPIRE_PERIOD`. Replicas
+/// skip it and wait for the master's `DEL`s.
+pub async fn expire_cron(backend: Backend) {
+    let mut ticker = tokio::time::interval(ACTIVE_EXPIRE_PERIOD);
+    loop {
+        ticker.tick().await;
+        if backend.replication().is_replica() {
+            continue;
+        }
+        let _guard = backend.write_guard();
+        backend.active_expire_cycle();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{replication, BulkString, Client, Config, Entry, RespPush, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_expiry_propagates_del() {
+        let backend = Backend::new();
+        // an attached replica starts the replication backlog
+        replication::psync(&backend, &Client::new(), "?", -1).unwrap();
+        backend.insert_entry(Entry {
+            key: Bytes::from("k"),
+            value: Value::String(Bytes::from("v")),
+            expire_at: Some(1),
+        });
+        let offset = backend.replication().offset();
+        assert_eq!(backend.get(b"k").unwrap(), None);
+        assert_eq!(backend.expired_keys(), 1);
+        let del = b"*2\r\n$3\r\nDEL\r\n$1\r\nk\r\n";
+        assert_eq!(backend.replication().offset(), offset + del.len() as i64);
+        // the key is gone, so reading it again sends nothing
+        assert!(!backend.exists(b"k"));
+        assert_eq!(backend.replication().offset(), offset + del.len() as i64);
+    }
+
+    #[test]
+    fn test_active_expire() {
+        let mut config = Config::default();
+        config
+            .set(&[("notify-keyspace-events".to_string(), "Ex".to_string())])
+            .unwrap();
+        let backend = Backend::with_config(config);
+        let mut client = Client::new();
+        let mut inbox = client.subscriptions.take_inbox().unwrap();
+        backend
+            .pubsub()
+            .subscribe(&mut client, Bytes::from("__keyevent@0__:expired"));
+        let later = now_ms() + 60_000;
+        for (key, at) in [("gone", 1), ("ttl", later)] {
+            backend.insert_entry(Entry {
+                key: Bytes::from(key),
+                value: Value::String(Bytes::from("v")),
+                expire_at: Some(at),
+            });
+        }
+
+        // nothing reads "gone", the cycles find it on their own; keys are
+        // sampled at random, so one cycle may miss it like in Redis
+        let expired = (0..1000)
+            .map(|_| backend.active_expire_cycle())
+            .find(|expired| *expired > 0);
+        assert_eq!(expired, Some(1));
+        assert_eq!(backend.key_count(), 1);
+        assert_eq!(backend.expired_keys(), 1);
+        assert_eq!(
+            inbox.try_recv().unwrap(),
+            RespPush::new([
+                BulkString::new(Some("message")).into(),
+                BulkString::new(Some("__keyevent@0__:expired")).into(),
+                BulkString::new(Some("gone")).into(),
+            ])
+            .into()
+        );
+        assert_eq!(backend.active_expire_cycle(), 0);
+    }
+}
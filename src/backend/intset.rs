@@ -0,0 +1,148 @@
+/// A sorted set of integers in one buffer, each stored in the smallest of
+/// 2, 4 or 8 bytes that fits every member, like Redis's intset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntSet {
+    /// Bytes per member.
+    width: usize,
+    data: Vec<u8>,
+}
+
+impl Default for IntSet {
+    fn default() -> Self {
+        IntSet {
+            width: 2,
+            data: Vec::new(),
+        }
+    }
+}
+
+fn width_of(value: i64) -> usize {
+    if i16::try_from(value).is_ok() {
+        2
+    } else if i32::try_from(value).is_ok() {
+        4
+    } else {
+        8
+    }
+}
+
+/// The integer a set member stands for, if it is written the way Redis
+/// prints integers, so it can be turned back into the same member.
+pub fn parse_member(member: &[u8]) -> Option<i64> {
+    let digits = member.strip_prefix(b"-").unwrap_or(member);
+    if member.len() > 20 || digits.starts_with(b"0") && member.len() > 1 {
+        return None;
+    }
+    if !digits.first().is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(member).ok()?.parse().ok()
+}
+
+impl IntSet {
+    pub fn len(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    /// Size of the encoded members.
+    pub fn bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    fn get(&self, index: usize) -> i64 {
+        let bytes = &self.data[index * self.width..(index + 1) * self.width];
+        match self.width {
+            2 => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            4 => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    /// Index of `value`, or where it would be inserted.
+    fn search(&self, value: i64) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.get(mid).cmp(&value) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.search(value).is_ok()
+    }
+
+    /// Add `value`, returning whether it was missing.
+    pub fn insert(&mut self, value: i64) -> bool {
+        let width = width_of(value);
+        if width > self.width {
+            let members = self.iter().collect::<Vec<_>>();
+            self.width = width;
+            self.data = Vec::with_capacity((members.len() + 1) * width);
+            members.into_iter().for_each(|member| self.push(member));
+        }
+        match self.search(value) {
+            Ok(_) => false,
+            Err(index) => {
+                let bytes = &value.to_le_bytes()[..self.width];
+                let at = index * self.width;
+                self.data.splice(at..at, bytes.iter().copied());
+                true
+            }
+        }
+    }
+
+    fn push(&mut self, value: i64) {
+        self.data
+            .extend_from_slice(&value.to_le_bytes()[..self.width]);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intset_upgrade() {
+        let mut set = IntSet::default();
+        assert!(set.insert(5));
+        assert!(set.insert(-3));
+        assert!(!set.insert(5));
+        assert!(set.insert(70_000));
+        assert!(set.insert(i64::MIN));
+        assert_eq!(set.len(), 4);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![i64::MIN, -3, 5, 70_000]
+        );
+        assert!(set.contains(-3));
+        assert!(!set.contains(4));
+    }
+
+    #[test]
+    fn test_parse_member() {
+        assert_eq!(parse_member(b"0"), Some(0));
+        assert_eq!(parse_member(b"-42"), Some(-42));
+        assert_eq!(parse_member(b"-9223372036854775808"), Some(i64::MIN));
+        for member in [
+            &b""[..],
+            b"-",
+            b"-0",
+            b"007",
+            b"+1",
+            b" 1",
+            b"1.0",
+            b"9223372036854775808",
+        ] {
+            assert_eq!(parse_member(member), None);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+/// Entries packed back to back in one buffer, each a varint length followed
+/// by its bytes. Like Redis's listpack it trades O(n) lookups for a single
+/// allocation, which is cheaper for the small collections it holds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Listpack {
+    data: Vec<u8>,
+    len: usize,
+}
+
+fn write_len(data: &mut Vec<u8>, mut len: usize) {
+    while len >= 0x80 {
+        data.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    data.push(len as u8);
+}
+
+/// Decode the entry at `pos`, returning it and the position of the next.
+fn read_entry(data: &[u8], mut pos: usize) -> (&[u8], usize) {
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[pos];
+        pos += 1;
+        len |= ((byte & 0x7f) as usize) << shift;
+        if byte < 0x80 {
+            break;
+        }
+        shift += 7;
+    }
+    (&data[pos..pos + len], pos + len)
+}
+
+impl Listpack {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Size of the encoded entries.
+    pub fn bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            data: &self.data,
+            pos: 0,
+        }
+    }
+
+    pub fn push(&mut self, entry: &[u8]) {
+        write_len(&mut self.data, entry.len());
+        self.data.extend_from_slice(entry);
+        self.len += 1;
+    }
+
+    /// Position of the entry after `key`, treating the entries as key and
+    /// value pairs, and the position of the pair that follows.
+    fn find_pair(&self, key: &[u8]) -> Option<(usize, usize)> {
+        let mut pos = 0;
+        while pos < self.data.len() {
+            let (entry, value_pos) = read_entry(&self.data, pos);
+            let (_, next) = read_entry(&self.data, value_pos);
+            if entry == key {
+                return Some((value_pos, next));
+            }
+            pos = next;
+        }
+        None
+    }
+
+    /// The value paired with `key`.
+    pub fn get_pair(&self, key: &[u8]) -> Option<&[u8]> {
+        let (value_pos, _) = self.find_pair(key)?;
+        Some(read_entry(&self.data, value_pos).0)
+    }
+
+    /// Set the value paired with `key`, returning whether the key is new.
+    pub fn set_pair(&mut self, key: &[u8], value: &[u8]) -> bool {
+        match self.find_pair(key) {
+            Some((value_pos, next)) => {
+                let mut entry = Vec::with_capacity(value.len() + 1);
+                write_len(&mut entry, value.len());
+                entry.extend_from_slice(value);
+                self.data.splice(value_pos..next, entry);
+                false
+            }
+            None => {
+                self.push(key);
+                self.push(value);
+                true
+            }
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let (entry, next) = read_entry(self.data, self.pos);
+        self.pos = next;
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listpack_pairs() {
+        let mut lp = Listpack::default();
+        assert!(lp.set_pair(b"a", b"1"));
+        assert!(lp.set_pair(b"long", &[b'x'; 200]));
+        assert!(lp.set_pair(b"", b"empty"));
+        assert_eq!(lp.len(), 6);
+        assert_eq!(lp.get_pair(b"long"), Some(&[b'x'; 200][..]));
+        // a value that matches a key is not mistaken for one
+        assert_eq!(lp.get_pair(b"1"), None);
+
+        assert!(!lp.set_pair(b"long", b"short"));
+        assert!(!lp.set_pair(b"a", &[b'y'; 300]));
+        assert_eq!(lp.len(), 6);
+        assert_eq!(
+            lp.iter().collect::<Vec<_>>(),
+            vec![&b"a"[..], &[b'y'; 300], b"long", b"short", b"", b"empty"]
+        );
+    }
+}
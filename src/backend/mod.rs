@@ -1,89 +1,482 @@
-use dashmap::{DashMap, DashSet};
-use std::{collections::BTreeMap, ops::Deref, sync::Arc};
+mod encoding;
+mod evict;
+mod expire;
+mod intset;
+mod listpack;
+mod notify;
+mod snapshot;
 
-use crate::{BulkString, RespArray, RespFrame};
+use bytes::Bytes;
+use dashmap::DashMap;
+use std::{
+    collections::BTreeMap,
+    hash::{BuildHasher, RandomState},
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    aof::Aof,
+    cluster::{key_slot, Cluster},
+    pubsub::PubSub,
+    rdb::SaveState,
+    replication::Replication,
+    BulkString, Config, RespArray, RespEncode, RespFrame,
+};
+
+pub use encoding::EncodingLimits;
+pub use evict::{Access, EvictionConfig, EvictionPolicy, LfuConfig};
+pub use expire::expire_cron;
+pub use notify::NotifyFlags;
+pub use snapshot::{Entry, Snapshot, Value};
+
+use encoding::{frame_usage, string_encoding, Hash, Set};
+use evict::{key_usage, EvictionPool, KeyIndex};
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackendInner>);
 
-#[derive(Debug, Clone)]
+/// The key holds a value of another type than the operation works on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrongType;
+
+const KEY_LOCK_STRIPES: usize = 64;
+
+/// Serializes the writes to a key, so checking what type it holds and
+/// writing it can't interleave with a write that changes the type.
+#[derive(Debug)]
+struct KeyLocks {
+    hasher: RandomState,
+    stripes: Vec<Mutex<()>>,
+}
+
+impl Default for KeyLocks {
+    fn default() -> Self {
+        KeyLocks {
+            hasher: RandomState::new(),
+            stripes: (0..KEY_LOCK_STRIPES).map(|_| Mutex::default()).collect(),
+        }
+    }
+}
+
+impl KeyLocks {
+    fn lock(&self, key: &[u8]) -> MutexGuard<'_, ()> {
+        let stripe = self.hasher.hash_one(key) as usize % KEY_LOCK_STRIPES;
+        self.stripes[stripe]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug)]
 pub struct BackendInner {
-    map: DashMap<String, RespFrame>,
-    hmap: DashMap<String, DashMap<String, RespFrame>>,
-    hset: DashMap<String, DashSet<String>>,
+    /// A key is in at most one of `map`, `hmap` and `hset`.
+    map: DashMap<Bytes, RespFrame>,
+    hmap: DashMap<Bytes, Hash>,
+    hset: DashMap<Bytes, Set>,
+    key_locks: KeyLocks,
+    /// Absolute expiry times in unix milliseconds.
+    expires: DashMap<Bytes, i64>,
+    /// Held shared by every write command until it has been propagated,
+    /// and exclusively while a snapshot is copied, so a snapshot never sees
+    /// half of a command's changes or a change missing from the AOF.
+    snapshot_lock: RwLock<()>,
+    /// Changes made since startup; only ever grows.
+    dirty: AtomicU64,
+    /// Value of `dirty` covered by the last successful save.
+    saved: AtomicU64,
+    config: RwLock<Config>,
+    save_state: SaveState,
+    aof: Aof,
+    replication: Replication,
+    /// Set at startup when cluster mode is enabled.
+    cluster: OnceLock<Cluster>,
+    pubsub: PubSub,
+    /// Estimated size of the dataset, checked against `maxmemory`.
+    used_memory: AtomicUsize,
+    peak_memory: AtomicUsize,
+    /// Every key with when and how often it was used, for eviction.
+    keys: KeyIndex,
+    eviction_pool: EvictionPool,
+    evicted_keys: AtomicU64,
+    expired_keys: AtomicU64,
+}
+
+/// Current unix time in milliseconds.
+pub(crate) fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
 }
 
 impl BackendInner {
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+    pub fn config(&self) -> RwLockReadGuard<'_, Config> {
+        self.config.read().unwrap_or_else(PoisonError::into_inner)
     }
 
-    pub fn set(&self, key: String, value: RespFrame) -> Option<RespFrame> {
-        self.map.insert(key, value)
+    pub fn config_mut(&self) -> RwLockWriteGuard<'_, Config> {
+        self.config.write().unwrap_or_else(PoisonError::into_inner)
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|field_map| field_map.get(field).map(|v| v.value().clone()))
+    pub fn save_state(&self) -> &SaveState {
+        &self.save_state
     }
 
-    pub fn hmget(&self, key: &str, fields: &[String]) -> Option<Vec<Option<RespFrame>>> {
-        self.hmap.get(key).map(|field_map| {
-            fields
-                .iter()
-                .map(|field| field_map.get(field).map(|v| v.value().clone()))
-                .collect()
-        })
+    pub fn aof(&self) -> &Aof {
+        &self.aof
+    }
+
+    pub fn replication(&self) -> &Replication {
+        &self.replication
+    }
+
+    /// How the value of `key` is stored, as `OBJECT ENCODING` reports it.
+    pub fn encoding(&self, key: &[u8]) -> Option<&'static str> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        if let Some(value) = self.map.get(key) {
+            Some(string_encoding(&value))
+        } else if let Some(hash) = self.hmap.get(key) {
+            Some(hash.encoding())
+        } else {
+            self.hset.get(key).map(|set| set.encoding())
+        }
     }
 
-    pub fn hmset(&self, key: String, fields: Vec<String>, values: Vec<RespFrame>) -> RespFrame {
-        let hmap = self.hmap.entry(key).or_default();
+    pub fn cluster(&self) -> Option<&Cluster> {
+        self.cluster.get()
+    }
+
+    /// Enable cluster mode. The topology is static, so only the first call
+    /// has an effect.
+    pub fn set_cluster(&self, cluster: Cluster) {
+        let _ = self.cluster.set(cluster);
+    }
+
+    pub fn pubsub(&self) -> &PubSub {
+        &self.pubsub
+    }
+
+    /// Changes since the last successful save.
+    pub fn dirty(&self) -> u64 {
+        self.changes()
+            .saturating_sub(self.saved.load(Ordering::Relaxed))
+    }
+
+    /// Forget `saved` changes once they have been persisted.
+    pub(crate) fn clear_dirty(&self, saved: u64) {
+        self.saved.fetch_add(saved, Ordering::Relaxed);
+    }
+
+    /// Changes made since startup. Unlike [`dirty`](Self::dirty) it is not
+    /// reset by saves, so comparing two readings tells whether anything
+    /// changed in between.
+    pub(crate) fn changes(&self) -> u64 {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
+    /// Taken by the command layer around a write command and its
+    /// propagation; the methods below don't take it themselves.
+    pub fn write_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.snapshot_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record a write command that changed the dataset in the AOF and send
+    /// it to replicas.
+    pub fn propagate(&self, frame: &RespFrame) {
+        let (fsync, backlog_size, output_limit) = {
+            let config = self.config();
+            (
+                config.appendfsync(),
+                config.repl_backlog_size(),
+                config.replica_output_buffer_limit(),
+            )
+        };
+        let data = frame.encode();
+        self.aof.append(&data, fsync);
+        self.replication.feed(&data, backlog_size, output_limit);
+    }
+
+    /// Send the deletion of `key` by eviction or expiry to the AOF and to
+    /// replicas as a `DEL`, like Redis's `propagateDeletion`, so they don't
+    /// keep a key this server dropped on its own.
+    fn propagate_deletion(&self, key: &[u8]) {
+        let del = RespArray::new(Some(vec![
+            BulkString::new(Some("DEL")).into(),
+            BulkString::from(Bytes::copy_from_slice(key)).into(),
+        ]));
+        self.propagate(&del.into());
+    }
+
+    /// Drop `key` whatever its type, with its expiry time and access
+    /// record, returning whether it held a value.
+    fn delete(&self, key: &[u8]) -> bool {
+        let mut freed = vec![];
+        if let Some((_, value)) = self.map.remove(key) {
+            freed.push(frame_usage(&value));
+        }
+        if let Some((_, hash)) = self.hmap.remove(key) {
+            freed.push(hash.memory_usage());
+        }
+        if let Some((_, set)) = self.hset.remove(key) {
+            freed.push(set.memory_usage());
+        }
+        self.expires.remove(key);
+        self.keys.remove(key);
+        for usage in &freed {
+            self.shrink(key_usage(key, *usage));
+        }
+        !freed.is_empty()
+    }
+
+    /// Account for a collection that changed from `before` to `after`
+    /// bytes, or was `created` with them.
+    fn resize(&self, key: &[u8], created: bool, before: usize, after: usize) {
+        if created {
+            self.grow(key_usage(key, after));
+        } else if after >= before {
+            self.grow(after - before);
+        } else {
+            self.shrink(before - after);
+        }
+    }
+
+    /// Fail if `key` exists but not in `expected`, the map of the type the
+    /// operation works on. Doesn't expire `key`, so callers holding its
+    /// lock can use it after expiring it themselves.
+    fn check_type<V>(&self, key: &[u8], expected: &DashMap<Bytes, V>) -> Result<(), WrongType> {
+        if !expected.contains_key(key) && self.contains(key) {
+            return Err(WrongType);
+        }
+        Ok(())
+    }
+
+    /// Publish the `keymiss` event of a read that found no `key`.
+    fn key_miss(&self, key: &[u8]) {
+        self.notify(NotifyFlags::KEY_MISS, "keymiss", key);
+    }
+
+    /// Record a read of `key` for the LRU and LFU policies.
+    fn touch(&self, key: &[u8]) {
+        let lfu = self.config().lfu();
+        self.keys.touch(key, &lfu);
+    }
+
+    /// Whether `key` holds a value of any type.
+    pub fn exists(&self, key: &[u8]) -> bool {
+        !self.expire_if_needed(key) && self.contains(key)
+    }
+
+    /// Whether any of the maps holds `key`, expired or not.
+    fn contains(&self, key: &[u8]) -> bool {
+        self.map.contains_key(key) || self.hmap.contains_key(key) || self.hset.contains_key(key)
+    }
+
+    /// Number of keys, including expired ones not yet deleted.
+    pub fn key_count(&self) -> usize {
+        self.map.len() + self.hmap.len() + self.hset.len()
+    }
+
+    /// Delete `key` whatever its type, returning whether it existed.
+    pub fn remove(&self, key: &[u8]) -> bool {
+        let _lock = self.key_locks.lock(key);
+        self.expire_locked(key);
+        let removed = self.delete(key);
+        if removed {
+            self.dirty.fetch_add(1, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    /// Up to `count` keys that hash to `slot`. Keys are not indexed by slot,
+    /// so this scans the whole keyspace.
+    pub fn keys_in_slot(&self, slot: u16, count: usize) -> Vec<Bytes> {
+        let now = now_ms();
+        let live = |key: &Bytes| self.expires.get(key).is_none_or(|at| *at > now);
+        self.map
+            .iter()
+            .map(|item| item.key().clone())
+            .chain(self.hmap.iter().map(|item| item.key().clone()))
+            .chain(self.hset.iter().map(|item| item.key().clone()))
+            .filter(|key| key_slot(key) == slot && live(key))
+            .take(count)
+            .collect()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<RespFrame>, WrongType> {
+        if self.expire_if_needed(key) {
+            self.key_miss(key);
+            return Ok(None);
+        }
+        if self.hmap.contains_key(key) || self.hset.contains_key(key) {
+            return Err(WrongType);
+        }
+        self.touch(key);
+        let value = self.map.get(key).map(|v| v.value().clone());
+        if value.is_none() {
+            self.key_miss(key);
+        }
+        Ok(value)
+    }
+
+    /// Store a string, replacing a value of any type.
+    pub fn set(&self, key: Bytes, value: RespFrame) -> Option<RespFrame> {
+        let _lock = self.key_locks.lock(&key);
+        self.expire_locked(&key);
+        self.dirty.fetch_add(1, Ordering::Relaxed);
+        let replaced =
+            (self.hmap.contains_key(&key) || self.hset.contains_key(&key)) && self.delete(&key);
+        self.expires.remove(&key);
+        self.keys.write(&key, &self.config().lfu());
+        self.grow(key_usage(&key, frame_usage(&value)));
+        let old = self.map.insert(key.clone(), value);
+        match &old {
+            Some(old) => self.shrink(key_usage(&key, frame_usage(old))),
+            None if !replaced => self.notify(NotifyFlags::NEW, "new", &key),
+            None => {}
+        }
+        old
+    }
+
+    pub fn hget(&self, key: &[u8], field: &[u8]) -> Result<Option<RespFrame>, WrongType> {
+        if self.expire_if_needed(key) {
+            self.key_miss(key);
+            return Ok(None);
+        }
+        self.check_type(key, &self.hmap)?;
+        self.touch(key);
+        let Some(hash) = self.hmap.get(key) else {
+            self.key_miss(key);
+            return Ok(None);
+        };
+        Ok(hash.get(field))
+    }
+
+    pub fn hmget(
+        &self,
+        key: &[u8],
+        fields: &[Bytes],
+    ) -> Result<Option<Vec<Option<RespFrame>>>, WrongType> {
+        if self.expire_if_needed(key) {
+            self.key_miss(key);
+            return Ok(None);
+        }
+        self.check_type(key, &self.hmap)?;
+        self.touch(key);
+        let Some(hash) = self.hmap.get(key) else {
+            self.key_miss(key);
+            return Ok(None);
+        };
+        Ok(Some(fields.iter().map(|field| hash.get(field)).collect()))
+    }
+
+    pub fn hmset(
+        &self,
+        key: Bytes,
+        fields: Vec<Bytes>,
+        values: Vec<RespFrame>,
+    ) -> Result<RespFrame, WrongType> {
+        let _lock = self.key_locks.lock(&key);
+        self.expire_locked(&key);
+        self.check_type(&key, &self.hmap)?;
+        self.dirty.fetch_add(fields.len() as u64, Ordering::Relaxed);
+        let (limits, lfu) = {
+            let config = self.config();
+            (config.encoding_limits(), config.lfu())
+        };
+        self.keys.write(&key, &lfu);
+        let mut created = false;
+        let mut hash = self.hmap.entry(key.clone()).or_insert_with(|| {
+            created = true;
+            Hash::default()
+        });
+        let before = hash.memory_usage();
         let success_count = fields
             .into_iter()
             .zip(values)
-            .map(|(field, value)| hmap.insert(field, value))
-            .filter(Option::is_none)
+            .map(|(field, value)| hash.insert(field, value, &limits))
+            .filter(|new| *new)
             .count();
-        RespFrame::Integer(success_count as i64)
-    }
-
-    pub fn hgetall(&self, key: &str) -> Option<RespFrame> {
-        self.hmap.get(key).map(|field_map| {
-            let mut map = BTreeMap::new();
-            field_map.iter().for_each(|entry| {
-                map.insert(entry.key().clone(), entry.value().clone());
-            });
-            let mut vec = Vec::with_capacity(map.len() * 2);
-            map.into_iter().for_each(|(key, value)| {
-                vec.push(BulkString::new(Some(key)).into());
-                vec.push(value);
-            });
-            RespFrame::Array(RespArray::new(Some(vec)))
-        })
-    }
-
-    pub fn sadd(&self, key: String, fields: Vec<String>) -> RespFrame {
-        let field_set = self.hset.entry(key).or_default();
+        let after = hash.memory_usage();
+        drop(hash);
+        self.resize(&key, created, before, after);
+        if created {
+            self.notify(NotifyFlags::NEW, "new", &key);
+        }
+        Ok(RespFrame::Integer(success_count as i64))
+    }
+
+    pub fn hgetall(&self, key: &[u8]) -> Result<Option<RespFrame>, WrongType> {
+        if self.expire_if_needed(key) {
+            self.key_miss(key);
+            return Ok(None);
+        }
+        self.check_type(key, &self.hmap)?;
+        self.touch(key);
+        let Some(hash) = self.hmap.get(key) else {
+            self.key_miss(key);
+            return Ok(None);
+        };
+        let map = hash.pairs().into_iter().collect::<BTreeMap<_, _>>();
+        let mut vec = Vec::with_capacity(map.len() * 2);
+        map.into_iter().for_each(|(key, value)| {
+            vec.push(BulkString::from(key).into());
+            vec.push(BulkString::from(value).into());
+        });
+        Ok(Some(RespFrame::Array(RespArray::new(Some(vec)))))
+    }
+
+    pub fn sadd(&self, key: Bytes, fields: Vec<Bytes>) -> Result<RespFrame, WrongType> {
+        let _lock = self.key_locks.lock(&key);
+        self.expire_locked(&key);
+        self.check_type(&key, &self.hset)?;
+        let (limits, lfu) = {
+            let config = self.config();
+            (config.encoding_limits(), config.lfu())
+        };
+        self.keys.write(&key, &lfu);
+        let mut created = false;
+        let mut set = self.hset.entry(key.clone()).or_insert_with(|| {
+            created = true;
+            Set::default()
+        });
+        let before = set.memory_usage();
         let success_count = fields
             .into_iter()
-            .map(|field| field_set.insert(field))
-            .filter(|b| *b)
+            .map(|field| set.insert(field, &limits))
+            .filter(|new| *new)
             .count();
-        RespFrame::Integer(success_count as i64)
+        let after = set.memory_usage();
+        drop(set);
+        self.resize(&key, created, before, after);
+        if created {
+            self.notify(NotifyFlags::NEW, "new", &key);
+        }
+        self.dirty
+            .fetch_add(success_count as u64, Ordering::Relaxed);
+        Ok(RespFrame::Integer(success_count as i64))
     }
 
-    pub fn sismember(&self, key: &str, field: &str) -> RespFrame {
-        self.hset
-            .get(key)
-            .map(|field_map| {
-                if field_map.contains(field) {
-                    RespFrame::Integer(1)
-                } else {
-                    RespFrame::Integer(0)
-                }
-            })
-            .unwrap_or(RespFrame::Integer(0))
+    pub fn sismember(&self, key: &[u8], field: &[u8]) -> Result<RespFrame, WrongType> {
+        if self.expire_if_needed(key) {
+            self.key_miss(key);
+            return Ok(RespFrame::Integer(0));
+        }
+        self.check_type(key, &self.hset)?;
+        self.touch(key);
+        let Some(set) = self.hset.get(key) else {
+            self.key_miss(key);
+            return Ok(RespFrame::Integer(0));
+        };
+        Ok(RespFrame::Integer(set.contains(field) as i64))
     }
 }
 
@@ -97,7 +490,11 @@ impl Deref for Backend {
 
 impl Backend {
     pub fn new() -> Self {
-        Backend(Arc::new(BackendInner::new()))
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        Backend(Arc::new(BackendInner::new(config)))
     }
 }
 
@@ -108,11 +505,28 @@ impl Default for Backend {
 }
 
 impl BackendInner {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         BackendInner {
             map: DashMap::new(),
             hmap: DashMap::new(),
             hset: DashMap::new(),
+            key_locks: KeyLocks::default(),
+            expires: DashMap::new(),
+            snapshot_lock: RwLock::new(()),
+            dirty: AtomicU64::new(0),
+            saved: AtomicU64::new(0),
+            config: RwLock::new(config),
+            save_state: SaveState::default(),
+            aof: Aof::default(),
+            replication: Replication::default(),
+            cluster: OnceLock::new(),
+            pubsub: PubSub::default(),
+            used_memory: AtomicUsize::new(0),
+            peak_memory: AtomicUsize::new(0),
+            keys: KeyIndex::default(),
+            eviction_pool: EvictionPool::default(),
+            evicted_keys: AtomicU64::new(0),
+            expired_keys: AtomicU64::new(0),
         }
     }
 }
@@ -0,0 +1,188 @@
+//! Keyspace notifications: events published on `__keyspace@0__:<key>` and
+//! `__keyevent@0__:<event>` when commands or the server change a key, as
+//! selected by the `notify-keyspace-events` classes.
+
+use std::fmt;
+
+use bytes::{BufMut, BytesMut};
+
+use super::BackendInner;
+
+/// A set of keyspace event classes, each named by the character Redis uses
+/// for it in `notify-keyspace-events`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotifyFlags(u16);
+
+impl NotifyFlags {
+    pub const KEYSPACE: NotifyFlags = NotifyFlags(1 << 0);
+    pub const KEYEVENT: NotifyFlags = NotifyFlags(1 << 1);
+    pub const GENERIC: NotifyFlags = NotifyFlags(1 << 2);
+    pub const STRING: NotifyFlags = NotifyFlags(1 << 3);
+    pub const LIST: NotifyFlags = NotifyFlags(1 << 4);
+    pub const SET: NotifyFlags = NotifyFlags(1 << 5);
+    pub const HASH: NotifyFlags = NotifyFlags(1 << 6);
+    pub const ZSET: NotifyFlags = NotifyFlags(1 << 7);
+    pub const EXPIRED: NotifyFlags = NotifyFlags(1 << 8);
+    pub const EVICTED: NotifyFlags = NotifyFlags(1 << 9);
+    pub const STREAM: NotifyFlags = NotifyFlags(1 << 10);
+    pub const KEY_MISS: NotifyFlags = NotifyFlags(1 << 11);
+    pub const MODULE: NotifyFlags = NotifyFlags(1 << 12);
+    pub const NEW: NotifyFlags = NotifyFlags(1 << 13);
+    /// What `A` stands for: every class except key misses and new keys.
+    pub const ALL: NotifyFlags = NotifyFlags(
+        Self::GENERIC.0
+            | Self::STRING.0
+            | Self::LIST.0
+            | Self::SET.0
+            | Self::HASH.0
+            | Self::ZSET.0
+            | Self::EXPIRED.0
+            | Self::EVICTED.0
+            | Self::STREAM.0
+            | Self::MODULE.0,
+    );
+
+    /// The class characters in the order `CONFIG GET` lists them.
+    const CLASSES: [(char, NotifyFlags); 13] = [
+        ('g', Self::GENERIC),
+        ('$', Self::STRING),
+        ('l', Self::LIST),
+        ('s', Self::SET),
+        ('h', Self::HASH),
+        ('z', Self::ZSET),
+        ('x', Self::EXPIRED),
+        ('e', Self::EVICTED),
+        ('t', Self::STREAM),
+        ('d', Self::MODULE),
+        ('K', Self::KEYSPACE),
+        ('E', Self::KEYEVENT),
+        ('m', Self::KEY_MISS),
+    ];
+
+    /// Parse a class string such as `KEA` or `Kgx`, like Redis's
+    /// `keyspaceEventsStringToFlags`. `None` if a character names no class.
+    pub fn parse(classes: &str) -> Option<NotifyFlags> {
+        classes
+            .chars()
+            .try_fold(NotifyFlags::default(), |flags, c| {
+                let class = match c {
+                    'A' => Self::ALL,
+                    'n' => Self::NEW,
+                    c => Self::CLASSES.iter().find(|(name, _)| *name == c)?.1,
+                };
+                Some(flags | class)
+            })
+    }
+
+    pub fn contains(self, other: NotifyFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self, other: NotifyFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl std::ops::BitOr for NotifyFlags {
+    type Output = NotifyFlags;
+
+    fn bitor(self, rhs: NotifyFlags) -> NotifyFlags {
+        NotifyFlags(self.0 | rhs.0)
+    }
+}
+
+/// The canonical class string, with `A` standing for all of its classes.
+impl fmt::Display for NotifyFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let all = self.contains(Self::ALL);
+        if all {
+            f.write_str("A")?;
+        }
+        for (name, class) in Self::CLASSES {
+            if self.contains(class) && !(all && Self::ALL.contains(class)) {
+                write!(f, "{}", name)?;
+            }
+        }
+        if self.contains(Self::NEW) {
+            f.write_str("n")?;
+        }
+        Ok(())
+    }
+}
+
+impl BackendInner {
+    /// Publish `event` for `key` if `class` is enabled, on the keyspace
+    /// channel (the event name as payload) and/or the keyevent channel (the
+    /// key as payload).
+    pub fn notify(&self, class: NotifyFlags, event: &str, key: &[u8]) {
+        let flags = self.config().notify_keyspace_events();
+        if !flags.intersects(class) {
+            return;
+        }
+        if flags.contains(NotifyFlags::KEYSPACE) {
+            let mut channel = BytesMut::with_capacity(15 + key.len());
+            channel.put_slice(b"__keyspace@0__:");
+            channel.put_slice(key);
+            self.pubsub.publish(&channel, event.as_bytes());
+        }
+        if flags.contains(NotifyFlags::KEYEVENT) {
+            let channel = format!("__keyevent@0__:{}", event);
+            self.pubsub.publish(channel.as_bytes(), key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tokio::sync::mpsc::error::TryRecvError;
+
+    use crate::{Backend, BulkString, Client, Config, RespFrame, RespPush};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_flags() {
+        let flags = NotifyFlags::parse("Kx").unwrap();
+        assert!(flags.contains(NotifyFlags::KEYSPACE | NotifyFlags::EXPIRED));
+        assert!(!flags.intersects(NotifyFlags::KEYEVENT));
+        assert_eq!(NotifyFlags::parse("KEA").unwrap().to_string(), "AKE");
+        assert_eq!(NotifyFlags::parse("Eh$gA").unwrap().to_string(), "AE");
+        assert_eq!(NotifyFlags::parse("xKg").unwrap().to_string(), "gxK");
+        assert_eq!(NotifyFlags::parse("nm").unwrap().to_string(), "mn");
+        assert_eq!(NotifyFlags::parse("").unwrap().to_string(), "");
+        assert_eq!(NotifyFlags::parse("KQ"), None);
+    }
+
+    fn message(channel: &str, payload: &str) -> RespFrame {
+        RespPush::new([
+            BulkString::new(Some("message")).into(),
+            BulkString::new(Some(channel)).into(),
+            BulkString::new(Some(payload)).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_notify() {
+        let mut config = Config::default();
+        config
+            .set(&[("notify-keyspace-events".to_string(), "K$".to_string())])
+            .unwrap();
+        let backend = Backend::with_config(config);
+        let mut client = Client::new();
+        let mut inbox = client.subscriptions.take_inbox().unwrap();
+        backend
+            .pubsub()
+            .subscribe(&mut client, Bytes::from("__keyspace@0__:foo"));
+        backend
+            .pubsub()
+            .subscribe(&mut client, Bytes::from("__keyevent@0__:set"));
+
+        backend.notify(NotifyFlags::STRING, "set", b"foo");
+        assert_eq!(inbox.try_recv(), Ok(message("__keyspace@0__:foo", "set")));
+        // hash events and keyevent channels are not enabled
+        backend.notify(NotifyFlags::HASH, "hset", b"foo");
+        assert_eq!(inbox.try_recv(), Err(TryRecvError::Empty));
+    }
+}
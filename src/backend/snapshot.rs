@@ -0,0 +1,234 @@
+use bytes::Bytes;
+
+use std::sync::atomic::Ordering;
+
+use super::{
+    encoding::{frame_bytes, frame_usage, Hash, Set},
+    evict::key_usage,
+    now_ms, BackendInner, NotifyFlags,
+};
+use crate::BulkString;
+
+/// A key's value detached from the backend maps, in the shape it is
+/// serialized to and loaded from RDB files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(Bytes),
+    Hash(Vec<(Bytes, Bytes)>),
+    Set(Vec<Bytes>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Bytes,
+    pub value: Value,
+    /// Absolute expiry time in unix milliseconds.
+    pub expire_at: Option<i64>,
+}
+
+/// Every live key at one point in time.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub entries: Vec<Entry>,
+    /// The backend's change counter when the copy was taken.
+    pub dirty: u64,
+}
+
+impl BackendInner {
+    /// Copy every key that has not expired. Write commands are blocked
+    /// while the copy is taken; values are reference counted so this only
+    /// clones pointers, and the copy can then be serialized without holding
+    /// up clients.
+    pub fn snapshot(&self) -> Snapshot {
+        self.snapshot_and(|| ()).0
+    }
+
+    /// Take a snapshot and run `f` while writes are still blocked, e.g. to
+    /// switch AOF files at exactly the point the copy reflects.
+    pub fn snapshot_and<T>(&self, f: impl FnOnce() -> T) -> (Snapshot, T) {
+        let _guard = self
+            .snapshot_lock
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let now = now_ms();
+        let expire_at = |key: &Bytes| self.expires.get(key).map(|at| *at);
+        let live = |key: &Bytes| expire_at(key).is_none_or(|at| at > now);
+
+        let mut entries = Vec::with_capacity(self.map.len() + self.hmap.len() + self.hset.len());
+        for item in self.map.iter().filter(|item| live(item.key())) {
+            if let Some(value) = frame_bytes(item.value()) {
+                entries.push(Entry {
+                    key: item.key().clone(),
+                    value: Value::String(value),
+                    expire_at: expire_at(item.key()),
+                });
+            }
+        }
+        for item in self.hmap.iter().filter(|item| live(item.key())) {
+            entries.push(Entry {
+                key: item.key().clone(),
+                value: Value::Hash(item.value().pairs()),
+                expire_at: expire_at(item.key()),
+            });
+        }
+        for item in self.hset.iter().filter(|item| live(item.key())) {
+            entries.push(Entry {
+                key: item.key().clone(),
+                value: Value::Set(item.value().members()),
+                expire_at: expire_at(item.key()),
+            });
+        }
+        let snapshot = Snapshot {
+            entries,
+            dirty: self.dirty(),
+        };
+        (snapshot, f())
+    }
+
+    /// Copy a single key, if it exists and has not expired.
+    pub fn get_entry(&self, key: &[u8]) -> Option<Entry> {
+        if !self.exists(key) {
+            self.key_miss(key);
+            return None;
+        }
+        let value = if let Some(value) = self.map.get(key) {
+            Value::String(frame_bytes(value.value())?)
+        } else if let Some(hash) = self.hmap.get(key) {
+            Value::Hash(hash.pairs())
+        } else {
+            Value::Set(self.hset.get(key)?.members())
+        };
+        Some(Entry {
+            key: Bytes::copy_from_slice(key),
+            value,
+            expire_at: self.expires.get(key).map(|at| *at),
+        })
+    }
+
+    /// Store a key recreated by `RESTORE`, replacing whatever it held
+    /// before.
+    pub fn restore_entry(&self, entry: Entry) {
+        self.dirty.fetch_add(1, Ordering::Relaxed);
+        let key = entry.key.clone();
+        self.insert_entry(entry);
+        self.notify(NotifyFlags::NEW, "new", &key);
+    }
+
+    /// Drop every key, before loading a master's dataset.
+    pub fn clear(&self) {
+        self.map.clear();
+        self.hmap.clear();
+        self.hset.clear();
+        self.expires.clear();
+        self.keys.clear();
+        self.used_memory.store(0, Ordering::Relaxed);
+    }
+
+    /// Store a loaded key, replacing whatever it held before.
+    pub fn insert_entry(&self, entry: Entry) {
+        let Entry {
+            key,
+            value,
+            expire_at,
+        } = entry;
+        let _lock = self.key_locks.lock(&key);
+        self.delete(&key);
+        if let Some(at) = expire_at {
+            self.expires.insert(key.clone(), at);
+        }
+        let (limits, lfu) = {
+            let config = self.config();
+            (config.encoding_limits(), config.lfu())
+        };
+        self.keys.write(&key, &lfu);
+        let usage = match value {
+            Value::String(data) => {
+                let value = BulkString::new(Some(data)).into();
+                let usage = frame_usage(&value);
+                self.map.insert(key.clone(), value);
+                usage
+            }
+            Value::Hash(fields) => {
+                let hash = Hash::from_pairs(fields, &limits);
+                let usage = hash.memory_usage();
+                self.hmap.insert(key.clone(), hash);
+                usage
+            }
+            Value::Set(members) => {
+                let set = Set::from_members(members, &limits);
+                let usage = set.memory_usage();
+                self.hset.insert(key.clone(), set);
+                usage
+            }
+        };
+        self.grow(key_usage(&key, usage));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Backend;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("s"), BulkString::new(Some("v")).into());
+        backend
+            .hmset(
+                Bytes::from("h"),
+                vec![Bytes::from("f")],
+                vec![BulkString::new(Some("1")).into()],
+            )
+            .unwrap();
+        backend
+            .sadd(Bytes::from("set"), vec![Bytes::from("a"), Bytes::from("a")])
+            .unwrap();
+        backend.insert_entry(Entry {
+            key: Bytes::from("gone"),
+            value: Value::String(Bytes::from("x")),
+            expire_at: Some(1),
+        });
+        let later = now_ms() + 60_000;
+        backend.insert_entry(Entry {
+            key: Bytes::from("ttl"),
+            value: Value::String(Bytes::from("y")),
+            expire_at: Some(later),
+        });
+
+        let mut snapshot = backend.snapshot();
+        assert_eq!(snapshot.dirty, 3);
+        snapshot.entries.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(
+            snapshot.entries,
+            vec![
+                Entry {
+                    key: Bytes::from("h"),
+                    value: Value::Hash(vec![(Bytes::from("f"), Bytes::from("1"))]),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("s"),
+                    value: Value::String(Bytes::from("v")),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("set"),
+                    value: Value::Set(vec![Bytes::from("a")]),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("ttl"),
+                    value: Value::String(Bytes::from("y")),
+                    expire_at: Some(later),
+                },
+            ]
+        );
+        assert_eq!(backend.get(b"gone").unwrap(), None);
+        assert_eq!(
+            backend.get(b"ttl").unwrap(),
+            Some(BulkString::new(Some("y")).into())
+        );
+    }
+}
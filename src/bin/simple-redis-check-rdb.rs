@@ -0,0 +1,179 @@
+//! Validate an RDB file and summarize what simple-redis would load from
+//! it, in the spirit of `redis-check-rdb`.
+
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    env,
+    fs::File,
+    io::{self, BufReader, Read},
+    process,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use simple_redis::{
+    rdb::{read_records, Object, Record},
+    Value,
+};
+
+/// Counts the bytes read through it, to report where an error was found.
+struct Counting<R> {
+    inner: R,
+    offset: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.offset.set(self.offset.get() + read as u64);
+        Ok(read)
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    keys: u64,
+    expires: u64,
+    already_expired: u64,
+    /// Keys per type, with whether simple-redis can load the type.
+    types: BTreeMap<String, (u64, bool)>,
+    unsupported_keys: Vec<String>,
+    module_aux: u64,
+    functions: u64,
+}
+
+/// Unsupported keys listed by name before the rest are only counted.
+const MAX_LISTED_KEYS: usize = 10;
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Hash(_) => "hash",
+        Value::Set(_) => "set",
+    }
+}
+
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("Usage: simple-redis-check-rdb <rdb-file-name>");
+        process::exit(1);
+    };
+    let offset = Rc::new(Cell::new(0));
+    let at = || format!("[offset {}]", offset.get());
+    println!("{} Checking RDB file {}", at(), path);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!(
+                "Fatal error: can't open the RDB file {} for reading: {}",
+                path, e
+            );
+            process::exit(1);
+        }
+    };
+    let input = Counting {
+        inner: BufReader::new(file),
+        offset: offset.clone(),
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let mut summary = Summary::default();
+    let result = read_records(input, |record| {
+        match record {
+            Record::Aux(key, value) => println!(
+                "{} AUX FIELD {} = '{}'",
+                at(),
+                String::from_utf8_lossy(&key),
+                String::from_utf8_lossy(&value)
+            ),
+            Record::SelectDb(db) => println!("{} Selecting DB ID {}", at(), db),
+            Record::ModuleAux(name) => {
+                println!("{} Skipping aux data of module '{}'", at(), name);
+                summary.module_aux += 1;
+            }
+            Record::Function => {
+                println!("{} Skipping a function library", at());
+                summary.functions += 1;
+            }
+            Record::Key {
+                key,
+                object,
+                expire_at,
+            } => {
+                summary.keys += 1;
+                if let Some(expire_at) = expire_at {
+                    summary.expires += 1;
+                    if expire_at <= now {
+                        summary.already_expired += 1;
+                    }
+                }
+                let (name, supported) = match &object {
+                    Object::Value(value) => (type_name(value).to_string(), true),
+                    Object::Unsupported(kind) => (kind.clone(), false),
+                };
+                if !supported {
+                    summary
+                        .unsupported_keys
+                        .push(String::from_utf8_lossy(&key).into_owned());
+                }
+                summary.types.entry(name).or_insert((0, supported)).0 += 1;
+            }
+        }
+        Ok(())
+    });
+
+    let info = match result {
+        Ok(info) => info,
+        Err(e) => {
+            println!("--- RDB ERROR DETECTED ---");
+            println!("{} {}", at(), e);
+            println!(
+                "[additional info] {} keys read before the error",
+                summary.keys
+            );
+            process::exit(1);
+        }
+    };
+    println!("{} RDB version {}", at(), info.version);
+    match info.checksum {
+        Some(crc) => println!("{} Checksum OK ({:016x})", at(), crc),
+        None => println!(
+            "{} RDB file was saved with checksum disabled: no check performed.",
+            at()
+        ),
+    }
+    println!("{} \\o/ RDB looks OK! \\o/", at());
+    println!("[info] {} keys read", summary.keys);
+    println!("[info] {} expires", summary.expires);
+    println!("[info] {} already expired", summary.already_expired);
+    for (name, (count, supported)) in &summary.types {
+        let note = if *supported {
+            ""
+        } else {
+            " (not supported, skipped when loading)"
+        };
+        println!("[info] {} {} keys{}", count, name, note);
+    }
+    if summary.module_aux > 0 {
+        println!("[info] {} module aux fields skipped", summary.module_aux);
+    }
+    if summary.functions > 0 {
+        println!("[info] {} function libraries skipped", summary.functions);
+    }
+    let unsupported = &summary.unsupported_keys;
+    if !unsupported.is_empty() {
+        let listed = unsupported.iter().take(MAX_LISTED_KEYS);
+        let listed = listed.map(|key| format!("'{}'", key)).collect::<Vec<_>>();
+        let more = unsupported.len().saturating_sub(MAX_LISTED_KEYS);
+        let more = if more > 0 {
+            format!(" and {} more", more)
+        } else {
+            String::new()
+        };
+        println!("[info] unsupported keys: {}{}", listed.join(", "), more);
+    }
+}
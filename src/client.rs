@@ -0,0 +1,49 @@
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use bytes::Bytes;
+
+use crate::{pubsub::Subscriptions, replication::ReplicaHandoff, RespVersion};
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Per-connection state, owned by the connection's stream handler.
+#[derive(Debug)]
+pub struct Client {
+    pub id: u64,
+    pub protocol: RespVersion,
+    pub name: Option<Bytes>,
+    pub addr: Option<SocketAddr>,
+    /// Port a replica accepts connections on, from `REPLCONF
+    /// listening-port`.
+    pub listening_port: Option<u16>,
+    /// Set by `PSYNC`; the connection becomes a replica link once the
+    /// reply has been written.
+    pub replica: Option<ReplicaHandoff>,
+    /// Set by `ASKING`, for the next command only.
+    pub asking: bool,
+    pub subscriptions: Subscriptions,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Client {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            protocol: RespVersion::default(),
+            name: None,
+            addr: None,
+            listening_port: None,
+            replica: None,
+            asking: false,
+            subscriptions: Subscriptions::new(),
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
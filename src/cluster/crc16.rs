@@ -0,0 +1,42 @@
+//! CRC16-CCITT (XMODEM), the checksum Redis Cluster maps keys to slots
+//! with: polynomial 0x1021, initial value 0, no reflection.
+
+const TABLE: [u16; 256] = make_table();
+
+const fn make_table() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, byte| {
+        (crc << 8) ^ TABLE[(((crc >> 8) as u8) ^ byte) as usize]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc16() {
+        // the check value of the XMODEM variant, as in Redis's crc16.c
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(crc16(b""), 0);
+    }
+}
@@ -0,0 +1,677 @@
+//! Cluster mode with a static topology: keys map to one of 16384 hash
+//! slots, and requests for slots served by another node are redirected
+//! with `MOVED` or `ASK`. The topology is read at startup from
+//! `cluster-config-file`, in the format of Redis's `nodes.conf`.
+
+mod crc16;
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use thiserror::Error;
+
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, SimpleString};
+
+use self::crc16::crc16;
+
+pub const CLUSTER_SLOTS: usize = 16384;
+
+/// The hash slot of `key`. When the key has a non-empty `{...}` section
+/// only that part is hashed, so related keys can be put in the same slot.
+pub fn key_slot(key: &[u8]) -> u16 {
+    let key = hash_tag(key).unwrap_or(key);
+    crc16(key) & (CLUSTER_SLOTS as u16 - 1)
+}
+
+fn hash_tag(key: &[u8]) -> Option<&[u8]> {
+    let open = key.iter().position(|b| *b == b'{')?;
+    let tag = &key[open + 1..];
+    let close = tag.iter().position(|b| *b == b'}')?;
+    (close > 0).then(|| &tag[..close])
+}
+
+#[derive(Error, Debug)]
+pub enum ClusterError {
+    #[error("Can't open the cluster config file '{}': {1}", .0.display())]
+    Open(PathBuf, io::Error),
+    #[error("Unrecoverable error: corrupted cluster config file, line {line}: {reason}")]
+    Corrupt { line: usize, reason: String },
+    #[error(
+        "No node in the cluster config file is flagged myself or listens on port {0} of this host"
+    )]
+    NoMyself(u16),
+    #[error("Several nodes in the cluster config file may be this one on port {0}; flag one of them myself")]
+    AmbiguousMyself(u16),
+}
+
+/// A line of the topology file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub ip: String,
+    pub port: u16,
+    pub cport: u16,
+    /// Flags as written in the file, without `myself`, which only picks the
+    /// node this process is.
+    pub flags: Vec<String>,
+    /// ID of the master, for replicas.
+    pub master: Option<String>,
+    pub config_epoch: u64,
+    /// Inclusive slot ranges the node serves.
+    pub slots: Vec<(u16, u16)>,
+    /// `[slot->-id]`: slots being moved to the node with that ID.
+    pub migrating: Vec<(u16, String)>,
+    /// `[slot-<-id]`: slots being moved here from the node with that ID.
+    pub importing: Vec<(u16, String)>,
+}
+
+impl Node {
+    fn is_replica(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|flag| flag == "slave" || flag == "replica")
+    }
+
+    fn addr(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+fn parse_slot(slot: &str) -> Result<u16, String> {
+    slot.parse()
+        .ok()
+        .filter(|slot| (*slot as usize) < CLUSTER_SLOTS)
+        .ok_or_else(|| format!("invalid slot '{}'", slot))
+}
+
+/// `<id> <ip:port@cport> <flags> <master> <ping-sent> <pong-recv>
+/// <config-epoch> <link-state> <slot> ...`, and whether the node is flagged
+/// `myself`.
+fn parse_node(line: &str) -> Result<(Node, bool), String> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if fields.len() < 8 {
+        return Err("too few fields".to_string());
+    }
+    // a hostname may follow the address after a comma
+    let addr = fields[1].split(',').next().unwrap_or_default();
+    let (addr, cport) = addr
+        .split_once('@')
+        .ok_or_else(|| format!("invalid address '{}'", fields[1]))?;
+    let (ip, port) = addr
+        .rsplit_once(':')
+        .ok_or_else(|| format!("invalid address '{}'", fields[1]))?;
+    let port = port
+        .parse()
+        .map_err(|_| format!("invalid port '{}'", port))?;
+    let cport = cport
+        .parse()
+        .map_err(|_| format!("invalid cluster bus port '{}'", cport))?;
+    let config_epoch = fields[6]
+        .parse()
+        .map_err(|_| format!("invalid config epoch '{}'", fields[6]))?;
+
+    let myself = fields[2].split(',').any(|flag| flag == "myself");
+    let mut node = Node {
+        id: fields[0].to_string(),
+        ip: ip.to_string(),
+        port,
+        cport,
+        flags: fields[2]
+            .split(',')
+            .filter(|flag| !matches!(*flag, "myself" | "noflags"))
+            .map(str::to_string)
+            .collect(),
+        master: (fields[3] != "-").then(|| fields[3].to_string()),
+        config_epoch,
+        slots: Vec::new(),
+        migrating: Vec::new(),
+        importing: Vec::new(),
+    };
+    for slot in &fields[8..] {
+        if let Some(state) = slot.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            if let Some((slot, id)) = state.split_once("->-") {
+                node.migrating.push((parse_slot(slot)?, id.to_string()));
+            } else if let Some((slot, id)) = state.split_once("-<-") {
+                node.importing.push((parse_slot(slot)?, id.to_string()));
+            } else {
+                return Err(format!("invalid slot state '{}'", slot));
+            }
+        } else if let Some((start, end)) = slot.split_once('-') {
+            let (start, end) = (parse_slot(start)?, parse_slot(end)?);
+            if start > end {
+                return Err(format!("invalid slot range '{}'", slot));
+            }
+            node.slots.push((start, end));
+        } else {
+            let slot = parse_slot(slot)?;
+            node.slots.push((slot, slot));
+        }
+    }
+    Ok((node, myself))
+}
+
+/// Whether a server bound to `bind` accepts connections on `ip`.
+fn listens_on(bind: &[&str], ip: &str) -> bool {
+    bind.iter()
+        .any(|addr| matches!(*addr, "0.0.0.0" | "::") || *addr == ip)
+}
+
+#[derive(Debug)]
+pub struct Cluster {
+    nodes: Vec<Node>,
+    /// Index of this node in `nodes`.
+    myself: usize,
+    /// Index of the node serving each slot.
+    owners: Vec<Option<usize>>,
+    /// Slots this node is moving out, by the index of the target.
+    migrating: HashMap<u16, usize>,
+    /// Slots this node is taking over, by the index of the source.
+    importing: HashMap<u16, usize>,
+}
+
+impl Cluster {
+    /// Read the topology from `path`. This node is the one flagged
+    /// `myself`, or else the only one on `port` of an address in `bind`.
+    pub fn load(path: &Path, bind: &[&str], port: u16) -> Result<Self, ClusterError> {
+        let text =
+            fs::read_to_string(path).map_err(|e| ClusterError::Open(path.to_path_buf(), e))?;
+        Self::parse(&text, bind, port)
+    }
+
+    pub fn parse(text: &str, bind: &[&str], port: u16) -> Result<Self, ClusterError> {
+        let mut nodes = Vec::new();
+        let mut lines = Vec::new();
+        let mut flagged = None;
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            // `vars` holds the epochs Redis persists, not a node
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("vars ") {
+                continue;
+            }
+            let (node, myself) = parse_node(trimmed).map_err(|reason| ClusterError::Corrupt {
+                line: i + 1,
+                reason,
+            })?;
+            if myself && flagged.replace(nodes.len()).is_some() {
+                return Err(ClusterError::Corrupt {
+                    line: i + 1,
+                    reason: "more than one node is flagged myself".to_string(),
+                });
+            }
+            nodes.push(node);
+            lines.push(i + 1);
+        }
+
+        let corrupt = |index: usize, reason: String| ClusterError::Corrupt {
+            line: lines[index],
+            reason,
+        };
+        let find = |id: &str| nodes.iter().position(|node| node.id == id);
+        let mut owners = vec![None; CLUSTER_SLOTS];
+        for (index, node) in nodes.iter().enumerate() {
+            if find(&node.id) != Some(index) {
+                return Err(corrupt(index, format!("duplicate node ID {}", node.id)));
+            }
+            if let Some(master) = &node.master {
+                if find(master).is_none() {
+                    return Err(corrupt(index, format!("unknown master {}", master)));
+                }
+            }
+            for &(start, end) in &node.slots {
+                for slot in start..=end {
+                    if owners[slot as usize].replace(index).is_some() {
+                        return Err(corrupt(index, format!("slot {} is already assigned", slot)));
+                    }
+                }
+            }
+        }
+
+        let myself = match flagged {
+            Some(index) => index,
+            None => {
+                let mut candidates = nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, node)| node.port == port && listens_on(bind, &node.ip))
+                    .map(|(index, _)| index);
+                let index = candidates.next().ok_or(ClusterError::NoMyself(port))?;
+                if candidates.next().is_some() {
+                    return Err(ClusterError::AmbiguousMyself(port));
+                }
+                index
+            }
+        };
+        let states = |list: &[(u16, String)]| {
+            list.iter()
+                .map(|(slot, id)| match find(id) {
+                    Some(index) => Ok((*slot, index)),
+                    None => Err(corrupt(myself, format!("unknown node {}", id))),
+                })
+                .collect::<Result<HashMap<_, _>, _>>()
+        };
+        let migrating = states(&nodes[myself].migrating)?;
+        let importing = states(&nodes[myself].importing)?;
+        Ok(Cluster {
+            nodes,
+            myself,
+            owners,
+            migrating,
+            importing,
+        })
+    }
+
+    pub fn myself(&self) -> &Node {
+        &self.nodes[self.myself]
+    }
+
+    /// Check that this node serves the keys of a request. The error is the
+    /// reply sending the client elsewhere: `MOVED` to the slot's owner, or
+    /// `ASK` for keys already moved out of a migrating slot. `asking` is
+    /// set after `ASKING`, which lets a client reach a slot being imported;
+    /// `exists` tells whether a key is stored here.
+    pub fn check_keys(
+        &self,
+        keys: &[Bytes],
+        asking: bool,
+        exists: impl Fn(&[u8]) -> bool,
+    ) -> Result<(), String> {
+        let Some(slot) = keys.first().map(|key| key_slot(key)) else {
+            return Ok(());
+        };
+        if keys.iter().any(|key| key_slot(key) != slot) {
+            return Err("CROSSSLOT Keys in request don't hash to the same slot".to_string());
+        }
+        let missing = || keys.iter().filter(|key| !exists(key)).count();
+        let owner = self.owners[slot as usize];
+        if owner == Some(self.myself) {
+            let Some(target) = self.migrating.get(&slot) else {
+                return Ok(());
+            };
+            return match missing() {
+                0 => Ok(()),
+                n if n == keys.len() => Err(format!("ASK {} {}", slot, self.nodes[*target].addr())),
+                _ => Err("TRYAGAIN Multiple keys request during rehashing of slot".to_string()),
+            };
+        }
+        if asking && self.importing.contains_key(&slot) {
+            if keys.len() > 1 && missing() > 0 {
+                return Err("TRYAGAIN Multiple keys request during rehashing of slot".to_string());
+            }
+            return Ok(());
+        }
+        match owner {
+            Some(owner) => Err(format!("MOVED {} {}", slot, self.nodes[owner].addr())),
+            None => Err("CLUSTERDOWN Hash slot not served".to_string()),
+        }
+    }
+
+    /// Runs of consecutive slots served by the same node, as `(start, end,
+    /// node index)`.
+    fn slot_ranges(&self) -> Vec<(u16, u16, usize)> {
+        let mut ranges: Vec<(u16, u16, usize)> = Vec::new();
+        for (slot, owner) in self.owners.iter().enumerate() {
+            let Some(owner) = *owner else {
+                continue;
+            };
+            match ranges.last_mut() {
+                Some((_, end, last)) if *last == owner && *end as usize + 1 == slot => {
+                    *end = slot as u16;
+                }
+                _ => ranges.push((slot as u16, slot as u16, owner)),
+            }
+        }
+        ranges
+    }
+
+    fn replicas_of(&self, index: usize) -> impl Iterator<Item = &Node> {
+        let id = &self.nodes[index].id;
+        self.nodes
+            .iter()
+            .filter(move |node| node.master.as_ref() == Some(id))
+    }
+
+    /// The body of `CLUSTER INFO`.
+    pub fn info(&self) -> String {
+        let assigned = self.owners.iter().filter(|owner| owner.is_some()).count();
+        let state = if assigned == CLUSTER_SLOTS {
+            "ok"
+        } else {
+            "fail"
+        };
+        let size = self
+            .nodes
+            .iter()
+            .filter(|node| !node.is_replica() && !node.slots.is_empty())
+            .count();
+        let current_epoch = self.nodes.iter().map(|node| node.config_epoch).max();
+        let myself = self.myself();
+        let my_epoch = match &myself.master {
+            Some(master) => self
+                .nodes
+                .iter()
+                .find(|node| &node.id == master)
+                .map_or(0, |node| node.config_epoch),
+            None => myself.config_epoch,
+        };
+        let mut info = String::new();
+        for (name, value) in [
+            ("cluster_state", state.to_string()),
+            ("cluster_slots_assigned", assigned.to_string()),
+            ("cluster_slots_ok", assigned.to_string()),
+            ("cluster_slots_pfail", "0".to_string()),
+            ("cluster_slots_fail", "0".to_string()),
+            ("cluster_known_nodes", self.nodes.len().to_string()),
+            ("cluster_size", size.to_string()),
+            (
+                "cluster_current_epoch",
+                current_epoch.unwrap_or_default().to_string(),
+            ),
+            ("cluster_my_epoch", my_epoch.to_string()),
+            ("cluster_stats_messages_sent", "0".to_string()),
+            ("cluster_stats_messages_received", "0".to_string()),
+            ("total_cluster_links_buffer_limit_exceeded", "0".to_string()),
+        ] {
+            write!(info, "{}:{}\r\n", name, value).expect("write to string");
+        }
+        info
+    }
+
+    /// The body of `CLUSTER NODES`, one line per node.
+    pub fn nodes(&self) -> String {
+        let mut out = String::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut flags = node.flags.clone();
+            if index == self.myself {
+                flags.insert(0, "myself".to_string());
+            }
+            let flags = if flags.is_empty() {
+                "noflags".to_string()
+            } else {
+                flags.join(",")
+            };
+            write!(
+                out,
+                "{} {}:{}@{} {} {} 0 0 {} connected",
+                node.id,
+                node.ip,
+                node.port,
+                node.cport,
+                flags,
+                node.master.as_deref().unwrap_or("-"),
+                node.config_epoch
+            )
+            .expect("write to string");
+            for &(start, end) in &node.slots {
+                if start == end {
+                    write!(out, " {}", start).expect("write to string");
+                } else {
+                    write!(out, " {}-{}", start, end).expect("write to string");
+                }
+            }
+            if index == self.myself {
+                for (slot, id) in &node.migrating {
+                    write!(out, " [{}->-{}]", slot, id).expect("write to string");
+                }
+                for (slot, id) in &node.importing {
+                    write!(out, " [{}-<-{}]", slot, id).expect("write to string");
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The reply of `CLUSTER SLOTS`: each range of slots with its master
+    /// and replicas.
+    pub fn slots(&self) -> RespFrame {
+        let node_frame = |node: &Node| -> RespFrame {
+            RespArray::new(Some(vec![
+                BulkString::new(Some(node.ip.clone())).into(),
+                RespFrame::Integer(node.port as i64),
+                BulkString::new(Some(node.id.clone())).into(),
+                RespMap::new().into(),
+            ]))
+            .into()
+        };
+        let ranges = self
+            .slot_ranges()
+            .into_iter()
+            .map(|(start, end, owner)| {
+                let mut frames = vec![
+                    RespFrame::Integer(start as i64),
+                    RespFrame::Integer(end as i64),
+                    node_frame(&self.nodes[owner]),
+                ];
+                frames.extend(self.replicas_of(owner).map(node_frame));
+                RespArray::new(Some(frames)).into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(Some(ranges)).into()
+    }
+
+    /// The reply of `CLUSTER SHARDS`: every master with its slot ranges and
+    /// the nodes of its shard. `offset` is this node's replication offset.
+    pub fn shards(&self, offset: i64) -> RespFrame {
+        let bulk = |value: &str| RespFrame::from(BulkString::new(Some(value.to_string())));
+        let node_frame = |index: usize, node: &Node| -> RespFrame {
+            let mut map = RespMap::new();
+            map.insert(SimpleString::new("id"), bulk(&node.id));
+            map.insert(
+                SimpleString::new("port"),
+                RespFrame::Integer(node.port as i64),
+            );
+            map.insert(SimpleString::new("ip"), bulk(&node.ip));
+            map.insert(SimpleString::new("endpoint"), bulk(&node.ip));
+            let role = if node.is_replica() {
+                "replica"
+            } else {
+                "master"
+            };
+            map.insert(SimpleString::new("role"), bulk(role));
+            let offset = if index == self.myself { offset } else { 0 };
+            map.insert(
+                SimpleString::new("replication-offset"),
+                RespFrame::Integer(offset),
+            );
+            map.insert(SimpleString::new("health"), bulk("online"));
+            map.into()
+        };
+        let ranges = self.slot_ranges();
+        let shards = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.is_replica())
+            .map(|(index, node)| {
+                let slots = ranges
+                    .iter()
+                    .filter(|(_, _, owner)| *owner == index)
+                    .flat_map(|(start, end, _)| {
+                        [
+                            RespFrame::Integer(*start as i64),
+                            RespFrame::Integer(*end as i64),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                let mut nodes = vec![node_frame(index, node)];
+                nodes.extend(
+                    self.nodes
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, replica)| replica.master.as_ref() == Some(&node.id))
+                        .map(|(index, replica)| node_frame(index, replica)),
+                );
+                let mut shard = RespMap::new();
+                shard.insert(
+                    SimpleString::new("slots"),
+                    RespArray::new(Some(slots)).into(),
+                );
+                shard.insert(
+                    SimpleString::new("nodes"),
+                    RespArray::new(Some(nodes)).into(),
+                );
+                shard.into()
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(Some(shards)).into()
+    }
+}
+
+/// Enable cluster mode with the topology in `cluster-config-file`.
+pub fn load(backend: &Backend) -> Result<(), ClusterError> {
+    let (path, bind, port) = {
+        let config = backend.config();
+        let bind = config
+            .bind_addresses()
+            .into_iter()
+            .map(|(addr, _)| addr)
+            .collect::<Vec<_>>();
+        (config.cluster_config_path(), bind, config.port())
+    };
+    let bind = bind.iter().map(String::as_str).collect::<Vec<_>>();
+    let cluster = Cluster::load(&path, &bind, port)?;
+    backend.set_cluster(cluster);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOPOLOGY: &str = "\
+aaaa 127.0.0.1:7000@17000 master - 0 0 1 connected 0-5460 [5000->-bbbb]
+bbbb 127.0.0.1:7001@17001 master - 0 0 2 connected 5461-10922
+cccc 127.0.0.1:7002@17002 master - 0 0 3 connected 10923-16383
+dddd 127.0.0.1:7003@17003 slave aaaa 0 0 1 connected
+vars currentEpoch 3 lastVoteEpoch 0
+";
+
+    const BIND: &[&str] = &["127.0.0.1"];
+
+    fn keys(keys: &[&str]) -> Vec<Bytes> {
+        keys.iter()
+            .map(|key| Bytes::from(key.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_key_slot() {
+        assert_eq!(key_slot(b"123456789"), 12739);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+        // an empty tag hashes the whole key
+        assert_eq!(key_slot(b"foo{}{bar}"), crc16(b"foo{}{bar}") & 0x3fff);
+        assert_eq!(key_slot(b"foo{{bar}}zap"), key_slot(b"{bar"));
+    }
+
+    #[test]
+    fn test_parse_topology() {
+        let cluster = Cluster::parse(TOPOLOGY, BIND, 7001).unwrap();
+        assert_eq!(cluster.myself().id, "bbbb");
+        assert_eq!(
+            cluster.slot_ranges(),
+            vec![(0, 5460, 0), (5461, 10922, 1), (10923, 16383, 2)]
+        );
+        // `myself` moves with the port
+        assert!(cluster.nodes().starts_with(
+            "aaaa 127.0.0.1:7000@17000 master - 0 0 1 connected 0-5460\n\
+             bbbb 127.0.0.1:7001@17001 myself,master - 0 0 2 connected 5461-10922\n"
+        ));
+        assert!(cluster.info().starts_with("cluster_state:ok\r\n"));
+
+        // the `myself` flag wins over the port
+        let flagged = TOPOLOGY.replace("7002@17002 master", "7002@17002 myself,master");
+        assert_eq!(
+            Cluster::parse(&flagged, BIND, 7001).unwrap().myself().id,
+            "cccc"
+        );
+        let twice = flagged.replace("7001@17001 master", "7001@17001 myself,master");
+        assert_eq!(
+            Cluster::parse(&twice, BIND, 7001).unwrap_err().to_string(),
+            "Unrecoverable error: corrupted cluster config file, line 3: more than one node is flagged myself"
+        );
+
+        // without the flag both the host and the port have to match
+        assert!(matches!(
+            Cluster::parse(TOPOLOGY, &["10.0.0.1"], 7001),
+            Err(ClusterError::NoMyself(7001))
+        ));
+        let hosts = "aaaa 10.0.0.1:7000@17000 master - 0 0 1 connected 0-8191\n\
+                     bbbb 10.0.0.2:7000@17000 master - 0 0 2 connected 8192-16383\n";
+        assert_eq!(
+            Cluster::parse(hosts, &["10.0.0.2"], 7000)
+                .unwrap()
+                .myself()
+                .id,
+            "bbbb"
+        );
+        assert!(matches!(
+            Cluster::parse(hosts, &["0.0.0.0"], 7000),
+            Err(ClusterError::AmbiguousMyself(7000))
+        ));
+
+        assert!(matches!(
+            Cluster::parse(TOPOLOGY, BIND, 6379),
+            Err(ClusterError::NoMyself(6379))
+        ));
+        let overlap = "aaaa 127.0.0.1:7000@17000 master - 0 0 1 connected 0-10\n\
+                       bbbb 127.0.0.1:7001@17001 master - 0 0 1 connected 10\n";
+        assert_eq!(
+            Cluster::parse(overlap, BIND, 7000).unwrap_err().to_string(),
+            "Unrecoverable error: corrupted cluster config file, line 2: slot 10 is already assigned"
+        );
+        assert!(
+            Cluster::parse("aaaa 127.0.0.1:7000 master - 0 0 1 connected", BIND, 7000).is_err()
+        );
+    }
+
+    #[test]
+    fn test_check_keys() {
+        let cluster = Cluster::parse(TOPOLOGY, BIND, 7000).unwrap();
+        let none = |_: &[u8]| false;
+        let all = |_: &[u8]| true;
+        // slot 12182
+        assert_eq!(
+            cluster.check_keys(&keys(&["foo"]), false, all),
+            Err("MOVED 12182 127.0.0.1:7002".to_string())
+        );
+        // slot 5061
+        assert_eq!(cluster.check_keys(&keys(&["bar"]), false, none), Ok(()));
+        assert_eq!(
+            cluster.check_keys(&keys(&["foo", "bar"]), false, all),
+            Err("CROSSSLOT Keys in request don't hash to the same slot".to_string())
+        );
+        assert_eq!(cluster.check_keys(&[], false, none), Ok(()));
+
+        // slot 5000 is migrating to bbbb
+        let key = (0..)
+            .map(|i| format!("key:{}", i))
+            .find(|key| key_slot(key.as_bytes()) == 5000)
+            .unwrap();
+        assert_eq!(cluster.check_keys(&keys(&[&key]), false, all), Ok(()));
+        assert_eq!(
+            cluster.check_keys(&keys(&[&key]), false, none),
+            Err("ASK 5000 127.0.0.1:7001".to_string())
+        );
+
+        let importing = TOPOLOGY.replace("5461-10922", "5461-10922 [5000-<-aaaa]");
+        let cluster = Cluster::parse(&importing, BIND, 7001).unwrap();
+        assert_eq!(
+            cluster.check_keys(&keys(&[&key]), false, none),
+            Err("MOVED 5000 127.0.0.1:7000".to_string())
+        );
+        assert_eq!(cluster.check_keys(&keys(&[&key]), true, none), Ok(()));
+
+        // a replica sends clients to its master
+        let cluster = Cluster::parse(TOPOLOGY, BIND, 7003).unwrap();
+        assert_eq!(
+            cluster.check_keys(&keys(&["bar"]), false, all),
+            Err("MOVED 5061 127.0.0.1:7000".to_string())
+        );
+    }
+}
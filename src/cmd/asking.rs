@@ -0,0 +1,40 @@
+use crate::{Backend, Client, RespFrame, SimpleError};
+
+use super::{cluster::CLUSTER_DISABLED, CommandError, CommandExecutor, RET_OK};
+
+/// `ASKING`, sent by a cluster client before retrying a command at the node
+/// an `ASK` redirect named.
+#[derive(Debug, PartialEq)]
+pub struct Asking;
+
+impl CommandExecutor for Asking {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.cluster().is_none() {
+            return SimpleError::new(CLUSTER_DISABLED).into();
+        }
+        RET_OK.clone()
+    }
+}
+
+impl Asking {
+    pub fn new() -> Self {
+        Asking
+    }
+
+    /// Let the client's next command reach a slot this node is importing.
+    pub fn apply(self, backend: &Backend, client: &mut Client) -> RespFrame {
+        if backend.cluster().is_none() {
+            return SimpleError::new(CLUSTER_DISABLED).into();
+        }
+        client.asking = true;
+        RET_OK.clone()
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Asking {
+    type Error = CommandError;
+
+    fn try_from(_value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(Asking::new())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{aof, Backend, RespFrame, SimpleError, SimpleString};
+
+use super::{CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct BgRewriteAof;
+
+impl CommandExecutor for BgRewriteAof {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match aof::bgrewrite(backend) {
+            Ok(()) => SimpleString::new("Background append only file rewriting started").into(),
+            Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for BgRewriteAof {
+    type Error = CommandError;
+
+    fn try_from(_value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(BgRewriteAof::new())
+    }
+}
+
+impl BgRewriteAof {
+    pub fn new() -> Self {
+        BgRewriteAof
+    }
+}
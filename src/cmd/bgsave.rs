@@ -0,0 +1,71 @@
+use crate::{rdb, Backend, BulkString, RespFrame, SimpleError, SimpleString};
+
+use super::{CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct BgSave {
+    /// `SCHEDULE`: if a save is already running, start another one after it
+    /// instead of failing.
+    schedule: bool,
+}
+
+impl CommandExecutor for BgSave {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.schedule && backend.save_state().in_progress() {
+            backend.save_state().schedule();
+            return SimpleString::new("Background saving scheduled").into();
+        }
+        match rdb::bgsave(backend) {
+            Ok(()) => SimpleString::new("Background saving started").into(),
+            Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for BgSave {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        match value.as_slice() {
+            [] => Ok(BgSave::new(false)),
+            [RespFrame::BulkString(BulkString(Some(arg)))]
+                if arg.eq_ignore_ascii_case(b"schedule") =>
+            {
+                Ok(BgSave::new(true))
+            }
+            _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+        }
+    }
+}
+
+impl BgSave {
+    pub fn new(schedule: bool) -> Self {
+        BgSave { schedule }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cmd::Command, RespArray, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_bgsave_try_from() {
+        let mut buf = BytesMut::from(b"*2\r\n$6\r\nbgsave\r\n$8\r\nSCHEDULE\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        assert_eq!(
+            Command::try_from(array).unwrap(),
+            Command::BgSave(BgSave::new(true))
+        );
+
+        let mut buf = BytesMut::from(b"*2\r\n$6\r\nbgsave\r\n$3\r\nnow\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        assert_eq!(
+            Command::try_from(array).unwrap_err().to_string(),
+            "ERR syntax error"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use bytes::Bytes;
+
+use crate::{
+    cluster::{key_slot, CLUSTER_SLOTS},
+    Backend, BulkString, RespArray, RespFrame, SimpleError, VerbatimString,
+};
+
+use super::{extract_bytes, extract_string, CommandError, CommandExecutor};
+
+pub(super) const CLUSTER_DISABLED: &str = "ERR This instance has cluster support disabled";
+
+#[derive(Debug, PartialEq)]
+pub struct ClusterCmd {
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    Info,
+    MyId,
+    Nodes,
+    Slots,
+    Shards,
+    KeySlot(Bytes),
+    CountKeysInSlot(u16),
+    GetKeysInSlot(u16, usize),
+}
+
+impl CommandExecutor for ClusterCmd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(cluster) = backend.cluster() else {
+            return SimpleError::new(CLUSTER_DISABLED).into();
+        };
+        match self.subcommand {
+            Subcommand::Info => VerbatimString::new(*b"txt", cluster.info()).into(),
+            Subcommand::MyId => BulkString::new(Some(cluster.myself().id.clone())).into(),
+            Subcommand::Nodes => VerbatimString::new(*b"txt", cluster.nodes()).into(),
+            Subcommand::Slots => cluster.slots(),
+            Subcommand::Shards => cluster.shards(backend.replication().offset()),
+            Subcommand::KeySlot(key) => RespFrame::Integer(key_slot(&key) as i64),
+            Subcommand::CountKeysInSlot(slot) => {
+                RespFrame::Integer(backend.keys_in_slot(slot, usize::MAX).len() as i64)
+            }
+            Subcommand::GetKeysInSlot(slot, count) => {
+                let keys = backend
+                    .keys_in_slot(slot, count)
+                    .into_iter()
+                    .map(|key| BulkString::from(key).into())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(Some(keys)).into()
+            }
+        }
+    }
+}
+
+fn parse_slot(arg: Option<RespFrame>) -> Result<u16, CommandError> {
+    extract_string(arg)?
+        .parse::<u16>()
+        .ok()
+        .filter(|slot| (*slot as usize) < CLUSTER_SLOTS)
+        .ok_or_else(|| CommandError::InvalidArgument("Invalid or out of range slot".to_string()))
+}
+
+impl TryFrom<Vec<RespFrame>> for ClusterCmd {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let subcommand = extract_string(args.next())?.to_ascii_lowercase();
+        let subcommand = match (subcommand.as_str(), args.len()) {
+            ("info", 0) => Subcommand::Info,
+            ("myid", 0) => Subcommand::MyId,
+            ("nodes", 0) => Subcommand::Nodes,
+            ("slots", 0) => Subcommand::Slots,
+            ("shards", 0) => Subcommand::Shards,
+            ("keyslot", 1) => Subcommand::KeySlot(extract_bytes(args.next())?),
+            ("countkeysinslot", 1) => Subcommand::CountKeysInSlot(parse_slot(args.next())?),
+            ("getkeysinslot", 2) => {
+                let slot = parse_slot(args.next())?;
+                let count = extract_string(args.next())?.parse::<i64>().map_err(|_| {
+                    CommandError::InvalidArgument(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                })?;
+                let count = usize::try_from(count).map_err(|_| {
+                    CommandError::InvalidArgument("Invalid number of keys".to_string())
+                })?;
+                Subcommand::GetKeysInSlot(slot, count)
+            }
+            (
+                "info" | "myid" | "nodes" | "slots" | "shards" | "keyslot" | "countkeysinslot"
+                | "getkeysinslot",
+                _,
+            ) => return Err(CommandError::WrongArity(format!("cluster|{}", subcommand))),
+            _ => {
+                return Err(CommandError::UnknownSubcommand(
+                    subcommand,
+                    "CLUSTER".to_string(),
+                ))
+            }
+        };
+        Ok(ClusterCmd::new(subcommand))
+    }
+}
+
+impl ClusterCmd {
+    pub fn new(subcommand: Subcommand) -> Self {
+        ClusterCmd { subcommand }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cluster::Cluster, cmd::Command};
+
+    use super::*;
+
+    fn cluster(args: &[&str]) -> Result<Command, CommandError> {
+        let frames = std::iter::once("cluster")
+            .chain(args.iter().copied())
+            .map(|arg| BulkString::new(Some(arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    fn bulk(value: &str) -> RespFrame {
+        BulkString::new(Some(value)).into()
+    }
+
+    #[test]
+    fn test_cluster_disabled() {
+        let backend = Backend::new();
+        assert_eq!(
+            cluster(&["keyslot", "foo"]).unwrap().execute(&backend),
+            SimpleError::new(CLUSTER_DISABLED).into()
+        );
+    }
+
+    #[test]
+    fn test_cluster_try_from() {
+        assert_eq!(
+            cluster(&["KEYSLOT", "foo"]).unwrap(),
+            ClusterCmd::new(Subcommand::KeySlot(Bytes::from("foo"))).into()
+        );
+        assert_eq!(
+            cluster(&["countkeysinslot", "16384"])
+                .unwrap_err()
+                .to_string(),
+            "ERR Invalid or out of range slot"
+        );
+        assert_eq!(
+            cluster(&["getkeysinslot", "1", "-1"])
+                .unwrap_err()
+                .to_string(),
+            "ERR Invalid number of keys"
+        );
+        assert_eq!(
+            cluster(&["meet"]).unwrap_err().to_string(),
+            "ERR unknown subcommand 'meet'. Try CLUSTER HELP."
+        );
+        assert_eq!(
+            cluster(&["slots", "x"]).unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'cluster|slots' command"
+        );
+    }
+
+    #[test]
+    fn test_cluster_keys_in_slot() {
+        let backend = Backend::new();
+        let topology = "aaaa 127.0.0.1:7000@17000 master - 0 0 1 connected 0-16383\n";
+        backend.set_cluster(Cluster::parse(topology, &["127.0.0.1"], 7000).unwrap());
+        for key in ["{user}:1", "{user}:2", "other"] {
+            backend.set(Bytes::from(key), bulk("v"));
+        }
+        let slot = key_slot(b"user").to_string();
+
+        assert_eq!(
+            cluster(&["keyslot", "{user}:1"]).unwrap().execute(&backend),
+            RespFrame::Integer(key_slot(b"user") as i64)
+        );
+        assert_eq!(
+            cluster(&["countkeysinslot", &slot])
+                .unwrap()
+                .execute(&backend),
+            RespFrame::Integer(2)
+        );
+        let RespFrame::Array(RespArray(Some(keys))) = cluster(&["getkeysinslot", &slot, "1"])
+            .unwrap()
+            .execute(&backend)
+        else {
+            panic!("GETKEYSINSLOT should reply with an array");
+        };
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0] == bulk("{user}:1") || keys[0] == bulk("{user}:2"));
+        assert_eq!(cluster(&["myid"]).unwrap().execute(&backend), bulk("aaaa"));
+    }
+}
@@ -0,0 +1,367 @@
+use bytes::Bytes;
+
+use crate::{
+    glob::glob_match, Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError, SimpleString,
+};
+
+use super::{
+    extract_bytes, extract_string,
+    spec::{all_commands, lookup_command, lookup_request, COMMAND_TABLE},
+    CommandError, CommandExecutor, RET_NULL,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandCmd {
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    All,
+    Count,
+    Info(Vec<String>),
+    Docs(Vec<String>),
+    GetKeys(Vec<Bytes>),
+    List(Option<ListFilter>),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ListFilter {
+    Module(String),
+    AclCat(String),
+    Pattern(String),
+}
+
+impl CommandExecutor for CommandCmd {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.subcommand {
+            Subcommand::All => all_infos(),
+            Subcommand::Count => RespFrame::Integer(COMMAND_TABLE.len() as i64),
+            Subcommand::Info(names) if names.is_empty() => all_infos(),
+            Subcommand::Info(names) => RespArray::new(Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        lookup_command(name.as_bytes())
+                            .map(|spec| spec.info_frame())
+                            .unwrap_or(RET_NULL.clone())
+                    })
+                    .collect::<Vec<_>>(),
+            ))
+            .into(),
+            Subcommand::Docs(names) => {
+                let mut docs = RespMap::new();
+                if names.is_empty() {
+                    COMMAND_TABLE.iter().for_each(|spec| {
+                        docs.insert(SimpleString::new(spec.name), spec.docs_frame());
+                    });
+                } else {
+                    names
+                        .iter()
+                        .filter_map(|name| lookup_command(name.as_bytes()))
+                        .for_each(|spec| {
+                            docs.insert(SimpleString::new(spec.name), spec.docs_frame());
+                        });
+                }
+                docs.into()
+            }
+            Subcommand::GetKeys(args) => getkeys(&args),
+            Subcommand::List(filter) => RespArray::new(Some(
+                all_commands()
+                    .filter(|spec| match &filter {
+                        None => true,
+                        Some(ListFilter::Module(_)) => false,
+                        Some(ListFilter::AclCat(category)) => spec.has_category(category),
+                        Some(ListFilter::Pattern(pattern)) => {
+                            glob_match(pattern.as_bytes(), spec.name.as_bytes(), true)
+                        }
+                    })
+                    .map(|spec| BulkString::new(Some(spec.name)).into())
+                    .collect::<Vec<_>>(),
+            ))
+            .into(),
+            Subcommand::Help => help(),
+        }
+    }
+}
+
+const HELP: &[&str] = &[
+    "COMMAND <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "(no subcommand)",
+    "    Return details about all Redis commands.",
+    "COUNT",
+    "    Return the total number of commands in this Redis server.",
+    "LIST [FILTERBY (MODULE <module-name>|ACLCAT <category>|PATTERN <pattern>)]",
+    "    Return a list of all commands in this Redis server.",
+    "INFO [<command-name> ...]",
+    "    Return details about multiple Redis commands.",
+    "    If no command names are given, documentation details for all",
+    "    commands are returned.",
+    "DOCS [<command-name> ...]",
+    "    Return documentation details about multiple Redis commands.",
+    "    If no command names are given, documentation details for all",
+    "    commands are returned.",
+    "GETKEYS <full-command>",
+    "    Return the keys from a full Redis command.",
+    "HELP",
+    "    Print this help.",
+];
+
+/// `COMMAND HELP`: one status line per line of text, like Redis's
+/// `addReplyHelp`.
+fn help() -> RespFrame {
+    RespArray::new(Some(
+        HELP.iter()
+            .map(|line| SimpleString::new(*line).into())
+            .collect::<Vec<_>>(),
+    ))
+    .into()
+}
+
+/// `COMMAND` and `COMMAND INFO` without names: every command's info.
+fn all_infos() -> RespFrame {
+    RespArray::new(Some(
+        COMMAND_TABLE
+            .iter()
+            .map(|spec| spec.info_frame())
+            .collect::<Vec<_>>(),
+    ))
+    .into()
+}
+
+fn getkeys(args: &[Bytes]) -> RespFrame {
+    let spec = match lookup_request(args) {
+        Some(spec) => spec,
+        None => return RespFrame::SimpleError(SimpleError::new("ERR Invalid command specified")),
+    };
+    if !spec.check_arity(args.len()) {
+        return RespFrame::SimpleError(SimpleError::new(
+            "ERR Invalid number of arguments specified for command",
+        ));
+    }
+    let keys = spec.extract_keys(args);
+    if keys.is_empty() {
+        return RespFrame::SimpleError(SimpleError::new("ERR The command has no key arguments"));
+    }
+    RespArray::new(Some(
+        keys.into_iter()
+            .map(|key| BulkString::new(Some(key)).into())
+            .collect::<Vec<_>>(),
+    ))
+    .into()
+}
+
+impl TryFrom<Vec<RespFrame>> for CommandCmd {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut frame_iter = value.into_iter();
+        let subcommand = match frame_iter.next() {
+            None => return Ok(CommandCmd::new(Subcommand::All)),
+            Some(frame) => extract_string(Some(frame))?.to_ascii_lowercase(),
+        };
+        let rest = frame_iter.len();
+        let subcommand = match (subcommand.as_str(), rest) {
+            ("count", 0) => Subcommand::Count,
+            ("info", _) => Subcommand::Info(
+                frame_iter
+                    .map(|f| extract_string(Some(f)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("docs", _) => Subcommand::Docs(
+                frame_iter
+                    .map(|f| extract_string(Some(f)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("getkeys", n) if n >= 1 => Subcommand::GetKeys(
+                frame_iter
+                    .map(|f| extract_bytes(Some(f)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("help", 0) => Subcommand::Help,
+            ("list", 0) => Subcommand::List(None),
+            ("list", 3) => {
+                let filterby = extract_string(frame_iter.next())?;
+                let kind = extract_string(frame_iter.next())?.to_ascii_lowercase();
+                let value = extract_string(frame_iter.next())?;
+                if !filterby.eq_ignore_ascii_case("filterby") {
+                    return Err(CommandError::InvalidArgument("syntax error".to_string()));
+                }
+                Subcommand::List(Some(match kind.as_str() {
+                    "module" => ListFilter::Module(value),
+                    "aclcat" => ListFilter::AclCat(value),
+                    "pattern" => ListFilter::Pattern(value),
+                    _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+                }))
+            }
+            ("count" | "getkeys" | "list" | "help", _) => {
+                return Err(CommandError::WrongArity(format!("command|{}", subcommand)))
+            }
+            _ => {
+                return Err(CommandError::UnknownSubcommand(
+                    subcommand,
+                    "COMMAND".to_string(),
+                ))
+            }
+        };
+        Ok(CommandCmd::new(subcommand))
+    }
+}
+
+impl CommandCmd {
+    pub fn new(subcommand: Subcommand) -> Self {
+        CommandCmd { subcommand }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cmd::Command, RespDecode, RespEncode, RespSet};
+
+    use super::*;
+
+    fn execute(input: &[u8]) -> RespFrame {
+        let mut buf = BytesMut::from(input);
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        cmd.execute(&Backend::new())
+    }
+
+    #[test]
+    fn test_command_try_from() {
+        let mut buf =
+            BytesMut::from(b"*3\r\n$7\r\ncommand\r\n$4\r\nINFO\r\n$3\r\nget\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Command(CommandCmd::new(Subcommand::Info(vec!["get".to_string()])))
+        );
+    }
+
+    #[test]
+    fn test_cmd_command_count() {
+        let ret = execute(b"*2\r\n$7\r\ncommand\r\n$5\r\ncount\r\n");
+        assert_eq!(ret, RespFrame::Integer(COMMAND_TABLE.len() as i64));
+    }
+
+    #[test]
+    fn test_cmd_command_info() {
+        let ret = execute(b"*4\r\n$7\r\ncommand\r\n$4\r\ninfo\r\n$3\r\nget\r\n$3\r\nfoo\r\n");
+        let RespFrame::Array(RespArray(Some(infos))) = ret else {
+            panic!("expect array, got {:?}", ret);
+        };
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[1], RET_NULL.clone());
+        let RespFrame::Array(RespArray(Some(info))) = &infos[0] else {
+            panic!("expect array, got {:?}", infos[0]);
+        };
+        assert_eq!(info.len(), 10);
+        assert_eq!(info[0], BulkString::new(Some("get")).into());
+        assert_eq!(info[1], RespFrame::Integer(2));
+        assert_eq!(
+            info[2],
+            RespSet::new(vec![
+                SimpleString::new("readonly").into(),
+                SimpleString::new("fast").into()
+            ])
+            .into()
+        );
+        assert_eq!(info[3..6], [1.into(), 1.into(), 1.into()]);
+    }
+
+    #[test]
+    fn test_cmd_command_docs() {
+        let ret = execute(b"*3\r\n$7\r\ncommand\r\n$4\r\ndocs\r\n$4\r\nhset\r\n");
+        let RespFrame::Map(docs) = ret.clone() else {
+            panic!("expect map, got {:?}", ret);
+        };
+        // keys are bulk strings, as Redis writes them
+        let encoded = ret.encode();
+        assert!(encoded.starts_with(b"%1\r\n$4\r\nhset\r\n"));
+        assert!(encoded
+            .windows(b"$7\r\nsummary\r\n".len())
+            .any(|w| w == b"$7\r\nsummary\r\n"));
+        let RespFrame::Map(hset) = &docs[&SimpleString::new("hset")] else {
+            panic!("expect map");
+        };
+        assert_eq!(
+            hset[&SimpleString::new("group")],
+            BulkString::new(Some("hash")).into()
+        );
+        assert!(hset.contains_key(&SimpleString::new("arguments")));
+    }
+
+    #[test]
+    fn test_cmd_command_help() {
+        let ret = execute(b"*2\r\n$7\r\ncommand\r\n$4\r\nhelp\r\n");
+        let RespFrame::Array(RespArray(Some(lines))) = ret else {
+            panic!("expect array, got {:?}", ret);
+        };
+        assert_eq!(lines.len(), HELP.len());
+        assert_eq!(
+            lines.last(),
+            Some(&SimpleString::new("    Print this help.").into())
+        );
+    }
+
+    #[test]
+    fn test_cmd_command_getkeys() {
+        let ret = execute(
+            b"*5\r\n$7\r\ncommand\r\n$7\r\ngetkeys\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n",
+        );
+        assert_eq!(
+            ret,
+            RespArray::new(Some(vec![BulkString::new(Some("key")).into()])).into()
+        );
+
+        let ret = execute(b"*4\r\n$7\r\ncommand\r\n$7\r\ngetkeys\r\n$3\r\nset\r\n$3\r\nkey\r\n");
+        assert_eq!(
+            ret,
+            RespFrame::SimpleError(SimpleError::new(
+                "ERR Invalid number of arguments specified for command"
+            ))
+        );
+
+        let ret = execute(b"*4\r\n$7\r\ncommand\r\n$7\r\ngetkeys\r\n$4\r\necho\r\n$2\r\nhi\r\n");
+        assert_eq!(
+            ret,
+            RespFrame::SimpleError(SimpleError::new("ERR The command has no key arguments"))
+        );
+
+        // keys of container commands follow the subcommand name
+        let ret = execute(
+            b"*5\r\n$7\r\ncommand\r\n$7\r\ngetkeys\r\n$6\r\nobject\r\n$8\r\nencoding\r\n$3\r\nkey\r\n",
+        );
+        assert_eq!(
+            ret,
+            RespArray::new(Some(vec![BulkString::new(Some("key")).into()])).into()
+        );
+    }
+
+    #[test]
+    fn test_cmd_command_list() {
+        let ret = execute(
+            b"*5\r\n$7\r\ncommand\r\n$4\r\nlist\r\n$8\r\nfilterby\r\n$7\r\npattern\r\n$2\r\nh*\r\n",
+        );
+        let RespFrame::Array(RespArray(Some(names))) = ret else {
+            panic!("expect array, got {:?}", ret);
+        };
+        assert_eq!(names.len(), 5);
+
+        let ret = execute(
+            b"*5\r\n$7\r\ncommand\r\n$4\r\nlist\r\n$8\r\nfilterby\r\n$6\r\naclcat\r\n$3\r\nset\r\n",
+        );
+        assert_eq!(
+            ret,
+            RespArray::new(Some(vec![
+                BulkString::new(Some("sadd")).into(),
+                BulkString::new(Some("sismember")).into(),
+            ]))
+            .into()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{aof, Backend, BulkString, ConfigError, RespFrame, RespMap, SimpleError, SimpleString};
+
+use super::{extract_string, CommandError, CommandExecutor, RET_OK};
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigCmd {
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    Get(Vec<String>),
+    Set(Vec<(String, String)>),
+    Rewrite,
+    ResetStat,
+}
+
+impl CommandExecutor for ConfigCmd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let result = match self.subcommand {
+            Subcommand::Get(patterns) => {
+                let config = backend.config();
+                let mut values = RespMap::new();
+                for pattern in &patterns {
+                    config.matching(pattern).for_each(|(name, value)| {
+                        values.insert(SimpleString::new(name), BulkString::new(Some(value)).into());
+                    });
+                }
+                return values.into();
+            }
+            Subcommand::Set(pairs) => set(backend, &pairs),
+            Subcommand::Rewrite => backend.config().rewrite(),
+            Subcommand::ResetStat => {
+                backend.reset_stats();
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) => RET_OK.clone(),
+            Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+/// `CONFIG SET`, then turn AOF on or off when `appendonly` changed. If AOF
+/// can't be started the parameter is switched back off.
+fn set(backend: &Backend, pairs: &[(String, String)]) -> Result<(), ConfigError> {
+    let was_on = backend.config().appendonly();
+    backend.config_mut().set(pairs)?;
+    let on = backend.config().appendonly();
+    if on == was_on {
+        return Ok(());
+    }
+    let result = if on {
+        aof::start(backend).map_err(|e| e.to_string())
+    } else {
+        aof::stop(backend).map_err(|e| e.to_string())
+    };
+    result.map_err(|reason| {
+        if on {
+            let off = [("appendonly".to_string(), "no".to_string())];
+            let _ = backend.config_mut().set(&off);
+        }
+        ConfigError::SetFailed("appendonly".to_string(), reason)
+    })
+}
+
+impl TryFrom<Vec<RespFrame>> for ConfigCmd {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value
+            .into_iter()
+            .map(|frame| extract_string(Some(frame)))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let subcommand = args
+            .next()
+            .ok_or_else(|| CommandError::WrongArity("config".to_string()))?
+            .to_ascii_lowercase();
+        let subcommand = match (subcommand.as_str(), args.len()) {
+            ("get", n) if n >= 1 => Subcommand::Get(args.collect()),
+            ("set", n) if n >= 2 && n.is_multiple_of(2) => {
+                let mut pairs = Vec::with_capacity(n / 2);
+                while let (Some(name), Some(value)) = (args.next(), args.next()) {
+                    pairs.push((name, value));
+                }
+                Subcommand::Set(pairs)
+            }
+            ("rewrite", 0) => Subcommand::Rewrite,
+            ("resetstat", 0) => Subcommand::ResetStat,
+            ("get" | "set" | "rewrite" | "resetstat", _) => {
+                return Err(CommandError::WrongArity(format!("config|{}", subcommand)))
+            }
+            _ => {
+                return Err(CommandError::UnknownSubcommand(
+                    subcommand,
+                    "CONFIG".to_string(),
+                ))
+            }
+        };
+        Ok(ConfigCmd::new(subcommand))
+    }
+}
+
+impl ConfigCmd {
+    pub fn new(subcommand: Subcommand) -> Self {
+        ConfigCmd { subcommand }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate::{cmd::Command, Entry, RespArray, RespDecode, Value};
+
+    use super::*;
+
+    fn command(input: &[u8]) -> Result<Command, CommandError> {
+        let mut buf = BytesMut::from(input);
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        Command::try_from(array)
+    }
+
+    #[test]
+    fn test_config_try_from() {
+        let cmd =
+            command(b"*4\r\n$6\r\nconfig\r\n$3\r\nSET\r\n$4\r\nport\r\n$4\r\n7000\r\n").unwrap();
+        assert_eq!(
+            cmd,
+            Command::Config(ConfigCmd::new(Subcommand::Set(vec![(
+                "port".to_string(),
+                "7000".to_string()
+            )])))
+        );
+
+        let err = command(b"*3\r\n$6\r\nconfig\r\n$3\r\nset\r\n$4\r\nport\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'config|set' command"
+        );
+        let err = command(
+            b"*5\r\n$6\r\nconfig\r\n$3\r\nset\r\n$4\r\nport\r\n$4\r\n7000\r\n$4\r\nbind\r\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'config|set' command"
+        );
+        let err = command(b"*2\r\n$6\r\nconfig\r\n$5\r\nreset\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown subcommand 'reset'. Try CONFIG HELP."
+        );
+    }
+
+    #[test]
+    fn test_config_get_and_set() {
+        let backend = Backend::new();
+        let ret = command(
+            b"*6\r\n$6\r\nconfig\r\n$3\r\nset\r\n$18\r\nproto-max-bulk-len\r\n$3\r\n2mb\r\n$25\r\nclient-query-buffer-limit\r\n$3\r\n4mb\r\n",
+        )
+        .unwrap()
+        .execute(&backend);
+        assert_eq!(ret, RET_OK.clone());
+
+        let ret = command(b"*4\r\n$6\r\nconfig\r\n$3\r\nget\r\n$6\r\n*-LEN*\r\n$4\r\nport\r\n")
+            .unwrap()
+            .execute(&backend);
+        let mut expected = RespMap::new();
+        expected.insert(
+            SimpleString::new("proto-max-bulk-len"),
+            BulkString::new(Some("2097152")).into(),
+        );
+        expected.insert(
+            SimpleString::new("proto-max-multibulk-len"),
+            BulkString::new(Some("2147483647")).into(),
+        );
+        expected.insert(
+            SimpleString::new("port"),
+            BulkString::new(Some("6379")).into(),
+        );
+        assert_eq!(ret, expected.into());
+
+        let ret = command(b"*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$4\r\nport\r\n$4\r\n7000\r\n")
+            .unwrap()
+            .execute(&backend);
+        assert_eq!(
+            ret,
+            SimpleError::new(
+                "ERR CONFIG SET failed (possibly related to argument 'port') - can't set immutable config"
+            )
+            .into()
+        );
+
+        let ret = command(b"*2\r\n$6\r\nconfig\r\n$7\r\nrewrite\r\n")
+            .unwrap()
+            .execute(&backend);
+        assert_eq!(
+            ret,
+            SimpleError::new("ERR The server is running without a config file").into()
+        );
+    }
+
+    #[test]
+    fn test_config_resetstat() {
+        let backend = Backend::new();
+        backend.set(
+            Bytes::from("big"),
+            BulkString::new(Some("x".repeat(1024))).into(),
+        );
+        backend.remove(b"big");
+        backend.insert_entry(Entry {
+            key: Bytes::from("k"),
+            value: Value::String(Bytes::from("v")),
+            expire_at: Some(1),
+        });
+        assert_eq!(backend.get(b"k").unwrap(), None);
+        assert_eq!(backend.expired_keys(), 1);
+        assert!(backend.peak_memory() > 1024);
+
+        let ret = command(b"*2\r\n$6\r\nconfig\r\n$9\r\nRESETSTAT\r\n")
+            .unwrap()
+            .execute(&backend);
+        assert_eq!(ret, RET_OK.clone());
+        assert_eq!(backend.expired_keys(), 0);
+        assert_eq!(backend.evicted_keys(), 0);
+        assert_eq!(backend.peak_memory(), backend.used_memory());
+
+        let err = command(b"*3\r\n$6\r\nconfig\r\n$9\r\nresetstat\r\n$3\r\nnow\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'config|resetstat' command"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use bytes::Bytes;
+
+use crate::{Backend, NotifyFlags, RespFrame};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct Del {
+    keys: Vec<Bytes>,
+}
+
+impl CommandExecutor for Del {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut deleted = 0;
+        for key in &self.keys {
+            if backend.remove(key) {
+                backend.notify(NotifyFlags::GENERIC, "del", key);
+                deleted += 1;
+            }
+        }
+        RespFrame::Integer(deleted)
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Del {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let keys = value
+            .into_iter()
+            .map(|frame| extract_bytes(Some(frame)))
+            .collect::<Result<_, _>>()?;
+        Ok(Del::new(keys))
+    }
+}
+
+impl Del {
+    pub fn new(keys: Vec<Bytes>) -> Self {
+        Del { keys }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cmd::Command, BulkString, RespArray, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_del() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("a"), BulkString::new(Some("1")).into());
+        backend
+            .sadd(Bytes::from("b"), vec![Bytes::from("x")])
+            .unwrap();
+        let mut buf =
+            BytesMut::from(b"*4\r\n$3\r\ndel\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Del(Del::new(vec![
+                Bytes::from("a"),
+                Bytes::from("b"),
+                Bytes::from("c")
+            ]))
+        );
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert!(!backend.exists(b"a") && !backend.exists(b"b"));
+    }
+}
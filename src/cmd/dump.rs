@@ -0,0 +1,59 @@
+use bytes::Bytes;
+
+use crate::{rdb, Backend, BulkString, RespFrame};
+
+use super::{extract_bytes, CommandError, CommandExecutor, RET_NULL};
+
+#[derive(Debug, PartialEq)]
+pub struct Dump {
+    key: Bytes,
+}
+
+impl CommandExecutor for Dump {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.get_entry(&self.key) {
+            Some(entry) => BulkString::new(Some(rdb::dump_value(&entry.value))).into(),
+            None => RET_NULL.clone(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Dump {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(Dump::new(extract_bytes(value.into_iter().next())?))
+    }
+}
+
+impl Dump {
+    pub fn new(key: Bytes) -> Self {
+        Dump { key }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cmd::Command, RespArray, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_cmd_dump() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("hello"), BulkString::new(Some(b"world")).into());
+        let mut buf = BytesMut::from(b"*2\r\n$4\r\ndump\r\n$5\r\nhello\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(cmd, Command::Dump(Dump::new(Bytes::from_static(b"hello"))));
+        let RespFrame::BulkString(BulkString(Some(payload))) = cmd.execute(&backend) else {
+            panic!("DUMP should return a bulk string");
+        };
+        assert_eq!(&payload[..7], b"\x00\x05world");
+
+        let missing = Dump::new(Bytes::from_static(b"missing")).execute(&backend);
+        assert_eq!(missing, RET_NULL.clone());
+    }
+}
@@ -1,17 +1,20 @@
+use bytes::Bytes;
+
 use crate::RespFrame;
 
-use super::{extract_string, validate_nums_of_argument, CommandError, CommandExecutor, RET_NULL};
+use super::{extract_bytes, CommandError, CommandExecutor, RET_NULL, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct Get {
-    key: String,
+    key: Bytes,
 }
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
         match backend.get(&self.key) {
-            Some(val) => val,
-            None => RET_NULL.clone(),
+            Ok(Some(val)) => val,
+            Ok(None) => RET_NULL.clone(),
+            Err(_) => RET_WRONGTYPE.clone(),
         }
     }
 }
@@ -20,19 +23,18 @@ impl TryFrom<Vec<RespFrame>> for Get {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "get", 1, 1)?;
-        Ok(Get::new(extract_string(value.into_iter().next())?))
+        Ok(Get::new(extract_bytes(value.into_iter().next())?))
     }
 }
 
 impl Get {
-    pub fn new(key: String) -> Self {
+    pub fn new(key: Bytes) -> Self {
         Get { key }
     }
 }
 
-impl From<String> for Get {
-    fn from(value: String) -> Self {
+impl From<Bytes> for Get {
+    fn from(value: Bytes) -> Self {
         Get::new(value)
     }
 }
@@ -41,7 +43,10 @@ impl From<String> for Get {
 mod tests {
     use bytes::BytesMut;
 
-    use crate::{cmd::Command, Backend, BulkString, RespArray, RespDecode, SimpleString};
+    use crate::{
+        cmd::{set::Set, Command},
+        Backend, BulkString, RespArray, RespDecode, SimpleString,
+    };
 
     use super::*;
 
@@ -50,7 +55,7 @@ mod tests {
         let mut buf = BytesMut::from(b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n".as_slice());
         let array = RespArray::decode(&mut buf).expect("error in decode resp array");
         let get = Command::try_from(array).unwrap();
-        assert_eq!(get, Command::Get(Get::new("hello".to_string())))
+        assert_eq!(get, Command::Get(Get::new(Bytes::from_static(b"hello"))))
     }
 
     #[test]
@@ -69,4 +74,28 @@ mod tests {
         let ret = get.execute(&backend);
         assert_eq!(ret, RespFrame::BulkString(BulkString::new(Some(b"world"))));
     }
+
+    #[test]
+    fn test_cmd_get_set_binary() {
+        let backend = Backend::new();
+        let mut buf = BytesMut::from(
+            b"*3\r\n$3\r\nset\r\n$6\r\n\xff\xfe\r\nk\x00\r\n$4\r\n\xc3\x28\r\n\r\n".as_slice(),
+        );
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Set(Set::new(
+                Bytes::from_static(b"\xff\xfe\r\nk\x00"),
+                BulkString::new(Some(b"\xc3\x28\r\n")).into()
+            ))
+        );
+        cmd.execute(&backend);
+
+        let mut buf = BytesMut::from(b"*2\r\n$3\r\nget\r\n$6\r\n\xff\xfe\r\nk\x00\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let get = Command::try_from(array).unwrap();
+        let ret = get.execute(&backend);
+        assert_eq!(ret, BulkString::new(Some(b"\xc3\x28\r\n")).into());
+    }
 }
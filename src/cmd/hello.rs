@@ -0,0 +1,245 @@
+use bytes::Bytes;
+
+use crate::{
+    Backend, BulkString, Client, RespArray, RespFrame, RespMap, RespVersion, SimpleError,
+    SimpleString,
+};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
+
+pub const SERVER_VERSION: &str = "7.0.0";
+
+#[derive(Debug, PartialEq)]
+pub struct Hello {
+    protover: Option<RespVersion>,
+    auth: Option<(Bytes, Bytes)>,
+    setname: Option<Bytes>,
+}
+
+impl CommandExecutor for Hello {
+    /// Without a connection the reply describes a fresh RESP2 client.
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.apply(backend, &mut Client::new())
+    }
+}
+
+impl Hello {
+    pub fn new(
+        protover: Option<RespVersion>,
+        auth: Option<(Bytes, Bytes)>,
+        setname: Option<Bytes>,
+    ) -> Self {
+        Hello {
+            protover,
+            auth,
+            setname,
+        }
+    }
+
+    /// Switch the connection protocol and return the server info map.
+    pub fn apply(self, backend: &Backend, client: &mut Client) -> RespFrame {
+        if let Some((user, _)) = &self.auth {
+            // no ACL users are configured, only the default user exists
+            if user.as_ref() != b"default" {
+                return RespFrame::SimpleError(SimpleError::new(
+                    "WRONGPASS invalid username-password pair or user is disabled.",
+                ));
+            }
+        }
+        if let Some(protocol) = self.protover {
+            client.protocol = protocol;
+        }
+        if let Some(name) = self.setname {
+            client.name = Some(name);
+        }
+
+        let proto = match client.protocol {
+            RespVersion::Resp2 => 2,
+            RespVersion::Resp3 => 3,
+        };
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new("server"), bulk("redis"));
+        map.insert(SimpleString::new("version"), bulk(SERVER_VERSION));
+        map.insert(SimpleString::new("proto"), RespFrame::Integer(proto));
+        map.insert(
+            SimpleString::new("id"),
+            RespFrame::Integer(client.id as i64),
+        );
+        let mode = match backend.cluster() {
+            Some(_) => "cluster",
+            None => "standalone",
+        };
+        map.insert(SimpleString::new("mode"), bulk(mode));
+        let role = match backend.replication().is_replica() {
+            true => "replica",
+            false => "master",
+        };
+        map.insert(SimpleString::new("role"), bulk(role));
+        map.insert(
+            SimpleString::new("modules"),
+            RespArray::new(Some([])).into(),
+        );
+        map.into()
+    }
+}
+
+fn bulk(s: &'static str) -> RespFrame {
+    BulkString::new(Some(s)).into()
+}
+
+impl TryFrom<Vec<RespFrame>> for Hello {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let Some(protover) = args.next() else {
+            return Ok(Hello::new(None, None, None));
+        };
+        let protover = std::str::from_utf8(&extract_bytes(Some(protover))?)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or_else(|| {
+                CommandError::InvalidArgument(
+                    "Protocol version is not an integer or out of range".to_string(),
+                )
+            })?;
+        let protover = match protover {
+            2 => RespVersion::Resp2,
+            3 => RespVersion::Resp3,
+            _ => return Err(CommandError::NoProto),
+        };
+
+        let mut hello = Hello::new(Some(protover), None, None);
+        while let Some(option) = args.next() {
+            let option = extract_bytes(Some(option))?;
+            let remaining = args.len();
+            if option.eq_ignore_ascii_case(b"auth") && remaining >= 2 {
+                let user = extract_bytes(args.next())?;
+                let pass = extract_bytes(args.next())?;
+                hello.auth = Some((user, pass));
+            } else if option.eq_ignore_ascii_case(b"setname") && remaining >= 1 {
+                let name = extract_bytes(args.next())?;
+                if name.iter().any(|b| *b <= b' ' || *b > b'~') {
+                    return Err(CommandError::InvalidArgument(
+                        "Client names cannot contain spaces, newlines or special characters."
+                            .to_string(),
+                    ));
+                }
+                hello.setname = Some(name);
+            } else {
+                return Err(CommandError::InvalidArgument(format!(
+                    "Syntax error in HELLO option '{}'",
+                    String::from_utf8_lossy(&option)
+                )));
+            }
+        }
+        Ok(hello)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cluster::Cluster, cmd::Command, replication, RespArray, RespDecode, RespEncode};
+
+    use super::*;
+
+    fn parse(input: &[u8]) -> Result<Command, CommandError> {
+        let mut buf = BytesMut::from(input);
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        Command::try_from(array)
+    }
+
+    #[test]
+    fn test_hello_try_from() {
+        assert_eq!(
+            parse(b"*1\r\n$5\r\nhello\r\n").unwrap(),
+            Command::Hello(Hello::new(None, None, None))
+        );
+        assert_eq!(
+            parse(b"*6\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$4\r\nauth\r\n$7\r\ndefault\r\n$3\r\npwd\r\n$7\r\nsetname\r\n")
+                .unwrap_err()
+                .to_string(),
+            "ERR Syntax error in HELLO option 'setname'"
+        );
+        assert_eq!(
+            parse(b"*7\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$4\r\nauth\r\n$7\r\ndefault\r\n$3\r\npwd\r\n$7\r\nsetname\r\n$3\r\ncli\r\n")
+                .unwrap(),
+            Command::Hello(Hello::new(
+                Some(RespVersion::Resp3),
+                Some((Bytes::from_static(b"default"), Bytes::from_static(b"pwd"))),
+                Some(Bytes::from_static(b"cli"))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_hello_bad_protover() {
+        let err = parse(b"*2\r\n$5\r\nhello\r\n$1\r\n4\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "NOPROTO unsupported protocol version");
+        let err = parse(b"*2\r\n$5\r\nhello\r\n$3\r\nabc\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR Protocol version is not an integer or out of range"
+        );
+    }
+
+    #[test]
+    fn test_hello_apply() {
+        let mut client = Client::new();
+        let hello = Hello::new(
+            Some(RespVersion::Resp3),
+            None,
+            Some(Bytes::from_static(b"cli")),
+        );
+        let reply = hello.apply(&Backend::new(), &mut client);
+        // keys are bulk strings, like Redis's
+        assert!(reply.encode().starts_with(b"%7\r\n$2\r\nid\r\n"));
+        let RespFrame::Map(map) = reply else {
+            panic!("expected map reply");
+        };
+        assert_eq!(client.protocol, RespVersion::Resp3);
+        assert_eq!(client.name, Some(Bytes::from_static(b"cli")));
+        assert_eq!(map.get(&SimpleString::new("proto")), Some(&3.into()));
+        assert_eq!(
+            map.get(&SimpleString::new("id")),
+            Some(&(client.id as i64).into())
+        );
+        assert_eq!(
+            map.get(&SimpleString::new("mode")),
+            Some(&bulk("standalone"))
+        );
+        assert_eq!(map.get(&SimpleString::new("role")), Some(&bulk("master")));
+        assert_eq!(map.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_hello_mode_and_role() {
+        let backend = Backend::new();
+        let topology = "aaaa 127.0.0.1:7000@0 myself,master - 0 0 1 connected 0-16383\n";
+        backend.set_cluster(Cluster::parse(topology, &["127.0.0.1"], 7000).unwrap());
+        // port 1 refuses connections, so the link stays down
+        replication::set_master(&backend, Some(("127.0.0.1".to_string(), 1)));
+        let RespFrame::Map(map) = Hello::new(None, None, None).execute(&backend) else {
+            panic!("expected map reply");
+        };
+        assert_eq!(map.get(&SimpleString::new("mode")), Some(&bulk("cluster")));
+        assert_eq!(map.get(&SimpleString::new("role")), Some(&bulk("replica")));
+    }
+
+    #[test]
+    fn test_hello_wrong_user() {
+        let mut client = Client::new();
+        let hello = Hello::new(
+            Some(RespVersion::Resp3),
+            Some((Bytes::from_static(b"bob"), Bytes::from_static(b"pwd"))),
+            None,
+        );
+        assert!(matches!(
+            hello.apply(&Backend::new(), &mut client),
+            RespFrame::SimpleError(_)
+        ));
+        assert_eq!(client.protocol, RespVersion::Resp2);
+    }
+}
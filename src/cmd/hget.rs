@@ -1,18 +1,22 @@
+use bytes::Bytes;
+
 use crate::RespFrame;
 
-use super::{extract_string, validate_nums_of_argument, CommandError, CommandExecutor, RET_NULL};
+use super::{extract_bytes, CommandError, CommandExecutor, RET_NULL, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct HGet {
-    key: String,
-    field: String,
+    key: Bytes,
+    field: Bytes,
 }
 
 impl CommandExecutor for HGet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend
-            .hget(&self.key, &self.field)
-            .unwrap_or(RET_NULL.clone())
+        match backend.hget(&self.key, &self.field) {
+            Ok(Some(val)) => val,
+            Ok(None) => RET_NULL.clone(),
+            Err(_) => RET_WRONGTYPE.clone(),
+        }
     }
 }
 
@@ -20,17 +24,15 @@ impl TryFrom<Vec<RespFrame>> for HGet {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "hget", 2, 2)?;
-
         let mut frame_iter = value.into_iter();
-        let key = extract_string(frame_iter.next())?;
-        let field = extract_string(frame_iter.next())?;
+        let key = extract_bytes(frame_iter.next())?;
+        let field = extract_bytes(frame_iter.next())?;
         Ok(HGet::new(key, field))
     }
 }
 
 impl HGet {
-    pub fn new(key: String, field: String) -> Self {
+    pub fn new(key: Bytes, field: Bytes) -> Self {
         HGet { key, field }
     }
 }
@@ -51,7 +53,10 @@ mod tests {
         let hget = Command::try_from(array).unwrap();
         assert_eq!(
             hget,
-            Command::HGet(HGet::new("hello".to_string(), "world".to_string()))
+            Command::HGet(HGet::new(
+                Bytes::from_static(b"hello"),
+                Bytes::from_static(b"world")
+            ))
         )
     }
 
@@ -73,4 +78,23 @@ mod tests {
         let ret = get.execute(&backend);
         assert_eq!(ret, RespFrame::BulkString(BulkString::new(Some(b"world"))));
     }
+
+    #[test]
+    fn test_cmd_hget_hset_binary() {
+        let backend = Backend::new();
+        let mut buf = BytesMut::from(
+            b"*4\r\n$4\r\nhset\r\n$3\r\n\x80\x81\x82\r\n$3\r\n\r\n\xff\r\n$1\r\n1\r\n".as_slice(),
+        );
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let mut buf = BytesMut::from(
+            b"*3\r\n$4\r\nhget\r\n$3\r\n\x80\x81\x82\r\n$3\r\n\r\n\xff\r\n".as_slice(),
+        );
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let get = Command::try_from(array).unwrap();
+        let ret = get.execute(&backend);
+        assert_eq!(ret, RespFrame::BulkString(BulkString::new(Some(b"1"))));
+    }
 }
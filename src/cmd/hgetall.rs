@@ -1,17 +1,21 @@
+use bytes::Bytes;
+
 use crate::RespFrame;
 
-use super::{
-    extract_string, validate_nums_of_argument, CommandError, CommandExecutor, RET_NULL_ARRAY,
-};
+use super::{extract_bytes, CommandError, CommandExecutor, RET_NULL_ARRAY, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct HGetAll {
-    key: String,
+    key: Bytes,
 }
 
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.hgetall(&self.key).unwrap_or(RET_NULL_ARRAY.clone())
+        match backend.hgetall(&self.key) {
+            Ok(Some(val)) => val,
+            Ok(None) => RET_NULL_ARRAY.clone(),
+            Err(_) => RET_WRONGTYPE.clone(),
+        }
     }
 }
 
@@ -19,13 +23,12 @@ impl TryFrom<Vec<RespFrame>> for HGetAll {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "hgetall", 1, 1)?;
-        Ok(HGetAll::new(extract_string(value.into_iter().next())?))
+        Ok(HGetAll::new(extract_bytes(value.into_iter().next())?))
     }
 }
 
 impl HGetAll {
-    pub fn new(key: String) -> Self {
+    pub fn new(key: Bytes) -> Self {
         HGetAll { key }
     }
 }
@@ -34,7 +37,7 @@ impl TryFrom<Vec<u8>> for HGetAll {
     type Error = CommandError;
 
     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        Ok(HGetAll::new(String::from_utf8(value)?))
+        Ok(HGetAll::new(value.into()))
     }
 }
 
@@ -51,7 +54,10 @@ mod tests {
         let mut buf = BytesMut::from(b"*2\r\n$7\r\nhgetall\r\n$5\r\nhello\r\n".as_slice());
         let array = RespArray::decode(&mut buf).expect("error in decode resp array");
         let hgetall = Command::try_from(array).unwrap();
-        assert_eq!(hgetall, Command::HGetAll(HGetAll::new("hello".to_string())))
+        assert_eq!(
+            hgetall,
+            Command::HGetAll(HGetAll::new(Bytes::from_static(b"hello")))
+        )
     }
 
     #[test]
@@ -1,28 +1,34 @@
-use crate::{cmd::RET_NULL, Backend, RespArray, RespFrame};
+use bytes::Bytes;
 
-use super::{extract_string, validate_nums_of_argument, CommandError, CommandExecutor};
+use crate::{
+    cmd::{RET_NULL, RET_WRONGTYPE},
+    Backend, RespArray, RespFrame,
+};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
 
 #[derive(Debug, PartialEq)]
 pub struct Hmget {
-    key: String,
-    fields: Vec<String>,
+    key: Bytes,
+    fields: Vec<Bytes>,
 }
 
 impl CommandExecutor for Hmget {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend
-            .hmget(&self.key, &self.fields)
-            .map(|values| {
+        match backend.hmget(&self.key, &self.fields) {
+            Ok(Some(values)) => {
                 let array = values
                     .into_iter()
                     .map(|value| value.unwrap_or(RET_NULL.clone()))
                     .collect();
                 RespFrame::Array(RespArray(Some(array)))
-            })
-            .unwrap_or_else(|| {
+            }
+            Ok(None) => {
                 let array: Vec<_> = self.fields.iter().map(|_| RET_NULL.clone()).collect();
                 RespFrame::Array(RespArray::new(Some(array)))
-            })
+            }
+            Err(_) => RET_WRONGTYPE.clone(),
+        }
     }
 }
 
@@ -30,21 +36,19 @@ impl TryFrom<Vec<RespFrame>> for Hmget {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "hmget", value.len(), 2)?;
-
         let mut frame_iter = value.into_iter();
-        let key = extract_string(frame_iter.next())?;
+        let key = extract_bytes(frame_iter.next())?;
 
         let mut fields = Vec::with_capacity(frame_iter.len());
         for field in frame_iter {
-            fields.push(extract_string(Some(field))?);
+            fields.push(extract_bytes(Some(field))?);
         }
         Ok(Hmget::new(key, fields))
     }
 }
 
 impl Hmget {
-    pub fn new(key: String, fields: Vec<String>) -> Self {
+    pub fn new(key: Bytes, fields: Vec<Bytes>) -> Self {
         Self { key, fields }
     }
 }
@@ -67,8 +71,8 @@ mod tests {
         assert_eq!(
             hget,
             Command::Hmget(Hmget::new(
-                "map".to_string(),
-                vec!["name".to_string(), "age".to_string()]
+                Bytes::from_static(b"map"),
+                vec![Bytes::from_static(b"name"), Bytes::from_static(b"age")]
             ))
         )
     }
@@ -1,19 +1,23 @@
-use crate::RespFrame;
+use bytes::Bytes;
 
-use super::{
-    extract_frame, extract_string, validate_nums_of_argument, CommandError, CommandExecutor,
-};
+use crate::{NotifyFlags, RespFrame};
+
+use super::{extract_bytes, extract_frame, CommandError, CommandExecutor, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct HSet {
-    key: String,
-    fields: Vec<String>,
+    key: Bytes,
+    fields: Vec<Bytes>,
     values: Vec<RespFrame>,
 }
 
 impl CommandExecutor for HSet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.hmset(self.key, self.fields, self.values)
+        let Ok(reply) = backend.hmset(self.key.clone(), self.fields, self.values) else {
+            return RET_WRONGTYPE.clone();
+        };
+        backend.notify(NotifyFlags::HASH, "hset", &self.key);
+        reply
     }
 }
 
@@ -21,17 +25,17 @@ impl TryFrom<Vec<RespFrame>> for HSet {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        let mut expect_len = value.len() - 1;
-        if expect_len % 2 == 1 {
-            expect_len += 1;
+        // the key, then fields and values in pairs
+        if value.len().is_multiple_of(2) {
+            return Err(CommandError::WrongArity("hset".to_string()));
         }
-        validate_nums_of_argument(&value, "hset", expect_len + 1, 3)?;
+        let pairs = value.len() / 2;
         let mut frame_iter = value.into_iter();
-        let mut fields = Vec::with_capacity(expect_len / 2);
-        let mut values = Vec::with_capacity(expect_len / 2);
-        let key = extract_string(frame_iter.next())?;
-        for _ in 0..expect_len / 2 {
-            fields.push(extract_string(frame_iter.next())?);
+        let mut fields = Vec::with_capacity(pairs);
+        let mut values = Vec::with_capacity(pairs);
+        let key = extract_bytes(frame_iter.next())?;
+        for _ in 0..pairs {
+            fields.push(extract_bytes(frame_iter.next())?);
             values.push(extract_frame(frame_iter.next())?);
         }
 
@@ -40,7 +44,7 @@ impl TryFrom<Vec<RespFrame>> for HSet {
 }
 
 impl HSet {
-    pub fn new(key: String, fields: Vec<String>, values: Vec<RespFrame>) -> Self {
+    pub fn new(key: Bytes, fields: Vec<Bytes>, values: Vec<RespFrame>) -> Self {
         HSet {
             key,
             fields,
@@ -53,7 +57,7 @@ impl HSet {
 mod tests {
     use bytes::BytesMut;
 
-    use crate::{cmd::Command, BulkString, RespArray, RespDecode};
+    use crate::{cmd::Command, Backend, BulkString, RespArray, RespDecode};
 
     use super::*;
 
@@ -67,10 +71,23 @@ mod tests {
         assert_eq!(
             hset,
             Command::HSet(HSet::new(
-                "map".to_string(),
-                vec!["hello".to_string()],
+                Bytes::from_static(b"map"),
+                vec![Bytes::from_static(b"hello")],
                 vec![RespFrame::BulkString(BulkString::new(Some(b"world")))]
             ))
         )
     }
+
+    #[test]
+    fn test_hset_counts_each_field() {
+        let backend = Backend::new();
+        let fields = vec![Bytes::from("a"), Bytes::from("b")];
+        let values = vec![
+            BulkString::new(Some("1")).into(),
+            BulkString::new(Some("2")).into(),
+        ];
+        let hset = HSet::new(Bytes::from("map"), fields, values);
+        assert_eq!(hset.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.dirty(), 2);
+    }
 }
@@ -0,0 +1,165 @@
+use std::fmt::Write as _;
+
+use crate::{Backend, RespFrame, VerbatimString};
+
+use super::{extract_string, CommandError, CommandExecutor};
+
+/// Sections `INFO` knows about, in output order. Like Redis, unknown
+/// section names are ignored.
+const SECTIONS: &[&str] = &["memory", "stats", "replication", "cluster"];
+
+#[derive(Debug, PartialEq)]
+pub struct Info {
+    /// Lowercased section names; empty for the default set.
+    sections: Vec<String>,
+}
+
+impl CommandExecutor for Info {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let all = self.sections.is_empty()
+            || self
+                .sections
+                .iter()
+                .any(|section| matches!(section.as_str(), "all" | "default" | "everything"));
+        let mut info = String::new();
+        for section in SECTIONS {
+            if !all && !self.sections.iter().any(|name| name == section) {
+                continue;
+            }
+            if !info.is_empty() {
+                info.push_str("\r\n");
+            }
+            let (title, body) = match *section {
+                "memory" => ("Memory", memory_info(backend)),
+                "stats" => (
+                    "Stats",
+                    format!(
+                        "expired_keys:{}\r\nevicted_keys:{}\r\n",
+                        backend.expired_keys(),
+                        backend.evicted_keys()
+                    ),
+                ),
+                "replication" => (
+                    "Replication",
+                    backend
+                        .replication()
+                        .info(backend.config().replica_read_only()),
+                ),
+                "cluster" => (
+                    "Cluster",
+                    format!("cluster_enabled:{}\r\n", backend.cluster().is_some() as u8),
+                ),
+                _ => unreachable!(),
+            };
+            write!(info, "# {}\r\n{}", title, body).expect("write to string");
+        }
+        VerbatimString::new(*b"txt", info).into()
+    }
+}
+
+fn memory_info(backend: &Backend) -> String {
+    let used = backend.used_memory();
+    let peak = backend.peak_memory();
+    let config = backend.config();
+    let maxmemory = config.eviction().maxmemory;
+    let policy = config.get("maxmemory-policy").unwrap_or_default();
+    format!(
+        "used_memory:{}\r\nused_memory_human:{}\r\nused_memory_peak:{}\r\nused_memory_peak_human:{}\r\nmaxmemory:{}\r\nmaxmemory_human:{}\r\nmaxmemory_policy:{}\r\n",
+        used,
+        bytes_to_human(used),
+        peak,
+        bytes_to_human(peak),
+        maxmemory,
+        bytes_to_human(maxmemory),
+        policy
+    )
+}
+
+/// A byte count the way Redis's `bytesToHuman` prints it, e.g. `1.50M`.
+fn bytes_to_human(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}", value, UNITS[unit])
+}
+
+impl TryFrom<Vec<RespFrame>> for Info {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let sections = value
+            .into_iter()
+            .map(|frame| extract_string(Some(frame)).map(|name| name.to_ascii_lowercase()))
+            .collect::<Result<_, _>>()?;
+        Ok(Info::new(sections))
+    }
+}
+
+impl Info {
+    pub fn new(sections: Vec<String>) -> Self {
+        Info { sections }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(sections: &[&str], backend: &Backend) -> String {
+        let sections = sections.iter().map(|s| s.to_string()).collect();
+        match Info::new(sections).execute(backend) {
+            RespFrame::VerbatimString(info) => String::from_utf8(info.data.to_vec()).unwrap(),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_info_replication() {
+        let backend = Backend::new();
+        let replication = info(&["replication"], &backend);
+        assert!(replication.starts_with("# Replication\r\nrole:master\r\nconnected_slaves:0\r\n"));
+        assert!(replication.contains("\r\nmaster_repl_offset:0\r\n"));
+        assert!(replication.contains("\r\nrepl_backlog_active:0\r\n"));
+        let all = info(&[], &backend);
+        assert!(all.starts_with("# Memory\r\n"));
+        assert!(all.ends_with(&format!(
+            "\r\n{}\r\n# Cluster\r\ncluster_enabled:0\r\n",
+            replication
+        )));
+        assert_eq!(info(&["keyspace"], &backend), "");
+    }
+
+    #[test]
+    fn test_info_memory_and_stats() {
+        let backend = Backend::new();
+        assert_eq!(
+            info(&["memory"], &backend),
+            "# Memory\r\nused_memory:0\r\nused_memory_human:0B\r\nused_memory_peak:0\r\nused_memory_peak_human:0B\r\nmaxmemory:0\r\nmaxmemory_human:0B\r\nmaxmemory_policy:noeviction\r\n"
+        );
+        assert_eq!(
+            info(&["stats"], &backend),
+            "# Stats\r\nexpired_keys:0\r\nevicted_keys:0\r\n"
+        );
+        assert_eq!(bytes_to_human(1536), "1.50K");
+        assert_eq!(bytes_to_human(3 << 30), "3.00G");
+    }
+
+    #[test]
+    fn test_info_cluster() {
+        let backend = Backend::new();
+        let topology = "aaaa 127.0.0.1:7000@17000 master - 0 0 1 connected 0-16383\n";
+        backend
+            .set_cluster(crate::cluster::Cluster::parse(topology, &["127.0.0.1"], 7000).unwrap());
+        assert_eq!(
+            info(&["cluster"], &backend),
+            "# Cluster\r\ncluster_enabled:1\r\n"
+        );
+    }
+}
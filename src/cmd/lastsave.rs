@@ -0,0 +1,40 @@
+use crate::{Backend, RespFrame};
+
+use super::{CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct LastSave;
+
+impl CommandExecutor for LastSave {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.save_state().lastsave())
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for LastSave {
+    type Error = CommandError;
+
+    fn try_from(_value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(LastSave::new())
+    }
+}
+
+impl LastSave {
+    pub fn new() -> Self {
+        LastSave
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lastsave() {
+        let backend = Backend::new();
+        let RespFrame::Integer(lastsave) = LastSave::new().execute(&backend) else {
+            panic!("expect integer");
+        };
+        assert_eq!(lastsave, backend.save_state().lastsave());
+    }
+}
@@ -0,0 +1,228 @@
+use bytes::Bytes;
+
+use crate::{Backend, RespFrame, RespMap, SimpleString, VerbatimString};
+
+use super::{extract_bytes, extract_string, CommandError, CommandExecutor, RET_NULL};
+
+/// Below this the doctor has too little to go on.
+const DOCTOR_MIN_MEMORY: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub struct Memory {
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    /// `SAMPLES` is accepted for compatibility: sizes are tracked as values
+    /// change, so they are never estimated from a sample.
+    Usage(Bytes),
+    Stats,
+    Doctor,
+}
+
+impl CommandExecutor for Memory {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.subcommand {
+            Subcommand::Usage(key) => match backend.memory_usage(&key) {
+                Some(usage) => RespFrame::Integer(usage as i64),
+                None => RET_NULL.clone(),
+            },
+            Subcommand::Stats => stats(backend),
+            Subcommand::Doctor => VerbatimString::new(*b"txt", doctor(backend)).into(),
+        }
+    }
+}
+
+/// The breakdown of `used_memory` into per-key overhead and the dataset.
+fn stats(backend: &Backend) -> RespFrame {
+    let total = backend.used_memory();
+    let peak = backend.peak_memory().max(total);
+    let keys = backend.key_count();
+    let overhead = backend.memory_overhead().min(total);
+    let dataset = total - overhead;
+    let percentage = |part: usize, whole: usize| match whole {
+        0 => 0.0,
+        whole => part as f64 * 100.0 / whole as f64,
+    };
+
+    let mut map = RespMap::new();
+    let mut int = |name: &'static str, value: usize| {
+        map.insert(SimpleString::new(name), RespFrame::Integer(value as i64));
+    };
+    int("peak.allocated", peak);
+    int("total.allocated", total);
+    int("startup.allocated", 0);
+    int("overhead.total", overhead);
+    int("keys.count", keys);
+    int("keys.bytes-per-key", total.checked_div(keys).unwrap_or(0));
+    int("dataset.bytes", dataset);
+    map.insert(
+        SimpleString::new("dataset.percentage"),
+        RespFrame::Double(percentage(dataset, total)),
+    );
+    map.insert(
+        SimpleString::new("peak.percentage"),
+        RespFrame::Double(percentage(total, peak)),
+    );
+    if keys > 0 {
+        let mut db = RespMap::new();
+        db.insert(
+            SimpleString::new("overhead.hashtable.main"),
+            RespFrame::Integer(overhead as i64),
+        );
+        map.insert(SimpleString::new("db.0"), db.into());
+    }
+    map.into()
+}
+
+/// Redis's memory doctor, reduced to the issues an estimated dataset size
+/// can reveal.
+fn doctor(backend: &Backend) -> String {
+    let used = backend.used_memory();
+    if used < DOCTOR_MIN_MEMORY {
+        return "Hi Sam, this instance is empty or is using very little memory, my issues detector can't be used in these conditions. Please, leave for your mission on Earth and fill it with some data. The new Sam and I will be back to our programming as soon as I finished rebooting.".to_string();
+    }
+    let mut issues = String::new();
+    if backend.peak_memory() > used / 2 * 3 {
+        issues.push_str(" * Peak memory: In the past this instance used more than 150% the memory that is currently using. Memory freed since then is normally not returned to the operating system, so the process may look bigger than its dataset until it grows again.\n\n");
+    }
+    if issues.is_empty() {
+        return "Hi Sam, I can't find any memory issue in your instance. I can only account for what occurs on this base.".to_string();
+    }
+    format!(
+        "Sam, I detected a few issues in this Redis instance memory implants:\n\n{}I'm here to keep you safe, Sam. I want to help you.\n",
+        issues
+    )
+}
+
+impl TryFrom<Vec<RespFrame>> for Memory {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let subcommand = extract_string(args.next())?.to_ascii_lowercase();
+        let subcommand = match (subcommand.as_str(), args.len()) {
+            ("usage", _) => {
+                let key = extract_bytes(args.next())?;
+                while let Some(option) = args.next() {
+                    let option = extract_string(Some(option))?;
+                    if !option.eq_ignore_ascii_case("samples") || args.len() == 0 {
+                        return Err(CommandError::InvalidArgument("syntax error".to_string()));
+                    }
+                    let samples = extract_string(args.next())?.parse::<i64>().map_err(|_| {
+                        CommandError::InvalidArgument(
+                            "value is not an integer or out of range".to_string(),
+                        )
+                    })?;
+                    if samples < 0 {
+                        return Err(CommandError::InvalidArgument("syntax error".to_string()));
+                    }
+                }
+                Subcommand::Usage(key)
+            }
+            ("stats", 0) => Subcommand::Stats,
+            ("doctor", 0) => Subcommand::Doctor,
+            ("stats" | "doctor", _) => {
+                return Err(CommandError::WrongArity(format!("memory|{}", subcommand)))
+            }
+            _ => {
+                return Err(CommandError::UnknownSubcommand(
+                    subcommand,
+                    "MEMORY".to_string(),
+                ))
+            }
+        };
+        Ok(Memory::new(subcommand))
+    }
+}
+
+impl Memory {
+    pub fn new(subcommand: Subcommand) -> Self {
+        Memory { subcommand }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    fn memory(args: &[&str]) -> Result<Command, CommandError> {
+        let frames = std::iter::once("memory")
+            .chain(args.iter().copied())
+            .map(|arg| BulkString::new(Some(arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("key"), BulkString::new(Some("value")).into());
+        let usage = backend.used_memory() as i64;
+        let cmd = memory(&["usage", "key"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(usage));
+        let cmd = memory(&["USAGE", "key", "SAMPLES", "0"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(usage));
+        let cmd = memory(&["usage", "missing"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_NULL.clone());
+
+        for (args, error) in [
+            (&["usage", "key", "samples"][..], "ERR syntax error"),
+            (&["usage", "key", "samples", "-1"], "ERR syntax error"),
+            (&["usage", "key", "count", "1"], "ERR syntax error"),
+            (
+                &["usage", "key", "samples", "x"],
+                "ERR value is not an integer or out of range",
+            ),
+            (
+                &["stats", "now"],
+                "ERR wrong number of arguments for 'memory|stats' command",
+            ),
+            (
+                &["purge"],
+                "ERR unknown subcommand 'purge'. Try MEMORY HELP.",
+            ),
+        ] {
+            assert_eq!(memory(args).unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn test_memory_stats() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("a"), BulkString::new(Some("1")).into());
+        backend
+            .sadd(Bytes::from("b"), vec![Bytes::from("x")])
+            .unwrap();
+        let RespFrame::Map(stats) = memory(&["stats"]).unwrap().execute(&backend) else {
+            panic!("MEMORY STATS should reply with a map");
+        };
+        let int = |name: &'static str| match stats.get(&SimpleString::new(name)) {
+            Some(RespFrame::Integer(value)) => *value as usize,
+            value => panic!("unexpected {} {:?}", name, value),
+        };
+        assert_eq!(int("keys.count"), 2);
+        assert_eq!(int("total.allocated"), backend.used_memory());
+        assert_eq!(
+            int("overhead.total") + int("dataset.bytes"),
+            int("total.allocated")
+        );
+        assert!(stats.contains_key(&SimpleString::new("db.0")));
+    }
+
+    #[test]
+    fn test_memory_doctor() {
+        let backend = Backend::new();
+        assert!(doctor(&backend).starts_with("Hi Sam, this instance is empty"));
+
+        let big = |size: usize| BulkString::new(Some(vec![b'x'; size])).into();
+        backend.set(Bytes::from("big"), big(10 * 1024 * 1024));
+        assert!(doctor(&backend).starts_with("Hi Sam, I can't find any memory issue"));
+        backend.remove(b"big");
+        backend.set(Bytes::from("smaller"), big(6 * 1024 * 1024));
+        assert!(doctor(&backend).contains(" * Peak memory:"));
+    }
+}
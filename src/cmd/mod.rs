@@ -1,55 +1,128 @@
+mod asking;
+mod bgrewriteaof;
+mod bgsave;
+mod cluster;
+mod command;
+mod config;
+mod del;
+mod dump;
 mod echo;
 mod get;
+mod hello;
 mod hget;
 mod hgetall;
 mod hmget;
 mod hset;
+mod info;
+mod lastsave;
+mod memory;
+mod object;
+mod ping;
+mod psync;
+mod publish;
+mod replconf;
+mod replicaof;
+mod restore;
+mod role;
 mod sadd;
+mod save;
 mod set;
 mod sismember;
+mod spec;
+mod subscribe;
+mod unsubscribe;
 use std::string::FromUtf8Error;
 
 use crate::Backend;
 use crate::BulkString;
+use crate::Client;
 use crate::RespArray;
 use crate::RespError;
 use crate::RespFrame;
+use crate::RespVersion;
+use crate::SimpleError;
 use crate::SimpleString;
+use bytes::Bytes;
 use enum_dispatch::enum_dispatch;
 use thiserror::Error;
 
+use self::asking::Asking;
+use self::bgrewriteaof::BgRewriteAof;
+use self::bgsave::BgSave;
+use self::cluster::ClusterCmd;
+use self::command::CommandCmd;
+use self::config::ConfigCmd;
+use self::del::Del;
+use self::dump::Dump;
 use self::echo::*;
 use self::get::Get;
+use self::hello::Hello;
 use self::hget::HGet;
 use self::hgetall::HGetAll;
 use self::hmget::Hmget;
 use self::hset::HSet;
+use self::info::Info;
+use self::lastsave::LastSave;
+use self::memory::Memory;
+use self::object::Object;
+use self::ping::Ping;
+use self::psync::Psync;
+use self::publish::Publish;
+use self::replconf::ReplConf;
+use self::replicaof::ReplicaOf;
+use self::restore::Restore;
+use self::role::Role;
 use self::sadd::Sadd;
+use self::save::Save;
 use self::set::Set;
 use self::sismember::Sismember;
+use self::spec::lookup_top_level;
+pub use self::spec::CommandSpec;
+use self::subscribe::{Subscribe, SubscriptionKind};
+use self::unsubscribe::Unsubscribe;
+
+pub use self::hello::SERVER_VERSION;
 use lazy_static::lazy_static;
 
 lazy_static! {
     static ref RET_NULL: RespFrame = RespFrame::BulkString(BulkString::new(None::<Vec<_>>));
     static ref RET_NULL_ARRAY: RespFrame = RespFrame::Array(RespArray::new(Some([])));
     static ref RET_OK: RespFrame = RespFrame::SimpleString(SimpleString::new("OK"));
+    static ref RET_WRONGTYPE: RespFrame =
+        RespFrame::SimpleError(SimpleError::new(CommandError::WrongType.to_string()));
 }
 
 #[derive(Error, Debug)]
 pub enum CommandError {
-    #[error("Invalid command: {0}")]
+    #[error("ERR unknown command '{0}', with args beginning with: {1}")]
+    UnknownCommand(String, String),
+    #[error("ERR unknown subcommand '{0}'. Try {1} HELP.")]
+    UnknownSubcommand(String, String),
+    #[error("ERR wrong number of arguments for '{0}' command")]
+    WrongArity(String),
+    #[error("ERR {0}")]
     InvalidCommand(String),
-    #[error("Invalid argument: {0}")]
+    #[error("ERR {0}")]
     InvalidArgument(String),
-    #[error("{0}")]
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("NOPROTO unsupported protocol version")]
+    NoProto,
+    #[error("ERR {0}")]
     RespError(#[from] RespError),
-    #[error("{0}")]
+    #[error("ERR {0}")]
     FromUtf8Error(#[from] FromUtf8Error),
 }
 
 #[enum_dispatch]
 pub trait CommandExecutor {
     fn execute(self, backend: &Backend) -> RespFrame;
+
+    /// The request as it is written to the AOF. Commands whose effect
+    /// depends on when they run record it in a form that replays the same.
+    fn propagated(&self, request: RespFrame) -> RespFrame {
+        request
+    }
 }
 
 #[enum_dispatch(CommandExecutor)]
@@ -63,46 +136,130 @@ pub enum Command {
     HGetAll(HGetAll),
     Sadd(Sadd),
     Sismember(Sismember),
+    Del(Del),
+    Dump(Dump),
+    Restore(Restore),
     Echo(Echo),
-    Unrecognized(Unrecognized),
+    Command(CommandCmd),
+    Config(ConfigCmd),
+    Hello(Hello),
+    Save(Save),
+    BgSave(BgSave),
+    BgRewriteAof(BgRewriteAof),
+    LastSave(LastSave),
+    Ping(Ping),
+    Info(Info),
+    ReplicaOf(ReplicaOf),
+    Psync(Psync),
+    ReplConf(ReplConf),
+    Role(Role),
+    Cluster(ClusterCmd),
+    Asking(Asking),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    Publish(Publish),
+    Object(Object),
+    Memory(Memory),
 }
 
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
 
     fn try_from(value: RespFrame) -> Result<Self, Self::Error> {
+        let spec = command_spec(&value);
+        Command::parse(value, spec)
+    }
+}
+
+impl TryFrom<RespArray> for Command {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let spec = array_spec(&value);
+        Command::from_array(value, spec)
+    }
+}
+
+impl Command {
+    /// Parse a request whose spec was already looked up with
+    /// [`command_spec`].
+    pub fn parse(
+        value: RespFrame,
+        spec: Option<&'static CommandSpec>,
+    ) -> Result<Self, CommandError> {
         match value {
-            RespFrame::Array(array) => Ok(array.try_into()?),
+            RespFrame::Array(array) => Command::from_array(array, spec),
             _ => Err(CommandError::InvalidCommand(format!(
                 "unsupported frame: {:?}",
                 value
             ))),
         }
     }
-}
-
-impl TryFrom<RespArray> for Command {
-    type Error = CommandError;
 
-    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+    fn from_array(
+        value: RespArray,
+        spec: Option<&'static CommandSpec>,
+    ) -> Result<Self, CommandError> {
         validate_command(&value)?;
         let (cmd, frames) = extract_cmd_and_argument(value);
-        println!("{:?}", frames);
-        match cmd.as_slice() {
-            b"get" => Ok(Get::try_from(frames)?.into()),
-            b"set" => Ok(Set::try_from(frames)?.into()),
-            b"hget" => Ok(HGet::try_from(frames)?.into()),
-            b"hmget" => Ok(Hmget::try_from(frames)?.into()),
-            b"hset" => Ok(HSet::try_from(frames)?.into()),
-            b"hgetall" => Ok(HGetAll::try_from(frames)?.into()),
-            b"sadd" => Ok(Sadd::try_from(frames)?.into()),
-            b"sismember" => Ok(Sismember::try_from(frames)?.into()),
-            b"echo" => Ok(Echo::try_from(frames)?.into()),
-            _ => Ok(Unrecognized.into()),
+        let spec = validate_arity(spec, &cmd, &frames)?;
+        match spec.name {
+            "get" => Ok(Get::try_from(frames)?.into()),
+            "set" => Ok(Set::try_from(frames)?.into()),
+            "hget" => Ok(HGet::try_from(frames)?.into()),
+            "hmget" => Ok(Hmget::try_from(frames)?.into()),
+            "hset" => Ok(HSet::try_from(frames)?.into()),
+            "hgetall" => Ok(HGetAll::try_from(frames)?.into()),
+            "sadd" => Ok(Sadd::try_from(frames)?.into()),
+            "sismember" => Ok(Sismember::try_from(frames)?.into()),
+            "del" => Ok(Del::try_from(frames)?.into()),
+            "dump" => Ok(Dump::try_from(frames)?.into()),
+            "restore" => Ok(Restore::try_from(frames)?.into()),
+            "echo" => Ok(Echo::try_from(frames)?.into()),
+            "command" => Ok(CommandCmd::try_from(frames)?.into()),
+            "config" => Ok(ConfigCmd::try_from(frames)?.into()),
+            "hello" => Ok(Hello::try_from(frames)?.into()),
+            "save" => Ok(Save::try_from(frames)?.into()),
+            "bgsave" => Ok(BgSave::try_from(frames)?.into()),
+            "bgrewriteaof" => Ok(BgRewriteAof::try_from(frames)?.into()),
+            "lastsave" => Ok(LastSave::try_from(frames)?.into()),
+            "ping" => Ok(Ping::try_from(frames)?.into()),
+            "info" => Ok(Info::try_from(frames)?.into()),
+            "replicaof" | "slaveof" => Ok(ReplicaOf::try_from(frames)?.into()),
+            "psync" => Ok(Psync::try_from(frames)?.into()),
+            "replconf" => Ok(ReplConf::try_from(frames)?.into()),
+            "role" => Ok(Role::try_from(frames)?.into()),
+            "cluster" => Ok(ClusterCmd::try_from(frames)?.into()),
+            "asking" => Ok(Asking::try_from(frames)?.into()),
+            "subscribe" => Ok(Subscribe::parse(frames, SubscriptionKind::Channel)?.into()),
+            "psubscribe" => Ok(Subscribe::parse(frames, SubscriptionKind::Pattern)?.into()),
+            "unsubscribe" => Ok(Unsubscribe::parse(frames, SubscriptionKind::Channel)?.into()),
+            "punsubscribe" => Ok(Unsubscribe::parse(frames, SubscriptionKind::Pattern)?.into()),
+            "publish" => Ok(Publish::try_from(frames)?.into()),
+            "object" => Ok(Object::try_from(frames)?.into()),
+            "memory" => Ok(Memory::try_from(frames)?.into()),
+            _ => Err(unknown_command(&cmd, &frames)),
         }
     }
 }
 
+/// The command table entry of the command a request names; for container
+/// commands the container's. Looked up once per request and passed to
+/// every step that needs it.
+pub fn command_spec(frame: &RespFrame) -> Option<&'static CommandSpec> {
+    match frame {
+        RespFrame::Array(array) => array_spec(array),
+        _ => None,
+    }
+}
+
+fn array_spec(array: &RespArray) -> Option<&'static CommandSpec> {
+    match array.as_deref()?.first()? {
+        RespFrame::BulkString(BulkString(Some(name))) => lookup_top_level(name),
+        _ => None,
+    }
+}
+
 fn validate_command(resp_array: &RespArray) -> Result<(), CommandError> {
     // test if the array is a null array
     let frames = resp_array.as_deref().ok_or(CommandError::InvalidCommand(
@@ -130,7 +287,129 @@ fn validate_command(resp_array: &RespArray) -> Result<(), CommandError> {
     Ok(())
 }
 
-fn extract_cmd_and_argument(array: RespArray) -> (Vec<u8>, Vec<RespFrame>) {
+/// Run a request for `client`. Write commands hold the backend's write
+/// guard until they have been propagated, so a snapshot never falls between
+/// a change and its entry in the AOF or the replication stream.
+pub fn execute_request(
+    frame: RespFrame,
+    spec: Option<&'static CommandSpec>,
+    backend: &Backend,
+    client: &mut Client,
+) -> RespFrame {
+    let write = spec.is_some_and(|spec| spec.has_flag("write"));
+    let request = write.then(|| frame.clone());
+    // commands that may grow the dataset, refused when it can't be shrunk
+    // below `maxmemory`
+    let denyoom = write && spec.is_some_and(|spec| spec.has_flag("denyoom"));
+    let keys = backend.cluster().map(|_| request_keys(&frame, spec));
+    // `ASKING` only covers the command right after it
+    let asking = std::mem::take(&mut client.asking);
+    // a subscribed RESP2 connection only carries pub/sub traffic
+    let subscribed = (client.protocol == RespVersion::Resp2 && client.subscriptions.count() > 0)
+        .then(|| request_summary(&frame, spec).map(|(name, _)| name))
+        .flatten();
+    let _guard = request.is_some().then(|| backend.write_guard());
+    let cmd = match Command::parse(frame, spec) {
+        Ok(cmd) => cmd,
+        Err(e) => return RespFrame::SimpleError(SimpleError::new(e.to_string())),
+    };
+    if let Some(name) = subscribed {
+        if !matches!(
+            cmd,
+            Command::Subscribe(_) | Command::Unsubscribe(_) | Command::Ping(_)
+        ) {
+            return SimpleError::new(format!(
+                "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+                name
+            ))
+            .into();
+        }
+    }
+    if let (Some(cluster), Some(keys)) = (backend.cluster(), keys) {
+        if let Err(redirect) = cluster.check_keys(&keys, asking, |key| backend.exists(key)) {
+            return SimpleError::new(redirect).into();
+        }
+    }
+    if request.is_some()
+        && backend.config().replica_read_only()
+        && backend.replication().is_replica()
+    {
+        return SimpleError::new("READONLY You can't write against a read only replica.").into();
+    }
+    // replicas ignore maxmemory, like Redis with replica-ignore-maxmemory
+    if request.is_some()
+        && !backend.replication().is_replica()
+        && !backend.evict_if_needed()
+        && denyoom
+    {
+        return SimpleError::new("OOM command not allowed when used memory > 'maxmemory'.").into();
+    }
+    let propagated = request.map(|request| cmd.propagated(request));
+    let changes = backend.changes();
+    let reply = match cmd {
+        // these change connection state, so they run against the client
+        Command::Hello(hello) => hello.apply(backend, client),
+        Command::Psync(psync) => psync.apply(backend, client),
+        Command::ReplConf(replconf) => replconf.apply(client),
+        Command::Asking(asking) => asking.apply(backend, client),
+        Command::Subscribe(subscribe) => subscribe.apply(backend, client),
+        Command::Unsubscribe(unsubscribe) => unsubscribe.apply(backend, client),
+        Command::Ping(ping) => ping.apply(backend, client),
+        cmd => cmd.execute(backend),
+    };
+    // like Redis, only commands that changed something reach the AOF and
+    // the replicas; a concurrent writer can at worst let a no-op through
+    if let Some(frame) = propagated {
+        if !matches!(reply, RespFrame::SimpleError(_)) && backend.changes() != changes {
+            backend.propagate(&frame);
+        }
+    }
+    reply
+}
+
+/// Lowercased command name and number of key arguments of a request, for
+/// logging. Returns `None` when the frame is not a command.
+pub fn request_summary(
+    frame: &RespFrame,
+    spec: Option<&'static CommandSpec>,
+) -> Option<(String, usize)> {
+    let args = request_args(frame)?;
+    let name = String::from_utf8_lossy(args.first()?).to_ascii_lowercase();
+    let keys = spec
+        .and_then(|spec| spec.for_args(&args))
+        .map(|spec| spec.extract_keys(&args).len())
+        .unwrap_or(0);
+    Some((name, keys))
+}
+
+/// The key arguments of a request, which decide the slot in cluster mode.
+fn request_keys(frame: &RespFrame, spec: Option<&'static CommandSpec>) -> Vec<Bytes> {
+    let (Some(spec), Some(args)) = (spec, request_args(frame)) else {
+        return vec![];
+    };
+    let Some(spec) = spec.for_args(&args) else {
+        return vec![];
+    };
+    spec.extract_keys(&args)
+        .into_iter()
+        .map(Bytes::copy_from_slice)
+        .collect()
+}
+
+fn request_args(frame: &RespFrame) -> Option<Vec<Bytes>> {
+    let RespFrame::Array(RespArray(Some(frames))) = frame else {
+        return None;
+    };
+    frames
+        .iter()
+        .map(|frame| match frame {
+            RespFrame::BulkString(BulkString(Some(arg))) => Some(arg.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn extract_cmd_and_argument(array: RespArray) -> (Bytes, Vec<RespFrame>) {
     let mut array_iter = match array {
         RespArray(Some(array)) => array.into_iter(),
         _ => unreachable!(),
@@ -141,52 +420,246 @@ fn extract_cmd_and_argument(array: RespArray) -> (Vec<u8>, Vec<RespFrame>) {
         RespFrame::BulkString(BulkString(Some(cmd))) => cmd,
         _ => unreachable!(),
     };
-    (cmd.to_ascii_lowercase(), array_iter.collect())
+    (cmd, array_iter.collect())
 }
 
 fn extract_string(frame: Option<RespFrame>) -> Result<String, CommandError> {
     frame
         .map(|f| match f {
-            RespFrame::BulkString(BulkString(Some(key))) => String::from_utf8(key).ok(),
+            RespFrame::BulkString(BulkString(Some(key))) => String::from_utf8(key.into()).ok(),
             _ => None,
         })
         .ok_or(CommandError::InvalidCommand("None".to_string()))?
         .ok_or(CommandError::InvalidCommand("None".to_string()))
 }
 
+fn extract_bytes(frame: Option<RespFrame>) -> Result<Bytes, CommandError> {
+    match frame {
+        Some(RespFrame::BulkString(BulkString(Some(bytes)))) => Ok(bytes),
+        _ => Err(CommandError::InvalidCommand("None".to_string())),
+    }
+}
+
 fn extract_frame(frame: Option<RespFrame>) -> Result<RespFrame, CommandError> {
     frame.ok_or(CommandError::InvalidCommand("None".to_string()))
 }
 
-fn validate_nums_of_argument(
-    frames: &[RespFrame],
-    validate_type: &str,
-    expect_num: usize,
-    min_num: usize,
-) -> Result<(), CommandError> {
-    if frames.len() < min_num {
-        return Err(CommandError::InvalidArgument(format!(
-            "wrong number of arguments for '{}' command, at least: {}, got: {}",
-            validate_type,
-            min_num,
-            frames.len()
-        )));
-    }
-    if frames.len() != expect_num {
-        return Err(CommandError::InvalidArgument(format!(
-            "wrong number of arguments for '{}' command, expect: {}, got: {}",
-            validate_type,
-            expect_num,
-            frames.len()
-        )));
+/// Check the argument count against the command table before the command's
+/// own `TryFrom<Vec<RespFrame>>` runs. Container commands such as `COMMAND`
+/// are checked against the arity of their subcommand. Returns the spec of
+/// a known command.
+fn validate_arity(
+    spec: Option<&'static CommandSpec>,
+    cmd: &[u8],
+    args: &[RespFrame],
+) -> Result<&'static CommandSpec, CommandError> {
+    let spec = spec.ok_or_else(|| unknown_command(cmd, args))?;
+    if !spec.check_arity(args.len() + 1) {
+        return Err(CommandError::WrongArity(spec.name.to_string()));
     }
-    Ok(())
+    if spec.subcommands.is_empty() {
+        return Ok(spec);
+    }
+    let Some(RespFrame::BulkString(BulkString(Some(sub)))) = args.first() else {
+        return Ok(spec);
+    };
+    let sub_spec = spec.subcommand(sub).ok_or_else(|| {
+        CommandError::UnknownSubcommand(
+            String::from_utf8_lossy(sub).to_string(),
+            spec.name.to_ascii_uppercase(),
+        )
+    })?;
+    if !sub_spec.check_arity(args.len() + 1) {
+        return Err(CommandError::WrongArity(sub_spec.name.to_string()));
+    }
+    Ok(spec)
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Unrecognized;
-impl CommandExecutor for Unrecognized {
-    fn execute(self, _backend: &Backend) -> RespFrame {
-        RET_OK.clone()
+/// Build Redis's unknown command error, quoting arguments until 128 bytes
+/// of them have been printed.
+fn unknown_command(cmd: &[u8], args: &[RespFrame]) -> CommandError {
+    let mut quoted = String::new();
+    for arg in args {
+        if quoted.len() >= 128 {
+            break;
+        }
+        if let RespFrame::BulkString(BulkString(Some(arg))) = arg {
+            // cut on bytes like Redis's `%.*s`, before any lossy conversion
+            let remaining = 128 - quoted.len();
+            let arg = String::from_utf8_lossy(&arg[..arg.len().min(remaining)]);
+            quoted.push_str(&format!("'{}' ", arg));
+        }
+    }
+    let cmd = String::from_utf8_lossy(&cmd[..cmd.len().min(128)]);
+    CommandError::UnknownCommand(cmd.into_owned(), quoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{RespDecode, RespEncode};
+
+    use super::*;
+
+    fn parse(input: &[u8]) -> Result<Command, CommandError> {
+        let mut buf = BytesMut::from(input);
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        Command::try_from(array)
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let err = parse(b"*3\r\n$4\r\nSETT\r\n$1\r\nk\r\n$1\r\nv\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown command 'SETT', with args beginning with: 'k' 'v' "
+        );
+
+        let err = parse(b"*1\r\n$3\r\nfoo\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown command 'foo', with args beginning with: "
+        );
+
+        // the 128 byte limits count bytes, not characters
+        let wide = "é".repeat(100);
+        let request = RespArray::new(Some(vec![
+            BulkString::new(Some(wide.clone())).into(),
+            BulkString::new(Some(wide)).into(),
+        ]));
+        let CommandError::UnknownCommand(cmd, args) = Command::try_from(request).unwrap_err()
+        else {
+            panic!("expect unknown command");
+        };
+        assert_eq!(cmd, "é".repeat(64));
+        assert_eq!(args, format!("'{}' ", "é".repeat(64)));
+    }
+
+    #[test]
+    fn test_errors_quoting_input_stay_on_one_line() {
+        let backend = Backend::new();
+        let mut client = Client::new();
+        for (request, reply) in [
+            (
+                &b"*2\r\n$7\r\ncommand\r\n$8\r\nx\r\n:1\r\nz\r\n"[..],
+                &b"-ERR unknown subcommand 'x  :1  z'. Try COMMAND HELP.\r\n"[..],
+            ),
+            (
+                b"*1\r\n$8\r\nx\r\n:1\r\nz\r\n",
+                b"-ERR unknown command 'x  :1  z', with args beginning with: \r\n",
+            ),
+        ] {
+            let frame = RespFrame::decode(&mut BytesMut::from(request)).unwrap();
+            let spec = command_spec(&frame);
+            assert_eq!(
+                execute_request(frame, spec, &backend, &mut client).encode(),
+                reply
+            );
+        }
+    }
+
+    #[test]
+    fn test_commands_against_another_type() {
+        let backend = Backend::new();
+        let mut client = Client::new();
+        let wrongtype = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+        for (request, reply) in [
+            (
+                &b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n"[..],
+                &b"+OK\r\n"[..],
+            ),
+            (
+                b"*4\r\n$4\r\nhset\r\n$1\r\nk\r\n$1\r\nf\r\n$1\r\nv\r\n",
+                wrongtype,
+            ),
+            (b"*3\r\n$4\r\nsadd\r\n$1\r\nk\r\n$1\r\nm\r\n", wrongtype),
+            (b"*2\r\n$7\r\nhgetall\r\n$1\r\nk\r\n", wrongtype),
+            (
+                b"*4\r\n$4\r\nhset\r\n$1\r\nh\r\n$1\r\nf\r\n$1\r\nv\r\n",
+                b":1\r\n",
+            ),
+            (b"*2\r\n$3\r\nget\r\n$1\r\nh\r\n", wrongtype),
+            (
+                b"*3\r\n$4\r\nhget\r\n$1\r\nh\r\n$1\r\nf\r\n",
+                b"$1\r\nv\r\n",
+            ),
+            (
+                b"*3\r\n$9\r\nsismember\r\n$1\r\nh\r\n$1\r\nf\r\n",
+                wrongtype,
+            ),
+            // SET replaces a value of any type
+            (b"*3\r\n$3\r\nset\r\n$1\r\nh\r\n$1\r\ns\r\n", b"+OK\r\n"),
+            (b"*2\r\n$3\r\nget\r\n$1\r\nh\r\n", b"$1\r\ns\r\n"),
+            (b"*3\r\n$5\r\nhmget\r\n$1\r\nh\r\n$1\r\nf\r\n", wrongtype),
+        ] {
+            let frame = RespFrame::decode(&mut BytesMut::from(request)).unwrap();
+            let spec = command_spec(&frame);
+            assert_eq!(
+                execute_request(frame, spec, &backend, &mut client).encode(),
+                reply
+            );
+        }
+        assert_eq!(backend.key_count(), 2);
+    }
+
+    #[test]
+    fn test_only_changes_are_propagated() {
+        let backend = Backend::new();
+        let mut client = Client::new();
+        crate::replication::psync(&backend, &client, "?", -1).unwrap();
+        for (request, propagated) in [
+            (&b"*3\r\n$4\r\nsadd\r\n$1\r\ns\r\n$1\r\nm\r\n"[..], true),
+            (b"*3\r\n$4\r\nsadd\r\n$1\r\ns\r\n$1\r\nm\r\n", false),
+            (b"*2\r\n$3\r\ndel\r\n$7\r\nmissing\r\n", false),
+            (b"*2\r\n$3\r\ndel\r\n$1\r\ns\r\n", true),
+        ] {
+            let offset = backend.replication().offset();
+            let frame = RespFrame::decode(&mut BytesMut::from(request)).unwrap();
+            let spec = command_spec(&frame);
+            execute_request(frame, spec, &backend, &mut client);
+            let expected = if propagated { request.len() as i64 } else { 0 };
+            assert_eq!(backend.replication().offset(), offset + expected);
+        }
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        let err = parse(b"*1\r\n$3\r\nGET\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'get' command"
+        );
+
+        let err = parse(b"*3\r\n$4\r\nhset\r\n$1\r\nk\r\n$1\r\nf\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'hset' command"
+        );
+
+        let err = parse(b"*3\r\n$7\r\ncommand\r\n$5\r\ncount\r\n$1\r\nx\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'command|count' command"
+        );
+    }
+
+    #[test]
+    fn test_unknown_subcommand() {
+        let err = parse(b"*2\r\n$7\r\ncommand\r\n$3\r\nfoo\r\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown subcommand 'foo'. Try COMMAND HELP."
+        );
+    }
+
+    #[test]
+    fn test_error_prefixes() {
+        assert!(CommandError::WrongType
+            .to_string()
+            .starts_with("WRONGTYPE "));
+        assert!(CommandError::InvalidArgument("syntax error".to_string())
+            .to_string()
+            .starts_with("ERR "));
     }
 }
@@ -0,0 +1,165 @@
+use bytes::Bytes;
+
+use crate::{backend::now_ms, Backend, BulkString, RespFrame, SimpleError};
+
+use super::{extract_bytes, extract_string, CommandError, CommandExecutor, RET_NULL};
+
+#[derive(Debug, PartialEq)]
+pub struct Object {
+    subcommand: Subcommand,
+    key: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    Encoding,
+    Freq,
+    IdleTime,
+    RefCount,
+}
+
+impl CommandExecutor for Object {
+    /// None of these count as an access to the key.
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if !backend.exists(&self.key) {
+            return RET_NULL.clone();
+        }
+        let lfu_policy = backend.config().eviction().policy.lfu();
+        match self.subcommand {
+            Subcommand::Encoding => match backend.encoding(&self.key) {
+                Some(encoding) => BulkString::new(Some(encoding)).into(),
+                None => RET_NULL.clone(),
+            },
+            Subcommand::Freq if !lfu_policy => SimpleError::new(
+                "ERR An LFU maxmemory policy is not selected, access frequency not tracked. Please note that when switching between policies at runtime LRU and LFU data will take some time to adjust.",
+            )
+            .into(),
+            Subcommand::IdleTime if lfu_policy => SimpleError::new(
+                "ERR An LFU maxmemory policy is selected, idle time not tracked. Please note that when switching between policies at runtime LRU and LFU data will take some time to adjust.",
+            )
+            .into(),
+            Subcommand::Freq => match backend.access(&self.key) {
+                Some(access) => {
+                    let lfu = backend.config().lfu();
+                    RespFrame::Integer(access.decayed_counter(now_ms(), &lfu) as i64)
+                }
+                None => RET_NULL.clone(),
+            },
+            Subcommand::IdleTime => match backend.access(&self.key) {
+                Some(access) => RespFrame::Integer((now_ms() - access.at).max(0) / 1000),
+                None => RET_NULL.clone(),
+            },
+            // values are never shared between keys
+            Subcommand::RefCount => RespFrame::Integer(1),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Object {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let subcommand = extract_string(args.next())?.to_ascii_lowercase();
+        let subcommand = match subcommand.as_str() {
+            "encoding" => Subcommand::Encoding,
+            "freq" => Subcommand::Freq,
+            "idletime" => Subcommand::IdleTime,
+            "refcount" => Subcommand::RefCount,
+            _ => {
+                return Err(CommandError::UnknownSubcommand(
+                    subcommand,
+                    "OBJECT".to_string(),
+                ))
+            }
+        };
+        // the arity was checked against the subcommand
+        let key = extract_bytes(args.next())?;
+        Ok(Object::new(subcommand, key))
+    }
+}
+
+impl Object {
+    pub fn new(subcommand: Subcommand, key: Bytes) -> Self {
+        Object { subcommand, key }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, Config, RespArray};
+
+    use super::*;
+
+    fn object(args: &[&str]) -> Result<Command, CommandError> {
+        let frames = std::iter::once("object")
+            .chain(args.iter().copied())
+            .map(|arg| BulkString::new(Some(arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    fn bulk(value: &str) -> RespFrame {
+        BulkString::new(Some(value)).into()
+    }
+
+    #[test]
+    fn test_object_encoding() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("int"), bulk("42"));
+        backend.set(Bytes::from("str"), bulk("hello"));
+        backend.set(Bytes::from("long"), bulk(&"x".repeat(64)));
+        backend
+            .sadd(Bytes::from("set"), vec![Bytes::from("1")])
+            .unwrap();
+        for (key, encoding) in [
+            ("int", "int"),
+            ("str", "embstr"),
+            ("long", "raw"),
+            ("set", "intset"),
+        ] {
+            let cmd = object(&["encoding", key]).unwrap();
+            assert_eq!(cmd.execute(&backend), bulk(encoding));
+        }
+        let cmd = object(&["ENCODING", "missing"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_NULL.clone());
+        let cmd = object(&["refcount", "str"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        assert_eq!(
+            object(&["encoding"]).unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'object|encoding' command"
+        );
+        assert_eq!(
+            object(&["lru", "str"]).unwrap_err().to_string(),
+            "ERR unknown subcommand 'lru'. Try OBJECT HELP."
+        );
+    }
+
+    #[test]
+    fn test_object_idletime_and_freq() {
+        let backend = Backend::new();
+        backend.set(Bytes::from("k"), bulk("v"));
+        let cmd = object(&["idletime", "k"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd = object(&["freq", "k"]).unwrap();
+        assert!(matches!(
+            cmd.execute(&backend),
+            RespFrame::SimpleError(e) if e.starts_with("ERR An LFU maxmemory policy is not selected")
+        ));
+
+        let mut config = Config::default();
+        config
+            .set(&[("maxmemory-policy".to_string(), "allkeys-lfu".to_string())])
+            .unwrap();
+        let backend = Backend::with_config(config);
+        backend.set(Bytes::from("k"), bulk("v"));
+        let cmd = object(&["freq", "k"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(5));
+        let cmd = object(&["idletime", "k"]).unwrap();
+        assert!(matches!(
+            cmd.execute(&backend),
+            RespFrame::SimpleError(e) if e.starts_with("ERR An LFU maxmemory policy is selected")
+        ));
+    }
+}
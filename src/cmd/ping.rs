@@ -0,0 +1,85 @@
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, Client, RespArray, RespFrame, RespVersion, SimpleString};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct Ping {
+    message: Option<Bytes>,
+}
+
+impl CommandExecutor for Ping {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.message {
+            Some(message) => RespFrame::BulkString(message.into()),
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Ping {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        if value.len() > 1 {
+            return Err(CommandError::WrongArity("ping".to_string()));
+        }
+        let message = match value.into_iter().next() {
+            Some(frame) => Some(extract_bytes(Some(frame))?),
+            None => None,
+        };
+        Ok(Ping::new(message))
+    }
+}
+
+impl Ping {
+    pub fn new(message: Option<Bytes>) -> Self {
+        Ping { message }
+    }
+
+    /// A subscribed RESP2 connection only carries pub/sub frames, so the
+    /// reply there is the array `pong <message>`.
+    pub fn apply(self, backend: &Backend, client: &Client) -> RespFrame {
+        if client.protocol != RespVersion::Resp2 || client.subscriptions.count() == 0 {
+            return self.execute(backend);
+        }
+        let message = self.message.unwrap_or_default();
+        RespArray::new(Some(vec![
+            BulkString::new(Some("pong")).into(),
+            BulkString::from(message).into(),
+        ]))
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    #[test]
+    fn test_cmd_ping() {
+        let backend = Backend::new();
+        let ping = |args: &[&str]| {
+            let frames = std::iter::once("ping")
+                .chain(args.iter().copied())
+                .map(|arg| BulkString::new(Some(arg)).into())
+                .collect::<Vec<RespFrame>>();
+            Command::try_from(RespArray::new(Some(frames)))
+        };
+        assert_eq!(
+            ping(&[]).unwrap().execute(&backend),
+            SimpleString::new("PONG").into()
+        );
+        assert_eq!(
+            ping(&["hi"]).unwrap().execute(&backend),
+            BulkString::new(Some("hi")).into()
+        );
+        assert_eq!(
+            ping(&["a", "b"]).unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'ping' command"
+        );
+    }
+}
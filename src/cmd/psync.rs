@@ -0,0 +1,97 @@
+use crate::{replication, Backend, Client, RespFrame, SimpleError, SimpleString};
+
+use super::{extract_string, CommandError, CommandExecutor};
+
+/// `PSYNC <replid> <offset>`, sent by a replica to start replicating.
+#[derive(Debug, PartialEq)]
+pub struct Psync {
+    replid: String,
+    offset: i64,
+}
+
+impl CommandExecutor for Psync {
+    /// Replication streams over the connection that asked for it.
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR PSYNC is only valid on a client connection").into()
+    }
+}
+
+impl Psync {
+    pub fn new(replid: String, offset: i64) -> Self {
+        Psync { replid, offset }
+    }
+
+    /// Attach the connection as a replica. The sync starts once the
+    /// `FULLRESYNC` or `CONTINUE` reply has been written.
+    pub fn apply(self, backend: &Backend, client: &mut Client) -> RespFrame {
+        if client.replica.is_some() {
+            return SimpleError::new("ERR Replica already attached").into();
+        }
+        match replication::psync(backend, client, &self.replid, self.offset) {
+            Ok((status, handoff)) => {
+                client.replica = Some(handoff);
+                SimpleString::new(status).into()
+            }
+            Err(e) => SimpleError::new(e).into(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Psync {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let replid = extract_string(args.next())?;
+        let offset = extract_string(args.next())?.parse().map_err(|_| {
+            CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+        })?;
+        Ok(Psync::new(replid, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    fn psync(args: &[&str]) -> Result<Command, CommandError> {
+        let frames = std::iter::once("psync")
+            .chain(args.iter().copied())
+            .map(|arg| BulkString::new(Some(arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    #[test]
+    fn test_psync_full_then_continue() {
+        let backend = Backend::new();
+        backend.set(bytes::Bytes::from("k"), BulkString::new(Some("v")).into());
+        let Command::Psync(cmd) = psync(&["?", "-1"]).unwrap() else {
+            panic!("expected PSYNC");
+        };
+        let mut client = Client::new();
+        let RespFrame::SimpleString(status) = cmd.apply(&backend, &mut client) else {
+            panic!("expected a status reply");
+        };
+        let status = String::from_utf8_lossy(&status).into_owned();
+        let words = status.split(' ').collect::<Vec<_>>();
+        assert_eq!(words[0], "FULLRESYNC");
+        assert_eq!(words[2], "0");
+        assert!(client.replica.is_some());
+
+        backend.propagate(&RespArray::new(Some(vec![BulkString::new(Some("ping")).into()])).into());
+        let Command::Psync(cmd) = psync(&[words[1], "1"]).unwrap() else {
+            panic!("expected PSYNC");
+        };
+        assert_eq!(
+            cmd.apply(&backend, &mut Client::new()),
+            SimpleString::new(format!("CONTINUE {}", words[1])).into()
+        );
+        assert_eq!(
+            psync(&["id", "x"]).unwrap_err().to_string(),
+            "ERR value is not an integer or out of range"
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use bytes::Bytes;
+
+use crate::{Backend, RespFrame};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
+
+/// `PUBLISH channel message`, replying with the number of deliveries.
+#[derive(Debug, PartialEq)]
+pub struct Publish {
+    channel: Bytes,
+    message: Bytes,
+}
+
+impl CommandExecutor for Publish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.pubsub().publish(&self.channel, &self.message) as i64)
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Publish {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let channel = extract_bytes(args.next())?;
+        let message = extract_bytes(args.next())?;
+        Ok(Publish::new(channel, message))
+    }
+}
+
+impl Publish {
+    pub fn new(channel: Bytes, message: Bytes) -> Self {
+        Publish { channel, message }
+    }
+}
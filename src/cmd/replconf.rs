@@ -0,0 +1,83 @@
+use bytes::Bytes;
+
+use crate::{Backend, Client, RespFrame};
+
+use super::{extract_bytes, CommandError, CommandExecutor, RET_OK};
+
+/// `REPLCONF <option> <value> ...`, sent by a replica before `PSYNC` to
+/// describe itself.
+#[derive(Debug, PartialEq)]
+pub struct ReplConf {
+    options: Vec<(Bytes, Bytes)>,
+}
+
+impl CommandExecutor for ReplConf {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        self.apply(&mut Client::new())
+    }
+}
+
+impl ReplConf {
+    pub fn new(options: Vec<(Bytes, Bytes)>) -> Self {
+        ReplConf { options }
+    }
+
+    pub fn apply(self, client: &mut Client) -> RespFrame {
+        for (option, value) in self.options {
+            if option.eq_ignore_ascii_case(b"listening-port") {
+                client.listening_port = std::str::from_utf8(&value)
+                    .ok()
+                    .and_then(|port| port.parse().ok());
+            }
+            // other options, such as `capa`, describe features this server
+            // doesn't use
+        }
+        RET_OK.clone()
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for ReplConf {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        if !value.len().is_multiple_of(2) {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = value.into_iter();
+        let mut options = Vec::new();
+        while let Some(option) = args.next() {
+            options.push((extract_bytes(Some(option))?, extract_bytes(args.next())?));
+        }
+        Ok(ReplConf::new(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    #[test]
+    fn test_replconf() {
+        let request = |args: &[&str]| {
+            let frames = std::iter::once("replconf")
+                .chain(args.iter().copied())
+                .map(|arg| BulkString::new(Some(arg)).into())
+                .collect::<Vec<RespFrame>>();
+            Command::try_from(RespArray::new(Some(frames)))
+        };
+        let Command::ReplConf(cmd) =
+            request(&["listening-port", "6380", "capa", "psync2"]).unwrap()
+        else {
+            panic!("expected REPLCONF");
+        };
+        let mut client = Client::new();
+        assert_eq!(cmd.apply(&mut client), RET_OK.clone());
+        assert_eq!(client.listening_port, Some(6380));
+        assert_eq!(
+            request(&["capa"]).unwrap_err().to_string(),
+            "ERR syntax error"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{replication, Backend, RespFrame, SimpleError, SimpleString};
+
+use super::{extract_string, CommandError, CommandExecutor, RET_OK};
+
+/// `REPLICAOF host port` and its older name `SLAVEOF`.
+#[derive(Debug, PartialEq)]
+pub struct ReplicaOf {
+    /// `None` for `NO ONE`, which turns a replica back into a master.
+    master: Option<(String, u16)>,
+}
+
+impl CommandExecutor for ReplicaOf {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.cluster().is_some() {
+            return SimpleError::new("ERR REPLICAOF not allowed in cluster mode.").into();
+        }
+        if replication::set_master(backend, self.master) {
+            RET_OK.clone()
+        } else {
+            SimpleString::new("OK Already connected to specified master").into()
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for ReplicaOf {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        let mut args = value.into_iter();
+        let host = extract_string(args.next())?;
+        let port = extract_string(args.next())?;
+        if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+            return Ok(ReplicaOf::new(None));
+        }
+        let port = port
+            .parse::<u16>()
+            .map_err(|_| CommandError::InvalidArgument("Invalid master port".to_string()))?;
+        Ok(ReplicaOf::new(Some((host, port))))
+    }
+}
+
+impl ReplicaOf {
+    pub fn new(master: Option<(String, u16)>) -> Self {
+        ReplicaOf { master }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    fn replicaof(args: &[&str]) -> Result<Command, CommandError> {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    #[test]
+    fn test_replicaof_try_from() {
+        assert_eq!(
+            replicaof(&["replicaof", "127.0.0.1", "6380"]).unwrap(),
+            Command::ReplicaOf(ReplicaOf::new(Some(("127.0.0.1".to_string(), 6380))))
+        );
+        assert_eq!(
+            replicaof(&["SLAVEOF", "No", "One"]).unwrap(),
+            Command::ReplicaOf(ReplicaOf::new(None))
+        );
+        assert_eq!(
+            replicaof(&["replicaof", "localhost", "70000"])
+                .unwrap_err()
+                .to_string(),
+            "ERR Invalid master port"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cmd_replicaof() {
+        let backend = Backend::new();
+        // port 1 refuses connections, so the link stays down
+        let cmd = replicaof(&["replicaof", "127.0.0.1", "1"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert!(backend.replication().is_replica());
+        assert_eq!(
+            backend.config().replicaof(),
+            Some(("127.0.0.1".to_string(), 1))
+        );
+        let cmd = replicaof(&["replicaof", "127.0.0.1", "1"]).unwrap();
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleString::new("OK Already connected to specified master").into()
+        );
+
+        let cmd = replicaof(&["replicaof", "no", "one"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert!(!backend.replication().is_replica());
+        assert_eq!(backend.config().replicaof(), None);
+    }
+}
@@ -0,0 +1,344 @@
+use bytes::Bytes;
+
+use crate::{
+    backend::now_ms,
+    rdb::{self, Object, RdbError},
+    Access, Backend, BulkString, Entry, NotifyFlags, RespArray, RespFrame, SimpleError,
+};
+
+use super::{extract_bytes, CommandError, CommandExecutor, RET_OK};
+
+#[derive(Debug, PartialEq)]
+pub struct Restore {
+    key: Bytes,
+    /// Absolute expiry time in unix milliseconds, resolved when the
+    /// command is parsed.
+    expire_at: Option<i64>,
+    /// Whether the TTL argument was already absolute.
+    absttl: bool,
+    payload: Bytes,
+    replace: bool,
+    /// `IDLETIME` in seconds, kept when the policy is not LFU.
+    idletime: Option<i64>,
+    /// `FREQ`, kept when the policy is LFU.
+    freq: Option<u8>,
+}
+
+impl CommandExecutor for Restore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if !self.replace && backend.exists(&self.key) {
+            return SimpleError::new("BUSYKEY Target key name already exists.").into();
+        }
+        let value = match rdb::undump_value(&self.payload) {
+            Ok(Object::Value(value)) => value,
+            Ok(Object::Unsupported(kind)) => {
+                let e = RdbError::UnsupportedType {
+                    key: String::from_utf8_lossy(&self.key).into_owned(),
+                    kind,
+                };
+                return SimpleError::new(format!("ERR {}", e)).into();
+            }
+            Err(e @ RdbError::BadPayload) => return SimpleError::new(format!("ERR {}", e)).into(),
+            Err(_) => return SimpleError::new("ERR Bad data format").into(),
+        };
+        if self.expire_at.is_some_and(|at| at <= now_ms()) {
+            // restoring an already expired key only drops the old value
+            if backend.remove(&self.key) {
+                backend.notify(NotifyFlags::GENERIC, "del", &self.key);
+            }
+            return RET_OK.clone();
+        }
+        backend.restore_entry(Entry {
+            key: self.key.clone(),
+            value,
+            expire_at: self.expire_at,
+        });
+        // like Redis, only what the current policy tracks is restored
+        let now = now_ms();
+        let access = match (self.idletime, self.freq) {
+            (_, Some(freq)) if backend.config().eviction().policy.lfu() => Some(Access {
+                counter: freq,
+                ..Access::new(now)
+            }),
+            (Some(idle), _) if !backend.config().eviction().policy.lfu() => {
+                Some(Access::new(now.saturating_sub(idle.saturating_mul(1000))))
+            }
+            _ => None,
+        };
+        if let Some(access) = access {
+            backend.set_access(&self.key, access);
+        }
+        backend.notify(NotifyFlags::GENERIC, "restore", &self.key);
+        RET_OK.clone()
+    }
+
+    /// A relative TTL would start over when the AOF is replayed, so the
+    /// resolved expiry time is recorded instead.
+    fn propagated(&self, request: RespFrame) -> RespFrame {
+        let (Some(expire_at), false) = (self.expire_at, self.absttl) else {
+            return request;
+        };
+        let RespFrame::Array(RespArray(Some(mut frames))) = request else {
+            return request;
+        };
+        frames[2] = BulkString::new(Some(expire_at.to_string())).into();
+        frames.push(BulkString::new(Some("ABSTTL")).into());
+        RespArray::new(Some(frames)).into()
+    }
+}
+
+fn parse_integer(arg: &[u8]) -> Result<i64, CommandError> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            CommandError::InvalidArgument("value is not an integer or out of range".to_string())
+        })
+}
+
+impl TryFrom<Vec<RespFrame>> for Restore {
+    type Error = CommandError;
+
+    fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        if value.len() < 3 {
+            return Err(CommandError::WrongArity("restore".to_string()));
+        }
+        let mut frame_iter = value.into_iter();
+        let key = extract_bytes(frame_iter.next())?;
+        let ttl = parse_integer(&extract_bytes(frame_iter.next())?)?;
+        let payload = extract_bytes(frame_iter.next())?;
+
+        let (mut replace, mut absttl) = (false, false);
+        let (mut idletime, mut freq) = (None, None);
+        while let Some(frame) = frame_iter.next() {
+            let option = extract_bytes(Some(frame))?.to_ascii_lowercase();
+            match option.as_slice() {
+                b"replace" => replace = true,
+                b"absttl" => absttl = true,
+                b"idletime" if freq.is_none() => {
+                    let idle = parse_integer(&extract_bytes(frame_iter.next())?)?;
+                    if idle < 0 {
+                        return Err(CommandError::InvalidArgument(
+                            "Invalid IDLETIME value, must be >= 0".to_string(),
+                        ));
+                    }
+                    idletime = Some(idle);
+                }
+                b"freq" if idletime.is_none() => {
+                    let count = parse_integer(&extract_bytes(frame_iter.next())?)?;
+                    if !(0..=255).contains(&count) {
+                        return Err(CommandError::InvalidArgument(
+                            "Invalid FREQ value, must be >= 0 and <= 255".to_string(),
+                        ));
+                    }
+                    freq = Some(count as u8);
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        if ttl < 0 {
+            return Err(CommandError::InvalidArgument(
+                "Invalid TTL value, must be >= 0".to_string(),
+            ));
+        }
+        let expire_at = match ttl {
+            0 => None,
+            ttl if absttl => Some(ttl),
+            ttl => Some(now_ms().saturating_add(ttl)),
+        };
+        Ok(Restore::new(
+            key, expire_at, absttl, payload, replace, idletime, freq,
+        ))
+    }
+}
+
+impl Restore {
+    pub fn new(
+        key: Bytes,
+        expire_at: Option<i64>,
+        absttl: bool,
+        payload: Bytes,
+        replace: bool,
+        idletime: Option<i64>,
+        freq: Option<u8>,
+    ) -> Self {
+        Restore {
+            key,
+            expire_at,
+            absttl,
+            payload,
+            replace,
+            idletime,
+            freq,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{
+        cmd::{dump::Dump, Command},
+        RespDecode, Value,
+    };
+
+    use super::*;
+
+    fn restore(args: &[&[u8]]) -> Result<Command, CommandError> {
+        let mut frames = vec![BulkString::new(Some(b"restore".to_vec())).into()];
+        frames.extend(
+            args.iter()
+                .map(|arg| BulkString::new(Some(arg.to_vec())).into()),
+        );
+        Command::try_from(RespArray::new(Some(frames)))
+    }
+
+    fn dump(backend: &Backend, key: &'static [u8]) -> Bytes {
+        match Dump::new(Bytes::from_static(key)).execute(backend) {
+            RespFrame::BulkString(BulkString(Some(payload))) => payload,
+            frame => panic!("unexpected DUMP reply {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_restore_try_from() {
+        let mut buf = BytesMut::from(
+            b"*6\r\n$7\r\nrestore\r\n$1\r\nk\r\n$3\r\n100\r\n$1\r\np\r\n$6\r\nABSTTL\r\n$7\r\nreplace\r\n"
+                .as_slice(),
+        );
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        assert_eq!(
+            Command::try_from(array).unwrap(),
+            Command::Restore(Restore::new(
+                Bytes::from_static(b"k"),
+                Some(100),
+                true,
+                Bytes::from_static(b"p"),
+                true,
+                None,
+                None
+            ))
+        );
+
+        for (args, error) in [
+            (
+                &[&b"k"[..], b"-1", b"p"][..],
+                "ERR Invalid TTL value, must be >= 0",
+            ),
+            (
+                &[b"k", b"ten", b"p"],
+                "ERR value is not an integer or out of range",
+            ),
+            (
+                &[b"k", b"0", b"p", b"IDLETIME", b"-1"],
+                "ERR Invalid IDLETIME value, must be >= 0",
+            ),
+            (
+                &[b"k", b"0", b"p", b"FREQ", b"256"],
+                "ERR Invalid FREQ value, must be >= 0 and <= 255",
+            ),
+            (
+                &[b"k", b"0", b"p", b"IDLETIME", b"1", b"FREQ", b"1"],
+                "ERR syntax error",
+            ),
+            (&[b"k", b"0", b"p", b"KEEPTTL"], "ERR syntax error"),
+        ] {
+            assert_eq!(restore(args).unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn test_cmd_dump_restore() {
+        let backend = Backend::new();
+        backend
+            .sadd(Bytes::from("set"), vec![Bytes::from("a"), Bytes::from("b")])
+            .unwrap();
+        let payload = dump(&backend, b"set");
+
+        let cmd = restore(&[b"set", b"0", &payload]).unwrap();
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("BUSYKEY Target key name already exists.").into()
+        );
+
+        let cmd = restore(&[b"copy", b"0", &payload]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        let mut members = match backend.get_entry(b"copy").unwrap().value {
+            Value::Set(members) => members,
+            value => panic!("unexpected value {:?}", value),
+        };
+        members.sort();
+        assert_eq!(members, vec![Bytes::from("a"), Bytes::from("b")]);
+
+        let cmd = restore(&[b"copy", b"10000", &payload, b"REPLACE"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert!(backend.get_entry(b"copy").unwrap().expire_at.is_some());
+
+        // an expiry in the past deletes the key instead
+        let cmd = restore(&[b"copy", b"1", &payload, b"REPLACE", b"ABSTTL"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert!(!backend.exists(b"copy"));
+
+        let mut corrupted = payload.to_vec();
+        corrupted[1] ^= 1;
+        let cmd = restore(&[b"copy", b"0", &corrupted]).unwrap();
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR DUMP payload version or checksum are wrong").into()
+        );
+    }
+
+    #[test]
+    fn test_restore_propagates_absolute_ttl() {
+        let request = |args: &[&[u8]]| {
+            let mut frames = vec![BulkString::new(Some(b"restore".to_vec())).into()];
+            frames.extend(
+                args.iter()
+                    .map(|arg| BulkString::new(Some(arg.to_vec())).into()),
+            );
+            RespFrame::Array(RespArray::new(Some(frames)))
+        };
+        let relative = request(&[b"k", b"5000", b"p"]);
+        let Command::Restore(cmd) = Command::try_from(relative.clone()).unwrap() else {
+            panic!("expected RESTORE");
+        };
+        let expire_at = cmd.expire_at.unwrap().to_string();
+        assert_eq!(
+            cmd.propagated(relative),
+            request(&[b"k", expire_at.as_bytes(), b"p", b"ABSTTL"])
+        );
+
+        let persistent = request(&[b"k", b"0", b"p", b"REPLACE"]);
+        let Command::Restore(cmd) = Command::try_from(persistent.clone()).unwrap() else {
+            panic!("expected RESTORE");
+        };
+        assert_eq!(cmd.propagated(persistent.clone()), persistent);
+    }
+
+    #[test]
+    fn test_restore_idletime() {
+        let backend = Backend::new();
+        backend
+            .sadd(Bytes::from("set"), vec![Bytes::from("a")])
+            .unwrap();
+        let payload = dump(&backend, b"set");
+
+        let cmd = restore(&[b"copy", b"0", &payload, b"IDLETIME", b"100"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        let idle = now_ms() - backend.access(b"copy").unwrap().at;
+        assert!((100_000..110_000).contains(&idle));
+
+        // FREQ is only kept under an LFU policy
+        let cmd = restore(&[b"copy", b"0", &payload, b"REPLACE", b"FREQ", b"100"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert_eq!(backend.access(b"copy").unwrap().counter, 5);
+        backend
+            .config_mut()
+            .set(&[("maxmemory-policy".to_string(), "allkeys-lfu".to_string())])
+            .unwrap();
+        let cmd = restore(&[b"copy", b"0", &payload, b"REPLACE", b"FREQ", b"100"]).unwrap();
+        assert_eq!(cmd.execute(&backend), RET_OK.clone());
+        assert_eq!(backend.access(b"copy").unwrap().counter, 100);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{Backend, RespFrame};
+
+use super::{CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct Role;
+
+impl CommandExecutor for Role {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.replication().role()
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Role {
+    type Error = CommandError;
+
+    fn try_from(_value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(Role::new())
+    }
+}
+
+impl Role {
+    pub fn new() -> Self {
+        Role
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BulkString, RespArray};
+
+    use super::*;
+
+    #[test]
+    fn test_role_master() {
+        let backend = Backend::new();
+        assert_eq!(
+            Role::new().execute(&backend),
+            RespArray::new(Some(vec![
+                BulkString::new(Some("master")).into(),
+                RespFrame::Integer(0),
+                RespArray::new(Some(vec![])).into(),
+            ]))
+            .into()
+        );
+    }
+}
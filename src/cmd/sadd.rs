@@ -1,16 +1,24 @@
-use crate::RespFrame;
+use bytes::Bytes;
 
-use super::{extract_string, validate_nums_of_argument, CommandError, CommandExecutor};
+use crate::{NotifyFlags, RespFrame};
+
+use super::{extract_bytes, CommandError, CommandExecutor, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct Sadd {
-    key: String,
-    fields: Vec<String>,
+    key: Bytes,
+    fields: Vec<Bytes>,
 }
 
 impl CommandExecutor for Sadd {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.sadd(self.key, self.fields)
+        let Ok(reply) = backend.sadd(self.key.clone(), self.fields) else {
+            return RET_WRONGTYPE.clone();
+        };
+        if reply != RespFrame::Integer(0) {
+            backend.notify(NotifyFlags::SET, "sadd", &self.key);
+        }
+        reply
     }
 }
 
@@ -18,12 +26,11 @@ impl TryFrom<Vec<RespFrame>> for Sadd {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "sadd", value.len(), 2)?;
         let mut frame_iter = value.into_iter();
-        let key = extract_string(frame_iter.next())?;
+        let key = extract_bytes(frame_iter.next())?;
         let mut fields = Vec::with_capacity(frame_iter.len());
         for frame in frame_iter {
-            let field = extract_string(Some(frame))?;
+            let field = extract_bytes(Some(frame))?;
             fields.push(field);
         }
         Ok(Sadd::new(key, fields))
@@ -31,7 +38,7 @@ impl TryFrom<Vec<RespFrame>> for Sadd {
 }
 
 impl Sadd {
-    pub fn new(key: String, fields: Vec<String>) -> Self {
+    pub fn new(key: Bytes, fields: Vec<Bytes>) -> Self {
         Sadd { key, fields }
     }
 }
@@ -51,7 +58,10 @@ mod tests {
         let sadd = Command::try_from(array).unwrap();
         assert_eq!(
             sadd,
-            Command::Sadd(Sadd::new("set".to_string(), vec!["one".to_string()]))
+            Command::Sadd(Sadd::new(
+                Bytes::from_static(b"set"),
+                vec![Bytes::from_static(b"one")]
+            ))
         )
     }
 }
@@ -0,0 +1,55 @@
+use crate::{rdb, Backend, RespFrame, SimpleError};
+
+use super::{CommandError, CommandExecutor, RET_OK};
+
+#[derive(Debug, PartialEq)]
+pub struct Save;
+
+impl CommandExecutor for Save {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match rdb::save(backend) {
+            Ok(()) => RET_OK.clone(),
+            Err(rdb::RdbError::SaveInProgress) => {
+                SimpleError::new("ERR Background save already in progress").into()
+            }
+            Err(_) => SimpleError::new("ERR").into(),
+        }
+    }
+}
+
+impl TryFrom<Vec<RespFrame>> for Save {
+    type Error = CommandError;
+
+    fn try_from(_value: Vec<RespFrame>) -> Result<Self, Self::Error> {
+        Ok(Save::new())
+    }
+}
+
+impl Save {
+    pub fn new() -> Self {
+        Save
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{cmd::Command, RespArray, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_save_try_from() {
+        let mut buf = BytesMut::from(b"*1\r\n$4\r\nSAVE\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        assert_eq!(
+            Command::try_from(array).unwrap(),
+            Command::Save(Save::new())
+        );
+
+        let mut buf = BytesMut::from(b"*2\r\n$4\r\nsave\r\n$1\r\nx\r\n".as_slice());
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        assert!(Command::try_from(array).is_err());
+    }
+}
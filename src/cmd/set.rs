@@ -1,18 +1,19 @@
-use crate::RespFrame;
+use bytes::Bytes;
 
-use super::{
-    extract_frame, extract_string, validate_nums_of_argument, CommandError, CommandExecutor, RET_OK,
-};
+use crate::{NotifyFlags, RespFrame};
+
+use super::{extract_bytes, extract_frame, CommandError, CommandExecutor, RET_OK};
 
 #[derive(Debug, PartialEq)]
 pub struct Set {
-    key: String,
+    key: Bytes,
     value: RespFrame,
 }
 
 impl CommandExecutor for Set {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.set(self.key, self.value);
+        backend.set(self.key.clone(), self.value);
+        backend.notify(NotifyFlags::STRING, "set", &self.key);
         RET_OK.clone()
     }
 }
@@ -21,16 +22,15 @@ impl TryFrom<Vec<RespFrame>> for Set {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "set", 2, 2)?;
         let mut frame_iter = value.into_iter();
-        let key = extract_string(frame_iter.next())?;
+        let key = extract_bytes(frame_iter.next())?;
         let frame_value = extract_frame(frame_iter.next())?;
         Ok(Set::new(key, frame_value))
     }
 }
 
 impl Set {
-    pub fn new(key: String, value: RespFrame) -> Self {
+    pub fn new(key: Bytes, value: RespFrame) -> Self {
         Set { key, value }
     }
 }
@@ -53,7 +53,10 @@ mod tests {
         let value = BulkString::decode(&mut buf).unwrap();
         assert_eq!(
             set,
-            Command::Set(Set::new("hello".to_string(), RespFrame::BulkString(value)))
+            Command::Set(Set::new(
+                Bytes::from_static(b"hello"),
+                RespFrame::BulkString(value)
+            ))
         )
     }
 }
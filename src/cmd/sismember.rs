@@ -1,16 +1,20 @@
+use bytes::Bytes;
+
 use crate::RespFrame;
 
-use super::{extract_string, validate_nums_of_argument, CommandError, CommandExecutor};
+use super::{extract_bytes, CommandError, CommandExecutor, RET_WRONGTYPE};
 
 #[derive(Debug, PartialEq)]
 pub struct Sismember {
-    key: String,
-    field: String,
+    key: Bytes,
+    field: Bytes,
 }
 
 impl CommandExecutor for Sismember {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.sismember(&self.key, &self.field)
+        backend
+            .sismember(&self.key, &self.field)
+            .unwrap_or_else(|_| RET_WRONGTYPE.clone())
     }
 }
 
@@ -18,16 +22,15 @@ impl TryFrom<Vec<RespFrame>> for Sismember {
     type Error = CommandError;
 
     fn try_from(value: Vec<RespFrame>) -> Result<Self, Self::Error> {
-        validate_nums_of_argument(&value, "sismember", 2, 2)?;
         let mut frame_iter = value.into_iter();
-        let key = extract_string(frame_iter.next())?;
-        let field = extract_string(frame_iter.next())?;
+        let key = extract_bytes(frame_iter.next())?;
+        let field = extract_bytes(frame_iter.next())?;
         Ok(Sismember::new(key, field))
     }
 }
 
 impl Sismember {
-    pub fn new(key: String, field: String) -> Self {
+    pub fn new(key: Bytes, field: Bytes) -> Self {
         Sismember { key, field }
     }
 }
@@ -48,7 +51,10 @@ mod tests {
         let sadd = Command::try_from(array).unwrap();
         assert_eq!(
             sadd,
-            Command::Sismember(Sismember::new("set".to_string(), "one".to_string()))
+            Command::Sismember(Sismember::new(
+                Bytes::from_static(b"set"),
+                Bytes::from_static(b"one")
+            ))
         )
     }
 
@@ -81,4 +87,22 @@ mod tests {
         let ret = get.execute(&backend);
         assert_eq!(ret, RespFrame::Integer(0));
     }
+
+    #[test]
+    fn test_cmd_sismember_binary() {
+        let backend = Backend::new();
+        backend
+            .sadd(
+                Bytes::from_static(b"\xfe\r\n"),
+                vec![Bytes::from_static(b"\x00\r\n\xff")],
+            )
+            .unwrap();
+
+        let mut buf = BytesMut::from(
+            b"*3\r\n$9\r\nsismember\r\n$3\r\n\xfe\r\n\r\n$4\r\n\x00\r\n\xff\r\n".as_slice(),
+        );
+        let array = RespArray::decode(&mut buf).expect("error in decode resp array");
+        let cmd = Command::try_from(array).unwrap();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+    }
 }
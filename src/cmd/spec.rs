@@ -0,0 +1,1209 @@
+use bytes::Bytes;
+
+use crate::{BulkString, RespArray, RespFrame, RespMap, RespSet, SimpleString};
+
+/// Static metadata of a command, the same information Redis keeps in its
+/// command table and exposes through `COMMAND INFO` / `COMMAND DOCS`.
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    /// Positive: exact number of arguments (including the command name).
+    /// Negative: minimum number of arguments.
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    pub acl_categories: &'static [&'static str],
+    pub key_flags: &'static [&'static str],
+    pub summary: &'static str,
+    pub since: &'static str,
+    pub group: &'static str,
+    pub complexity: &'static str,
+    pub arguments: &'static [ArgSpec],
+    pub subcommands: &'static [CommandSpec],
+}
+
+#[derive(Debug)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub flags: &'static [&'static str],
+    pub arguments: &'static [ArgSpec],
+}
+
+const fn arg(name: &'static str, kind: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        flags: &[],
+        arguments: &[],
+    }
+}
+
+const fn multiple(name: &'static str, kind: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        flags: &["multiple"],
+        arguments: &[],
+    }
+}
+
+const fn optional(name: &'static str, kind: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        flags: &["optional"],
+        arguments: &[],
+    }
+}
+
+const KEY: ArgSpec = arg("key", "key");
+
+const SPEC_DEFAULT: CommandSpec = CommandSpec {
+    name: "",
+    arity: 0,
+    flags: &[],
+    first_key: 0,
+    last_key: 0,
+    step: 0,
+    acl_categories: &[],
+    key_flags: &[],
+    summary: "",
+    since: "",
+    group: "",
+    complexity: "",
+    arguments: &[],
+    subcommands: &[],
+};
+
+const COMMAND_SUBCOMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "command|count",
+        arity: 2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns a count of commands.",
+        since: "2.8.13",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command|docs",
+        arity: -2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns documentary information about one, multiple or all commands.",
+        since: "7.0.0",
+        group: "server",
+        complexity: "O(N) where N is the number of commands to look up",
+        arguments: &[ArgSpec {
+            name: "command-name",
+            kind: "string",
+            flags: &["optional", "multiple"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command|getkeys",
+        arity: -3,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Extracts the key names from an arbitrary command.",
+        since: "2.8.13",
+        group: "server",
+        complexity: "O(N) where N is the number of arguments to the command",
+        arguments: &[
+            arg("command", "string"),
+            ArgSpec {
+                name: "arg",
+                kind: "string",
+                flags: &["optional", "multiple"],
+                arguments: &[],
+            },
+        ],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command|help",
+        arity: 2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns helpful text about the different subcommands.",
+        since: "5.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command|info",
+        arity: -2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns information about one, multiple or all commands.",
+        since: "2.8.13",
+        group: "server",
+        complexity: "O(N) where N is the number of commands to look up",
+        arguments: &[ArgSpec {
+            name: "command-name",
+            kind: "string",
+            flags: &["optional", "multiple"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command|list",
+        arity: -2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns a list of command names.",
+        since: "7.0.0",
+        group: "server",
+        complexity: "O(N) where N is the total number of Redis commands",
+        arguments: &[ArgSpec {
+            name: "filterby",
+            kind: "oneof",
+            flags: &["optional"],
+            arguments: &[
+                arg("module-name", "string"),
+                arg("category", "string"),
+                arg("pattern", "pattern"),
+            ],
+        }],
+        ..SPEC_DEFAULT
+    },
+];
+
+const CONFIG_SUBCOMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "config|get",
+        arity: -3,
+        flags: &["admin", "noscript", "loading", "stale"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Returns the effective values of configuration parameters.",
+        since: "2.0.0",
+        group: "server",
+        complexity: "O(N) when N is the number of configuration parameters provided",
+        arguments: &[multiple("parameter", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "config|set",
+        arity: -4,
+        flags: &["admin", "noscript", "loading", "stale"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Sets configuration parameters in-flight.",
+        since: "2.0.0",
+        group: "server",
+        complexity: "O(N) when N is the number of configuration parameters provided",
+        arguments: &[ArgSpec {
+            name: "data",
+            kind: "block",
+            flags: &["multiple"],
+            arguments: &[arg("parameter", "string"), arg("value", "string")],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "config|rewrite",
+        arity: 2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Persists the effective configuration to file.",
+        since: "2.8.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "config|resetstat",
+        arity: 2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        acl_categories: &["admin", "fast", "dangerous"],
+        summary: "Resets the server's statistics.",
+        since: "2.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+];
+
+const CLUSTER_SUBCOMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "cluster|countkeysinslot",
+        arity: 3,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the number of keys in a hash slot.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the total number of keys",
+        arguments: &[arg("slot", "integer")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|getkeysinslot",
+        arity: 4,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the key names in a hash slot.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the total number of keys",
+        arguments: &[arg("slot", "integer"), arg("count", "integer")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|info",
+        arity: 2,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns information about the state of a node.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|keyslot",
+        arity: 3,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the hash slot for a key.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the number of bytes in the key",
+        arguments: &[arg("key", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|myid",
+        arity: 2,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the ID of a node.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|nodes",
+        arity: 2,
+        flags: &["stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the cluster configuration for a node.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the total number of Cluster nodes",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|shards",
+        arity: 2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the mapping of cluster slots to shards.",
+        since: "7.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the total number of cluster nodes",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster|slots",
+        arity: 2,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow"],
+        summary: "Returns the mapping of cluster slots to nodes.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(N) where N is the total number of Cluster nodes",
+        ..SPEC_DEFAULT
+    },
+];
+
+const OBJECT_SUBCOMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "object|encoding",
+        arity: 3,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        key_flags: &["RO"],
+        summary: "Returns the internal encoding of a Redis object.",
+        since: "2.2.3",
+        group: "generic",
+        complexity: "O(1)",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "object|freq",
+        arity: 3,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        key_flags: &["RO"],
+        summary: "Returns the logarithmic access frequency counter of a Redis object.",
+        since: "4.0.0",
+        group: "generic",
+        complexity: "O(1)",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "object|idletime",
+        arity: 3,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        key_flags: &["RO"],
+        summary: "Returns the time since the last access to a Redis object.",
+        since: "2.2.3",
+        group: "generic",
+        complexity: "O(1)",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "object|refcount",
+        arity: 3,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        key_flags: &["RO"],
+        summary: "Returns the reference count of a value of a key.",
+        since: "2.2.3",
+        group: "generic",
+        complexity: "O(1)",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+];
+
+const MEMORY_SUBCOMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "memory|doctor",
+        arity: 2,
+        acl_categories: &["slow"],
+        summary: "Outputs a memory problems report.",
+        since: "4.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "memory|stats",
+        arity: 2,
+        acl_categories: &["slow"],
+        summary: "Returns details about memory usage.",
+        since: "4.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "memory|usage",
+        arity: -3,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["read", "slow"],
+        key_flags: &["RO"],
+        summary: "Estimates the memory usage of a key.",
+        since: "4.0.0",
+        group: "server",
+        complexity: "O(N) where N is the number of samples.",
+        arguments: &[KEY, optional("count", "integer")],
+        ..SPEC_DEFAULT
+    },
+];
+
+pub const COMMAND_TABLE: &[CommandSpec] = &[
+    CommandSpec {
+        name: "get",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "string", "fast"],
+        key_flags: &["RO", "access"],
+        summary: "Returns the string value of a key.",
+        since: "1.0.0",
+        group: "string",
+        complexity: "O(1)",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "set",
+        arity: 3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "slow"],
+        key_flags: &["RW", "access", "update"],
+        summary: "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist.",
+        since: "1.0.0",
+        group: "string",
+        complexity: "O(1)",
+        arguments: &[KEY, arg("value", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "hget",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "hash", "fast"],
+        key_flags: &["RO", "access"],
+        summary: "Returns the value of a field in a hash.",
+        since: "2.0.0",
+        group: "hash",
+        complexity: "O(1)",
+        arguments: &[KEY, arg("field", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "hmget",
+        arity: -3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "hash", "fast"],
+        key_flags: &["RO", "access"],
+        summary: "Returns the values of all fields in a hash.",
+        since: "2.0.0",
+        group: "hash",
+        complexity: "O(N) where N is the number of fields being requested.",
+        arguments: &[KEY, multiple("field", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "hset",
+        arity: -4,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "hash", "fast"],
+        key_flags: &["RW", "update"],
+        summary: "Creates or modifies the value of a field in a hash.",
+        since: "2.0.0",
+        group: "hash",
+        complexity: "O(1) for each field/value pair added, so O(N) to add N field/value pairs when the command is called with multiple field/value pairs.",
+        arguments: &[
+            KEY,
+            ArgSpec {
+                name: "data",
+                kind: "block",
+                flags: &["multiple"],
+                arguments: &[arg("field", "string"), arg("value", "string")],
+            },
+        ],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "hgetall",
+        arity: 2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "hash", "slow"],
+        key_flags: &["RO", "access"],
+        summary: "Returns all fields and values in a hash.",
+        since: "2.0.0",
+        group: "hash",
+        complexity: "O(N) where N is the size of the hash.",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "sadd",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "set", "fast"],
+        key_flags: &["RW", "insert"],
+        summary: "Adds one or more members to a set. Creates the key if it doesn't exist.",
+        since: "1.0.0",
+        group: "set",
+        complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        arguments: &[KEY, multiple("member", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "sismember",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "set", "fast"],
+        key_flags: &["RO"],
+        summary: "Determines whether a member belongs to a set.",
+        since: "1.0.0",
+        group: "set",
+        complexity: "O(1)",
+        arguments: &[KEY, arg("member", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "del",
+        arity: -2,
+        flags: &["write"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "slow"],
+        key_flags: &["RM", "delete"],
+        summary: "Deletes one or more keys.",
+        since: "1.0.0",
+        group: "generic",
+        complexity: "O(N) where N is the number of keys that will be removed. When a key to remove holds a value other than a string, the individual complexity for this key is O(M) where M is the number of elements in the list, set, sorted set or hash. Removing a single key that holds a string value is O(1).",
+        arguments: &[multiple("key", "key")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "dump",
+        arity: 2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        key_flags: &["RO", "access"],
+        summary: "Returns a serialized representation of the value stored at a key.",
+        since: "2.6.0",
+        group: "generic",
+        complexity: "O(1) to access the key and additional O(N*M) to serialize it, where N is the number of Redis objects composing the value and M their average size. For small string values the time complexity is thus O(1)+O(1*M) where M is small, so simply O(1).",
+        arguments: &[KEY],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "restore",
+        arity: -4,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "slow", "dangerous"],
+        key_flags: &["OW", "update"],
+        summary: "Creates a key from the serialized representation of a value.",
+        since: "2.6.0",
+        group: "generic",
+        complexity: "O(1) to create the new key and additional O(N*M) to reconstruct the serialized value, where N is the number of Redis objects composing the value and M their average size.",
+        arguments: &[
+            KEY,
+            arg("ttl", "integer"),
+            arg("serialized-value", "string"),
+            optional("replace", "pure-token"),
+            optional("absttl", "pure-token"),
+            optional("seconds", "integer"),
+            optional("frequency", "integer"),
+        ],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "echo",
+        arity: 2,
+        flags: &["loading", "stale", "fast"],
+        acl_categories: &["fast", "connection"],
+        summary: "Returns the given string.",
+        since: "1.0.0",
+        group: "connection",
+        complexity: "O(1)",
+        arguments: &[arg("message", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "command",
+        arity: -1,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "connection"],
+        summary: "Returns detailed information about all commands.",
+        since: "2.8.13",
+        group: "server",
+        complexity: "O(N) where N is the total number of Redis commands",
+        subcommands: COMMAND_SUBCOMMANDS,
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "config",
+        arity: -2,
+        summary: "A container for server configuration commands.",
+        since: "2.0.0",
+        group: "server",
+        complexity: "Depends on subcommand.",
+        subcommands: CONFIG_SUBCOMMANDS,
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "save",
+        arity: 1,
+        flags: &["admin", "noscript", "no_async_loading", "no_multi"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Synchronously saves the database(s) to disk.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(N) where N is the total number of keys in all databases",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "bgsave",
+        arity: -1,
+        flags: &["admin", "noscript", "no_async_loading"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Asynchronously saves the database(s) to disk.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(1)",
+        arguments: &[ArgSpec {
+            name: "schedule",
+            kind: "pure-token",
+            flags: &["optional"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "bgrewriteaof",
+        arity: 1,
+        flags: &["admin", "noscript", "no_async_loading"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Asynchronously rewrites the append-only file to disk.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "lastsave",
+        arity: 1,
+        flags: &["loading", "stale", "fast"],
+        acl_categories: &["fast", "admin", "dangerous"],
+        summary: "Returns the Unix timestamp of the last successful save to disk.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "ping",
+        arity: -1,
+        flags: &["fast"],
+        acl_categories: &["fast", "connection"],
+        summary: "Returns the server's liveliness response.",
+        since: "1.0.0",
+        group: "connection",
+        complexity: "O(1)",
+        arguments: &[optional("message", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "info",
+        arity: -1,
+        flags: &["loading", "stale"],
+        acl_categories: &["slow", "dangerous"],
+        summary: "Returns information and statistics about the server.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(1)",
+        arguments: &[ArgSpec {
+            name: "section",
+            kind: "string",
+            flags: &["optional", "multiple"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "replicaof",
+        arity: 3,
+        flags: &["admin", "noscript", "stale", "no_async_loading"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Configures a server as replica of another, or promotes it to a master.",
+        since: "5.0.0",
+        group: "server",
+        complexity: "O(1)",
+        arguments: &[arg("host", "string"), arg("port", "integer")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "slaveof",
+        arity: 3,
+        flags: &["admin", "noscript", "stale", "no_async_loading"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "Sets a Redis server as a replica of another, or promotes it to being a master.",
+        since: "1.0.0",
+        group: "server",
+        complexity: "O(1)",
+        arguments: &[arg("host", "string"), arg("port", "integer")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "psync",
+        arity: -3,
+        flags: &["admin", "noscript", "no_async_loading", "no_multi"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "An internal command used in replication.",
+        since: "2.8.0",
+        group: "server",
+        arguments: &[arg("replicationid", "string"), arg("offset", "integer")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "replconf",
+        arity: -1,
+        flags: &["admin", "noscript", "loading", "stale", "allow_busy"],
+        acl_categories: &["admin", "slow", "dangerous"],
+        summary: "An internal command for configuring the replication stream.",
+        since: "3.0.0",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "role",
+        arity: 1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        acl_categories: &["admin", "fast", "dangerous"],
+        summary: "Returns the replication role.",
+        since: "2.8.12",
+        group: "server",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "cluster",
+        arity: -2,
+        summary: "A container for Redis Cluster commands.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "Depends on subcommand.",
+        subcommands: CLUSTER_SUBCOMMANDS,
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "asking",
+        arity: 1,
+        flags: &["fast"],
+        acl_categories: &["fast", "connection"],
+        summary: "Signals that a cluster client is following an -ASK redirect.",
+        since: "3.0.0",
+        group: "cluster",
+        complexity: "O(1)",
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "subscribe",
+        arity: -2,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        acl_categories: &["pubsub", "slow"],
+        summary: "Listens for messages published to channels.",
+        since: "2.0.0",
+        group: "pubsub",
+        complexity: "O(N) where N is the number of channels to subscribe to.",
+        arguments: &[multiple("channel", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "psubscribe",
+        arity: -2,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        acl_categories: &["pubsub", "slow"],
+        summary: "Listens for messages published to channels that match one or more patterns.",
+        since: "2.0.0",
+        group: "pubsub",
+        complexity: "O(N) where N is the number of patterns to subscribe to.",
+        arguments: &[multiple("pattern", "pattern")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "unsubscribe",
+        arity: -1,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        acl_categories: &["pubsub", "slow"],
+        summary: "Stops listening to messages posted to channels.",
+        since: "2.0.0",
+        group: "pubsub",
+        complexity: "O(N) where N is the number of channels to unsubscribe.",
+        arguments: &[ArgSpec {
+            name: "channel",
+            kind: "string",
+            flags: &["optional", "multiple"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "punsubscribe",
+        arity: -1,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        acl_categories: &["pubsub", "slow"],
+        summary: "Stops listening to messages published to channels that match one or more patterns.",
+        since: "2.0.0",
+        group: "pubsub",
+        complexity: "O(N) where N is the number of patterns to unsubscribe.",
+        arguments: &[ArgSpec {
+            name: "pattern",
+            kind: "pattern",
+            flags: &["optional", "multiple"],
+            arguments: &[],
+        }],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "publish",
+        arity: 3,
+        flags: &["pubsub", "loading", "stale", "fast"],
+        acl_categories: &["pubsub", "fast"],
+        summary: "Posts a message to a channel.",
+        since: "2.0.0",
+        group: "pubsub",
+        complexity: "O(N+M) where N is the number of clients subscribed to the receiving channel and M is the total number of subscribed patterns (by any client).",
+        arguments: &[arg("channel", "string"), arg("message", "string")],
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "object",
+        arity: -2,
+        summary: "A container for object introspection commands.",
+        since: "2.2.3",
+        group: "generic",
+        complexity: "Depends on subcommand.",
+        subcommands: OBJECT_SUBCOMMANDS,
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "memory",
+        arity: -2,
+        summary: "A container for memory diagnostics commands.",
+        since: "4.0.0",
+        group: "server",
+        complexity: "Depends on subcommand.",
+        subcommands: MEMORY_SUBCOMMANDS,
+        ..SPEC_DEFAULT
+    },
+    CommandSpec {
+        name: "hello",
+        arity: -1,
+        flags: &["noscript", "loading", "stale", "fast", "no_auth"],
+        acl_categories: &["fast", "connection"],
+        summary: "Handshakes with the Redis server.",
+        since: "6.0.0",
+        group: "connection",
+        complexity: "O(1)",
+        arguments: &[ArgSpec {
+            name: "arguments",
+            kind: "block",
+            flags: &["optional"],
+            arguments: &[
+                arg("protover", "integer"),
+                ArgSpec {
+                    name: "auth",
+                    kind: "block",
+                    flags: &["optional"],
+                    arguments: &[arg("username", "string"), arg("password", "string")],
+                },
+                ArgSpec {
+                    name: "clientname",
+                    kind: "string",
+                    flags: &["optional"],
+                    arguments: &[],
+                },
+            ],
+        }],
+        ..SPEC_DEFAULT
+    },
+];
+
+/// Find a command by name (case-insensitive). `container|subcommand` names
+/// resolve to the subcommand's spec.
+pub fn lookup_command(name: &[u8]) -> Option<&'static CommandSpec> {
+    match name.iter().position(|b| *b == b'|') {
+        Some(pos) => lookup_top_level(&name[..pos])?.subcommand(&name[pos + 1..]),
+        None => lookup_top_level(name),
+    }
+}
+
+/// Find a command by name (case-insensitive), not counting subcommands.
+pub fn lookup_top_level(name: &[u8]) -> Option<&'static CommandSpec> {
+    COMMAND_TABLE
+        .iter()
+        .find(|spec| spec.name.as_bytes().eq_ignore_ascii_case(name))
+}
+
+/// The spec a command line runs under, see [`CommandSpec::for_args`].
+pub fn lookup_request(args: &[Bytes]) -> Option<&'static CommandSpec> {
+    lookup_top_level(args.first()?)?.for_args(args)
+}
+
+/// Iterate every command and subcommand in the table.
+pub fn all_commands() -> impl Iterator<Item = &'static CommandSpec> {
+    COMMAND_TABLE
+        .iter()
+        .flat_map(|spec| std::iter::once(spec).chain(spec.subcommands.iter()))
+}
+
+impl CommandSpec {
+    /// `argc` counts the command name itself, as Redis does.
+    pub fn check_arity(&self, argc: usize) -> bool {
+        if self.arity >= 0 {
+            argc as i64 == self.arity
+        } else {
+            argc as i64 >= -self.arity
+        }
+    }
+
+    /// Extract the key arguments from a full command line (`args[0]` is the
+    /// command name) using the legacy first/last/step triple.
+    pub fn extract_keys<'a>(&self, args: &'a [Bytes]) -> Vec<&'a [u8]> {
+        if self.first_key <= 0 {
+            return vec![];
+        }
+        let last = if self.last_key < 0 {
+            args.len() as i64 + self.last_key
+        } else {
+            self.last_key
+        };
+        (self.first_key..=last.min(args.len() as i64 - 1))
+            .step_by(self.step.max(1) as usize)
+            .map(|i| args[i as usize].as_ref())
+            .collect()
+    }
+
+    /// The subcommand `name` of a container command (case-insensitive).
+    pub fn subcommand(&self, name: &[u8]) -> Option<&'static CommandSpec> {
+        self.subcommands.iter().find(|sub| {
+            sub.name
+                .split_once('|')
+                .is_some_and(|(_, sub)| sub.as_bytes().eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The spec a command line (`args[0]` is the command name) runs under:
+    /// its subcommand's for container commands such as `OBJECT`, whose keys
+    /// follow the subcommand name.
+    pub fn for_args(&'static self, args: &[Bytes]) -> Option<&'static CommandSpec> {
+        match args.get(1) {
+            Some(sub) if !self.subcommands.is_empty() => self.subcommand(sub),
+            _ => Some(self),
+        }
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    pub fn has_category(&self, category: &str) -> bool {
+        self.acl_categories
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(category))
+    }
+
+    /// The ten element reply of `COMMAND INFO` for this command.
+    pub fn info_frame(&self) -> RespFrame {
+        let flags = self
+            .flags
+            .iter()
+            .map(|flag| SimpleString::new(*flag).into())
+            .collect::<Vec<_>>();
+        let categories = self
+            .acl_categories
+            .iter()
+            .map(|c| SimpleString::new(format!("@{}", c)).into())
+            .collect::<Vec<_>>();
+        let subcommands = self
+            .subcommands
+            .iter()
+            .map(CommandSpec::info_frame)
+            .collect::<Vec<_>>();
+        RespArray::new(Some(vec![
+            BulkString::new(Some(self.name)).into(),
+            self.arity.into(),
+            RespSet::new(flags).into(),
+            self.first_key.into(),
+            self.last_key.into(),
+            self.step.into(),
+            RespSet::new(categories).into(),
+            RespArray::new(Some([])).into(),
+            RespArray::new(Some(self.key_specs())).into(),
+            RespArray::new(Some(subcommands)).into(),
+        ]))
+        .into()
+    }
+
+    fn key_specs(&self) -> Vec<RespFrame> {
+        if self.first_key <= 0 {
+            return vec![];
+        }
+        let mut begin_search = RespMap::new();
+        begin_search.insert(SimpleString::new("type"), bulk("index"));
+        begin_search.insert(
+            SimpleString::new("spec"),
+            single_entry_map("index", self.first_key.into()),
+        );
+
+        let lastkey = if self.last_key < 0 {
+            self.last_key
+        } else {
+            self.last_key - self.first_key
+        };
+        let mut find_keys_spec = RespMap::new();
+        find_keys_spec.insert(SimpleString::new("lastkey"), lastkey.into());
+        find_keys_spec.insert(SimpleString::new("keystep"), self.step.into());
+        find_keys_spec.insert(SimpleString::new("limit"), 0.into());
+        let mut find_keys = RespMap::new();
+        find_keys.insert(SimpleString::new("type"), bulk("range"));
+        find_keys.insert(SimpleString::new("spec"), find_keys_spec.into());
+
+        let mut key_spec = RespMap::new();
+        key_spec.insert(SimpleString::new("flags"), simple_set(self.key_flags));
+        key_spec.insert(SimpleString::new("begin_search"), begin_search.into());
+        key_spec.insert(SimpleString::new("find_keys"), find_keys.into());
+        vec![key_spec.into()]
+    }
+
+    /// The per-command map of `COMMAND DOCS`.
+    pub fn docs_frame(&self) -> RespFrame {
+        let mut docs = RespMap::new();
+        docs.insert(SimpleString::new("summary"), bulk(self.summary));
+        docs.insert(SimpleString::new("since"), bulk(self.since));
+        docs.insert(SimpleString::new("group"), bulk(self.group));
+        docs.insert(SimpleString::new("complexity"), bulk(self.complexity));
+        if !self.arguments.is_empty() {
+            docs.insert(
+                SimpleString::new("arguments"),
+                args_frame(self.arguments, &mut 0),
+            );
+        }
+        if !self.subcommands.is_empty() {
+            let mut subcommands = RespMap::new();
+            self.subcommands.iter().for_each(|sub| {
+                subcommands.insert(SimpleString::new(sub.name), sub.docs_frame());
+            });
+            docs.insert(SimpleString::new("subcommands"), subcommands.into());
+        }
+        docs.into()
+    }
+}
+
+fn args_frame(args: &[ArgSpec], key_spec_index: &mut i64) -> RespFrame {
+    let frames = args
+        .iter()
+        .map(|arg| {
+            let mut map = RespMap::new();
+            map.insert(SimpleString::new("name"), bulk(arg.name));
+            map.insert(SimpleString::new("type"), bulk(arg.kind));
+            if arg.kind == "key" {
+                map.insert(
+                    SimpleString::new("key_spec_index"),
+                    (*key_spec_index).into(),
+                );
+                *key_spec_index += 1;
+            }
+            if !arg.flags.is_empty() {
+                map.insert(SimpleString::new("flags"), simple_set(arg.flags));
+            }
+            if !arg.arguments.is_empty() {
+                map.insert(
+                    SimpleString::new("arguments"),
+                    args_frame(arg.arguments, key_spec_index),
+                );
+            }
+            map.into()
+        })
+        .collect::<Vec<_>>();
+    RespArray::new(Some(frames)).into()
+}
+
+fn bulk(s: &str) -> RespFrame {
+    BulkString::new(Some(s)).into()
+}
+
+fn simple_set(items: &[&'static str]) -> RespFrame {
+    RespSet::new(
+        items
+            .iter()
+            .map(|item| SimpleString::new(*item).into())
+            .collect::<Vec<_>>(),
+    )
+    .into()
+}
+
+fn single_entry_map(key: &'static str, value: RespFrame) -> RespFrame {
+    let mut map = RespMap::new();
+    map.insert(SimpleString::new(key), value);
+    map.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_command() {
+        assert_eq!(lookup_command(b"GET").unwrap().name, "get");
+        assert_eq!(
+            lookup_command(b"command|COUNT").unwrap().name,
+            "command|count"
+        );
+        assert!(lookup_command(b"sett").is_none());
+        assert!(lookup_command(b"get|foo").is_none());
+        assert!(lookup_top_level(b"command|count").is_none());
+        assert_eq!(
+            lookup_top_level(b"OBJECT")
+                .unwrap()
+                .subcommand(b"Encoding")
+                .unwrap()
+                .name,
+            "object|encoding"
+        );
+    }
+
+    #[test]
+    fn test_check_arity() {
+        let get = lookup_command(b"get").unwrap();
+        assert!(get.check_arity(2));
+        assert!(!get.check_arity(3));
+        let hset = lookup_command(b"hset").unwrap();
+        assert!(!hset.check_arity(3));
+        assert!(hset.check_arity(4));
+        assert!(hset.check_arity(6));
+    }
+
+    #[test]
+    fn test_extract_keys() {
+        let args = vec![
+            Bytes::from_static(b"hmget"),
+            Bytes::from_static(b"map"),
+            Bytes::from_static(b"f1"),
+        ];
+        let spec = lookup_command(b"hmget").unwrap();
+        assert_eq!(spec.extract_keys(&args), vec![b"map".as_slice()]);
+        let spec = lookup_command(b"echo").unwrap();
+        assert!(spec.extract_keys(&args).is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use bytes::Bytes;
+
+use crate::{Backend, BulkString, Client, RespFrame, RespPush, SimpleError};
+
+use super::{extract_bytes, CommandError, CommandExecutor};
+
+/// Whether a (un)subscribe command names channels or glob patterns.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SubscriptionKind {
+    Channel,
+    Pattern,
+}
+
+/// `SUBSCRIBE channel [channel ...]` or `PSUBSCRIBE pattern [pattern ...]`.
+#[derive(Debug, PartialEq)]
+pub struct Subscribe {
+    kind: SubscriptionKind,
+    channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for Subscribe {
+    /// Messages are delivered over the connection that subscribed.
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new(format!(
+            "ERR {} is only valid on a client connection",
+            self.name().to_ascii_uppercase()
+        ))
+        .into()
+    }
+}
+
+impl Subscribe {
+    pub fn new(kind: SubscriptionKind, channels: Vec<Bytes>) -> Self {
+        Subscribe { kind, channels }
+    }
+
+    pub fn parse(value: Vec<RespFrame>, kind: SubscriptionKind) -> Result<Self, CommandError> {
+        let channels = value
+            .into_iter()
+            .map(|frame| extract_bytes(Some(frame)))
+            .collect::<Result<_, _>>()?;
+        Ok(Subscribe::new(kind, channels))
+    }
+
+    fn name(&self) -> &'static str {
+        match self.kind {
+            SubscriptionKind::Channel => "subscribe",
+            SubscriptionKind::Pattern => "psubscribe",
+        }
+    }
+
+    /// Subscribe the client. Redis confirms each channel with its own
+    /// frame: all but the last are queued in the client's mailbox, which
+    /// the connection writes ahead of the reply.
+    pub fn apply(self, backend: &Backend, client: &mut Client) -> RespFrame {
+        let name = self.name();
+        let mut confirmations = self
+            .channels
+            .into_iter()
+            .map(|channel| {
+                match self.kind {
+                    SubscriptionKind::Channel => {
+                        backend.pubsub().subscribe(client, channel.clone())
+                    }
+                    SubscriptionKind::Pattern => {
+                        backend.pubsub().psubscribe(client, channel.clone())
+                    }
+                };
+                confirmation(name, Some(channel), client.subscriptions.count())
+            })
+            .collect::<Vec<_>>();
+        let reply = confirmations.pop().expect("arity checked");
+        confirmations
+            .into_iter()
+            .for_each(|frame| queue(client, frame));
+        reply
+    }
+}
+
+/// `<kind> <channel> <subscription count>`, the push confirming a
+/// (un)subscribe.
+pub(super) fn confirmation(kind: &str, channel: Option<Bytes>, count: usize) -> RespFrame {
+    RespPush::new([
+        BulkString::new(Some(kind)).into(),
+        BulkString(channel).into(),
+        RespFrame::Integer(count as i64),
+    ])
+    .into()
+}
+
+pub(super) fn queue(client: &Client, frame: RespFrame) {
+    // the receiver lives as long as the connection
+    let _ = client.subscriptions.mailbox().send(frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, RespArray};
+
+    use super::*;
+
+    fn command(args: &[&str]) -> Command {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<RespFrame>>();
+        Command::try_from(RespArray::new(Some(frames))).unwrap()
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let backend = Backend::new();
+        let mut client = Client::new();
+        let mut inbox = client.subscriptions.take_inbox().unwrap();
+        let Command::Subscribe(subscribe) = command(&["subscribe", "a", "b"]) else {
+            panic!("SUBSCRIBE should parse as Subscribe");
+        };
+        assert_eq!(
+            subscribe.apply(&backend, &mut client),
+            confirmation("subscribe", Some(Bytes::from("b")), 2)
+        );
+        assert_eq!(
+            inbox.try_recv(),
+            Ok(confirmation("subscribe", Some(Bytes::from("a")), 1))
+        );
+
+        let Command::Subscribe(psubscribe) = command(&["PSUBSCRIBE", "a*"]) else {
+            panic!("PSUBSCRIBE should parse as Subscribe");
+        };
+        assert_eq!(
+            psubscribe.apply(&backend, &mut client),
+            confirmation("psubscribe", Some(Bytes::from("a*")), 3)
+        );
+        assert_eq!(backend.pubsub().publish(b"a", b"hi"), 2);
+        assert_eq!(
+            command(&["subscribe", "a"]).execute(&backend),
+            SimpleError::new("ERR SUBSCRIBE is only valid on a client connection").into()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use bytes::Bytes;
+
+use crate::{Backend, Client, RespFrame, SimpleError};
+
+use super::{
+    extract_bytes,
+    subscribe::{confirmation, queue, SubscriptionKind},
+    CommandError, CommandExecutor,
+};
+
+/// `UNSUBSCRIBE [channel ...]` or `PUNSUBSCRIBE [pattern ...]`. Without
+/// arguments the client leaves every channel (or pattern) it is in.
+#[derive(Debug, PartialEq)]
+pub struct Unsubscribe {
+    kind: SubscriptionKind,
+    channels: Vec<Bytes>,
+}
+
+impl CommandExecutor for Unsubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new(format!(
+            "ERR {} is only valid on a client connection",
+            self.name().to_ascii_uppercase()
+        ))
+        .into()
+    }
+}
+
+impl Unsubscribe {
+    pub fn new(kind: SubscriptionKind, channels: Vec<Bytes>) -> Self {
+        Unsubscribe { kind, channels }
+    }
+
+    pub fn parse(value: Vec<RespFrame>, kind: SubscriptionKind) -> Result<Self, CommandError> {
+        let channels = value
+            .into_iter()
+            .map(|frame| extract_bytes(Some(frame)))
+            .collect::<Result<_, _>>()?;
+        Ok(Unsubscribe::new(kind, channels))
+    }
+
+    fn name(&self) -> &'static str {
+        match self.kind {
+            SubscriptionKind::Channel => "unsubscribe",
+            SubscriptionKind::Pattern => "punsubscribe",
+        }
+    }
+
+    /// Unsubscribe the client, confirming each channel like `SUBSCRIBE`.
+    pub fn apply(self, backend: &Backend, client: &mut Client) -> RespFrame {
+        let name = self.name();
+        let subscribed = match self.kind {
+            SubscriptionKind::Channel => &client.subscriptions.channels,
+            SubscriptionKind::Pattern => &client.subscriptions.patterns,
+        };
+        let channels = if self.channels.is_empty() {
+            subscribed.iter().cloned().collect()
+        } else {
+            self.channels
+        };
+        if channels.is_empty() {
+            return confirmation(name, None, client.subscriptions.count());
+        }
+        let mut confirmations = channels
+            .into_iter()
+            .map(|channel| {
+                match self.kind {
+                    SubscriptionKind::Channel => backend.pubsub().unsubscribe(client, &channel),
+                    SubscriptionKind::Pattern => backend.pubsub().punsubscribe(client, &channel),
+                };
+                confirmation(name, Some(channel), client.subscriptions.count())
+            })
+            .collect::<Vec<_>>();
+        let reply = confirmations.pop().expect("not empty");
+        confirmations
+            .into_iter()
+            .for_each(|frame| queue(client, frame));
+        reply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd::Command, BulkString, RespArray};
+
+    use super::*;
+
+    fn apply(backend: &Backend, client: &mut Client, args: &[&str]) -> RespFrame {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<RespFrame>>();
+        match Command::try_from(RespArray::new(Some(frames))).unwrap() {
+            Command::Subscribe(cmd) => cmd.apply(backend, client),
+            Command::Unsubscribe(cmd) => cmd.apply(backend, client),
+            cmd => panic!("unexpected command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let backend = Backend::new();
+        let mut client = Client::new();
+        let mut inbox = client.subscriptions.take_inbox().unwrap();
+        assert_eq!(
+            apply(&backend, &mut client, &["unsubscribe"]),
+            confirmation("unsubscribe", None, 0)
+        );
+        apply(&backend, &mut client, &["subscribe", "a", "b"]);
+        apply(&backend, &mut client, &["psubscribe", "c*"]);
+        inbox.try_recv().unwrap();
+
+        assert_eq!(
+            apply(&backend, &mut client, &["unsubscribe"]),
+            confirmation("unsubscribe", Some(Bytes::from("b")), 1)
+        );
+        assert_eq!(
+            inbox.try_recv(),
+            Ok(confirmation("unsubscribe", Some(Bytes::from("a")), 2))
+        );
+        assert_eq!(
+            apply(&backend, &mut client, &["punsubscribe", "x*"]),
+            confirmation("punsubscribe", Some(Bytes::from("x*")), 1)
+        );
+        assert_eq!(
+            apply(&backend, &mut client, &["punsubscribe"]),
+            confirmation("punsubscribe", Some(Bytes::from("c*")), 0)
+        );
+        assert_eq!(backend.pubsub().publish(b"a", b"hi"), 0);
+    }
+}
@@ -0,0 +1,1418 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use thiserror::Error;
+
+use crate::{
+    aof::AppendFsync,
+    backend::{EncodingLimits, EvictionConfig, EvictionPolicy, LfuConfig, NotifyFlags},
+    glob::glob_match,
+    logging::{self, LogConfig, LogFormat},
+    network::split_inline_args,
+    replication::OutputBufferLimit,
+    ProtocolLimits,
+};
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("\n*** FATAL CONFIG FILE ERROR ***\nReading the configuration file, at line {line}\n>>> '{text}'\n{reason}")]
+    Fatal {
+        line: usize,
+        text: String,
+        reason: String,
+    },
+    #[error("Fatal error, can't open config file '{}': {1}", .0.display())]
+    Open(PathBuf, io::Error),
+    #[error("Unknown option or number of arguments for CONFIG SET - '{0}'")]
+    UnknownOption(String),
+    #[error("CONFIG SET failed (possibly related to argument '{0}') - {1}")]
+    SetFailed(String, String),
+    #[error("Invalid value for '{0}' - {1}")]
+    Invalid(&'static str, String),
+    #[error("The server is running without a config file")]
+    NoConfigFile,
+    #[error("Rewriting config file: {0}")]
+    Rewrite(#[from] io::Error),
+}
+
+#[derive(Debug)]
+enum ParamKind {
+    /// `yes` or `no`.
+    Bool,
+    Int {
+        min: i64,
+        max: i64,
+    },
+    /// A byte count that accepts `k`/`kb`/`m`/`mb`/`g`/`gb` suffixes and is
+    /// stored as a plain number.
+    Memory {
+        min: u64,
+        max: u64,
+    },
+    Enum(&'static [&'static str]),
+    /// One or more space separated addresses.
+    Addresses,
+    /// `<seconds> <changes>` pairs; an empty value disables the triggers.
+    SavePoints,
+    /// `<host> <port>` of the master to replicate, or empty for none.
+    ReplicaOf,
+    /// Keyspace event classes such as `KEA`, or empty for none.
+    KeyspaceEvents,
+    /// `<class> <hard> <soft> <soft seconds>` groups. A directive only
+    /// changes the classes it names.
+    OutputBufferLimits,
+    /// Free text, with an optional extra check.
+    String(Option<Check>),
+}
+
+/// Extra validation of a string parameter, returning the reason it failed.
+type Check = fn(&str) -> Result<(), String>;
+
+/// A configuration parameter, the equivalent of an entry in Redis's
+/// `standardConfig` table.
+#[derive(Debug)]
+struct ParamSpec {
+    name: &'static str,
+    kind: ParamKind,
+    default: &'static str,
+    /// Whether `CONFIG SET` may change the parameter at runtime.
+    mutable: bool,
+}
+
+const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "bind",
+        kind: ParamKind::Addresses,
+        default: "* -::*",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "port",
+        kind: ParamKind::Int { min: 0, max: 65535 },
+        default: "6379",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "proto-max-bulk-len",
+        kind: ParamKind::Memory {
+            min: 1024 * 1024,
+            max: i64::MAX as u64,
+        },
+        default: "512mb",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "proto-max-multibulk-len",
+        kind: ParamKind::Int {
+            min: 1,
+            max: i32::MAX as i64,
+        },
+        default: "2147483647",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "proto-max-nesting-depth",
+        kind: ParamKind::Int { min: 1, max: 1024 },
+        default: "128",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "client-query-buffer-limit",
+        kind: ParamKind::Memory {
+            min: 1024 * 1024,
+            max: i64::MAX as u64,
+        },
+        default: "1gb",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "save",
+        kind: ParamKind::SavePoints,
+        default: "3600 1 300 100 60 10000",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "dbfilename",
+        kind: ParamKind::String(Some(check_file_name)),
+        default: "dump.rdb",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "dir",
+        kind: ParamKind::String(Some(check_dir)),
+        default: "./",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "rdbchecksum",
+        kind: ParamKind::Bool,
+        default: "yes",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "appendonly",
+        kind: ParamKind::Bool,
+        default: "no",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "appendfilename",
+        kind: ParamKind::String(Some(check_aof_file_name)),
+        default: "appendonly.aof",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "appenddirname",
+        kind: ParamKind::String(Some(check_aof_dir_name)),
+        default: "appendonlydir",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "appendfsync",
+        kind: ParamKind::Enum(&["always", "everysec", "no"]),
+        default: "everysec",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "aof-load-truncated",
+        kind: ParamKind::Bool,
+        default: "yes",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "replicaof",
+        kind: ParamKind::ReplicaOf,
+        default: "",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "replica-read-only",
+        kind: ParamKind::Bool,
+        default: "yes",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "repl-backlog-size",
+        kind: ParamKind::Memory {
+            min: 1,
+            max: i64::MAX as u64,
+        },
+        default: "1mb",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "client-output-buffer-limit",
+        kind: ParamKind::OutputBufferLimits,
+        default: "normal 0 0 0 slave 256mb 64mb 60 pubsub 32mb 8mb 60",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "cluster-enabled",
+        kind: ParamKind::Bool,
+        default: "no",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "cluster-config-file",
+        kind: ParamKind::String(Some(check_cluster_config_file)),
+        default: "nodes.conf",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "hash-max-listpack-entries",
+        kind: ParamKind::Int {
+            min: 0,
+            max: i64::MAX,
+        },
+        default: "128",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "hash-max-listpack-value",
+        kind: ParamKind::Memory {
+            min: 0,
+            max: i64::MAX as u64,
+        },
+        default: "64",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "set-max-intset-entries",
+        kind: ParamKind::Int {
+            min: 0,
+            max: i64::MAX,
+        },
+        default: "512",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "maxmemory",
+        kind: ParamKind::Memory {
+            min: 0,
+            max: i64::MAX as u64,
+        },
+        default: "0",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "maxmemory-policy",
+        kind: ParamKind::Enum(EvictionPolicy::NAMES),
+        default: "noeviction",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "maxmemory-samples",
+        kind: ParamKind::Int { min: 1, max: 64 },
+        default: "5",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "lfu-log-factor",
+        kind: ParamKind::Int {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+        default: "10",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "lfu-decay-time",
+        kind: ParamKind::Int {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+        default: "1",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "notify-keyspace-events",
+        kind: ParamKind::KeyspaceEvents,
+        default: "",
+        mutable: true,
+    },
+    ParamSpec {
+        name: "log-format",
+        kind: ParamKind::Enum(&["text", "json"]),
+        default: "text",
+        mutable: false,
+    },
+    ParamSpec {
+        name: "log-payloads",
+        kind: ParamKind::Bool,
+        default: "no",
+        mutable: true,
+    },
+];
+
+/// Most addresses a single `bind` directive may list.
+const MAX_BIND_ADDRESSES: usize = 16;
+
+/// The client classes of `client-output-buffer-limit`, in the order
+/// `CONFIG GET` lists them. Only the replica class is enforced.
+const OUTPUT_BUFFER_CLASSES: [&str; 3] = ["normal", "slave", "pubsub"];
+
+fn check_file_name(name: &str) -> Result<(), String> {
+    if name.contains('/') {
+        return Err("dbfilename can't be a path, just a filename".to_string());
+    }
+    Ok(())
+}
+
+fn check_aof_file_name(name: &str) -> Result<(), String> {
+    if name.contains('/') {
+        return Err("appendfilename can't be a path, just a filename".to_string());
+    }
+    Ok(())
+}
+
+fn check_aof_dir_name(name: &str) -> Result<(), String> {
+    if name.contains('/') {
+        return Err("appenddirname can't be a path, just a directory name".to_string());
+    }
+    Ok(())
+}
+
+fn check_cluster_config_file(name: &str) -> Result<(), String> {
+    if name.contains('/') {
+        return Err("cluster-config-file can't be a path, just a filename".to_string());
+    }
+    Ok(())
+}
+
+fn check_dir(dir: &str) -> Result<(), String> {
+    if !Path::new(dir).is_dir() {
+        return Err("No such file or directory".to_string());
+    }
+    Ok(())
+}
+
+fn lookup_param(name: &str) -> Option<&'static ParamSpec> {
+    PARAMS
+        .iter()
+        .find(|spec| spec.name.eq_ignore_ascii_case(name))
+}
+
+impl ParamSpec {
+    /// Validate the arguments of a directive and return the value in the
+    /// form `CONFIG GET` reports it. Errors carry Redis's wording.
+    fn parse(&self, args: &[String]) -> Result<String, String> {
+        if let ParamKind::Addresses = self.kind {
+            if args.is_empty() {
+                return Err("wrong number of arguments".to_string());
+            }
+            if args.len() > MAX_BIND_ADDRESSES {
+                return Err("Too many bind addresses specified.".to_string());
+            }
+            return Ok(args.join(" "));
+        }
+        if let ParamKind::SavePoints = self.kind {
+            if args.is_empty() || args == [""] {
+                return Ok(String::new());
+            }
+            let valid =
+                args.len().is_multiple_of(2) && args.iter().all(|arg| arg.parse::<u64>().is_ok());
+            if !valid {
+                return Err("Invalid save parameters".to_string());
+            }
+            return Ok(args.join(" "));
+        }
+        if let ParamKind::ReplicaOf = self.kind {
+            return match args {
+                [] => Ok(String::new()),
+                [arg] if arg.is_empty() => Ok(String::new()),
+                [host, port] => match port.parse::<u16>() {
+                    Ok(port) if port > 0 => Ok(format!("{} {}", host, port)),
+                    _ => Err("Invalid master port".to_string()),
+                },
+                _ => Err("wrong number of arguments".to_string()),
+            };
+        }
+        if let ParamKind::KeyspaceEvents = self.kind {
+            let classes = match args {
+                [] => "",
+                [arg] => arg.as_str(),
+                _ => return Err("wrong number of arguments".to_string()),
+            };
+            return NotifyFlags::parse(classes)
+                .map(|flags| flags.to_string())
+                .ok_or_else(|| {
+                    "Invalid event class character. Use 'Ag$lshzxeKEtmdn'.".to_string()
+                });
+        }
+        if let ParamKind::OutputBufferLimits = self.kind {
+            return parse_output_buffer_limits(args);
+        }
+        let [arg] = args else {
+            return Err("wrong number of arguments".to_string());
+        };
+        match self.kind {
+            ParamKind::Bool => match arg.to_ascii_lowercase().as_str() {
+                value @ ("yes" | "no") => Ok(value.to_string()),
+                _ => Err("argument must be 'yes' or 'no'".to_string()),
+            },
+            ParamKind::Int { min, max } => {
+                let value = arg
+                    .parse::<i64>()
+                    .map_err(|_| "argument couldn't be parsed into an integer".to_string())?;
+                if !(min..=max).contains(&value) {
+                    return Err(format!(
+                        "argument must be between {} and {} inclusive",
+                        min, max
+                    ));
+                }
+                Ok(value.to_string())
+            }
+            ParamKind::Memory { min, max } => {
+                let value = parse_memory(arg)
+                    .ok_or_else(|| "argument must be a memory value".to_string())?;
+                if !(min..=max).contains(&value) {
+                    return Err(format!(
+                        "argument must be between {} and {} inclusive",
+                        min, max
+                    ));
+                }
+                Ok(value.to_string())
+            }
+            ParamKind::Enum(choices) => choices
+                .iter()
+                .find(|choice| choice.eq_ignore_ascii_case(arg))
+                .map(|choice| choice.to_string())
+                .ok_or_else(|| {
+                    format!(
+                        "argument(s) must be one of the following: {}",
+                        choices.join(", ")
+                    )
+                }),
+            ParamKind::String(check) => {
+                if let Some(check) = check {
+                    check(arg)?;
+                }
+                Ok(arg.clone())
+            }
+            ParamKind::Addresses
+            | ParamKind::SavePoints
+            | ParamKind::ReplicaOf
+            | ParamKind::KeyspaceEvents
+            | ParamKind::OutputBufferLimits => unreachable!(),
+        }
+    }
+
+    fn default_value(&self) -> String {
+        let args = self
+            .default
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        self.parse(&args).expect("invalid default value")
+    }
+
+    /// The directive as `CONFIG REWRITE` writes it.
+    fn format_line(&self, value: &str) -> String {
+        if let ParamKind::OutputBufferLimits = self.kind {
+            // one line per class, as Redis writes them
+            let words = value.split_whitespace().collect::<Vec<_>>();
+            return words
+                .chunks_exact(4)
+                .map(|limit| {
+                    let memory = |n: &str| format_memory(n.parse().expect("invalid memory value"));
+                    format!(
+                        "{} {} {} {} {}",
+                        self.name,
+                        limit[0],
+                        memory(limit[1]),
+                        memory(limit[2]),
+                        limit[3]
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        let mut line = self.name.to_string();
+        match self.kind {
+            ParamKind::Memory { .. } => {
+                let bytes = value.parse::<u64>().expect("invalid memory value");
+                write!(line, " {}", format_memory(bytes)).expect("write to string");
+            }
+            ParamKind::Addresses | ParamKind::SavePoints | ParamKind::ReplicaOf
+                if !value.is_empty() =>
+            {
+                value
+                    .split_whitespace()
+                    .for_each(|arg| write!(line, " {}", quote_arg(arg)).expect("write to string"))
+            }
+            _ => write!(line, " {}", quote_arg(value)).expect("write to string"),
+        }
+        line
+    }
+}
+
+/// Validate `client-output-buffer-limit` groups, returning them with the
+/// class names normalized and the sizes in bytes.
+fn parse_output_buffer_limits(args: &[String]) -> Result<String, String> {
+    if args.is_empty() || !args.len().is_multiple_of(4) {
+        return Err("Wrong number of arguments in buffer limit configuration.".to_string());
+    }
+    let mut limits = Vec::with_capacity(args.len() / 4);
+    for limit in args.chunks_exact(4) {
+        let class = match limit[0].to_ascii_lowercase().as_str() {
+            "normal" => "normal",
+            "replica" | "slave" => "slave",
+            "pubsub" => "pubsub",
+            _ => {
+                return Err(
+                    "Invalid client class specified in buffer limit configuration.".to_string(),
+                )
+            }
+        };
+        let (Some(hard), Some(soft), Ok(seconds)) = (
+            parse_memory(&limit[1]),
+            parse_memory(&limit[2]),
+            limit[3].parse::<u64>(),
+        ) else {
+            return Err(
+                "Error in hard, soft or soft_seconds setting in buffer limit configuration."
+                    .to_string(),
+            );
+        };
+        limits.push(format!("{} {} {} {}", class, hard, soft, seconds));
+    }
+    Ok(limits.join(" "))
+}
+
+/// Apply the classes named in `update` to the full `client-output-buffer-limit`
+/// value `current`, keeping the others.
+fn merge_output_buffer_limits(current: &str, update: &str) -> String {
+    let mut limits = BTreeMap::new();
+    for value in [current, update] {
+        let words = value.split_whitespace().collect::<Vec<_>>();
+        for limit in words.chunks_exact(4) {
+            limits.insert(limit[0], limit[1..].join(" "));
+        }
+    }
+    OUTPUT_BUFFER_CLASSES
+        .iter()
+        .filter_map(|class| Some(format!("{} {}", class, limits.get(class)?)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse a memory amount such as `100`, `1k` (1000) or `1kb` (1024), like
+/// Redis's `memtoull`.
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits);
+    let multiplier = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Render a byte count with the largest binary unit that divides it.
+fn format_memory(bytes: u64) -> String {
+    const UNITS: [(u64, &str); 3] = [(1 << 30, "gb"), (1 << 20, "mb"), (1 << 10, "kb")];
+    for (size, unit) in UNITS {
+        if bytes != 0 && bytes.is_multiple_of(size) {
+            return format!("{}{}", bytes / size, unit);
+        }
+    }
+    bytes.to_string()
+}
+
+/// Quote an argument so that `split_inline_args` reads it back unchanged.
+pub(crate) fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !matches!(b, b'"' | b'\'' | b'\\'));
+    if plain {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for b in arg.bytes() {
+        match b {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x07 => quoted.push_str("\\a"),
+            0x08 => quoted.push_str("\\b"),
+            b if b.is_ascii_graphic() || b == b' ' => quoted.push(b as char),
+            b => write!(quoted, "\\x{:02x}", b).expect("write to string"),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Split a config line into its arguments. `None` means unbalanced quotes.
+fn split_line(line: &str) -> Option<Vec<String>> {
+    split_inline_args(line.as_bytes()).map(|args| {
+        args.into_iter()
+            .map(|arg| String::from_utf8_lossy(&arg).into_owned())
+            .collect()
+    })
+}
+
+/// The parameters that are not plain strings or flags, parsed once whenever
+/// the configuration changes so that reading them is a field access.
+#[derive(Debug, Clone)]
+struct Settings {
+    port: u16,
+    protocol_limits: ProtocolLimits,
+    save_points: Vec<(u64, u64)>,
+    appendfsync: AppendFsync,
+    replicaof: Option<(String, u16)>,
+    repl_backlog_size: usize,
+    replica_output_buffer_limit: OutputBufferLimit,
+    encoding_limits: EncodingLimits,
+    eviction: EvictionConfig,
+    lfu: LfuConfig,
+    notify_keyspace_events: NotifyFlags,
+}
+
+/// Why a stored value could not be turned into a setting: the parameter
+/// and the reason.
+type SettingError = (&'static str, String);
+
+fn number<T: std::str::FromStr>(
+    values: &BTreeMap<&'static str, String>,
+    name: &'static str,
+) -> Result<T, SettingError> {
+    values[name].parse().map_err(|_| {
+        (
+            name,
+            "argument couldn't be parsed into an integer".to_string(),
+        )
+    })
+}
+
+/// The `client-output-buffer-limit` of `class`.
+fn output_buffer_limit(
+    values: &BTreeMap<&'static str, String>,
+    class: &str,
+) -> Result<OutputBufferLimit, SettingError> {
+    let name = "client-output-buffer-limit";
+    let words = values[name].split_whitespace().collect::<Vec<_>>();
+    let invalid = || {
+        (
+            name,
+            "Invalid client class specified in buffer limit configuration.".to_string(),
+        )
+    };
+    let limit = words
+        .chunks_exact(4)
+        .find(|limit| limit[0] == class)
+        .ok_or_else(invalid)?;
+    Ok(OutputBufferLimit {
+        hard: limit[1].parse().map_err(|_| invalid())?,
+        soft: limit[2].parse().map_err(|_| invalid())?,
+        soft_seconds: limit[3].parse().map_err(|_| invalid())?,
+    })
+}
+
+impl Settings {
+    fn from_values(values: &BTreeMap<&'static str, String>) -> Result<Settings, SettingError> {
+        let save_points = values["save"]
+            .split_whitespace()
+            .map(|n| n.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ("save", "Invalid save parameters".to_string()))?;
+        let replicaof = match values["replicaof"].split_once(' ') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| ("replicaof", "Invalid master port".to_string()))?;
+                Some((host.to_string(), port))
+            }
+            None => None,
+        };
+        Ok(Settings {
+            port: number(values, "port")?,
+            protocol_limits: ProtocolLimits {
+                max_bulk_len: number(values, "proto-max-bulk-len")?,
+                max_multibulk_len: number(values, "proto-max-multibulk-len")?,
+                max_depth: number(values, "proto-max-nesting-depth")?,
+                max_query_buffer: number(values, "client-query-buffer-limit")?,
+            },
+            save_points: save_points
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            appendfsync: match values["appendfsync"].as_str() {
+                "always" => AppendFsync::Always,
+                "no" => AppendFsync::No,
+                _ => AppendFsync::EverySec,
+            },
+            replicaof,
+            repl_backlog_size: number(values, "repl-backlog-size")?,
+            replica_output_buffer_limit: output_buffer_limit(values, "slave")?,
+            encoding_limits: EncodingLimits {
+                hash_max_listpack_entries: number(values, "hash-max-listpack-entries")?,
+                hash_max_listpack_value: number(values, "hash-max-listpack-value")?,
+                set_max_intset_entries: number(values, "set-max-intset-entries")?,
+            },
+            eviction: EvictionConfig {
+                maxmemory: number(values, "maxmemory")?,
+                policy: EvictionPolicy::from_name(&values["maxmemory-policy"])
+                    .ok_or(("maxmemory-policy", "unknown eviction policy".to_string()))?,
+                samples: number(values, "maxmemory-samples")?,
+            },
+            lfu: LfuConfig {
+                log_factor: number(values, "lfu-log-factor")?,
+                decay_time: number(values, "lfu-decay-time")?,
+            },
+            notify_keyspace_events: NotifyFlags::parse(&values["notify-keyspace-events"]).ok_or(
+                (
+                    "notify-keyspace-events",
+                    "Invalid event class character. Use 'Ag$lshzxeKEtmdn'.".to_string(),
+                ),
+            )?,
+        })
+    }
+}
+
+/// The server configuration: every parameter's current value and the file it
+/// was loaded from, if any.
+#[derive(Debug, Clone)]
+pub struct Config {
+    values: BTreeMap<&'static str, String>,
+    settings: Settings,
+    path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let values = PARAMS
+            .iter()
+            .map(|spec| (spec.name, spec.default_value()))
+            .collect();
+        let settings = Settings::from_values(&values).expect("invalid default value");
+        Config {
+            values,
+            settings,
+            path: None,
+        }
+    }
+}
+
+impl Config {
+    /// Build the configuration from command line arguments, as
+    /// `redis-server [configfile] [--name value ...]` does. Options given on
+    /// the command line are appended to the file and so take precedence.
+    pub fn from_args<I>(args: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter().peekable();
+        let path = args
+            .next_if(|arg| !arg.starts_with("--"))
+            .map(PathBuf::from);
+        let mut content = match &path {
+            Some(path) => {
+                fs::read_to_string(path).map_err(|e| ConfigError::Open(path.clone(), e))?
+            }
+            None => String::new(),
+        };
+        let mut options = String::new();
+        for arg in args {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    if !options.is_empty() {
+                        options.push('\n');
+                    }
+                    options.push_str(name);
+                }
+                None => {
+                    options.push(' ');
+                    options.push_str(&quote_arg(&arg));
+                }
+            }
+        }
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&options);
+        let mut config = Config::parse(&content)?;
+        config.path = path;
+        Ok(config)
+    }
+
+    /// Parse the contents of a redis.conf style file. Later directives
+    /// override earlier ones, except that `save` lines accumulate as they
+    /// do in Redis.
+    pub fn parse(content: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        let mut seen_save = false;
+        for (i, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let fatal = |reason: &str| ConfigError::Fatal {
+                line: i + 1,
+                text: trimmed.to_string(),
+                reason: reason.to_string(),
+            };
+            let args = split_line(trimmed)
+                .ok_or_else(|| fatal("Unbalanced quotes in configuration line"))?;
+            let spec = lookup_param(&args[0])
+                .ok_or_else(|| fatal("Bad directive or wrong number of arguments"))?;
+            let mut value = spec.parse(&args[1..]).map_err(|reason| fatal(&reason))?;
+            if let ParamKind::OutputBufferLimits = spec.kind {
+                value = merge_output_buffer_limits(&config.values[spec.name], &value);
+            }
+            if let ParamKind::SavePoints = spec.kind {
+                if seen_save && !value.is_empty() && !config.values[spec.name].is_empty() {
+                    value = format!("{} {}", config.values[spec.name], value);
+                }
+                seen_save = true;
+            }
+            config.values.insert(spec.name, value);
+        }
+        config.settings = Settings::from_values(&config.values)
+            .map_err(|(name, reason)| ConfigError::Invalid(name, reason))?;
+        Ok(config)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let spec = lookup_param(name)?;
+        Some(&self.values[spec.name])
+    }
+
+    /// Parameters whose name matches the glob `pattern`, case-insensitively.
+    pub fn matching<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = (&'static str, &'a str)> + 'a {
+        self.values
+            .iter()
+            .filter(move |(name, _)| glob_match(pattern.as_bytes(), name.as_bytes(), true))
+            .map(|(name, value)| (*name, value.as_str()))
+    }
+
+    /// Set several parameters at once. Every pair is validated before any is
+    /// applied, so on error the configuration is left untouched.
+    pub fn set(&mut self, pairs: &[(String, String)]) -> Result<(), ConfigError> {
+        let mut updates = BTreeMap::new();
+        for (name, value) in pairs {
+            let spec =
+                lookup_param(name).ok_or_else(|| ConfigError::UnknownOption(name.clone()))?;
+            let failed = |reason: &str| ConfigError::SetFailed(name.clone(), reason.to_string());
+            if !spec.mutable {
+                return Err(failed("can't set immutable config"));
+            }
+            let args = match spec.kind {
+                ParamKind::Addresses
+                | ParamKind::SavePoints
+                | ParamKind::ReplicaOf
+                | ParamKind::OutputBufferLimits => {
+                    value.split_whitespace().map(String::from).collect()
+                }
+                _ => vec![value.clone()],
+            };
+            let mut value = spec.parse(&args).map_err(|reason| failed(&reason))?;
+            if let ParamKind::OutputBufferLimits = spec.kind {
+                value = merge_output_buffer_limits(&self.values[spec.name], &value);
+            }
+            if updates.insert(spec.name, value).is_some() {
+                return Err(failed("duplicate parameter"));
+            }
+        }
+        let mut values = self.values.clone();
+        values.extend(updates.iter().map(|(name, value)| (*name, value.clone())));
+        self.settings = Settings::from_values(&values)
+            .map_err(|(name, reason)| ConfigError::SetFailed(name.to_string(), reason))?;
+        self.values = values;
+        updates.keys().for_each(|name| self.apply(name));
+        Ok(())
+    }
+
+    /// Push a changed value to the component that reads it. Parameters not
+    /// handled here are read where they are used, e.g. the protocol limits
+    /// by each connection after a batch of requests.
+    fn apply(&self, name: &str) {
+        if name == "log-payloads" {
+            logging::set_log_payloads(self.get_bool(name));
+        }
+    }
+
+    /// Write the current configuration back to the file it was loaded from.
+    /// Comments, blank lines and their order are kept; the first directive
+    /// of each parameter is updated in place and repeats are dropped.
+    /// Parameters that differ from their default and are missing from the
+    /// file are appended at the end.
+    pub fn rewrite(&self) -> Result<(), ConfigError> {
+        let path = self.path.as_ref().ok_or(ConfigError::NoConfigFile)?;
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut written = BTreeSet::new();
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let spec = split_line(line.trim())
+                .and_then(|args| args.first().and_then(|name| lookup_param(name)))
+                .filter(|_| !line.trim_start().starts_with('#'));
+            match spec {
+                Some(spec) => {
+                    if written.insert(spec.name) {
+                        lines.push(spec.format_line(&self.values[spec.name]));
+                    }
+                }
+                None => lines.push(line.to_string()),
+            }
+        }
+        let missing = PARAMS
+            .iter()
+            .filter(|spec| !written.contains(spec.name))
+            .filter(|spec| self.values[spec.name] != spec.default_value())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            lines.push("# Generated by CONFIG REWRITE".to_string());
+            lines.extend(
+                missing
+                    .into_iter()
+                    .map(|spec| spec.format_line(&self.values[spec.name])),
+            );
+        }
+
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(format!(".tmp-{}", process::id()));
+        let mut output = lines.join("\n");
+        output.push('\n');
+        fs::write(&tmp, output)?;
+        fs::rename(&tmp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })?;
+        Ok(())
+    }
+
+    fn get_bool(&self, name: &str) -> bool {
+        self.values[name] == "yes"
+    }
+
+    pub fn port(&self) -> u16 {
+        self.settings.port
+    }
+
+    /// The `bind` addresses with `*` expanded to the IPv4 wildcard and
+    /// `::*` to the IPv6 one. The flag is set for addresses prefixed with
+    /// `-`, which the server skips when they can't be bound.
+    pub fn bind_addresses(&self) -> Vec<(String, bool)> {
+        self.values["bind"]
+            .split_whitespace()
+            .map(|addr| {
+                let (addr, optional) = match addr.strip_prefix('-') {
+                    Some(addr) => (addr, true),
+                    None => (addr, false),
+                };
+                let addr = match addr {
+                    "*" => "0.0.0.0",
+                    "::*" => "::",
+                    addr => addr,
+                };
+                (addr.to_string(), optional)
+            })
+            .collect()
+    }
+
+    pub fn protocol_limits(&self) -> ProtocolLimits {
+        self.settings.protocol_limits
+    }
+
+    /// Where snapshots are written and loaded from: `dbfilename` in `dir`.
+    pub fn rdb_path(&self) -> PathBuf {
+        Path::new(&self.values["dir"]).join(&self.values["dbfilename"])
+    }
+
+    /// The `save` triggers as `(seconds, changes)` pairs.
+    pub fn save_points(&self) -> Vec<(u64, u64)> {
+        self.settings.save_points.clone()
+    }
+
+    pub fn rdb_checksum(&self) -> bool {
+        self.get_bool("rdbchecksum")
+    }
+
+    pub fn appendonly(&self) -> bool {
+        self.get_bool("appendonly")
+    }
+
+    /// Directory holding the AOF files and their manifest.
+    pub fn aof_dir(&self) -> PathBuf {
+        Path::new(&self.values["dir"]).join(&self.values["appenddirname"])
+    }
+
+    /// Prefix of every AOF file name.
+    pub fn aof_file_name(&self) -> &str {
+        &self.values["appendfilename"]
+    }
+
+    pub fn appendfsync(&self) -> AppendFsync {
+        self.settings.appendfsync
+    }
+
+    pub fn aof_load_truncated(&self) -> bool {
+        self.get_bool("aof-load-truncated")
+    }
+
+    /// The master this server replicates, from `replicaof`.
+    pub fn replicaof(&self) -> Option<(String, u16)> {
+        self.settings.replicaof.clone()
+    }
+
+    /// Record the master set by `REPLICAOF`, so `CONFIG REWRITE` keeps it.
+    pub fn set_replicaof(&mut self, master: Option<(&str, u16)>) {
+        let value = master.map_or_else(String::new, |(host, port)| format!("{} {}", host, port));
+        self.values.insert("replicaof", value);
+        self.settings.replicaof = master.map(|(host, port)| (host.to_string(), port));
+    }
+
+    pub fn replica_read_only(&self) -> bool {
+        self.get_bool("replica-read-only")
+    }
+
+    pub fn repl_backlog_size(&self) -> usize {
+        self.settings.repl_backlog_size
+    }
+
+    /// How many bytes may be queued for a replica before its link is
+    /// dropped, from the replica class of `client-output-buffer-limit`.
+    pub fn replica_output_buffer_limit(&self) -> OutputBufferLimit {
+        self.settings.replica_output_buffer_limit
+    }
+
+    pub fn cluster_enabled(&self) -> bool {
+        self.get_bool("cluster-enabled")
+    }
+
+    /// The static cluster topology: `cluster-config-file` in `dir`.
+    pub fn cluster_config_path(&self) -> PathBuf {
+        Path::new(&self.values["dir"]).join(&self.values["cluster-config-file"])
+    }
+
+    pub fn encoding_limits(&self) -> EncodingLimits {
+        self.settings.encoding_limits
+    }
+
+    pub fn eviction(&self) -> EvictionConfig {
+        self.settings.eviction
+    }
+
+    pub fn lfu(&self) -> LfuConfig {
+        self.settings.lfu
+    }
+
+    /// The keyspace event classes published, from `notify-keyspace-events`.
+    pub fn notify_keyspace_events(&self) -> NotifyFlags {
+        self.settings.notify_keyspace_events
+    }
+
+    pub fn log_config(&self) -> LogConfig {
+        LogConfig {
+            format: match self.values["log-format"].as_str() {
+                "json" => LogFormat::Json,
+                _ => LogFormat::Text,
+            },
+            log_payloads: self.get_bool("log-payloads"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("simple-redis-{}-{}.conf", name, process::id()))
+    }
+
+    #[test]
+    fn test_parse_config_file() {
+        let config = Config::parse(
+            "# comment\n\nport 7000\r\nbind 127.0.0.1 -::1\nPROTO-MAX-BULK-LEN 2mb\n  log-format \"json\"\nport 7001\n",
+        )
+        .unwrap();
+        assert_eq!(config.port(), 7001);
+        assert_eq!(
+            config.bind_addresses(),
+            vec![("127.0.0.1".to_string(), false), ("::1".to_string(), true)]
+        );
+        assert_eq!(config.get("proto-max-bulk-len"), Some("2097152"));
+        assert_eq!(config.protocol_limits().max_bulk_len, 2 * 1024 * 1024);
+        assert_eq!(config.log_config().format, LogFormat::Json);
+        assert_eq!(config.get("client-query-buffer-limit"), Some("1073741824"));
+    }
+
+    #[test]
+    fn test_persistence_params() {
+        let config = Config::default();
+        assert_eq!(
+            config.save_points(),
+            vec![(3600, 1), (300, 100), (60, 10000)]
+        );
+        assert_eq!(config.rdb_path(), Path::new("./dump.rdb"));
+
+        let config =
+            Config::parse("save 900 1\nsave 300 10\ndir /tmp\ndbfilename a.rdb\n").unwrap();
+        assert_eq!(config.save_points(), vec![(900, 1), (300, 10)]);
+        assert_eq!(config.rdb_path(), Path::new("/tmp/a.rdb"));
+        let config = Config::parse("save 900 1\nsave \"\"\n").unwrap();
+        assert_eq!(config.save_points(), vec![]);
+        assert!(Config::parse("save 900").is_err());
+
+        let mut config = Config::default();
+        config.set(&[("save".to_string(), "".to_string())]).unwrap();
+        assert_eq!(config.get("save"), Some(""));
+        assert_eq!(
+            ParamSpec::format_line(lookup_param("save").unwrap(), ""),
+            "save \"\""
+        );
+        let err = config
+            .set(&[("dbfilename".to_string(), "a/b.rdb".to_string())])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("dbfilename can't be a path, just a filename"));
+        assert!(config
+            .set(&[("dir".to_string(), "/nonexistent/dir".to_string())])
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_config_file_errors() {
+        let err = Config::parse("port 6379\nfoo bar\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\n*** FATAL CONFIG FILE ERROR ***\nReading the configuration file, at line 2\n>>> 'foo bar'\nBad directive or wrong number of arguments"
+        );
+        let err = Config::parse("port 70000").unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("argument must be between 0 and 65535 inclusive"));
+        let err = Config::parse("log-payloads maybe").unwrap_err();
+        assert!(err.to_string().ends_with("argument must be 'yes' or 'no'"));
+        let err = Config::parse("port 1 2").unwrap_err();
+        assert!(err.to_string().ends_with("wrong number of arguments"));
+        assert!(Config::parse("bind \"127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_notify_keyspace_events() {
+        let mut config = Config::default();
+        assert_eq!(config.get("notify-keyspace-events"), Some(""));
+        config
+            .set(&[("notify-keyspace-events".to_string(), "KEA".to_string())])
+            .unwrap();
+        assert_eq!(config.get("notify-keyspace-events"), Some("AKE"));
+        assert!(config
+            .notify_keyspace_events()
+            .contains(NotifyFlags::KEYSPACE | NotifyFlags::EVICTED));
+        let err = config
+            .set(&[("notify-keyspace-events".to_string(), "KQ".to_string())])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("Invalid event class character. Use 'Ag$lshzxeKEtmdn'."));
+        let config = Config::parse("notify-keyspace-events Ex\n").unwrap();
+        assert_eq!(config.get("notify-keyspace-events"), Some("xE"));
+    }
+
+    #[test]
+    fn test_client_output_buffer_limit() {
+        let config = Config::default();
+        assert_eq!(
+            config.get("client-output-buffer-limit"),
+            Some("normal 0 0 0 slave 268435456 67108864 60 pubsub 33554432 8388608 60")
+        );
+        let config = Config::parse(
+            "client-output-buffer-limit replica 512mb 128mb 30\nclient-output-buffer-limit pubsub 1mb 0 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.replica_output_buffer_limit(),
+            OutputBufferLimit {
+                hard: 512 << 20,
+                soft: 128 << 20,
+                soft_seconds: 30,
+            }
+        );
+        assert_eq!(
+            ParamSpec::format_line(
+                lookup_param("client-output-buffer-limit").unwrap(),
+                config.get("client-output-buffer-limit").unwrap()
+            ),
+            "client-output-buffer-limit normal 0 0 0\n\
+             client-output-buffer-limit slave 512mb 128mb 30\n\
+             client-output-buffer-limit pubsub 1mb 0 0"
+        );
+
+        let mut config = Config::default();
+        config
+            .set(&[(
+                "client-output-buffer-limit".to_string(),
+                "slave 1mb 0 0".to_string(),
+            )])
+            .unwrap();
+        assert_eq!(config.replica_output_buffer_limit().hard, 1 << 20);
+        assert_eq!(
+            config.get("client-output-buffer-limit"),
+            Some("normal 0 0 0 slave 1048576 0 0 pubsub 33554432 8388608 60")
+        );
+        let err = Config::parse("client-output-buffer-limit master 1mb 0 0").unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("Invalid client class specified in buffer limit configuration."));
+        let err = Config::parse("client-output-buffer-limit replica 1mb 0").unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("Wrong number of arguments in buffer limit configuration."));
+        assert!(Config::parse("client-output-buffer-limit replica 1mb 0 x").is_err());
+        assert!(Config::parse("replica-output-buffer-limit 1mb").is_err());
+    }
+
+    #[test]
+    fn test_memory_values() {
+        assert_eq!(parse_memory("100"), Some(100));
+        assert_eq!(parse_memory("1k"), Some(1000));
+        assert_eq!(parse_memory("1KB"), Some(1024));
+        assert_eq!(parse_memory("3gb"), Some(3 << 30));
+        assert_eq!(parse_memory("-1"), None);
+        assert_eq!(parse_memory("1tb"), None);
+        assert_eq!(format_memory(512 << 20), "512mb");
+        assert_eq!(format_memory(1536), "1536");
+        assert_eq!(format_memory(3 << 10), "3kb");
+    }
+
+    #[test]
+    fn test_command_line_overrides_file() {
+        let path = temp_path("args");
+        fs::write(&path, "port 7000\nlog-payloads yes\n").unwrap();
+        let config = Config::from_args(args(&[
+            path.to_str().unwrap(),
+            "--port",
+            "7002",
+            "--bind",
+            "127.0.0.1",
+            "::1",
+        ]))
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(config.path(), Some(path.as_path()));
+        assert_eq!(config.port(), 7002);
+        assert_eq!(config.get("bind"), Some("127.0.0.1 ::1"));
+        assert_eq!(config.get("log-payloads"), Some("yes"));
+
+        let config = Config::from_args(args(&["--port", "7003"])).unwrap();
+        assert_eq!(config.path(), None);
+        assert_eq!(config.port(), 7003);
+        assert!(Config::from_args(args(&["/nonexistent/redis.conf"])).is_err());
+    }
+
+    #[test]
+    fn test_matching_patterns() {
+        let config = Config::default();
+        let names = config
+            .matching("*BULK*")
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["proto-max-bulk-len", "proto-max-multibulk-len"]);
+        assert_eq!(
+            config.matching("port").collect::<Vec<_>>(),
+            vec![("port", "6379")]
+        );
+        assert_eq!(config.matching("nope*").count(), 0);
+    }
+
+    #[test]
+    fn test_set_is_atomic() {
+        let mut config = Config::default();
+        config
+            .set(&[
+                ("proto-max-bulk-len".to_string(), "1gb".to_string()),
+                ("client-query-buffer-limit".to_string(), "2mb".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(config.get("proto-max-bulk-len"), Some("1073741824"));
+        assert_eq!(config.protocol_limits().max_query_buffer, 2 << 20);
+
+        let err = config
+            .set(&[
+                ("proto-max-bulk-len".to_string(), "4mb".to_string()),
+                ("client-query-buffer-limit".to_string(), "1k".to_string()),
+            ])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CONFIG SET failed (possibly related to argument 'client-query-buffer-limit') - argument must be between 1048576 and 9223372036854775807 inclusive"
+        );
+        assert_eq!(config.get("proto-max-bulk-len"), Some("1073741824"));
+        assert_eq!(config.protocol_limits().max_bulk_len, 1 << 30);
+
+        config
+            .set(&[
+                ("proto-max-multibulk-len".to_string(), "1000".to_string()),
+                ("proto-max-nesting-depth".to_string(), "8".to_string()),
+            ])
+            .unwrap();
+        let limits = config.protocol_limits();
+        assert_eq!((limits.max_multibulk_len, limits.max_depth), (1000, 8));
+        let err = config
+            .set(&[("proto-max-nesting-depth".to_string(), "0".to_string())])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("argument must be between 1 and 1024 inclusive"));
+
+        let err = config
+            .set(&[("port".to_string(), "7000".to_string())])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CONFIG SET failed (possibly related to argument 'port') - can't set immutable config"
+        );
+        let err = config
+            .set(&[
+                ("proto-max-bulk-len".to_string(), "4mb".to_string()),
+                ("PROTO-MAX-BULK-LEN".to_string(), "8mb".to_string()),
+            ])
+            .unwrap_err();
+        assert!(err.to_string().ends_with("duplicate parameter"));
+        let err = config
+            .set(&[("foo".to_string(), "bar".to_string())])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown option or number of arguments for CONFIG SET - 'foo'"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_preserves_comments() {
+        let path = temp_path("rewrite");
+        fs::write(
+            &path,
+            "# Network\nport 7000\n\n# Limits\nproto-max-bulk-len 2mb\nproto-max-bulk-len 4mb\n# trailing comment\n",
+        )
+        .unwrap();
+        let mut config = Config::from_args(args(&[path.to_str().unwrap()])).unwrap();
+        config
+            .set(&[
+                ("proto-max-bulk-len".to_string(), "16mb".to_string()),
+                ("client-query-buffer-limit".to_string(), "3mb".to_string()),
+            ])
+            .unwrap();
+        config.rewrite().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            content,
+            "# Network\nport 7000\n\n# Limits\nproto-max-bulk-len 16mb\n# trailing comment\n# Generated by CONFIG REWRITE\nclient-query-buffer-limit 3mb\n"
+        );
+        assert!(matches!(
+            Config::default().rewrite(),
+            Err(ConfigError::NoConfigFile)
+        ));
+    }
+
+    #[test]
+    fn test_quote_arg_round_trip() {
+        for arg in [
+            "plain",
+            "",
+            "with space",
+            "quote\"s",
+            "new\nline",
+            "back\\slash",
+        ] {
+            let quoted = quote_arg(arg);
+            assert_eq!(split_line(&quoted).unwrap(), vec![arg.to_string()]);
+        }
+    }
+}
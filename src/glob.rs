@@ -0,0 +1,136 @@
+/// Redis-style glob matching (`*`, `?`, `[a-z]`, `[^abc]` and `\` escapes),
+/// with the semantics of `stringmatchlen` from the Redis source.
+///
+/// Every token but `*` matches exactly one byte, so on a mismatch only the
+/// last `*` needs to take one more byte: earlier stars could not lead to a
+/// match the last one misses. This keeps matching at O(pattern * string)
+/// where recursing at each star is exponential (CVE-2022-36021).
+pub fn glob_match(pattern: &[u8], string: &[u8], nocase: bool) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // pattern position after the last `*`, and where its match ends
+    let mut star = None;
+    loop {
+        if p < pattern.len() && pattern[p] == b'*' {
+            while p < pattern.len() && pattern[p] == b'*' {
+                p += 1;
+            }
+            if p == pattern.len() {
+                return true;
+            }
+            star = Some((p, s));
+            continue;
+        }
+        if p < pattern.len() && s < string.len() {
+            if let Some(next) = match_token(pattern, p, string[s], nocase) {
+                p = next;
+                s += 1;
+                continue;
+            }
+        } else if p == pattern.len() && s == string.len() {
+            return true;
+        }
+        match star {
+            Some((after_star, end)) if end < string.len() => {
+                star = Some((after_star, end + 1));
+                p = after_star;
+                s = end + 1;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Match `c` against the token at `pattern[p]`, which is not `*`, returning
+/// the position of the next token.
+fn match_token(pattern: &[u8], mut p: usize, c: u8, nocase: bool) -> Option<usize> {
+    let eq = |a: u8, b: u8| {
+        if nocase {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+
+    let matched = match pattern[p] {
+        b'?' => true,
+        b'[' => {
+            p += 1;
+            let not = p < pattern.len() && pattern[p] == b'^';
+            if not {
+                p += 1;
+            }
+            let mut matched = false;
+            while p < pattern.len() && pattern[p] != b']' {
+                if pattern[p] == b'\\' && p + 1 < pattern.len() {
+                    p += 1;
+                    matched |= eq(pattern[p], c);
+                } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' {
+                    let (mut start, mut end) = (pattern[p], pattern[p + 2]);
+                    if start > end {
+                        std::mem::swap(&mut start, &mut end);
+                    }
+                    matched |= if nocase {
+                        let c = c.to_ascii_lowercase();
+                        c >= start.to_ascii_lowercase() && c <= end.to_ascii_lowercase()
+                    } else {
+                        c >= start && c <= end
+                    };
+                    p += 2;
+                } else {
+                    matched |= eq(pattern[p], c);
+                }
+                p += 1;
+            }
+            matched != not
+        }
+        b'\\' if p + 1 < pattern.len() => {
+            p += 1;
+            eq(pattern[p], c)
+        }
+        token => eq(token, c),
+    };
+    // an unterminated `[` runs to the end of the pattern
+    matched.then_some((p + 1).min(pattern.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*", b"anything", false));
+        assert!(glob_match(b"h?llo", b"hello", false));
+        assert!(!glob_match(b"h?llo", b"hllo", false));
+        assert!(glob_match(b"h*llo", b"heeeello", false));
+        assert!(glob_match(b"h[ae]llo", b"hallo", false));
+        assert!(!glob_match(b"h[^e]llo", b"hello", false));
+        assert!(glob_match(b"h[a-b]llo", b"hbllo", false));
+        assert!(glob_match(b"h\\*llo", b"h*llo", false));
+        assert!(!glob_match(b"h\\*llo", b"hello", false));
+        assert!(glob_match(b"H*", b"hset", true));
+        assert!(!glob_match(b"H*", b"hset", false));
+        assert!(glob_match(b"max*", b"maxmemory", false));
+        assert!(glob_match(b"*a*b", b"xaxxb", false));
+        assert!(!glob_match(b"*a*b", b"xaxxbc", false));
+        assert!(glob_match(b"a*", b"a", false));
+        assert!(!glob_match(b"a?", b"a", false));
+        assert!(glob_match(b"h[-b", b"hb", false));
+        assert!(!glob_match(b"h[-b", b"hbx", false));
+    }
+
+    #[test]
+    fn test_glob_match_worst_case() {
+        // each star used to try every split of the rest of the string
+        let pattern = format!("{}b", "a*".repeat(50));
+        let string = "a".repeat(1000);
+        let started = std::time::Instant::now();
+        assert!(!glob_match(pattern.as_bytes(), string.as_bytes(), false));
+        assert!(glob_match(
+            pattern.as_bytes(),
+            format!("{}b", string).as_bytes(),
+            false
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+}
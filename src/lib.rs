@@ -1,11 +1,20 @@
+pub mod aof;
+pub mod cluster;
 #[allow(dead_code)]
 pub mod cmd;
+mod glob;
+pub mod logging;
 pub mod network;
+pub mod pubsub;
+pub mod rdb;
+pub mod replication;
 mod resp;
 pub use resp::*;
-mod resp2;
-pub use resp2::*;
 mod backend;
 pub use backend::*;
+mod client;
+pub use client::*;
+mod config;
+pub use config::*;
 
 pub const CRLF: &[u8] = b"\r\n";
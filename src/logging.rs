@@ -0,0 +1,101 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use tracing_subscriber::EnvFilter;
+
+use crate::{BulkString, RespArray, RespFrame};
+
+/// Whether TRACE events may include argument and reply contents.
+static LOG_PAYLOADS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// Log request and reply contents at TRACE instead of their sizes.
+    pub log_payloads: bool,
+}
+
+/// Switch payload logging on or off, e.g. after `CONFIG SET log-payloads`.
+pub fn set_log_payloads(enabled: bool) {
+    LOG_PAYLOADS.store(enabled, Ordering::Relaxed);
+}
+
+/// Install the global subscriber. The level comes from `RUST_LOG` and
+/// defaults to `info`.
+pub fn init(config: &LogConfig) {
+    set_log_payloads(config.log_payloads);
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
+
+/// Displays a request or reply for TRACE logging. Unless payload logging is
+/// enabled only the command name and the size of each part are shown, so
+/// keys and values never reach the logs by accident.
+pub struct Payload<'a>(pub &'a RespFrame);
+
+impl fmt::Display for Payload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_PAYLOADS.load(Ordering::Relaxed) {
+            return write!(f, "{:?}", self.0);
+        }
+        match self.0 {
+            RespFrame::Array(RespArray(Some(frames))) => {
+                for (i, frame) in frames.iter().enumerate() {
+                    match (i, frame) {
+                        (0, RespFrame::BulkString(BulkString(Some(name)))) => {
+                            write!(f, "{}", String::from_utf8_lossy(name))?
+                        }
+                        (_, frame) => write!(f, " {}", Redacted(frame))?,
+                    }
+                }
+                Ok(())
+            }
+            frame => write!(f, "{}", Redacted(frame)),
+        }
+    }
+}
+
+struct Redacted<'a>(&'a RespFrame);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            RespFrame::BulkString(BulkString(Some(data))) => write!(f, "<{} bytes>", data.len()),
+            RespFrame::Array(RespArray(Some(frames))) => {
+                write!(f, "<array of {}>", frames.len())
+            }
+            frame => write!(f, "<{}>", frame.type_name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_is_redacted() {
+        let frame: RespFrame = RespArray::new(Some(vec![
+            BulkString::new(Some("SET")).into(),
+            BulkString::new(Some("secret-key")).into(),
+            BulkString::new(Some("secret-value")).into(),
+        ]))
+        .into();
+        assert_eq!(Payload(&frame).to_string(), "SET <10 bytes> <12 bytes>");
+        let frame: RespFrame = 10.into();
+        assert_eq!(Payload(&frame).to_string(), "<integer>");
+    }
+}
@@ -1,23 +1,150 @@
-use anyhow::Result;
-use simple_redis::{network, Backend};
-use tokio::net::TcpListener;
-use tracing::{info, warn};
+use std::{env, process, time::Duration};
+
+use anyhow::{bail, Result};
+use simple_redis::{
+    aof, cluster, expire_cron, logging, network, rdb, replication, Backend, Config,
+};
+use tokio::{net::TcpListener, signal, task::JoinSet};
+use tracing::{error, info, warn};
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-    let addr = "0.0.0.0:6379";
-    info!("Simple-redis-server is Listening on {}", addr);
+    let config = Config::from_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    logging::init(&config.log_config());
+
+    let backend = Backend::with_config(config);
+    if backend.config().cluster_enabled() {
+        if let Err(e) = cluster::load(&backend) {
+            error!("{}", e);
+            process::exit(1);
+        }
+        info!(
+            "Cluster node {} ready",
+            backend.cluster().map_or("", |c| &c.myself().id)
+        );
+    }
+    load_data(&backend);
+
+    let mut listeners = Vec::new();
+    let (addresses, port) = {
+        let config = backend.config();
+        (config.bind_addresses(), config.port())
+    };
+    for (host, optional) in addresses {
+        let addr = format!("{}:{}", host, port);
+        match TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                info!("Simple-redis-server is Listening on {}", addr);
+                listeners.push(listener);
+            }
+            Err(e) if optional => warn!("skipping optional address {}: {}", addr, e),
+            Err(e) => bail!("could not bind {}: {}", addr, e),
+        }
+    }
+    if listeners.is_empty() {
+        bail!("no address to listen on");
+    }
 
-    let listener = TcpListener::bind(addr).await?;
-    let backend = Backend::new();
+    let master = backend.config().replicaof();
+    if master.is_some() {
+        replication::set_master(&backend, master);
+    }
+    tokio::spawn(rdb::save_cron(backend.clone()));
+    tokio::spawn(aof::fsync_cron(backend.clone()));
+    tokio::spawn(expire_cron(backend.clone()));
+    tokio::spawn(replication::ping_cron(backend.clone()));
+    let mut accept_loops = JoinSet::new();
+    for listener in listeners {
+        accept_loops.spawn(accept_loop(listener, backend.clone()));
+    }
+    tokio::select! {
+        Some(result) = accept_loops.join_next() => result?,
+        _ = shutdown_signal() => shutdown(&backend).await,
+    }
+}
+
+/// Load the dataset from the AOF when it is enabled, as it is the more
+/// complete of the two, and from the RDB file otherwise. With AOF enabled
+/// but no AOF files yet, the RDB file seeds the first base.
+fn load_data(backend: &Backend) {
+    let appendonly = backend.config().appendonly();
+    if appendonly {
+        match aof::load(backend) {
+            Ok(Some(_)) => {}
+            Ok(None) => load_rdb(backend),
+            Err(e) => {
+                error!("Fatal error loading the append only file: {}. Exiting.", e);
+                process::exit(1);
+            }
+        }
+        if let Err(e) = aof::resume(backend) {
+            error!("Can't open the append-only file: {}. Exiting.", e);
+            process::exit(1);
+        }
+    } else {
+        load_rdb(backend);
+    }
+}
+
+fn load_rdb(backend: &Backend) {
+    let rdb_path = backend.config().rdb_path();
+    if rdb_path.exists() {
+        if let Err(e) = rdb::load(backend, &rdb_path) {
+            error!(
+                "Fatal error loading the DB {}: {}. Exiting.",
+                rdb_path.display(),
+                e
+            );
+            process::exit(1);
+        }
+    }
+}
+
+async fn accept_loop(listener: TcpListener, backend: Backend) -> Result<()> {
     loop {
         let (stream, raddr) = listener.accept().await?;
         let cloned_backend = backend.clone();
-        info!("Accepted connection from: {}", raddr);
+        let limits = backend.config().protocol_limits();
         tokio::spawn(async move {
-            if let Err(e) = network::stream_handler(stream, cloned_backend).await {
+            if let Err(e) = network::stream_handler(stream, cloned_backend, limits).await {
                 warn!("handle error for {}: {:?}", raddr, e);
             }
         });
     }
 }
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut term = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = signal::ctrl_c() => {}
+            _ = term.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = signal::ctrl_c().await;
+}
+
+/// Flush the AOF and save a final snapshot when `save` points are
+/// configured, as Redis does on SIGINT and SIGTERM.
+async fn shutdown(backend: &Backend) -> Result<()> {
+    info!("Received shutdown signal, scheduling shutdown...");
+    if backend.aof().enabled() {
+        info!("Calling fsync() on the AOF file.");
+        backend.aof().fsync()?;
+    }
+    if !backend.config().save_points().is_empty() {
+        while backend.save_state().in_progress() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        info!("Saving the final RDB snapshot before exiting.");
+        rdb::save(backend)?;
+    }
+    info!("Simple-redis is now ready to exit, bye bye...");
+    Ok(())
+}
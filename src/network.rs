@@ -1,12 +1,17 @@
+use std::time::Instant;
+
 use anyhow::Result;
-use futures::{SinkExt, StreamExt};
+use bytes::BytesMut;
+use futures::{FutureExt, SinkExt, StreamExt};
 use tokio::net::TcpStream;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::{error, info};
+use tracing::{debug, debug_span, field, info, info_span, trace, warn, Instrument};
 
 use crate::{
-    cmd::{Command, CommandExecutor},
-    Backend, RespDecodeV2, RespEncode, RespError, RespFrame, SimpleError,
+    cmd::{command_spec, execute_request, request_summary},
+    logging::Payload,
+    replication, Backend, BulkString, Client, ProtocolLimits, RespArray, RespDecoder, RespEncode,
+    RespError, RespFrame, RespVersion, SimpleError,
 };
 
 #[derive(Debug)]
@@ -20,14 +25,39 @@ pub struct RedisResponse {
     frame: RespFrame,
 }
 
-pub struct RespFrameCodec;
+#[derive(Debug, Default)]
+pub struct RespFrameCodec {
+    /// Replies are downgraded to RESP2 types unless the client sent `HELLO 3`.
+    pub protocol: RespVersion,
+    decoder: RespDecoder,
+    /// Bytes of a partial inline command already searched for `\n`.
+    inline_scanned: usize,
+}
+
+impl RespFrameCodec {
+    pub fn with_limits(limits: ProtocolLimits) -> Self {
+        RespFrameCodec {
+            protocol: RespVersion::default(),
+            decoder: RespDecoder::with_limits(limits),
+            inline_scanned: 0,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: ProtocolLimits) {
+        self.decoder.set_limits(limits);
+    }
+}
 
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn encode(&mut self, item: RespFrame, dst: &mut bytes::BytesMut) -> Result<()> {
-        let encoded = item.encode();
-        dst.extend_from_slice(&encoded);
+        let item = match self.protocol {
+            RespVersion::Resp2 => item.into_resp2(),
+            RespVersion::Resp3 => item,
+        };
+        dst.reserve(item.encoded_len());
+        item.encode_to(dst);
         Ok(())
     }
 }
@@ -38,45 +68,435 @@ impl Decoder for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>> {
-        info!("decode {:?}", src);
-        match RespFrame::decode(src) {
-            Ok(frame) => Ok(Some(frame)),
-            Err(RespError::NotComplete) => Ok(None),
-            Err(e) => Err(e.into()),
+        // inline commands are only recognised between multibulk requests
+        while self.decoder.is_idle() && src.first().is_some_and(|b| *b != b'*') {
+            match self.decode_inline(src) {
+                Ok(Some(frame)) => return Ok(Some(frame)),
+                // an empty inline line is skipped, like Redis does
+                Ok(None) => continue,
+                Err(RespError::NotComplete) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
         }
+        Ok(self.decoder.decode(src)?)
+    }
+
+    /// A client that disconnects in the middle of a frame is simply gone;
+    /// there is nobody to report the truncated request to.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        let frame = self.decode(src)?;
+        if frame.is_none() {
+            src.clear();
+        }
+        Ok(frame)
     }
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
-    let mut framed = Framed::new(stream, RespFrameCodec);
+/// Longest inline command line accepted before the request is rejected.
+const INLINE_MAX_SIZE: usize = 64 * 1024;
+
+impl RespFrameCodec {
+    /// Decode a telnet-style inline command (`SET key "some value"\r\n`)
+    /// into the same array of bulk strings a RESP client would send. Returns
+    /// `None` for an empty line, which is consumed and ignored. The search
+    /// for `\n` resumes where the previous call stopped.
+    fn decode_inline(&mut self, src: &mut BytesMut) -> Result<Option<RespFrame>, RespError> {
+        let from = self.inline_scanned.min(src.len());
+        let Some(newline) = src[from..].iter().position(|b| *b == b'\n') else {
+            self.inline_scanned = src.len();
+            if src.len() > INLINE_MAX_SIZE {
+                return Err(RespError::ProtocolError(
+                    "too big inline request".to_string(),
+                ));
+            }
+            return Err(RespError::NotComplete);
+        };
+        let newline = from + newline;
+        self.inline_scanned = 0;
+        if newline > INLINE_MAX_SIZE {
+            return Err(RespError::ProtocolError(
+                "too big inline request".to_string(),
+            ));
+        }
+
+        let line = src.split_to(newline + 1);
+        let line = line.strip_suffix(b"\n").unwrap_or(&line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let args = split_inline_args(line)
+            .ok_or_else(|| RespError::ProtocolError("unbalanced quotes in request".to_string()))?;
+        if args.is_empty() {
+            return Ok(None);
+        }
+        let frames = args
+            .into_iter()
+            .map(|arg| BulkString::new(Some(arg)).into())
+            .collect::<Vec<_>>();
+        Ok(Some(RespArray::new(Some(frames)).into()))
+    }
+}
 
+/// Split a line into arguments following Redis's `sdssplitargs` rules:
+/// double quotes support `\n \r \t \b \a \xHH` escapes, single quotes only
+/// `\'`, and a closing quote must be followed by a space or the line end.
+/// Returns `None` on unbalanced quotes.
+pub(crate) fn split_inline_args(line: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut i = 0;
     loop {
-        let cloned_backend = backend.clone();
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Receive frame: {:?}", frame);
-                let request = RedisRequest {
-                    frame,
-                    backend: cloned_backend,
-                };
-                info!("Executing request: {:?}", request);
-                let response = redis_request_handler(request).await?;
-                info!("get response: {:?}", response);
-                framed.send(response.frame).await?;
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Some(args);
+        }
+
+        let mut current = Vec::new();
+        let mut in_double = false;
+        let mut in_single = false;
+        loop {
+            let Some(&c) = line.get(i) else {
+                if in_double || in_single {
+                    return None;
+                }
+                break;
+            };
+            if in_double {
+                match c {
+                    b'\\'
+                        if i + 3 < line.len()
+                            && line[i + 1] == b'x'
+                            && line[i + 2].is_ascii_hexdigit()
+                            && line[i + 3].is_ascii_hexdigit() =>
+                    {
+                        current.push(hex_value(line[i + 2]) * 16 + hex_value(line[i + 3]));
+                        i += 3;
+                    }
+                    b'\\' if i + 1 < line.len() => {
+                        i += 1;
+                        current.push(match line[i] {
+                            b'n' => b'\n',
+                            b'r' => b'\r',
+                            b't' => b'\t',
+                            b'b' => 0x08,
+                            b'a' => 0x07,
+                            other => other,
+                        });
+                    }
+                    b'"' => {
+                        // closing quote must be followed by a space or nothing
+                        if line.get(i + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return None;
+                        }
+                        i += 1;
+                        break;
+                    }
+                    _ => current.push(c),
+                }
+            } else if in_single {
+                match c {
+                    b'\\' if line.get(i + 1) == Some(&b'\'') => {
+                        i += 1;
+                        current.push(b'\'');
+                    }
+                    b'\'' => {
+                        if line.get(i + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return None;
+                        }
+                        i += 1;
+                        break;
+                    }
+                    _ => current.push(c),
+                }
+            } else {
+                match c {
+                    b' ' | b'\n' | b'\r' | b'\t' | 0x0b | 0x0c => break,
+                    b'"' => in_double = true,
+                    b'\'' => in_single = true,
+                    _ => current.push(c),
+                }
             }
-            Some(Err(e)) => {
-                error!("error for {:?}", e);
+            i += 1;
+        }
+        args.push(current);
+    }
+}
+
+fn hex_value(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => c - b'A' + 10,
+    }
+}
+
+/// Replies are buffered while requests keep arriving; once this many bytes
+/// are pending they are written out even in the middle of a batch.
+const WRITE_FLUSH_THRESHOLD: usize = 64 * 1024;
+
+pub async fn stream_handler(
+    stream: TcpStream,
+    backend: Backend,
+    limits: ProtocolLimits,
+) -> Result<()> {
+    let mut client = Client::new();
+    client.addr = stream.peer_addr().ok();
+    let span = info_span!(
+        "connection",
+        client_id = client.id,
+        peer = %client
+            .addr
+            .map_or_else(|| "unknown".to_string(), |addr| addr.to_string()),
+    );
+    let result = serve(stream, backend.clone(), limits, &mut client)
+        .instrument(span)
+        .await;
+    backend.pubsub().remove_client(&mut client);
+    result
+}
+
+async fn serve(
+    stream: TcpStream,
+    backend: Backend,
+    limits: ProtocolLimits,
+    client: &mut Client,
+) -> Result<()> {
+    let mut framed = Framed::new(stream, RespFrameCodec::with_limits(limits));
+    framed.set_backpressure_boundary(WRITE_FLUSH_THRESHOLD);
+    // pub/sub messages and extra subscribe confirmations for this client
+    let mut inbox = client
+        .subscriptions
+        .take_inbox()
+        .expect("client already served");
+    info!("client connected");
+
+    loop {
+        let next = tokio::select! {
+            next = framed.next() => next,
+            Some(message) = inbox.recv() => {
+                framed.feed(message).await?;
+                while let Ok(message) = inbox.try_recv() {
+                    framed.feed(message).await?;
+                }
+                framed.flush().await?;
                 continue;
             }
-            None => return Ok(()),
+        };
+        let Some(mut next) = next else {
+            info!("client disconnected");
+            return Ok(());
+        };
+        // handle every request that is already readable before flushing, so
+        // a pipelined batch costs one write instead of one per command
+        loop {
+            match next {
+                Ok(frame) => {
+                    let request = RedisRequest {
+                        frame,
+                        backend: backend.clone(),
+                    };
+                    let response = redis_request_handler(request, client).await?;
+                    framed.codec_mut().protocol = client.protocol;
+                    // anything queued while the command ran goes first, as
+                    // Redis writes it into the output buffer before the reply
+                    while let Ok(message) = inbox.try_recv() {
+                        framed.feed(message).await?;
+                    }
+                    framed.feed(response.frame).await?;
+                    // after PSYNC the connection carries the replication
+                    // stream instead of replies
+                    if let Some(handoff) = client.replica.take() {
+                        framed.flush().await?;
+                        let parts = framed.into_parts();
+                        return replication::serve_replica(
+                            backend,
+                            parts.io,
+                            parts.read_buf,
+                            handoff,
+                        )
+                        .await;
+                    }
+                }
+                Err(e) => {
+                    // the rest of the buffer cannot be trusted after a malformed
+                    // request: tell the client why and close, like Redis does
+                    let Some(e) = e.downcast_ref::<RespError>() else {
+                        return Err(e);
+                    };
+                    warn!("closing connection after protocol error: {}", e);
+                    framed.send(protocol_error_reply(e)).await?;
+                    return Ok(());
+                }
+            }
+            match framed.next().now_or_never() {
+                Some(Some(result)) => next = result,
+                _ => break,
+            }
         }
+        // pick up a CONFIG SET of the limits from any connection
+        let limits = backend.config().protocol_limits();
+        framed.codec_mut().set_limits(limits);
+        framed.flush().await?;
     }
 }
 
-async fn redis_request_handler(request: RedisRequest) -> Result<RedisResponse> {
-    let frame = match TryInto::<Command>::try_into(request.frame) {
-        Ok(cmd) => cmd.execute(&request.backend),
-        Err(e) => RespFrame::SimpleError(SimpleError::new(e.to_string())),
+fn protocol_error_reply(e: &RespError) -> RespFrame {
+    let reason = match e {
+        RespError::ProtocolError(reason) => reason.clone(),
+        e => e.to_string(),
     };
+    SimpleError::new(format!("ERR Protocol error: {}", reason)).into()
+}
+
+async fn redis_request_handler(
+    request: RedisRequest,
+    client: &mut Client,
+) -> Result<RedisResponse> {
+    let spec = command_spec(&request.frame);
+    let (name, keys) = request_summary(&request.frame, spec).unwrap_or_default();
+    let span = debug_span!(
+        "command",
+        cmd = %name,
+        keys,
+        duration_us = field::Empty,
+        reply = field::Empty,
+    );
+    let _guard = span.enter();
+    trace!(request = %Payload(&request.frame));
+
+    let start = Instant::now();
+    let frame = execute_request(request.frame, spec, &request.backend, client);
+
+    span.record("duration_us", start.elapsed().as_micros() as u64);
+    span.record("reply", frame.type_name());
+    trace!(reply = %Payload(&frame));
+    debug!("command executed");
     Ok(RedisResponse { frame })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{RespMap, SimpleString};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_downgrades_for_resp2() {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new("a"), true.into());
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(map.clone().into(), &mut buf).unwrap();
+        assert_eq!(&buf[..], b"*2\r\n$1\r\na\r\n:1\r\n");
+
+        codec.protocol = RespVersion::Resp3;
+        let mut buf = BytesMut::new();
+        codec.encode(map.into(), &mut buf).unwrap();
+        assert_eq!(&buf[..], b"%1\r\n$1\r\na\r\n#t\r\n");
+    }
+
+    fn decode_all(input: &[u8]) -> Vec<RespFrame> {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    fn command(args: &[&[u8]]) -> RespFrame {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<_>>();
+        RespArray::new(Some(frames)).into()
+    }
+
+    #[test]
+    fn test_decode_inline() {
+        let frames = decode_all(b"PING\r\nset  key   value\n\r\n*1\r\n$4\r\nPING\r\n");
+        assert_eq!(
+            frames,
+            vec![
+                command(&[b"PING"]),
+                command(&[b"set", b"key", b"value"]),
+                command(&[b"PING"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_inline_incomplete() {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&b"GET ke"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"y\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(command(&[b"GET", b"key"]))
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_inline_resumes_scan() {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&b"SET key "[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.inline_scanned, 8);
+        buf.extend_from_slice(b"value");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.inline_scanned, 13);
+        buf.extend_from_slice(b"\r\nGET key\r\n");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(command(&[b"SET", b"key", b"value"]))
+        );
+        assert_eq!(codec.inline_scanned, 0);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(command(&[b"GET", b"key"]))
+        );
+    }
+
+    #[test]
+    fn test_decode_inline_quotes() {
+        let frames = decode_all(b"set \"a b\\n\\x41\\\"\" 'it\\'s' x\"y z\"\n");
+        assert_eq!(
+            frames,
+            vec![command(&[b"set", b"a b\nA\"", b"it's", b"xy z"])]
+        );
+    }
+
+    #[test]
+    fn test_decode_inline_unbalanced_quotes() {
+        for input in [&b"set \"abc\n"[..], b"set 'abc\n", b"set \"a\"b\n"] {
+            let mut codec = RespFrameCodec::default();
+            let mut buf = BytesMut::from(input);
+            let err = codec.decode(&mut buf).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Protocol error: unbalanced quotes in request"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_inline_too_big() {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&vec![b'a'; INLINE_MAX_SIZE + 1][..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "Protocol error: too big inline request");
+    }
+
+    #[test]
+    fn test_decode_respects_limits() {
+        let mut codec = RespFrameCodec::with_limits(ProtocolLimits {
+            max_multibulk_len: 8,
+            ..Default::default()
+        });
+        let mut buf = BytesMut::from(&b"*9\r\n"[..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "Protocol error: invalid multibulk length");
+    }
+}
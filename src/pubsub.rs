@@ -0,0 +1,196 @@
+//! Pub/sub: which clients are subscribed to which channels and patterns,
+//! and delivery of published messages to them.
+
+use std::collections::{BTreeSet, HashMap};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::{glob::glob_match, BulkString, Client, RespFrame, RespPush};
+
+/// Where frames for a client are queued until its connection writes them.
+pub type Mailbox = UnboundedSender<RespFrame>;
+
+/// A client's side of pub/sub: what it is subscribed to and its mailbox.
+#[derive(Debug)]
+pub struct Subscriptions {
+    pub channels: BTreeSet<Bytes>,
+    pub patterns: BTreeSet<Bytes>,
+    mailbox: Mailbox,
+    inbox: Option<UnboundedReceiver<RespFrame>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        let (mailbox, inbox) = mpsc::unbounded_channel();
+        Subscriptions {
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            mailbox,
+            inbox: Some(inbox),
+        }
+    }
+
+    /// Channels plus patterns, the count subscribe replies report.
+    pub fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    pub fn mailbox(&self) -> &Mailbox {
+        &self.mailbox
+    }
+
+    /// The receiving end of the mailbox, for the connection to drain.
+    pub fn take_inbox(&mut self) -> Option<UnboundedReceiver<RespFrame>> {
+        self.inbox.take()
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Subscribers by channel and by pattern, keyed by client id.
+#[derive(Debug, Default)]
+pub struct PubSub {
+    channels: DashMap<Bytes, HashMap<u64, Mailbox>>,
+    patterns: DashMap<Bytes, HashMap<u64, Mailbox>>,
+}
+
+impl PubSub {
+    /// Subscribe `client` to `channel`, returning false if it already was.
+    pub fn subscribe(&self, client: &mut Client, channel: Bytes) -> bool {
+        let subscriptions = &mut client.subscriptions;
+        if !subscriptions.channels.insert(channel.clone()) {
+            return false;
+        }
+        self.channels
+            .entry(channel)
+            .or_default()
+            .insert(client.id, subscriptions.mailbox.clone());
+        true
+    }
+
+    pub fn psubscribe(&self, client: &mut Client, pattern: Bytes) -> bool {
+        let subscriptions = &mut client.subscriptions;
+        if !subscriptions.patterns.insert(pattern.clone()) {
+            return false;
+        }
+        self.patterns
+            .entry(pattern)
+            .or_default()
+            .insert(client.id, subscriptions.mailbox.clone());
+        true
+    }
+
+    /// Unsubscribe `client` from `channel`, returning false if it was not
+    /// subscribed.
+    pub fn unsubscribe(&self, client: &mut Client, channel: &[u8]) -> bool {
+        if !client.subscriptions.channels.remove(channel) {
+            return false;
+        }
+        remove_subscriber(&self.channels, channel, client.id);
+        true
+    }
+
+    pub fn punsubscribe(&self, client: &mut Client, pattern: &[u8]) -> bool {
+        if !client.subscriptions.patterns.remove(pattern) {
+            return false;
+        }
+        remove_subscriber(&self.patterns, pattern, client.id);
+        true
+    }
+
+    /// Drop every subscription of a client whose connection is closing.
+    pub fn remove_client(&self, client: &mut Client) {
+        let subscriptions = &mut client.subscriptions;
+        for channel in std::mem::take(&mut subscriptions.channels) {
+            remove_subscriber(&self.channels, &channel, client.id);
+        }
+        for pattern in std::mem::take(&mut subscriptions.patterns) {
+            remove_subscriber(&self.patterns, &pattern, client.id);
+        }
+    }
+
+    /// Send `message` to the subscribers of `channel` and of every pattern
+    /// matching it, returning how many deliveries were made.
+    pub fn publish(&self, channel: &[u8], message: &[u8]) -> usize {
+        let mut receivers = 0;
+        if let Some(subscribers) = self.channels.get(channel) {
+            let frame = push(["message".as_bytes(), channel, message]);
+            for mailbox in subscribers.values() {
+                let _ = mailbox.send(frame.clone());
+                receivers += 1;
+            }
+        }
+        for item in self.patterns.iter() {
+            if !glob_match(item.key(), channel, false) {
+                continue;
+            }
+            let frame = push(["pmessage".as_bytes(), item.key(), channel, message]);
+            for mailbox in item.value().values() {
+                let _ = mailbox.send(frame.clone());
+                receivers += 1;
+            }
+        }
+        receivers
+    }
+}
+
+fn remove_subscriber(registry: &DashMap<Bytes, HashMap<u64, Mailbox>>, name: &[u8], id: u64) {
+    registry.remove_if_mut(name, |_, subscribers| {
+        subscribers.remove(&id);
+        subscribers.is_empty()
+    });
+}
+
+/// A pub/sub push such as `message <channel> <payload>`. Clients on RESP2
+/// receive it as a plain array.
+pub(crate) fn push<const N: usize>(parts: [&[u8]; N]) -> RespFrame {
+    let frames = parts
+        .into_iter()
+        .map(|part| BulkString::new(Some(part.to_vec())).into())
+        .collect::<Vec<RespFrame>>();
+    RespPush::new(frames).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::error::TryRecvError;
+
+    use super::*;
+
+    #[test]
+    fn test_publish() {
+        let pubsub = PubSub::default();
+        let mut client = Client::new();
+        let mut inbox = client.subscriptions.take_inbox().unwrap();
+        assert!(pubsub.subscribe(&mut client, Bytes::from("news")));
+        assert!(!pubsub.subscribe(&mut client, Bytes::from("news")));
+        assert!(pubsub.psubscribe(&mut client, Bytes::from("n*")));
+        assert_eq!(client.subscriptions.count(), 2);
+
+        assert_eq!(pubsub.publish(b"news", b"hi"), 2);
+        assert_eq!(
+            inbox.try_recv(),
+            Ok(push([b"message".as_slice(), b"news", b"hi"]))
+        );
+        assert_eq!(
+            inbox.try_recv(),
+            Ok(push([b"pmessage".as_slice(), b"n*", b"news", b"hi"]))
+        );
+        assert_eq!(pubsub.publish(b"other", b"hi"), 0);
+
+        assert!(pubsub.unsubscribe(&mut client, b"news"));
+        assert!(!pubsub.unsubscribe(&mut client, b"news"));
+        assert_eq!(pubsub.publish(b"news", b"hi"), 1);
+        pubsub.remove_client(&mut client);
+        assert_eq!(pubsub.publish(b"news", b"hi"), 0);
+        assert!(pubsub.patterns.is_empty());
+        inbox.try_recv().unwrap();
+        assert_eq!(inbox.try_recv(), Err(TryRecvError::Empty));
+    }
+}
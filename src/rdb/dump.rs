@@ -0,0 +1,122 @@
+use super::{crc64::crc64, RdbError, RdbReader, RdbWriter, RDB_MAX_LOAD_VERSION, RDB_VERSION};
+use crate::{rdb::Object, Value};
+
+/// Serialize `value` the way `DUMP` does: the RDB type byte and payload,
+/// then the RDB version and a CRC64 of everything before it, both little
+/// endian.
+pub fn dump_value(value: &Value) -> Vec<u8> {
+    let mut writer = RdbWriter::new(Vec::new());
+    let written = writer
+        .write_type(value)
+        .and_then(|()| writer.write_value(value))
+        .and_then(|()| writer.write_raw(&RDB_VERSION.to_le_bytes()));
+    written.expect("writing to a Vec can't fail");
+    let crc = writer.checksum();
+    let mut payload = writer.into_inner();
+    payload.extend_from_slice(&crc.to_le_bytes());
+    payload
+}
+
+/// Check a `DUMP` payload's footer: a version this server can read and a
+/// matching checksum. `DUMP` always computes the checksum, so unlike an RDB
+/// file a zero one is not taken to mean it was skipped.
+pub fn verify_dump(payload: &[u8]) -> Result<(), RdbError> {
+    if payload.len() < 10 {
+        return Err(RdbError::BadPayload);
+    }
+    let (body, crc) = payload.split_at(payload.len() - 8);
+    let version = u16::from_le_bytes([body[body.len() - 2], body[body.len() - 1]]);
+    let crc = u64::from_le_bytes(crc.try_into().expect("8 bytes"));
+    if version > RDB_MAX_LOAD_VERSION || crc != crc64(body) {
+        return Err(RdbError::BadPayload);
+    }
+    Ok(())
+}
+
+/// Decode a verified `DUMP` payload, in any encoding Redis may have used.
+pub fn undump_value(payload: &[u8]) -> Result<Object, RdbError> {
+    verify_dump(payload)?;
+    let body = &payload[..payload.len() - 10];
+    let mut reader = RdbReader::new(body);
+    let kind = reader.read_u8()?;
+    let object = reader.read_object(kind)?;
+    if !reader.into_inner().is_empty() {
+        return Err(RdbError::Corrupt(
+            "trailing bytes after the value".to_string(),
+        ));
+    }
+    Ok(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn test_dump_round_trip() {
+        for value in [
+            Value::String(Bytes::from("hello")),
+            Value::String(Bytes::from("12345")),
+            Value::Hash(vec![(Bytes::from("f"), Bytes::from("v"))]),
+            Value::Set(vec![Bytes::from("a"), Bytes::from("b")]),
+        ] {
+            let payload = dump_value(&value);
+            assert_eq!(undump_value(&payload).unwrap(), Object::Value(value));
+        }
+    }
+
+    #[test]
+    fn test_redis_payloads() {
+        // type, length-prefixed string, RDB version 11, then the checksum
+        let mut expected = b"\x00\x03bar\x0b\x00".to_vec();
+        expected.extend_from_slice(&crc64(&expected).to_le_bytes());
+        assert_eq!(dump_value(&Value::String(Bytes::from("bar"))), expected);
+
+        // Redis dumps small sets of integers as an intset
+        let mut intset =
+            b"\x0b\x0c\x02\x00\x00\x00\x02\x00\x00\x00\x01\x00\x02\x00\x0b\x00".to_vec();
+        intset.extend_from_slice(&crc64(&intset).to_le_bytes());
+        assert_eq!(
+            undump_value(&intset).unwrap(),
+            Object::Value(Value::Set(vec![Bytes::from("1"), Bytes::from("2")]))
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_payloads() {
+        let mut payload = dump_value(&Value::String(Bytes::from("bar")));
+        assert!(matches!(
+            undump_value(&payload[..9]),
+            Err(RdbError::BadPayload)
+        ));
+
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        assert!(matches!(undump_value(&payload), Err(RdbError::BadPayload)));
+
+        // a zero checksum is not a way around verification
+        let mut unchecked = b"\x00\x03bar\x0b\x00".to_vec();
+        unchecked.extend_from_slice(&[0; 8]);
+        assert!(matches!(
+            undump_value(&unchecked),
+            Err(RdbError::BadPayload)
+        ));
+
+        let mut newer = b"\x00\x03bar\x63\x00".to_vec();
+        newer.extend_from_slice(&crc64(&newer).to_le_bytes());
+        assert!(matches!(undump_value(&newer), Err(RdbError::BadPayload)));
+
+        let mut trailing = b"\x00\x03barX\x0b\x00".to_vec();
+        trailing.extend_from_slice(&crc64(&trailing).to_le_bytes());
+        assert!(matches!(undump_value(&trailing), Err(RdbError::Corrupt(_))));
+
+        // an LZF string declaring a 64 bit uncompressed length
+        let mut lzf = b"\x00\xc3\x02\x81".to_vec();
+        lzf.extend_from_slice(&u64::MAX.to_be_bytes());
+        lzf.extend_from_slice(b"\x00a\x0b\x00");
+        lzf.extend_from_slice(&crc64(&lzf).to_le_bytes());
+        assert!(matches!(undump_value(&lzf), Err(RdbError::Corrupt(_))));
+    }
+}
@@ -0,0 +1,374 @@
+//! The compact in-memory encodings Redis stores as single strings in RDB
+//! files: ziplists, listpacks, intsets and zipmaps. Each decodes to its
+//! elements, with integers turned back into their decimal strings.
+
+use bytes::Bytes;
+
+use super::RdbError;
+
+/// Bounds-checked little endian reads over an encoded blob.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+    what: &'static str,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8], what: &'static str) -> Self {
+        Cursor { data, pos: 0, what }
+    }
+
+    fn corrupt(&self, reason: &str) -> RdbError {
+        RdbError::Corrupt(format!("{} {} at byte {}", self.what, reason, self.pos))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RdbError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| self.corrupt("is truncated"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], RdbError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, RdbError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RdbError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, RdbError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    /// A little endian signed integer of `N` bytes.
+    fn int<const N: usize>(&mut self) -> Result<i64, RdbError> {
+        let bytes = self.array::<N>()?;
+        let mut buf = [0; 8];
+        buf[..N].copy_from_slice(&bytes);
+        let shift = 64 - 8 * N as u32;
+        Ok(i64::from_le_bytes(buf) << shift >> shift)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Bytes, RdbError> {
+        Ok(Bytes::copy_from_slice(self.take(len)?))
+    }
+
+    /// Check the header's total size and that nothing follows the end.
+    fn check_end(&self, total: u32) -> Result<(), RdbError> {
+        if total as usize != self.data.len() || self.pos != self.data.len() {
+            return Err(self.corrupt("has the wrong size"));
+        }
+        Ok(())
+    }
+}
+
+fn int_string(value: i64) -> Bytes {
+    value.to_string().into()
+}
+
+/// Element counts of 65535 and up are stored as 65535, meaning "count the
+/// entries".
+fn check_count(cursor: &Cursor, count: u16, found: usize) -> Result<(), RdbError> {
+    if count != u16::MAX && count as usize != found {
+        return Err(cursor.corrupt("has the wrong number of entries"));
+    }
+    Ok(())
+}
+
+/// The elements of a ziplist, the encoding of small lists, hashes and
+/// sorted sets before Redis 7.
+pub fn ziplist(data: &[u8]) -> Result<Vec<Bytes>, RdbError> {
+    let mut c = Cursor::new(data, "ziplist");
+    let total = c.u32()?;
+    let _tail = c.u32()?;
+    let count = c.u16()?;
+    let mut entries = Vec::new();
+    loop {
+        // the length of the previous entry, or the end marker
+        match c.u8()? {
+            0xff => break,
+            0xfe => {
+                c.take(4)?;
+            }
+            _ => {}
+        }
+        let enc = c.u8()?;
+        let entry = match enc >> 6 {
+            0 => c.string((enc & 0x3f) as usize)?,
+            1 => {
+                let len = ((enc as usize & 0x3f) << 8) | c.u8()? as usize;
+                c.string(len)?
+            }
+            2 => {
+                let len = u32::from_be_bytes(c.array()?) as usize;
+                c.string(len)?
+            }
+            _ => int_string(match enc {
+                0xc0 => c.int::<2>()?,
+                0xd0 => c.int::<4>()?,
+                0xe0 => c.int::<8>()?,
+                0xf0 => c.int::<3>()?,
+                0xfe => c.int::<1>()?,
+                // 4 bit immediate values 1..=13 stand for 0..=12
+                0xf1..=0xfd => (enc & 0x0f) as i64 - 1,
+                _ => return Err(c.corrupt("has an unknown entry encoding")),
+            }),
+        };
+        entries.push(entry);
+    }
+    check_count(&c, count, entries.len())?;
+    c.check_end(total)?;
+    Ok(entries)
+}
+
+/// Size of a listpack entry's trailing back length, which stores the
+/// entry's size 7 bits per byte.
+fn backlen_size(len: usize) -> usize {
+    match len {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    }
+}
+
+/// The elements of a listpack, the encoding of small lists, hashes, sets
+/// and sorted sets since Redis 7.
+pub fn listpack(data: &[u8]) -> Result<Vec<Bytes>, RdbError> {
+    let mut c = Cursor::new(data, "listpack");
+    let total = c.u32()?;
+    let count = c.u16()?;
+    let mut entries = Vec::new();
+    loop {
+        let start = c.pos;
+        let enc = c.u8()?;
+        if enc == 0xff {
+            break;
+        }
+        let entry = if enc & 0x80 == 0 {
+            int_string(enc as i64)
+        } else if enc & 0xc0 == 0x80 {
+            c.string((enc & 0x3f) as usize)?
+        } else if enc & 0xe0 == 0xc0 {
+            let value = ((enc as i64 & 0x1f) << 8) | c.u8()? as i64;
+            // 13 bit two's complement
+            int_string(value << 51 >> 51)
+        } else if enc & 0xf0 == 0xe0 {
+            let len = ((enc as usize & 0x0f) << 8) | c.u8()? as usize;
+            c.string(len)?
+        } else {
+            match enc {
+                0xf0 => {
+                    let len = c.u32()? as usize;
+                    c.string(len)?
+                }
+                0xf1 => int_string(c.int::<2>()?),
+                0xf2 => int_string(c.int::<3>()?),
+                0xf3 => int_string(c.int::<4>()?),
+                0xf4 => int_string(c.int::<8>()?),
+                _ => return Err(c.corrupt("has an unknown entry encoding")),
+            }
+        };
+        c.take(backlen_size(c.pos - start))?;
+        entries.push(entry);
+    }
+    check_count(&c, count, entries.len())?;
+    c.check_end(total)?;
+    Ok(entries)
+}
+
+/// The members of an intset, the encoding of small sets of integers.
+pub fn intset(data: &[u8]) -> Result<Vec<Bytes>, RdbError> {
+    let mut c = Cursor::new(data, "intset");
+    let width = c.u32()?;
+    let len = c.u32()?;
+    let mut members = Vec::with_capacity(len.min(1024) as usize);
+    for _ in 0..len {
+        members.push(int_string(match width {
+            2 => c.int::<2>()?,
+            4 => c.int::<4>()?,
+            8 => c.int::<8>()?,
+            _ => return Err(c.corrupt("has an unknown integer width")),
+        }));
+    }
+    c.check_end(data.len() as u32)?;
+    Ok(members)
+}
+
+/// The fields and values of a zipmap, the encoding of small hashes before
+/// Redis 2.6, flattened as field, value, field, value...
+pub fn zipmap(data: &[u8]) -> Result<Vec<Bytes>, RdbError> {
+    let mut c = Cursor::new(data, "zipmap");
+    let _count = c.u8()?;
+    let mut entries = Vec::new();
+    loop {
+        let len = match c.u8()? {
+            0xff => break,
+            0xfe => c.u32()? as usize,
+            len => len as usize,
+        };
+        entries.push(c.string(len)?);
+        let len = match c.u8()? {
+            0xfe => c.u32()? as usize,
+            0xff => return Err(c.corrupt("ends after a field")),
+            len => len as usize,
+        };
+        let free = c.u8()? as usize;
+        entries.push(c.string(len)?);
+        c.take(free)?;
+    }
+    c.check_end(data.len() as u32)?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(entries: &[&str]) -> Vec<Bytes> {
+        entries.iter().map(|e| Bytes::from(e.to_string())).collect()
+    }
+
+    /// Lay out a ziplist from encoded entries, filling in prevlen.
+    fn build_ziplist(entries: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut prev = 0;
+        for entry in entries {
+            body.push(prev as u8);
+            body.extend_from_slice(entry);
+            prev = entry.len() + 1;
+        }
+        let total = 10 + body.len() + 1;
+        let mut data = Vec::new();
+        data.extend_from_slice(&(total as u32).to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&body);
+        data.push(0xff);
+        data
+    }
+
+    /// Lay out a listpack from encoded entries, appending back lengths.
+    fn build_listpack(entries: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for entry in entries {
+            body.extend_from_slice(entry);
+            body.push(entry.len() as u8);
+        }
+        let total = 6 + body.len() + 1;
+        let mut data = Vec::new();
+        data.extend_from_slice(&(total as u32).to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&body);
+        data.push(0xff);
+        data
+    }
+
+    #[test]
+    fn test_ziplist() {
+        let data = build_ziplist(&[
+            b"\x05hello",
+            b"\xf1",
+            b"\xfd",
+            b"\xfe\x80",
+            b"\xc0\x00\x80",
+            b"\xf0\xff\xff\x7f",
+            b"\xd0\x00\x00\x00\x80",
+            b"\xe0\x01\x00\x00\x00\x00\x00\x00\x00",
+            b"\x40\x01x",
+        ]);
+        assert_eq!(
+            ziplist(&data).unwrap(),
+            strings(&[
+                "hello",
+                "0",
+                "12",
+                "-128",
+                "-32768",
+                "8388607",
+                "-2147483648",
+                "1",
+                "x"
+            ])
+        );
+        assert!(ziplist(&data[..data.len() - 1]).is_err());
+        let mut wrong_count = data.clone();
+        wrong_count[8] = 3;
+        assert!(ziplist(&wrong_count).is_err());
+    }
+
+    #[test]
+    fn test_listpack() {
+        let data = build_listpack(&[
+            b"\x85hello",
+            b"\x07",
+            b"\x7f",
+            b"\xdf\xff",
+            b"\xc1\x00",
+            b"\xf1\x00\x80",
+            b"\xf2\x00\x00\x80",
+            b"\xf3\xff\xff\xff\x7f",
+            b"\xf4\xff\xff\xff\xff\xff\xff\xff\xff",
+            b"\xe0\x01x",
+        ]);
+        assert_eq!(
+            listpack(&data).unwrap(),
+            strings(&[
+                "hello",
+                "7",
+                "127",
+                "-1",
+                "256",
+                "-32768",
+                "-8388608",
+                "2147483647",
+                "-1",
+                "x"
+            ])
+        );
+        assert!(listpack(&data[..data.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_listpack_long_backlen() {
+        // a 200 byte string is 202 bytes with its header, so its back
+        // length takes two bytes
+        let mut entry = vec![0xe0, 200];
+        entry.extend_from_slice(&[b'v'; 200]);
+        let mut data = Vec::new();
+        let total = 6 + entry.len() + 2 + 1;
+        data.extend_from_slice(&(total as u32).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&entry);
+        data.extend_from_slice(&[0x01, 0xca]);
+        data.push(0xff);
+        assert_eq!(listpack(&data).unwrap(), vec![Bytes::from(vec![b'v'; 200])]);
+    }
+
+    #[test]
+    fn test_intset() {
+        let mut data = vec![2, 0, 0, 0, 3, 0, 0, 0];
+        data.extend_from_slice(&[0xff, 0xff, 0x01, 0x00, 0x00, 0x01]);
+        assert_eq!(intset(&data).unwrap(), strings(&["-1", "1", "256"]));
+        assert!(intset(&data[..12]).is_err());
+        data[0] = 3;
+        assert!(intset(&data).is_err());
+    }
+
+    #[test]
+    fn test_zipmap() {
+        let data = b"\x02\x03foo\x03\x01bar\x00\x01a\x01\x00b\xff";
+        assert_eq!(zipmap(data).unwrap(), strings(&["foo", "bar", "a", "b"]));
+        assert!(zipmap(&data[..8]).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use super::RdbError;
+
+fn corrupt() -> RdbError {
+    RdbError::Corrupt("invalid LZF compressed string".to_string())
+}
+
+/// Decompress an LZF block into exactly `len` bytes. Each control byte
+/// starts either a literal run (`000LLLLL`, L+1 bytes follow) or a back
+/// reference (`LLLooooo`, with a second length byte when L is 7) copying
+/// L+2 bytes from `o + 1` bytes back in the output.
+pub fn decompress(input: &[u8], len: usize) -> Result<Vec<u8>, RdbError> {
+    // `len` comes from the payload, so it only bounds the initial capacity
+    // by what the input could plausibly expand to; the buffer grows as
+    // bytes are written
+    let mut out = Vec::with_capacity(len.min(input.len().saturating_mul(4)));
+    let mut i = 0;
+    while i < input.len() {
+        let ctrl = input[i] as usize;
+        i += 1;
+        if ctrl < 1 << 5 {
+            let run = input.get(i..i + ctrl + 1).ok_or_else(corrupt)?;
+            out.extend_from_slice(run);
+            i += ctrl + 1;
+        } else {
+            let mut run = ctrl >> 5;
+            if run == 7 {
+                run += *input.get(i).ok_or_else(corrupt)? as usize;
+                i += 1;
+            }
+            let back = ((ctrl & 0x1f) << 8) + *input.get(i).ok_or_else(corrupt)? as usize + 1;
+            i += 1;
+            let start = out.len().checked_sub(back).ok_or_else(corrupt)?;
+            // the source may overlap the bytes being written, so copy one by one
+            for k in start..start + run + 2 {
+                out.push(out[k]);
+            }
+        }
+        if out.len() > len {
+            return Err(corrupt());
+        }
+    }
+    if out.len() != len {
+        return Err(corrupt());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_length_is_not_trusted() {
+        assert!(decompress(&[0x00, b'a'], usize::MAX).is_err());
+        assert!(decompress(&[0x00, b'a'], 1 << 40).is_err());
+    }
+
+    #[test]
+    fn test_decompress() {
+        // literal "a", then 9 bytes copied from one byte back
+        assert_eq!(
+            decompress(&[0x00, b'a', 0xe0, 0x00, 0x00], 10).unwrap(),
+            b"aaaaaaaaaa"
+        );
+        // literal "abc", then "abcab" from three bytes back
+        assert_eq!(
+            decompress(&[0x02, b'a', b'b', b'c', 0x60, 0x02], 8).unwrap(),
+            b"abcabcab"
+        );
+        assert!(decompress(&[0x00, b'a', 0xe0, 0x00, 0x00], 9).is_err());
+        assert!(decompress(&[0x20, 0x05], 3).is_err());
+        assert!(decompress(&[0x05, b'a'], 6).is_err());
+    }
+}
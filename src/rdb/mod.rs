@@ -0,0 +1,421 @@
+//! Point-in-time snapshots in the Redis RDB format.
+
+mod crc64;
+mod dump;
+mod encodings;
+mod lzf;
+mod reader;
+mod writer;
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicI64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{backend::now_ms, Backend, Entry, Snapshot};
+
+pub use crc64::crc64;
+pub use dump::{dump_value, undump_value, verify_dump};
+pub use reader::{read_rdb, read_records, Object, RdbInfo, RdbReader, Record};
+pub use writer::{write_snapshot, RdbWriter};
+
+/// Version written to new files; older versions are accepted when loading.
+pub const RDB_VERSION: u16 = 11;
+/// Newest version accepted when loading, the one Redis 7.4 writes.
+const RDB_MAX_LOAD_VERSION: u16 = 12;
+
+const OPCODE_SLOT_INFO: u8 = 0xf4;
+const OPCODE_FUNCTION2: u8 = 0xf5;
+const OPCODE_FUNCTION_PRE_GA: u8 = 0xf6;
+const OPCODE_MODULE_AUX: u8 = 0xf7;
+const OPCODE_IDLE: u8 = 0xf8;
+const OPCODE_FREQ: u8 = 0xf9;
+const OPCODE_AUX: u8 = 0xfa;
+const OPCODE_RESIZEDB: u8 = 0xfb;
+const OPCODE_EXPIRETIME_MS: u8 = 0xfc;
+const OPCODE_EXPIRETIME: u8 = 0xfd;
+const OPCODE_SELECTDB: u8 = 0xfe;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_MODULE_PRE_GA: u8 = 6;
+const TYPE_MODULE_2: u8 = 7;
+const TYPE_HASH_ZIPMAP: u8 = 9;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_STREAM_LISTPACKS: u8 = 15;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+
+/// Opcodes inside a module value or module aux field.
+const MODULE_OPCODE_EOF: u64 = 0;
+const MODULE_OPCODE_SINT: u64 = 1;
+const MODULE_OPCODE_UINT: u64 = 2;
+const MODULE_OPCODE_FLOAT: u64 = 3;
+const MODULE_OPCODE_DOUBLE: u64 = 4;
+const MODULE_OPCODE_STRING: u64 = 5;
+
+/// First byte of 14, 32 and 64 bit lengths; 6 bit lengths are the byte
+/// itself.
+const LEN_14BIT: u8 = 0x40;
+const LEN_32BIT: u8 = 0x80;
+const LEN_64BIT: u8 = 0x81;
+/// Top bits marking a specially encoded string, the low bits say which.
+const ENCVAL: u8 = 0xc0;
+const ENC_INT8: u8 = 0;
+const ENC_INT16: u8 = 1;
+const ENC_INT32: u8 = 2;
+const ENC_LZF: u8 = 3;
+
+/// How long to wait before retrying an automatic save that failed.
+const SAVE_RETRY_DELAY: i64 = 5;
+
+#[derive(Error, Debug)]
+pub enum RdbError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("Short read loading DB, the file is truncated")]
+    ShortRead,
+    #[error("Wrong signature trying to load DB from file")]
+    BadSignature,
+    #[error("Can't handle RDB format version {0}")]
+    UnsupportedVersion(u16),
+    #[error("Unknown RDB string encoding type {0}")]
+    UnknownEncoding(u8),
+    #[error("Unknown RDB encoding type {0}")]
+    UnknownType(u8),
+    #[error("Wrong RDB checksum expected: ({expected:x}) got: ({computed:x})")]
+    Checksum { expected: u64, computed: u64 },
+    #[error("Corrupt RDB file: {0}")]
+    Corrupt(String),
+    #[error("Key '{key}' has type {kind}, which simple-redis does not support")]
+    UnsupportedType { key: String, kind: String },
+    #[error("DUMP payload version or checksum are wrong")]
+    BadPayload,
+    #[error("Background save already in progress")]
+    SaveInProgress,
+}
+
+/// Bookkeeping of saves, the part of Redis's `server` struct used by
+/// `SAVE`, `BGSAVE`, `LASTSAVE` and the `save` triggers.
+#[derive(Debug)]
+pub struct SaveState {
+    in_progress: AtomicBool,
+    /// Set by `BGSAVE SCHEDULE` while another save runs.
+    scheduled: AtomicBool,
+    /// Unix time of the last successful save, or of startup.
+    lastsave: AtomicI64,
+    last_attempt: AtomicI64,
+    last_ok: AtomicBool,
+}
+
+impl Default for SaveState {
+    fn default() -> Self {
+        let now = now_ms() / 1000;
+        SaveState {
+            in_progress: AtomicBool::new(false),
+            scheduled: AtomicBool::new(false),
+            lastsave: AtomicI64::new(now),
+            last_attempt: AtomicI64::new(now),
+            last_ok: AtomicBool::new(true),
+        }
+    }
+}
+
+impl SaveState {
+    pub fn lastsave(&self) -> i64 {
+        self.lastsave.load(Ordering::Relaxed)
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.in_progress.load(Ordering::Acquire)
+    }
+
+    pub fn schedule(&self) {
+        self.scheduled.store(true, Ordering::Relaxed);
+    }
+
+    fn begin(&self) -> Result<(), RdbError> {
+        self.in_progress
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| RdbError::SaveInProgress)
+    }
+}
+
+/// Write `snapshot` to `path` through a temporary file in the same
+/// directory, so a crash mid-save never leaves a partial file behind.
+fn write_file(snapshot: &Snapshot, path: &Path, checksum: bool) -> Result<(), RdbError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let tmp = dir.join(format!("temp-{}.rdb", std::process::id()));
+    let result = (|| {
+        let file = File::create(&tmp)?;
+        let mut out = write_snapshot(
+            snapshot,
+            BufWriter::new(file),
+            checksum,
+            false,
+            now_ms() / 1000,
+        )?;
+        out.flush()?;
+        out.get_ref().sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+/// Serialize a snapshot and record the outcome in the backend.
+fn save_snapshot(backend: &Backend, snapshot: Snapshot) -> Result<(), RdbError> {
+    let (path, checksum) = {
+        let config = backend.config();
+        (config.rdb_path(), config.rdb_checksum())
+    };
+    let state = &backend.save_state();
+    let result = write_file(&snapshot, &path, checksum);
+    if result.is_ok() {
+        backend.clear_dirty(snapshot.dirty);
+        state.lastsave.store(now_ms() / 1000, Ordering::Relaxed);
+    }
+    state.last_ok.store(result.is_ok(), Ordering::Relaxed);
+    state.in_progress.store(false, Ordering::Release);
+    result
+}
+
+/// Save in the calling thread, as `SAVE` does.
+pub fn save(backend: &Backend) -> Result<(), RdbError> {
+    backend.save_state().begin()?;
+    backend
+        .save_state()
+        .last_attempt
+        .store(now_ms() / 1000, Ordering::Relaxed);
+    let snapshot = backend.snapshot();
+    let result = save_snapshot(backend, snapshot);
+    match &result {
+        Ok(()) => info!("DB saved on disk"),
+        Err(e) => warn!("Failed saving the DB: {}", e),
+    }
+    result
+}
+
+/// Copy the data now and write it from a background thread, as `BGSAVE`
+/// does. The copy is the point in time the file reflects.
+pub fn bgsave(backend: &Backend) -> Result<(), RdbError> {
+    let state = &backend.save_state();
+    state.begin()?;
+    state.scheduled.store(false, Ordering::Relaxed);
+    state.last_attempt.store(now_ms() / 1000, Ordering::Relaxed);
+    let snapshot = backend.snapshot();
+    let backend = backend.clone();
+    let spawned = thread::Builder::new()
+        .name("bgsave".to_string())
+        .spawn(move || match save_snapshot(&backend, snapshot) {
+            Ok(()) => info!("Background saving terminated with success"),
+            Err(e) => warn!("Background saving error: {}", e),
+        });
+    if let Err(e) = spawned {
+        state.last_ok.store(false, Ordering::Relaxed);
+        state.in_progress.store(false, Ordering::Release);
+        return Err(e.into());
+    }
+    info!("Background saving started");
+    Ok(())
+}
+
+/// Load the RDB file at `path` into `backend`, skipping keys that have
+/// already expired. Keys of types the backend can't store are skipped with
+/// a warning, so a dump from Redis can be loaded for testing. Returns the
+/// number of keys loaded.
+pub fn load(backend: &Backend, path: &Path) -> Result<usize, RdbError> {
+    load_from(backend, BufReader::new(File::open(path)?))
+}
+
+/// Load an RDB file read from `input`, e.g. a master's snapshot; see
+/// `load`.
+pub fn load_from<R: Read>(backend: &Backend, input: R) -> Result<usize, RdbError> {
+    let start = Instant::now();
+    let now = now_ms();
+    let (mut loaded, mut expired, mut unsupported) = (0, 0, 0);
+    read_records(input, |record| {
+        match record {
+            Record::SelectDb(0) => {}
+            Record::SelectDb(db) => {
+                return Err(RdbError::Corrupt(format!(
+                    "database {} is out of range, only database 0 is supported",
+                    db
+                )))
+            }
+            Record::Key { expire_at, .. } if expire_at.is_some_and(|at| at <= now) => {
+                expired += 1;
+            }
+            Record::Key {
+                key,
+                object: Object::Value(value),
+                expire_at,
+            } => {
+                backend.insert_entry(Entry {
+                    key,
+                    value,
+                    expire_at,
+                });
+                loaded += 1;
+            }
+            Record::Key {
+                key,
+                object: Object::Unsupported(kind),
+                ..
+            } => {
+                warn!(
+                    "Skipping key '{}' of type {}, which simple-redis does not support",
+                    String::from_utf8_lossy(&key),
+                    kind
+                );
+                unsupported += 1;
+            }
+            Record::Aux(..) | Record::ModuleAux(_) | Record::Function => {}
+        }
+        Ok(())
+    })?;
+    info!(
+        keys = loaded,
+        expired,
+        unsupported,
+        "DB loaded from disk: {:.3} seconds",
+        start.elapsed().as_secs_f64()
+    );
+    Ok(loaded)
+}
+
+/// The first `save <seconds> <changes>` point that is due, if any.
+fn due_save_point(backend: &Backend, now: i64) -> Option<(u64, u64)> {
+    let state = &backend.save_state();
+    let elapsed = now - state.lastsave();
+    let retry_ok = state.last_ok.load(Ordering::Relaxed)
+        || now - state.last_attempt.load(Ordering::Relaxed) > SAVE_RETRY_DELAY;
+    let dirty = backend.dirty();
+    backend
+        .config()
+        .save_points()
+        .into_iter()
+        .find(|(seconds, changes)| dirty >= *changes && elapsed >= *seconds as i64 && retry_ok)
+}
+
+/// Start a background save whenever a `save` point is reached or one was
+/// scheduled, checking once per second like Redis's `serverCron`.
+pub async fn save_cron(backend: Backend) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+        if backend.save_state().in_progress() {
+            continue;
+        }
+        let reason = if backend.save_state().scheduled.load(Ordering::Relaxed) {
+            Some("Background saving scheduled".to_string())
+        } else {
+            due_save_point(&backend, now_ms() / 1000).map(|(seconds, changes)| {
+                format!("{} changes in {} seconds. Saving...", changes, seconds)
+            })
+        };
+        if let Some(reason) = reason {
+            info!("{}", reason);
+            if let Err(e) = bgsave(&backend) {
+                warn!("Can't save in background: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{BulkString, Config};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("simple-redis-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn backend_in(dir: &Path) -> Backend {
+        let mut config = Config::default();
+        config
+            .set(&[("dir".to_string(), dir.to_str().unwrap().to_string())])
+            .unwrap();
+        Backend::with_config(config)
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = temp_dir("save");
+        let backend = backend_in(&dir);
+        backend.set(Bytes::from("k"), BulkString::new(Some("v")).into());
+        backend
+            .sadd(Bytes::from("s"), vec![Bytes::from("m")])
+            .unwrap();
+        assert_eq!(backend.dirty(), 2);
+        save(&backend).unwrap();
+        assert_eq!(backend.dirty(), 0);
+
+        let restored = backend_in(&dir);
+        assert_eq!(load(&restored, &dir.join("dump.rdb")).unwrap(), 2);
+        assert_eq!(
+            restored.get(b"k").unwrap(),
+            Some(BulkString::new(Some("v")).into())
+        );
+        assert_eq!(restored.sismember(b"s", b"m").unwrap(), 1.into());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bgsave() {
+        let dir = temp_dir("bgsave");
+        let backend = backend_in(&dir);
+        backend.set(Bytes::from("k"), BulkString::new(Some("v")).into());
+        bgsave(&backend).unwrap();
+        // writes after the copy was taken are not in the file
+        backend.set(Bytes::from("later"), BulkString::new(Some("v")).into());
+        while backend.save_state().in_progress() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(backend.dirty(), 1);
+
+        let restored = backend_in(&dir);
+        assert_eq!(load(&restored, &dir.join("dump.rdb")).unwrap(), 1);
+        assert_eq!(restored.get(b"later").unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_due_save_point() {
+        let backend = Backend::new();
+        let now = backend.save_state().lastsave();
+        assert_eq!(due_save_point(&backend, now + 7200), None);
+        backend.set(Bytes::from("k"), BulkString::new(Some("v")).into());
+        assert_eq!(due_save_point(&backend, now + 10), None);
+        assert_eq!(due_save_point(&backend, now + 3600), Some((3600, 1)));
+    }
+}
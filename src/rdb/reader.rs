@@ -0,0 +1,737 @@
+use std::io::{self, Read};
+
+use bytes::Bytes;
+
+use super::{
+    crc64::Crc64, encodings, lzf, RdbError, ENC_INT16, ENC_INT32, ENC_INT8, ENC_LZF, LEN_32BIT,
+    LEN_64BIT, MODULE_OPCODE_DOUBLE, MODULE_OPCODE_EOF, MODULE_OPCODE_FLOAT, MODULE_OPCODE_SINT,
+    MODULE_OPCODE_STRING, MODULE_OPCODE_UINT, OPCODE_AUX, OPCODE_EOF, OPCODE_EXPIRETIME,
+    OPCODE_EXPIRETIME_MS, OPCODE_FREQ, OPCODE_FUNCTION2, OPCODE_FUNCTION_PRE_GA, OPCODE_IDLE,
+    OPCODE_MODULE_AUX, OPCODE_RESIZEDB, OPCODE_SELECTDB, OPCODE_SLOT_INFO, RDB_MAX_LOAD_VERSION,
+    TYPE_HASH, TYPE_HASH_LISTPACK, TYPE_HASH_ZIPLIST, TYPE_HASH_ZIPMAP, TYPE_LIST,
+    TYPE_LIST_QUICKLIST, TYPE_LIST_QUICKLIST_2, TYPE_LIST_ZIPLIST, TYPE_MODULE_2,
+    TYPE_MODULE_PRE_GA, TYPE_SET, TYPE_SET_INTSET, TYPE_SET_LISTPACK, TYPE_STREAM_LISTPACKS,
+    TYPE_STREAM_LISTPACKS_2, TYPE_STREAM_LISTPACKS_3, TYPE_STRING, TYPE_ZSET, TYPE_ZSET_2,
+    TYPE_ZSET_LISTPACK, TYPE_ZSET_ZIPLIST,
+};
+use crate::{Entry, Value};
+
+/// A length field: either a plain length or, for strings, the marker of a
+/// special encoding.
+enum Length {
+    Len(u64),
+    Encoded(u8),
+}
+
+/// A value read from a file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    /// A type the backend can store, whatever its encoding in the file.
+    Value(Value),
+    /// A type the backend has no equivalent for, skipped over. Holds the
+    /// type's name, e.g. `list` or `module 'ReJSON-RL'`.
+    Unsupported(String),
+}
+
+/// Everything a file can hold, in file order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    Aux(Bytes, Bytes),
+    SelectDb(u64),
+    Key {
+        key: Bytes,
+        object: Object,
+        /// Absolute expiry time in unix milliseconds.
+        expire_at: Option<i64>,
+    },
+    /// Module data that is not tied to a key, skipped over.
+    ModuleAux(String),
+    /// A function library, skipped over.
+    Function,
+}
+
+/// What the header and trailer of a file said.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RdbInfo {
+    pub version: u16,
+    /// The stored CRC64, `None` when the file has none or it is zero
+    /// because checksums were disabled.
+    pub checksum: Option<u64>,
+}
+
+/// Reads RDB primitives while keeping a running checksum of the input.
+pub struct RdbReader<R> {
+    input: R,
+    crc: Crc64,
+}
+
+impl<R: Read> RdbReader<R> {
+    pub fn new(input: R) -> Self {
+        RdbReader {
+            input,
+            crc: Crc64::default(),
+        }
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.crc.value()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), RdbError> {
+        self.input.read_exact(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => RdbError::ShortRead,
+            _ => e.into(),
+        })?;
+        self.crc.update(buf);
+        Ok(())
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], RdbError> {
+        let mut buf = [0; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, RdbError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_length_or_encoding(&mut self) -> Result<Length, RdbError> {
+        let first = self.read_u8()?;
+        Ok(match first >> 6 {
+            0 => Length::Len(first as u64),
+            1 => Length::Len(((first as u64 & 0x3f) << 8) | self.read_u8()? as u64),
+            3 => Length::Encoded(first & 0x3f),
+            _ if first == LEN_32BIT => Length::Len(u32::from_be_bytes(self.read_array()?) as u64),
+            _ if first == LEN_64BIT => Length::Len(u64::from_be_bytes(self.read_array()?)),
+            _ => {
+                return Err(RdbError::Corrupt(format!(
+                    "unknown length encoding {}",
+                    first
+                )))
+            }
+        })
+    }
+
+    pub fn read_length(&mut self) -> Result<u64, RdbError> {
+        match self.read_length_or_encoding()? {
+            Length::Len(len) => Ok(len),
+            Length::Encoded(_) => Err(RdbError::Corrupt(
+                "unexpected string encoding where a length was expected".to_string(),
+            )),
+        }
+    }
+
+    /// Read `len` raw bytes, through `take` so a corrupt length can't cause
+    /// a huge allocation.
+    fn read_bytes(&mut self, len: u64) -> Result<Vec<u8>, RdbError> {
+        let mut data = Vec::new();
+        (&mut self.input).take(len).read_to_end(&mut data)?;
+        if (data.len() as u64) < len {
+            return Err(RdbError::ShortRead);
+        }
+        self.crc.update(&data);
+        Ok(data)
+    }
+
+    pub fn read_string(&mut self) -> Result<Bytes, RdbError> {
+        let len = match self.read_length_or_encoding()? {
+            Length::Len(len) => len,
+            Length::Encoded(ENC_INT8) => {
+                return Ok(int_string(self.read_array::<1>()?[0] as i8 as i64))
+            }
+            Length::Encoded(ENC_INT16) => {
+                return Ok(int_string(i16::from_le_bytes(self.read_array()?) as i64))
+            }
+            Length::Encoded(ENC_INT32) => {
+                return Ok(int_string(i32::from_le_bytes(self.read_array()?) as i64))
+            }
+            Length::Encoded(ENC_LZF) => {
+                let compressed_len = self.read_length()?;
+                let len = self.read_length()?;
+                let compressed = self.read_bytes(compressed_len)?;
+                let len = usize::try_from(len)
+                    .map_err(|_| RdbError::Corrupt("LZF string is too long".to_string()))?;
+                return Ok(lzf::decompress(&compressed, len)?.into());
+            }
+            Length::Encoded(enc) => return Err(RdbError::UnknownEncoding(enc)),
+        };
+        Ok(self.read_bytes(len)?.into())
+    }
+
+    /// A sorted set score in the pre Redis 3.2 format: a length byte then
+    /// the score as text, with lengths 253 to 255 meaning nan, inf, -inf.
+    fn skip_text_double(&mut self) -> Result<(), RdbError> {
+        match self.read_u8()? {
+            253..=255 => Ok(()),
+            len => self.read_bytes(len as u64).map(|_| ()),
+        }
+    }
+
+    /// Skip a module's values, each tagged with its opcode, up to the
+    /// closing EOF opcode.
+    fn skip_module_values(&mut self) -> Result<(), RdbError> {
+        loop {
+            match self.read_length()? {
+                MODULE_OPCODE_EOF => return Ok(()),
+                MODULE_OPCODE_SINT | MODULE_OPCODE_UINT => {
+                    self.read_length()?;
+                }
+                MODULE_OPCODE_FLOAT => {
+                    self.read_array::<4>()?;
+                }
+                MODULE_OPCODE_DOUBLE => {
+                    self.read_array::<8>()?;
+                }
+                MODULE_OPCODE_STRING => {
+                    self.read_string()?;
+                }
+                opcode => {
+                    return Err(RdbError::Corrupt(format!(
+                        "unknown module opcode {}",
+                        opcode
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Skip a stream. Types 19 and 21 add fields to the stream, its
+    /// consumer groups and its consumers.
+    fn skip_stream(&mut self, kind: u8) -> Result<(), RdbError> {
+        for _ in 0..self.read_length()? {
+            // node key and listpack of entries
+            self.read_string()?;
+            self.read_string()?;
+        }
+        // length and last id, then first id, max deleted id and entries added
+        let fields = if kind >= TYPE_STREAM_LISTPACKS_2 {
+            8
+        } else {
+            3
+        };
+        for _ in 0..fields {
+            self.read_length()?;
+        }
+        for _ in 0..self.read_length()? {
+            self.read_string()?;
+            self.read_length()?;
+            self.read_length()?;
+            if kind >= TYPE_STREAM_LISTPACKS_2 {
+                self.read_length()?;
+            }
+            for _ in 0..self.read_length()? {
+                // entry id, delivery time and delivery count
+                self.read_array::<16>()?;
+                self.read_array::<8>()?;
+                self.read_length()?;
+            }
+            for _ in 0..self.read_length()? {
+                self.read_string()?;
+                self.read_array::<8>()?;
+                if kind >= TYPE_STREAM_LISTPACKS_3 {
+                    self.read_array::<8>()?;
+                }
+                for _ in 0..self.read_length()? {
+                    self.read_array::<16>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_strings(&mut self, len: u64) -> Result<Vec<Bytes>, RdbError> {
+        let mut strings = Vec::with_capacity(len.min(1024) as usize);
+        for _ in 0..len {
+            strings.push(self.read_string()?);
+        }
+        Ok(strings)
+    }
+
+    /// Read a value of the given type, after its key. Compact encodings are
+    /// expanded; types the backend can't store are read past and reported
+    /// as unsupported.
+    pub fn read_object(&mut self, kind: u8) -> Result<Object, RdbError> {
+        let unsupported = |name: &str| Ok(Object::Unsupported(name.to_string()));
+        let value = match kind {
+            TYPE_STRING => Value::String(self.read_string()?),
+            TYPE_SET => {
+                let len = self.read_length()?;
+                Value::Set(self.read_strings(len)?)
+            }
+            TYPE_HASH => {
+                let len = self.read_length()?;
+                let mut fields = Vec::with_capacity(len.min(1024) as usize);
+                for _ in 0..len {
+                    fields.push((self.read_string()?, self.read_string()?));
+                }
+                Value::Hash(fields)
+            }
+            TYPE_SET_INTSET => Value::Set(encodings::intset(&self.read_string()?)?),
+            TYPE_SET_LISTPACK => Value::Set(encodings::listpack(&self.read_string()?)?),
+            TYPE_HASH_ZIPMAP => hash_from_pairs(encodings::zipmap(&self.read_string()?)?)?,
+            TYPE_HASH_ZIPLIST => hash_from_pairs(encodings::ziplist(&self.read_string()?)?)?,
+            TYPE_HASH_LISTPACK => hash_from_pairs(encodings::listpack(&self.read_string()?)?)?,
+            TYPE_LIST => {
+                let len = self.read_length()?;
+                self.read_strings(len)?;
+                return unsupported("list");
+            }
+            TYPE_LIST_ZIPLIST => {
+                self.read_string()?;
+                return unsupported("list");
+            }
+            TYPE_LIST_QUICKLIST => {
+                let len = self.read_length()?;
+                self.read_strings(len)?;
+                return unsupported("list");
+            }
+            TYPE_LIST_QUICKLIST_2 => {
+                for _ in 0..self.read_length()? {
+                    // container kind, then a plain element or a listpack
+                    self.read_length()?;
+                    self.read_string()?;
+                }
+                return unsupported("list");
+            }
+            TYPE_ZSET => {
+                for _ in 0..self.read_length()? {
+                    self.read_string()?;
+                    self.skip_text_double()?;
+                }
+                return unsupported("zset");
+            }
+            TYPE_ZSET_2 => {
+                for _ in 0..self.read_length()? {
+                    self.read_string()?;
+                    self.read_array::<8>()?;
+                }
+                return unsupported("zset");
+            }
+            TYPE_ZSET_ZIPLIST | TYPE_ZSET_LISTPACK => {
+                self.read_string()?;
+                return unsupported("zset");
+            }
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                self.skip_stream(kind)?;
+                return unsupported("stream");
+            }
+            TYPE_MODULE_2 => {
+                let name = module_name(self.read_length()?);
+                self.skip_module_values()?;
+                return unsupported(&format!("module '{}'", name));
+            }
+            TYPE_MODULE_PRE_GA => {
+                return Err(RdbError::Corrupt(
+                    "module values in the pre-release format can't be skipped".to_string(),
+                ))
+            }
+            kind => return Err(RdbError::UnknownType(kind)),
+        };
+        Ok(Object::Value(value))
+    }
+
+    /// Read a value the backend can store, failing on any other type.
+    pub fn read_value(&mut self, kind: u8) -> Result<Value, RdbError> {
+        match self.read_object(kind)? {
+            Object::Value(value) => Ok(value),
+            Object::Unsupported(_) => Err(RdbError::UnknownType(kind)),
+        }
+    }
+}
+
+fn int_string(value: i64) -> Bytes {
+    value.to_string().into()
+}
+
+fn hash_from_pairs(entries: Vec<Bytes>) -> Result<Value, RdbError> {
+    if !entries.len().is_multiple_of(2) {
+        return Err(RdbError::Corrupt(
+            "hash with an odd number of elements".to_string(),
+        ));
+    }
+    let mut entries = entries.into_iter();
+    let mut fields = Vec::with_capacity(entries.len() / 2);
+    while let (Some(field), Some(value)) = (entries.next(), entries.next()) {
+        fields.push((field, value));
+    }
+    Ok(Value::Hash(fields))
+}
+
+const MODULE_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A module type id packs a 9 character name, 6 bits per character, above
+/// a 10 bit encoding version.
+fn module_name(id: u64) -> String {
+    (0..9)
+        .map(|i| MODULE_CHARSET[((id >> (58 - 6 * i)) & 63) as usize] as char)
+        .collect()
+}
+
+/// Read a whole RDB file, calling `on_record` for every aux field, key and
+/// database switch in it.
+pub fn read_records<R: Read>(
+    input: R,
+    mut on_record: impl FnMut(Record) -> Result<(), RdbError>,
+) -> Result<RdbInfo, RdbError> {
+    let mut reader = RdbReader::new(input);
+    let header = reader.read_array::<9>()?;
+    if &header[..5] != b"REDIS" {
+        return Err(RdbError::BadSignature);
+    }
+    let version = std::str::from_utf8(&header[5..])
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+        .ok_or(RdbError::BadSignature)?;
+    if !(1..=RDB_MAX_LOAD_VERSION).contains(&version) {
+        return Err(RdbError::UnsupportedVersion(version));
+    }
+
+    let mut expire_at = None;
+    loop {
+        let record = match reader.read_u8()? {
+            OPCODE_EXPIRETIME_MS => {
+                expire_at = Some(i64::from_le_bytes(reader.read_array()?));
+                continue;
+            }
+            OPCODE_EXPIRETIME => {
+                expire_at = Some(i32::from_le_bytes(reader.read_array()?) as i64 * 1000);
+                continue;
+            }
+            OPCODE_IDLE => {
+                reader.read_length()?;
+                continue;
+            }
+            OPCODE_FREQ => {
+                reader.read_u8()?;
+                continue;
+            }
+            OPCODE_RESIZEDB => {
+                reader.read_length()?;
+                reader.read_length()?;
+                continue;
+            }
+            OPCODE_SLOT_INFO => {
+                // slot, its key count and its expiring key count
+                for _ in 0..3 {
+                    reader.read_length()?;
+                }
+                continue;
+            }
+            OPCODE_SELECTDB => Record::SelectDb(reader.read_length()?),
+            OPCODE_AUX => Record::Aux(reader.read_string()?, reader.read_string()?),
+            OPCODE_MODULE_AUX => {
+                let name = module_name(reader.read_length()?);
+                let when_opcode = reader.read_length()?;
+                reader.read_length()?;
+                if when_opcode != MODULE_OPCODE_UINT {
+                    return Err(RdbError::Corrupt(format!(
+                        "bad when opcode in aux data of module '{}'",
+                        name
+                    )));
+                }
+                reader.skip_module_values()?;
+                Record::ModuleAux(name)
+            }
+            OPCODE_FUNCTION2 => {
+                reader.read_string()?;
+                Record::Function
+            }
+            OPCODE_FUNCTION_PRE_GA => {
+                return Err(RdbError::Corrupt(
+                    "functions in the pre-release format are not supported".to_string(),
+                ))
+            }
+            OPCODE_EOF => break,
+            kind => {
+                let key = reader.read_string()?;
+                let object = reader.read_object(kind)?;
+                Record::Key {
+                    key,
+                    object,
+                    expire_at: expire_at.take(),
+                }
+            }
+        };
+        on_record(record)?;
+    }
+
+    let mut checksum = None;
+    if version >= 5 {
+        let computed = reader.checksum();
+        let expected = u64::from_le_bytes(reader.read_array()?);
+        if expected != 0 && expected != computed {
+            return Err(RdbError::Checksum { expected, computed });
+        }
+        checksum = (expected != 0).then_some(expected);
+    }
+    Ok(RdbInfo { version, checksum })
+}
+
+/// Read a whole RDB file, calling `on_entry` for every key in database 0.
+/// Keys of types the backend can't store are an error. Returns the file's
+/// RDB version.
+pub fn read_rdb<R: Read>(
+    input: R,
+    mut on_entry: impl FnMut(Entry) -> Result<(), RdbError>,
+) -> Result<u16, RdbError> {
+    let info = read_records(input, |record| match record {
+        Record::SelectDb(0) => Ok(()),
+        Record::SelectDb(db) => Err(RdbError::Corrupt(format!(
+            "database {} is out of range, only database 0 is supported",
+            db
+        ))),
+        Record::Key {
+            key,
+            object: Object::Value(value),
+            expire_at,
+        } => on_entry(Entry {
+            key,
+            value,
+            expire_at,
+        }),
+        Record::Key {
+            key,
+            object: Object::Unsupported(kind),
+            ..
+        } => Err(RdbError::UnsupportedType {
+            key: String::from_utf8_lossy(&key).into_owned(),
+            kind,
+        }),
+        Record::Aux(..) | Record::ModuleAux(_) | Record::Function => Ok(()),
+    })?;
+    Ok(info.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rdb::writer::write_snapshot, Snapshot};
+
+    fn read_all(data: &[u8]) -> Result<Vec<Entry>, RdbError> {
+        let mut entries = Vec::new();
+        read_rdb(data, |entry| {
+            entries.push(entry);
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    fn sample() -> Snapshot {
+        Snapshot {
+            entries: vec![
+                Entry {
+                    key: Bytes::from("str"),
+                    value: Value::String(Bytes::from("hello")),
+                    expire_at: Some(1_700_000_000_000),
+                },
+                Entry {
+                    key: Bytes::from("num"),
+                    value: Value::String(Bytes::from("-42")),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("hash"),
+                    value: Value::Hash(vec![(Bytes::from("f"), Bytes::from("v".repeat(100)))]),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("set"),
+                    value: Value::Set(vec![Bytes::from("a"), Bytes::from("1")]),
+                    expire_at: None,
+                },
+            ],
+            dirty: 0,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = sample();
+        let data = write_snapshot(&snapshot, Vec::new(), true, false, 0).unwrap();
+        assert!(data.starts_with(b"REDIS0011"));
+        assert_eq!(read_all(&data).unwrap(), snapshot.entries);
+
+        let data = write_snapshot(&snapshot, Vec::new(), false, false, 0).unwrap();
+        assert!(data.ends_with(&[0; 8]));
+        assert_eq!(read_all(&data).unwrap(), snapshot.entries);
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let data = write_snapshot(&sample(), Vec::new(), true, false, 0).unwrap();
+
+        let mut flipped = data.clone();
+        let pos = flipped.windows(5).position(|w| w == b"hello").unwrap();
+        flipped[pos] = b'j';
+        assert!(matches!(read_all(&flipped), Err(RdbError::Checksum { .. })));
+        assert!(matches!(
+            read_all(&data[..data.len() - 12]),
+            Err(RdbError::ShortRead)
+        ));
+        assert!(matches!(
+            read_all(b"RODIS0011"),
+            Err(RdbError::BadSignature)
+        ));
+        assert!(matches!(
+            read_all(b"REDIS0099\xff"),
+            Err(RdbError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn test_reads_redis_file() {
+        // `SET foo bar`, `SADD s 7` and `HSET h f v` laid out as Redis 7.2
+        // saves them when the listpack/intset thresholds are 0
+        let mut data = b"REDIS0011".to_vec();
+        data.extend_from_slice(b"\xfa\x09redis-ver\x057.2.4");
+        data.extend_from_slice(b"\xfa\x0aredis-bits\xc0\x40");
+        data.extend_from_slice(b"\xfe\x00\xfb\x03\x00");
+        data.extend_from_slice(b"\x00\x03foo\x03bar");
+        data.extend_from_slice(b"\x02\x01s\x01\xc0\x07");
+        data.extend_from_slice(b"\x04\x01h\x01\x01f\x01v");
+        data.push(0xff);
+        let crc = super::super::crc64::crc64(&data);
+        data.extend_from_slice(&crc.to_le_bytes());
+
+        let entries = read_all(&data).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    key: Bytes::from("foo"),
+                    value: Value::String(Bytes::from("bar")),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("s"),
+                    value: Value::Set(vec![Bytes::from("7")]),
+                    expire_at: None,
+                },
+                Entry {
+                    key: Bytes::from("h"),
+                    value: Value::Hash(vec![(Bytes::from("f"), Bytes::from("v"))]),
+                    expire_at: None,
+                },
+            ]
+        );
+    }
+
+    fn module_id(name: &str, version: u64) -> u64 {
+        name.bytes().fold(0, |id, c| {
+            let pos = MODULE_CHARSET.iter().position(|x| *x == c).unwrap();
+            (id << 6) | pos as u64
+        }) << 10
+            | version
+    }
+
+    fn length(data: &mut Vec<u8>, len: u64) {
+        data.push(0x81);
+        data.extend_from_slice(&len.to_be_bytes());
+    }
+
+    fn string(data: &mut Vec<u8>, value: &[u8]) {
+        data.push(value.len() as u8);
+        data.extend_from_slice(value);
+    }
+
+    /// A Redis 7 file with every encoding and opcode the reader handles.
+    fn redis7_file() -> Vec<u8> {
+        let mut data = b"REDIS0011".to_vec();
+        data.extend_from_slice(b"\xfa\x09redis-ver\x057.2.4");
+        data.push(0xf5);
+        string(&mut data, b"#!lua name=lib");
+        data.push(0xf7);
+        length(&mut data, module_id("ReJSON-RL", 3));
+        data.extend_from_slice(b"\x02\x02\x02\x05\x05\x01x\x00");
+        data.extend_from_slice(b"\xfe\x00\xfb\x09\x01");
+
+        data.extend_from_slice(b"\xfc");
+        data.extend_from_slice(&1_700_000_000_000i64.to_le_bytes());
+        data.extend_from_slice(b"\x00\x03lzf\xc3\x05\x0a\x00a\xe0\x00\x00");
+        data.extend_from_slice(b"\xf8\x05\xf9\x03");
+        data.extend_from_slice(b"\x0b\x04iset");
+        string(
+            &mut data,
+            b"\x02\x00\x00\x00\x02\x00\x00\x00\x01\x00\x02\x00",
+        );
+        data.extend_from_slice(b"\x14\x05lpset");
+        string(&mut data, b"\x0c\x00\x00\x00\x02\x00\x81a\x02\x07\x01\xff");
+        data.extend_from_slice(b"\x0d\x06zlhash");
+        string(
+            &mut data,
+            b"\x11\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x01f\x03\x01v\xff",
+        );
+        data.extend_from_slice(b"\x10\x06lphash");
+        string(&mut data, b"\x0c\x00\x00\x00\x02\x00\x81f\x02\x01\x01\xff");
+        data.extend_from_slice(b"\x12\x04list\x01\x02");
+        string(&mut data, b"\x0c\x00\x00\x00\x02\x00\x81a\x02\x07\x01\xff");
+        data.extend_from_slice(b"\x05\x04zset\x01\x01m");
+        data.extend_from_slice(&1.5f64.to_le_bytes());
+        data.extend_from_slice(b"\x15\x06stream\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00");
+        data.extend_from_slice(b"\x07\x03doc");
+        length(&mut data, module_id("ReJSON-RL", 3));
+        data.push(0x04);
+        data.extend_from_slice(&2.0f64.to_le_bytes());
+        data.push(0x00);
+
+        data.push(0xff);
+        let crc = super::super::crc64::crc64(&data);
+        data.extend_from_slice(&crc.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_reads_compact_encodings() {
+        let data = redis7_file();
+        let mut records = Vec::new();
+        let info = read_records(data.as_slice(), |record| {
+            records.push(record);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(info.version, 11);
+        assert!(info.checksum.is_some());
+
+        let key = |key: &str, object: Object| Record::Key {
+            key: Bytes::from(key.to_string()),
+            object,
+            expire_at: None,
+        };
+        let value = |value: Value| Object::Value(value);
+        let b = |s: &str| Bytes::from(s.to_string());
+        let unsupported = |kind: &str| Object::Unsupported(kind.to_string());
+        assert_eq!(
+            records,
+            vec![
+                Record::Aux(b("redis-ver"), b("7.2.4")),
+                Record::Function,
+                Record::ModuleAux("ReJSON-RL".to_string()),
+                Record::SelectDb(0),
+                Record::Key {
+                    key: b("lzf"),
+                    object: value(Value::String(b("aaaaaaaaaa"))),
+                    expire_at: Some(1_700_000_000_000),
+                },
+                key("iset", value(Value::Set(vec![b("1"), b("2")]))),
+                key("lpset", value(Value::Set(vec![b("a"), b("7")]))),
+                key("zlhash", value(Value::Hash(vec![(b("f"), b("v"))]))),
+                key("lphash", value(Value::Hash(vec![(b("f"), b("1"))]))),
+                key("list", unsupported("list")),
+                key("zset", unsupported("zset")),
+                key("stream", unsupported("stream")),
+                key("doc", unsupported("module 'ReJSON-RL'")),
+            ]
+        );
+
+        let err = read_all(&data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Key 'list' has type list, which simple-redis does not support"
+        );
+    }
+}
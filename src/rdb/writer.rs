@@ -0,0 +1,201 @@
+use std::io::{self, Write};
+
+use super::{
+    crc64::Crc64, ENCVAL, ENC_INT16, ENC_INT32, ENC_INT8, LEN_14BIT, LEN_32BIT, LEN_64BIT,
+    OPCODE_AUX, OPCODE_EOF, OPCODE_EXPIRETIME_MS, OPCODE_RESIZEDB, OPCODE_SELECTDB, RDB_VERSION,
+    TYPE_HASH, TYPE_SET, TYPE_STRING,
+};
+use crate::{cmd::SERVER_VERSION, Snapshot, Value};
+
+/// Writes RDB primitives while keeping a running checksum of the output.
+pub struct RdbWriter<W> {
+    out: W,
+    crc: Crc64,
+}
+
+impl<W: Write> RdbWriter<W> {
+    pub fn new(out: W) -> Self {
+        RdbWriter {
+            out,
+            crc: Crc64::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.crc.value()
+    }
+
+    pub fn write_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.crc.update(data);
+        self.out.write_all(data)
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_raw(&[value])
+    }
+
+    /// The variable length encoding: 6, 14, 32 or 64 bits.
+    pub fn write_length(&mut self, len: u64) -> io::Result<()> {
+        if len < 1 << 6 {
+            self.write_u8(len as u8)
+        } else if len < 1 << 14 {
+            self.write_raw(&[LEN_14BIT | (len >> 8) as u8, len as u8])
+        } else if len <= u32::MAX as u64 {
+            self.write_u8(LEN_32BIT)?;
+            self.write_raw(&(len as u32).to_be_bytes())
+        } else {
+            self.write_u8(LEN_64BIT)?;
+            self.write_raw(&len.to_be_bytes())
+        }
+    }
+
+    /// A string, stored as an integer when it is the canonical form of one
+    /// that fits in 32 bits, like `rdbTryIntegerEncoding`.
+    pub fn write_string(&mut self, data: &[u8]) -> io::Result<()> {
+        if let Some(value) = canonical_int(data) {
+            if let Ok(value) = i8::try_from(value) {
+                return self.write_raw(&[ENCVAL | ENC_INT8, value as u8]);
+            }
+            if let Ok(value) = i16::try_from(value) {
+                self.write_u8(ENCVAL | ENC_INT16)?;
+                return self.write_raw(&value.to_le_bytes());
+            }
+            if let Ok(value) = i32::try_from(value) {
+                self.write_u8(ENCVAL | ENC_INT32)?;
+                return self.write_raw(&value.to_le_bytes());
+            }
+        }
+        self.write_length(data.len() as u64)?;
+        self.write_raw(data)
+    }
+
+    pub fn write_aux(&mut self, key: &str, value: &str) -> io::Result<()> {
+        self.write_u8(OPCODE_AUX)?;
+        self.write_string(key.as_bytes())?;
+        self.write_string(value.as_bytes())
+    }
+
+    /// The type byte of `value`.
+    pub fn write_type(&mut self, value: &Value) -> io::Result<()> {
+        self.write_u8(match value {
+            Value::String(_) => TYPE_STRING,
+            Value::Set(_) => TYPE_SET,
+            Value::Hash(_) => TYPE_HASH,
+        })
+    }
+
+    /// The payload of `value`, without its type byte.
+    pub fn write_value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::String(data) => self.write_string(data),
+            Value::Set(members) => {
+                self.write_length(members.len() as u64)?;
+                members.iter().try_for_each(|m| self.write_string(m))
+            }
+            Value::Hash(fields) => {
+                self.write_length(fields.len() as u64)?;
+                fields.iter().try_for_each(|(field, value)| {
+                    self.write_string(field)?;
+                    self.write_string(value)
+                })
+            }
+        }
+    }
+}
+
+fn canonical_int(data: &[u8]) -> Option<i64> {
+    if data.is_empty() || data.len() > 11 {
+        return None;
+    }
+    let value = std::str::from_utf8(data).ok()?.parse::<i64>().ok()?;
+    (value.to_string().as_bytes() == data).then_some(value)
+}
+
+/// Serialize `snapshot` as a complete RDB file. Without `checksum` the
+/// trailer is zero, which loaders take as "not computed". `aof_base` marks
+/// files written as the base of a multi-part AOF.
+pub fn write_snapshot<W: Write>(
+    snapshot: &Snapshot,
+    out: W,
+    checksum: bool,
+    aof_base: bool,
+    ctime: i64,
+) -> io::Result<W> {
+    let mut writer = RdbWriter::new(out);
+    writer.write_raw(format!("REDIS{:04}", RDB_VERSION).as_bytes())?;
+    writer.write_aux("redis-ver", SERVER_VERSION)?;
+    writer.write_aux("redis-bits", &(usize::BITS).to_string())?;
+    writer.write_aux("ctime", &ctime.to_string())?;
+    writer.write_aux("aof-base", if aof_base { "1" } else { "0" })?;
+
+    writer.write_u8(OPCODE_SELECTDB)?;
+    writer.write_length(0)?;
+    let expires = snapshot
+        .entries
+        .iter()
+        .filter(|entry| entry.expire_at.is_some())
+        .count();
+    writer.write_u8(OPCODE_RESIZEDB)?;
+    writer.write_length(snapshot.entries.len() as u64)?;
+    writer.write_length(expires as u64)?;
+    for entry in &snapshot.entries {
+        if let Some(at) = entry.expire_at {
+            writer.write_u8(OPCODE_EXPIRETIME_MS)?;
+            writer.write_raw(&at.to_le_bytes())?;
+        }
+        writer.write_type(&entry.value)?;
+        writer.write_string(&entry.key)?;
+        writer.write_value(&entry.value)?;
+    }
+    writer.write_u8(OPCODE_EOF)?;
+    let crc = if checksum { writer.checksum() } else { 0 };
+    let mut out = writer.into_inner();
+    out.write_all(&crc.to_le_bytes())?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(f: impl FnOnce(&mut RdbWriter<Vec<u8>>) -> io::Result<()>) -> Vec<u8> {
+        let mut writer = RdbWriter::new(Vec::new());
+        f(&mut writer).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_write_length() {
+        assert_eq!(encoded(|w| w.write_length(10)), [0x0a]);
+        assert_eq!(encoded(|w| w.write_length(700)), [0x42, 0xbc]);
+        assert_eq!(
+            encoded(|w| w.write_length(17000)),
+            [0x80, 0x00, 0x00, 0x42, 0x68]
+        );
+        assert_eq!(
+            encoded(|w| w.write_length(1 << 33)),
+            [0x81, 0, 0, 0, 2, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_write_string_int_encoding() {
+        assert_eq!(encoded(|w| w.write_string(b"12")), [0xc0, 12]);
+        assert_eq!(encoded(|w| w.write_string(b"-300")), [0xc1, 0xd4, 0xfe]);
+        assert_eq!(
+            encoded(|w| w.write_string(b"100000")),
+            [0xc2, 0xa0, 0x86, 0x01, 0x00]
+        );
+        // not canonical, or too large for 32 bits: written as raw strings
+        assert_eq!(encoded(|w| w.write_string(b"012")), b"\x03012");
+        assert_eq!(
+            encoded(|w| w.write_string(b"4294967296")),
+            b"\x0a4294967296"
+        );
+        assert_eq!(encoded(|w| w.write_string(b"")), [0x00]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+/// The tail of the replication stream, kept so a replica that reconnects
+/// can continue from its offset instead of syncing everything again.
+/// Offsets count bytes of the stream from 1, as in Redis.
+#[derive(Debug)]
+pub struct Backlog {
+    data: VecDeque<u8>,
+    size: usize,
+    /// Stream offset of the first byte held.
+    first_byte: i64,
+}
+
+impl Backlog {
+    /// An empty backlog whose next byte is at stream offset `offset + 1`.
+    pub fn new(size: usize, offset: i64) -> Self {
+        Backlog {
+            data: VecDeque::new(),
+            size,
+            first_byte: offset + 1,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn first_byte(&self) -> i64 {
+        self.first_byte
+    }
+
+    pub fn histlen(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Append bytes of the stream, dropping the oldest ones past `size`.
+    pub fn push(&mut self, data: &[u8]) {
+        self.data.extend(data);
+        self.trim();
+    }
+
+    pub fn resize(&mut self, size: usize) {
+        self.size = size;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let excess = self.data.len().saturating_sub(self.size);
+        self.data.drain(..excess);
+        self.first_byte += excess as i64;
+    }
+
+    /// The stream from `offset` to its end, if the backlog still holds it.
+    pub fn since(&self, offset: i64) -> Option<Vec<u8>> {
+        let skip = usize::try_from(offset - self.first_byte).ok()?;
+        if skip > self.data.len() {
+            return None;
+        }
+        Some(self.data.range(skip..).copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backlog() {
+        let mut backlog = Backlog::new(8, 100);
+        assert_eq!(backlog.since(101), Some(vec![]));
+        assert_eq!(backlog.since(102), None);
+
+        backlog.push(b"abcdef");
+        assert_eq!(backlog.since(104), Some(b"def".to_vec()));
+        backlog.push(b"ghij");
+        assert_eq!(backlog.first_byte(), 103);
+        assert_eq!(backlog.histlen(), 8);
+        assert_eq!(backlog.since(102), None);
+        assert_eq!(backlog.since(103), Some(b"cdefghij".to_vec()));
+        assert_eq!(backlog.since(111), Some(vec![]));
+
+        backlog.resize(2);
+        assert_eq!(backlog.first_byte(), 109);
+        assert_eq!(backlog.since(109), Some(b"ij".to_vec()));
+    }
+}
@@ -0,0 +1,683 @@
+//! Master–replica replication: the replication stream, its backlog and
+//! the links to replicas on the master side, and the link to the master on
+//! the replica side (see `replica`).
+
+mod backlog;
+mod replica;
+
+use std::{
+    fmt::Write as _,
+    hash::{BuildHasher, Hasher, RandomState},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use tokio::{
+    io::{AsyncWriteExt, BufWriter},
+    net::TcpStream,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task::AbortHandle,
+};
+use tokio_util::codec::FramedRead;
+use tracing::{info, warn};
+
+use crate::{
+    backend::now_ms, network::RespFrameCodec, rdb::write_snapshot, Backend, BulkString, Client,
+    RespArray, RespEncode, RespFrame, Snapshot,
+};
+
+use self::backlog::Backlog;
+
+/// How often a master pings its replicas, so they can tell an idle link
+/// from a dead one.
+const PING_PERIOD: Duration = Duration::from_secs(10);
+
+/// Replication ID used for `master_replid2` when there is no previous one.
+const NO_REPLID: &str = "0000000000000000000000000000000000000000";
+
+/// A 40 character hex ID naming a history of the dataset.
+fn new_replid() -> String {
+    let random = RandomState::new();
+    let mut id = String::with_capacity(48);
+    for i in 0..3 {
+        let mut hasher = random.build_hasher();
+        hasher.write_u64(i);
+        hasher.write_i64(now_ms());
+        write!(id, "{:016x}", hasher.finish()).expect("write to string");
+    }
+    id.truncate(NO_REPLID.len());
+    id
+}
+
+/// State of the link to the master, as `ROLE` names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    /// Waiting to reconnect.
+    Connect,
+    Connecting,
+    /// Exchanging `PING`, `REPLCONF` and `PSYNC`.
+    Handshake,
+    /// Receiving the master's snapshot.
+    Sync,
+    Connected,
+}
+
+impl LinkState {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkState::Connect => "connect",
+            LinkState::Connecting => "connecting",
+            LinkState::Handshake => "handshake",
+            LinkState::Sync => "sync",
+            LinkState::Connected => "connected",
+        }
+    }
+}
+
+/// The replica class of `client-output-buffer-limit`: a link is dropped
+/// once `hard` bytes are queued for it, or once at least `soft` bytes have
+/// stayed queued for more than `soft_seconds`. A limit of 0 is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputBufferLimit {
+    pub hard: usize,
+    pub soft: usize,
+    pub soft_seconds: u64,
+}
+
+#[derive(Debug)]
+struct MasterLink {
+    host: String,
+    port: u16,
+    state: LinkState,
+    last_io: Instant,
+    task: AbortHandle,
+}
+
+#[derive(Debug)]
+struct ReplicaLink {
+    client_id: u64,
+    ip: String,
+    port: u16,
+    /// False until the snapshot of a full sync has been sent.
+    online: bool,
+    ack_offset: i64,
+    last_ack: Instant,
+    stream: UnboundedSender<Bytes>,
+    /// Bytes sent to `stream` and not yet written to the connection.
+    queued: Arc<AtomicUsize>,
+    /// When `queued` last went over the soft limit, while it stays over.
+    soft_limit_since: Option<Instant>,
+    /// Dropped with the link, which closes the connection without waiting
+    /// for the queued stream to be written.
+    _closing: oneshot::Sender<()>,
+}
+
+#[derive(Debug)]
+struct ReplState {
+    replid: String,
+    /// The ID this server replicated before its current one, so replicas of
+    /// a promoted replica can still continue from the old history.
+    replid2: String,
+    /// First offset that is not valid under `replid2`.
+    second_replid_offset: i64,
+    /// Bytes of the replication stream produced or received so far.
+    offset: i64,
+    /// Created when the first replica attaches, or once this server is a
+    /// replica; until then writes are not recorded.
+    backlog: Option<Backlog>,
+    replicas: Vec<ReplicaLink>,
+    master: Option<MasterLink>,
+}
+
+/// Replication bookkeeping owned by the backend, the part of Redis's
+/// `server` struct about masters and replicas.
+#[derive(Debug)]
+pub struct Replication {
+    state: Mutex<ReplState>,
+    /// Set once a backlog exists, so writes skip the lock until then.
+    recording: AtomicBool,
+}
+
+impl Default for Replication {
+    fn default() -> Self {
+        Replication {
+            recording: AtomicBool::new(false),
+            state: Mutex::new(ReplState {
+                replid: new_replid(),
+                replid2: NO_REPLID.to_string(),
+                second_replid_offset: -1,
+                offset: 0,
+                backlog: None,
+                replicas: Vec::new(),
+                master: None,
+            }),
+        }
+    }
+}
+
+/// A connection that sent `PSYNC`. Once the reply is written the
+/// connection stops serving commands and streams the dataset instead.
+#[derive(Debug)]
+pub struct ReplicaHandoff {
+    client_id: u64,
+    /// The dataset to send first, for a full sync.
+    snapshot: Option<Snapshot>,
+    stream: StreamReceiver,
+    /// Resolves when the master drops the link.
+    closing: oneshot::Receiver<()>,
+}
+
+/// The receiving end of a replica's stream, which keeps the count of bytes
+/// queued for it up to date.
+#[derive(Debug)]
+struct StreamReceiver {
+    receiver: UnboundedReceiver<Bytes>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl StreamReceiver {
+    async fn recv(&mut self) -> Option<Bytes> {
+        let data = self.receiver.recv().await?;
+        self.queued.fetch_sub(data.len(), Ordering::Relaxed);
+        Some(data)
+    }
+
+    fn try_recv(&mut self) -> Option<Bytes> {
+        let data = self.receiver.try_recv().ok()?;
+        self.queued.fetch_sub(data.len(), Ordering::Relaxed);
+        Some(data)
+    }
+}
+
+impl ReplState {
+    fn backlog_mut(&mut self, size: usize) -> &mut Backlog {
+        let offset = self.offset;
+        self.backlog
+            .get_or_insert_with(|| Backlog::new(size, offset))
+    }
+
+    /// Queue `data` for every replica, dropping the links whose queue
+    /// goes over `output_limit`, as Redis does with
+    /// `client-output-buffer-limit replica`.
+    fn send_to_replicas(&mut self, data: Bytes, output_limit: OutputBufferLimit) {
+        let now = Instant::now();
+        self.replicas.retain_mut(|replica| {
+            let queued = replica.queued.fetch_add(data.len(), Ordering::Relaxed) + data.len();
+            let hard = output_limit.hard > 0 && queued >= output_limit.hard;
+            let soft = if output_limit.soft > 0 && queued >= output_limit.soft {
+                let since = *replica.soft_limit_since.get_or_insert(now);
+                now.duration_since(since) > Duration::from_secs(output_limit.soft_seconds)
+            } else {
+                replica.soft_limit_since = None;
+                false
+            };
+            if hard || soft {
+                warn!(
+                    "Replica {}:{} scheduled to be closed ASAP for overcoming of output buffer limits.",
+                    replica.ip, replica.port
+                );
+                return false;
+            }
+            // a send only fails once the link is closing
+            let _ = replica.stream.send(data.clone());
+            true
+        });
+    }
+
+    /// Start a new history, e.g. when a replica is promoted. The old ID
+    /// stays valid up to the current offset.
+    fn shift_replid(&mut self) {
+        self.replid2 = std::mem::replace(&mut self.replid, new_replid());
+        self.second_replid_offset = self.offset + 1;
+        info!(
+            "Setting secondary replication ID to {}, valid up to offset: {}. New replication ID is {}",
+            self.replid2, self.second_replid_offset, self.replid
+        );
+    }
+
+    /// Drop the links to replicas so they reconnect and learn about a new
+    /// history.
+    fn disconnect_replicas(&mut self) {
+        if !self.replicas.is_empty() {
+            info!("Disconnecting {} replicas", self.replicas.len());
+            self.replicas.clear();
+        }
+    }
+
+    /// The stream a replica is missing if it can continue from `offset` of
+    /// history `replid` without a full sync.
+    fn continuation(&self, replid: &str, offset: i64) -> Option<Vec<u8>> {
+        let same_history = replid == self.replid
+            || (replid == self.replid2 && offset <= self.second_replid_offset);
+        if !same_history {
+            return None;
+        }
+        self.backlog.as_ref()?.since(offset)
+    }
+}
+
+impl Replication {
+    fn state(&self) -> MutexGuard<'_, ReplState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn is_replica(&self) -> bool {
+        self.state().master.is_some()
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.state().offset
+    }
+
+    /// Create the backlog if there is none yet, so writes are recorded
+    /// from now on.
+    fn start_backlog(&self, state: &mut ReplState, size: usize) {
+        state.backlog_mut(size);
+        self.recording.store(true, Ordering::Release);
+    }
+
+    /// Record bytes of the replication stream and send them to every
+    /// replica. Does nothing until a backlog exists.
+    pub fn feed(&self, data: &[u8], backlog_size: usize, output_limit: OutputBufferLimit) {
+        if !self.recording.load(Ordering::Acquire) {
+            return;
+        }
+        let mut state = self.state();
+        let Some(backlog) = state.backlog.as_mut() else {
+            return;
+        };
+        if backlog.size() != backlog_size {
+            backlog.resize(backlog_size);
+        }
+        backlog.push(data);
+        state.offset += data.len() as i64;
+        if !state.replicas.is_empty() {
+            state.send_to_replicas(Bytes::copy_from_slice(data), output_limit);
+        }
+    }
+
+    fn set_online(&self, client_id: u64) {
+        let mut state = self.state();
+        if let Some(replica) = state.replicas.iter_mut().find(|r| r.client_id == client_id) {
+            replica.online = true;
+        }
+    }
+
+    fn set_ack(&self, client_id: u64, offset: i64) {
+        let mut state = self.state();
+        if let Some(replica) = state.replicas.iter_mut().find(|r| r.client_id == client_id) {
+            replica.ack_offset = offset;
+            replica.last_ack = Instant::now();
+        }
+    }
+
+    fn detach(&self, client_id: u64) {
+        self.state()
+            .replicas
+            .retain(|replica| replica.client_id != client_id);
+    }
+
+    /// Lines of the `# Replication` section of `INFO`.
+    pub fn info(&self, read_only: bool) -> String {
+        let state = self.state();
+        let mut info = String::new();
+        let mut line = |key: &str, value: &dyn std::fmt::Display| {
+            write!(info, "{}:{}\r\n", key, value).expect("write to string")
+        };
+        match &state.master {
+            Some(master) => {
+                let up = master.state == LinkState::Connected;
+                line("role", &"slave");
+                line("master_host", &master.host);
+                line("master_port", &master.port);
+                line("master_link_status", &if up { "up" } else { "down" });
+                let last_io = master.last_io.elapsed().as_secs() as i64;
+                line("master_last_io_seconds_ago", &if up { last_io } else { -1 });
+                let syncing = master.state == LinkState::Sync;
+                line("master_sync_in_progress", &(syncing as u8));
+                line("slave_read_repl_offset", &state.offset);
+                line("slave_repl_offset", &state.offset);
+                line("slave_priority", &100);
+                line("slave_read_only", &(read_only as u8));
+                line("replica_announced", &1);
+            }
+            None => line("role", &"master"),
+        }
+        line("connected_slaves", &state.replicas.len());
+        for (i, replica) in state.replicas.iter().enumerate() {
+            line(
+                &format!("slave{}", i),
+                &format_args!(
+                    "ip={},port={},state={},offset={},lag={}",
+                    replica.ip,
+                    replica.port,
+                    if replica.online {
+                        "online"
+                    } else {
+                        "wait_bgsave"
+                    },
+                    replica.ack_offset,
+                    replica.last_ack.elapsed().as_secs()
+                ),
+            );
+        }
+        line("master_failover_state", &"no-failover");
+        line("master_replid", &state.replid);
+        line("master_replid2", &state.replid2);
+        line("master_repl_offset", &state.offset);
+        line("second_repl_offset", &state.second_replid_offset);
+        let backlog = state.backlog.as_ref();
+        line("repl_backlog_active", &(backlog.is_some() as u8));
+        line("repl_backlog_size", &backlog.map_or(0, Backlog::size));
+        line(
+            "repl_backlog_first_byte_offset",
+            &backlog.map_or(0, Backlog::first_byte),
+        );
+        line("repl_backlog_histlen", &backlog.map_or(0, Backlog::histlen));
+        info
+    }
+
+    /// The reply to `ROLE`.
+    pub fn role(&self) -> RespFrame {
+        let state = self.state();
+        let bulk = |value: String| RespFrame::from(BulkString::new(Some(value)));
+        let frames = match &state.master {
+            Some(master) => {
+                let offset = match master.state {
+                    LinkState::Connected => state.offset,
+                    _ => -1,
+                };
+                vec![
+                    bulk("slave".to_string()),
+                    bulk(master.host.clone()),
+                    RespFrame::Integer(master.port as i64),
+                    bulk(master.state.as_str().to_string()),
+                    RespFrame::Integer(offset),
+                ]
+            }
+            None => {
+                let replicas = state
+                    .replicas
+                    .iter()
+                    .filter(|replica| replica.online)
+                    .map(|replica| {
+                        RespArray::new(Some(vec![
+                            bulk(replica.ip.clone()),
+                            bulk(replica.port.to_string()),
+                            bulk(replica.ack_offset.to_string()),
+                        ]))
+                        .into()
+                    })
+                    .collect::<Vec<RespFrame>>();
+                vec![
+                    bulk("master".to_string()),
+                    RespFrame::Integer(state.offset),
+                    RespArray::new(Some(replicas)).into(),
+                ]
+            }
+        };
+        RespArray::new(Some(frames)).into()
+    }
+}
+
+/// Answer a replica's `PSYNC <replid> <offset>`: `CONTINUE` when the
+/// backlog still holds everything after `offset` of the same history, and
+/// `FULLRESYNC <replid> <offset>` with a snapshot otherwise. Returns the
+/// status line and the handoff to keep in the client.
+pub fn psync(
+    backend: &Backend,
+    client: &Client,
+    replid: &str,
+    offset: i64,
+) -> Result<(String, ReplicaHandoff), String> {
+    let repl = backend.replication();
+    let ip = client
+        .addr
+        .map_or_else(|| "?".to_string(), |addr| addr.ip().to_string());
+    let port = client.listening_port.unwrap_or(0);
+    let (sender, stream) = mpsc::unbounded_channel();
+    let (closing_sender, closing) = oneshot::channel();
+    let queued = Arc::new(AtomicUsize::new(0));
+    let mut link = ReplicaLink {
+        client_id: client.id,
+        ip,
+        port,
+        online: false,
+        ack_offset: 0,
+        last_ack: Instant::now(),
+        stream: sender,
+        queued: queued.clone(),
+        soft_limit_since: None,
+        _closing: closing_sender,
+    };
+    let handoff = |snapshot| ReplicaHandoff {
+        client_id: client.id,
+        snapshot,
+        stream: StreamReceiver {
+            receiver: stream,
+            queued,
+        },
+        closing,
+    };
+    info!("Replica {}:{} asks for synchronization", link.ip, link.port);
+
+    {
+        let mut state = repl.state();
+        if state
+            .master
+            .as_ref()
+            .is_some_and(|master| master.state != LinkState::Connected)
+        {
+            return Err("NOMASTERLINK Can't SYNC while not connected with my master".to_string());
+        }
+        if let Some(missing) = state.continuation(replid, offset) {
+            info!(
+                "Partial resynchronization request from {}:{} accepted. Sending {} bytes of backlog starting from offset {}.",
+                link.ip,
+                link.port,
+                missing.len(),
+                offset
+            );
+            link.online = true;
+            link.ack_offset = offset - 1;
+            link.queued.fetch_add(missing.len(), Ordering::Relaxed);
+            let _ = link.stream.send(Bytes::from(missing));
+            state.replicas.push(link);
+            return Ok((format!("CONTINUE {}", state.replid), handoff(None)));
+        }
+    }
+
+    info!("Full resync requested by replica {}:{}", link.ip, link.port);
+    let backlog_size = backend.config().repl_backlog_size();
+    // the replica is attached while writes are blocked, so everything
+    // after the snapshot reaches it through the stream
+    let (snapshot, (replid, offset)) = backend.snapshot_and(|| {
+        let mut state = repl.state();
+        repl.start_backlog(&mut state, backlog_size);
+        state.replicas.push(link);
+        (state.replid.clone(), state.offset)
+    });
+    Ok((
+        format!("FULLRESYNC {} {}", replid, offset),
+        handoff(Some(snapshot)),
+    ))
+}
+
+/// Serve a connection handed off by `PSYNC`: send the snapshot if there is
+/// one, then the replication stream, while reading `REPLCONF ACK`s.
+pub async fn serve_replica(
+    backend: Backend,
+    stream: TcpStream,
+    read_buf: BytesMut,
+    handoff: ReplicaHandoff,
+) -> Result<()> {
+    let ReplicaHandoff {
+        client_id,
+        snapshot,
+        stream: commands,
+        closing,
+    } = handoff;
+    let result = tokio::select! {
+        result = stream_to_replica(&backend, stream, read_buf, client_id, snapshot, commands) => result,
+        // dropped by the master, e.g. over its output buffer limit
+        _ = closing => Ok(()),
+    };
+    backend.replication().detach(client_id);
+    info!("Connection with replica lost");
+    result
+}
+
+async fn stream_to_replica(
+    backend: &Backend,
+    stream: TcpStream,
+    read_buf: BytesMut,
+    client_id: u64,
+    snapshot: Option<Snapshot>,
+    mut commands: StreamReceiver,
+) -> Result<()> {
+    let (read, write) = stream.into_split();
+    let mut reader = FramedRead::new(read, RespFrameCodec::default());
+    reader.read_buffer_mut().extend_from_slice(&read_buf);
+    let mut writer = BufWriter::new(write);
+
+    if let Some(snapshot) = snapshot {
+        let checksum = backend.config().rdb_checksum();
+        let rdb = tokio::task::spawn_blocking(move || {
+            write_snapshot(&snapshot, Vec::new(), checksum, false, now_ms() / 1000)
+        })
+        .await??;
+        writer
+            .write_all(format!("${}\r\n", rdb.len()).as_bytes())
+            .await?;
+        writer.write_all(&rdb).await?;
+        writer.flush().await?;
+        backend.replication().set_online(client_id);
+        info!("Synchronization with replica succeeded");
+    }
+
+    loop {
+        tokio::select! {
+            data = commands.recv() => {
+                // the master dropped the link, e.g. to make the replica
+                // resync with a new history
+                let Some(data) = data else {
+                    return Ok(());
+                };
+                writer.write_all(&data).await?;
+                while let Some(data) = commands.try_recv() {
+                    writer.write_all(&data).await?;
+                }
+                writer.flush().await?;
+            }
+            frame = reader.next() => {
+                let Some(frame) = frame else {
+                    return Ok(());
+                };
+                if let Some(offset) = ack_offset(&frame?) {
+                    backend.replication().set_ack(client_id, offset);
+                }
+            }
+        }
+    }
+}
+
+/// The offset of a `REPLCONF ACK <offset>` sent by a replica.
+fn ack_offset(frame: &RespFrame) -> Option<i64> {
+    let RespFrame::Array(RespArray(Some(frames))) = frame else {
+        return None;
+    };
+    let args = frames
+        .iter()
+        .map(|frame| match frame {
+            RespFrame::BulkString(BulkString(Some(arg))) => Some(arg.as_ref()),
+            _ => None,
+        })
+        .collect::<Option<Vec<&[u8]>>>()?;
+    match args.as_slice() {
+        [cmd, sub, offset, ..]
+            if cmd.eq_ignore_ascii_case(b"replconf") && sub.eq_ignore_ascii_case(b"ack") =>
+        {
+            std::str::from_utf8(offset).ok()?.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Send a `PING` down the replication stream every `PING_PERIOD` while
+/// replicas are attached, like Redis's `replicationCron`.
+pub async fn ping_cron(backend: Backend) {
+    let ping = RespFrame::from(RespArray::new(Some(vec![
+        BulkString::new(Some("PING")).into()
+    ])))
+    .encode();
+    let mut ticker = tokio::time::interval(PING_PERIOD);
+    loop {
+        ticker.tick().await;
+        let repl = backend.replication();
+        if repl.is_replica() || repl.state().replicas.is_empty() {
+            continue;
+        }
+        let (backlog_size, output_limit) = {
+            let config = backend.config();
+            (
+                config.repl_backlog_size(),
+                config.replica_output_buffer_limit(),
+            )
+        };
+        let _guard = backend.write_guard();
+        repl.feed(&ping, backlog_size, output_limit);
+    }
+}
+
+/// Make this server a replica of `host:port`, or a master again with
+/// `None`, as `REPLICAOF` does. Returns false when it already replicates
+/// that master.
+pub fn set_master(backend: &Backend, master: Option<(String, u16)>) -> bool {
+    let backlog_size = backend.config().repl_backlog_size();
+    let mut state = backend.replication().state();
+    if let (Some(current), Some((host, port))) = (&state.master, &master) {
+        if current.host == *host && current.port == *port {
+            return false;
+        }
+    }
+    if let Some(old) = state.master.take() {
+        old.task.abort();
+    } else if master.is_none() {
+        return true;
+    }
+    backend
+        .config_mut()
+        .set_replicaof(master.as_ref().map(|(host, port)| (host.as_str(), *port)));
+    match master {
+        Some((host, port)) => {
+            info!("Connecting to MASTER {}:{}", host, port);
+            // replicas of this server must follow the new master's history
+            state.disconnect_replicas();
+            let task = tokio::spawn(replica::run(backend.clone(), host.clone(), port));
+            state.master = Some(MasterLink {
+                host,
+                port,
+                state: LinkState::Connect,
+                last_io: Instant::now(),
+                task: task.abort_handle(),
+            });
+        }
+        None => {
+            state.shift_replid();
+            backend
+                .replication()
+                .start_backlog(&mut state, backlog_size);
+            info!("MASTER MODE enabled");
+        }
+    }
+    true
+}
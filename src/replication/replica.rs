@@ -0,0 +1,316 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use bytes::{Bytes, BytesMut};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tracing::{info, warn};
+
+use super::{LinkState, NO_REPLID};
+use crate::{
+    aof,
+    cmd::{command_spec, Command, CommandExecutor},
+    rdb, Backend, BulkString, RespArray, RespDecoder, RespEncode, RespFrame,
+};
+
+/// Wait between attempts to reach the master.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// How often the replica reports its offset with `REPLCONF ACK`.
+const ACK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Keep this server in sync with `host:port` until the task is aborted by
+/// `REPLICAOF`, reconnecting whenever the link drops.
+pub(super) async fn run(backend: Backend, host: String, port: u16) {
+    loop {
+        if let Err(e) = sync_with_master(&backend, &host, port).await {
+            warn!("Lost the link with MASTER {}:{}: {}", host, port, e);
+        }
+        set_link_state(&backend, LinkState::Connect);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+fn set_link_state(backend: &Backend, link_state: LinkState) {
+    let mut state = backend.replication().state();
+    if let Some(master) = state.master.as_mut() {
+        master.state = link_state;
+        master.last_io = Instant::now();
+    }
+}
+
+/// The connection to the master. Replies are read line by line since the
+/// snapshot that follows `FULLRESYNC` is not a RESP frame.
+struct MasterConn {
+    stream: TcpStream,
+    buf: BytesMut,
+}
+
+impl MasterConn {
+    async fn fill(&mut self) -> Result<()> {
+        if self.stream.read_buf(&mut self.buf).await? == 0 {
+            bail!("connection closed by the master");
+        }
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        loop {
+            if let Some(newline) = self.buf.iter().position(|b| *b == b'\n') {
+                let line = self.buf.split_to(newline + 1);
+                let line = line.strip_suffix(b"\n").unwrap_or(&line);
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                return Ok(String::from_utf8_lossy(line).into_owned());
+            }
+            self.fill().await?;
+        }
+    }
+
+    async fn read_exact(&mut self, len: usize) -> Result<Bytes> {
+        while self.buf.len() < len {
+            self.buf.reserve(len - self.buf.len());
+            self.fill().await?;
+        }
+        Ok(self.buf.split_to(len).freeze())
+    }
+
+    async fn send(&mut self, args: &[&str]) -> Result<()> {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<RespFrame>>();
+        let request = RespFrame::from(RespArray::new(Some(frames)));
+        self.stream.write_all(&request.encode()).await?;
+        Ok(())
+    }
+
+    async fn command(&mut self, args: &[&str]) -> Result<String> {
+        self.send(args).await?;
+        self.read_line().await
+    }
+
+    /// The snapshot sent after `FULLRESYNC`, as `$<len>\r\n<rdb>`. The
+    /// master may send bare newlines while it prepares it.
+    async fn read_snapshot(&mut self) -> Result<Bytes> {
+        loop {
+            let line = self.read_line().await?;
+            if line.is_empty() {
+                continue;
+            }
+            let len = line
+                .strip_prefix('$')
+                .and_then(|len| len.parse().ok())
+                .ok_or_else(|| anyhow!("bad protocol from MASTER: '{}'", line))?;
+            return self.read_exact(len).await;
+        }
+    }
+}
+
+async fn sync_with_master(backend: &Backend, host: &str, port: u16) -> Result<()> {
+    set_link_state(backend, LinkState::Connecting);
+    let stream = TcpStream::connect((host, port)).await?;
+    info!("MASTER <-> REPLICA sync started");
+    let mut conn = MasterConn {
+        stream,
+        buf: BytesMut::new(),
+    };
+
+    set_link_state(backend, LinkState::Handshake);
+    let reply = conn.command(&["PING"]).await?;
+    if !reply.starts_with('+') {
+        bail!("error reply to PING from master: '{}'", reply);
+    }
+    let listening_port = backend.config().port().to_string();
+    for args in [
+        &["REPLCONF", "listening-port", listening_port.as_str()][..],
+        &["REPLCONF", "capa", "psync2"],
+    ] {
+        let reply = conn.command(args).await?;
+        if !reply.starts_with('+') {
+            info!(
+                "(Non critical) Master does not understand {}: {}",
+                args[1], reply
+            );
+        }
+    }
+
+    let (replid, offset) = {
+        let state = backend.replication().state();
+        (state.replid.clone(), state.offset)
+    };
+    let next = (offset + 1).to_string();
+    let reply = conn.command(&["PSYNC", &replid, &next]).await?;
+    let mut words = reply.split_whitespace();
+    match words.next() {
+        Some("+FULLRESYNC") => {
+            let (Some(replid), Some(Ok(offset))) = (words.next(), words.next().map(str::parse))
+            else {
+                bail!("bad FULLRESYNC reply: '{}'", reply);
+            };
+            info!("Full resync from master: {}:{}", replid, offset);
+            set_link_state(backend, LinkState::Sync);
+            let payload = conn.read_snapshot().await?;
+            info!(
+                "MASTER <-> REPLICA sync: receiving {} bytes from master",
+                payload.len()
+            );
+            load_snapshot(backend, payload).await?;
+            synced(backend, replid.to_string(), offset);
+        }
+        Some("+CONTINUE") => {
+            continued(backend, words.next());
+            info!("Successful partial resynchronization with master.");
+        }
+        _ => bail!("unexpected reply to PSYNC from master: '{}'", reply),
+    }
+
+    set_link_state(backend, LinkState::Connected);
+    info!("MASTER <-> REPLICA sync: Finished with success");
+    apply_stream(backend, &mut conn).await
+}
+
+/// Replace the dataset with the master's snapshot.
+async fn load_snapshot(backend: &Backend, payload: Bytes) -> Result<()> {
+    let cloned = backend.clone();
+    tokio::task::spawn_blocking(move || {
+        info!("MASTER <-> REPLICA sync: Flushing old data");
+        cloned.clear();
+        info!("MASTER <-> REPLICA sync: Loading DB in memory");
+        rdb::load_from(&cloned, payload.as_ref())
+    })
+    .await??;
+    // the AOF describes the old dataset, so it starts over from this one
+    if backend.aof().enabled() {
+        if let Err(e) = aof::bgrewrite(backend) {
+            warn!("Can't rewrite the append only file after the sync: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Adopt the master's history after a full sync. Replicas of this server
+/// were following the old one, so they are dropped and resync.
+fn synced(backend: &Backend, replid: String, offset: i64) {
+    let backlog_size = backend.config().repl_backlog_size();
+    let mut state = backend.replication().state();
+    state.replid = replid;
+    state.replid2 = NO_REPLID.to_string();
+    state.second_replid_offset = -1;
+    state.offset = offset;
+    state.backlog = None;
+    backend
+        .replication()
+        .start_backlog(&mut state, backlog_size);
+    state.disconnect_replicas();
+}
+
+/// After a partial resync the master may report a new replication ID, e.g.
+/// because it was promoted; the old one stays valid up to our offset.
+fn continued(backend: &Backend, replid: Option<&str>) {
+    let backlog_size = backend.config().repl_backlog_size();
+    let mut state = backend.replication().state();
+    backend
+        .replication()
+        .start_backlog(&mut state, backlog_size);
+    let Some(replid) = replid.filter(|replid| *replid != state.replid) else {
+        return;
+    };
+    state.replid2 = std::mem::replace(&mut state.replid, replid.to_string());
+    state.second_replid_offset = state.offset + 1;
+    info!("Master replication ID changed to {}", state.replid);
+    state.disconnect_replicas();
+}
+
+/// Execute the commands the master propagates, acknowledging the offset
+/// once per second.
+async fn apply_stream(backend: &Backend, conn: &mut MasterConn) -> Result<()> {
+    let mut decoder = RespDecoder::new();
+    let mut ack = tokio::time::interval(ACK_PERIOD);
+    loop {
+        while let Some((frame, data)) = decoder.decode_raw(&mut conn.buf)? {
+            // like Redis, the ACK covers everything before the GETACK
+            if is_getack(&frame) {
+                send_ack(backend, conn).await?;
+            }
+            apply(backend, frame, &data);
+        }
+        tokio::select! {
+            read = conn.stream.read_buf(&mut conn.buf) => {
+                if read? == 0 {
+                    bail!("connection closed by the master");
+                }
+                set_link_state(backend, LinkState::Connected);
+            }
+            _ = ack.tick() => send_ack(backend, conn).await?,
+        }
+    }
+}
+
+async fn send_ack(backend: &Backend, conn: &mut MasterConn) -> Result<()> {
+    let offset = backend.replication().offset().to_string();
+    conn.send(&["REPLCONF", "ACK", &offset]).await
+}
+
+/// Execute one command from the master and pass it on to the AOF and to
+/// this server's own replicas. Everything the master sends counts toward
+/// the offset, including pings that are not executed. `data` holds the
+/// bytes as the master sent them, so the offset advances by exactly what
+/// was read from the stream.
+fn apply(backend: &Backend, frame: RespFrame, data: &[u8]) {
+    let (fsync, backlog_size, output_limit) = {
+        let config = backend.config();
+        (
+            config.appendfsync(),
+            config.repl_backlog_size(),
+            config.replica_output_buffer_limit(),
+        )
+    };
+    let _guard = backend.write_guard();
+    let spec = command_spec(&frame);
+    if spec.is_some_and(|spec| spec.has_flag("write")) {
+        match Command::parse(frame.clone(), spec) {
+            Ok(cmd) => {
+                let propagated = cmd.propagated(frame);
+                cmd.execute(backend);
+                backend.aof().append(&propagated.encode(), fsync);
+            }
+            Err(e) => warn!("Can't execute a command from the master: {}", e),
+        }
+    }
+    backend.replication().feed(data, backlog_size, output_limit);
+}
+
+/// Whether the master asks for an acknowledgement with
+/// `REPLCONF GETACK *`.
+fn is_getack(frame: &RespFrame) -> bool {
+    let RespFrame::Array(RespArray(Some(frames))) = frame else {
+        return false;
+    };
+    matches!(
+        frames.as_slice(),
+        [RespFrame::BulkString(BulkString(Some(cmd))), RespFrame::BulkString(BulkString(Some(sub))), ..]
+            if cmd.eq_ignore_ascii_case(b"replconf") && sub.eq_ignore_ascii_case(b"getack")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{replication, Client};
+
+    use super::*;
+
+    #[test]
+    fn test_apply_counts_bytes_as_sent() {
+        let backend = Backend::new();
+        replication::psync(&backend, &Client::new(), "?", -1).unwrap();
+        let offset = backend.replication().offset();
+        // a header written with a leading zero re-encodes one byte shorter
+        let mut buf = BytesMut::from(&b"*03\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n"[..]);
+        let (frame, data) = RespDecoder::new().decode_raw(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.encode().len() + 1, data.len());
+        apply(&backend, frame, &data);
+        assert_eq!(backend.replication().offset(), offset + data.len() as i64);
+        assert!(backend.exists(b"k"));
+    }
+}
@@ -2,70 +2,33 @@ use std::ops::Deref;
 
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
-use super::{frame::RespFrame, parse_aggregate_length, CRLF_LEN, DEFAULT_FRAME_SIZE};
+use super::{frame::RespFrame, header_len, write_header};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct RespArray(pub(crate) Option<Vec<RespFrame>>);
 
 impl RespEncode for RespArray {
-    fn encode(self) -> Vec<u8> {
-        let array = self.0.map(|array| {
-            let len = array.len();
-            let mut result = Vec::with_capacity(len * DEFAULT_FRAME_SIZE);
-            result.extend_from_slice(format!("*{}\r\n", len).as_bytes());
-            array
-                .into_iter()
-                .for_each(|frame| result.extend_from_slice(&frame.encode()));
-            result
-        });
-        array.unwrap_or(b"*-1\r\n".to_vec())
-    }
-}
-impl RespDecode for RespArray {
-    const PREFIX: &'static str = "*";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Array(*), got {:?}",
-                buf
-            )));
-        }
-
-        let expect_length = RespArray::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-
-        let (end, frame_count) = parse_aggregate_length(&data, Self::PREFIX.as_bytes())?;
-        if frame_count < -1 {
-            return Err(RespError::InvalidFrameLength(frame_count));
+    fn encoded_len(&self) -> usize {
+        match &self.0 {
+            Some(array) => {
+                header_len(array.len()) + array.iter().map(|f| f.encoded_len()).sum::<usize>()
+            }
+            None => b"*-1\r\n".len(),
         }
-
-        if frame_count == -1 {
-            return Ok(RespArray::new(None::<Vec<_>>));
-        }
-        let mut frames = vec![];
-        let mut tmp_buf = BytesMut::from(&data[end + CRLF_LEN..]);
-        for _ in 0..frame_count {
-            frames.push(RespFrame::decode(&mut tmp_buf)?);
-        }
-        Ok(RespArray::new(Some(frames)))
     }
 
-    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let (end, length) = parse_aggregate_length(buf, Self::PREFIX.as_bytes())?;
-        if length < 0 {
-            return Ok(end + CRLF_LEN);
-        }
-        let mut cur_index = end + CRLF_LEN;
-        for _ in 0..length {
-            let length = RespFrame::expect_length(&buf[cur_index..])?;
-            cur_index += length;
+    fn encode_to(&self, buf: &mut BytesMut) {
+        match &self.0 {
+            Some(array) => {
+                write_header(buf, b'*', array.len());
+                array.iter().for_each(|frame| frame.encode_to(buf));
+            }
+            None => buf.extend_from_slice(b"*-1\r\n"),
         }
-        Ok(cur_index)
     }
 }
-
 impl RespArray {
     pub fn new(vec: Option<impl Into<Vec<RespFrame>>>) -> Self {
         RespArray(vec.map(|v| v.into()))
@@ -82,7 +45,7 @@ impl Deref for RespArray {
 
 #[cfg(test)]
 mod tests {
-    use crate::resp::bulk_string::BulkString;
+    use crate::{resp::bulk_string::BulkString, RespDecode};
 
     use super::*;
 
@@ -100,6 +63,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encode_to_nested_array() {
+        let array: RespFrame = RespArray::new(Some(vec![
+            RespArray::new(Some(vec![10.into(), (-1.5).into(), true.into()])).into(),
+            BulkString::new(None::<Vec<u8>>).into(),
+            RespArray::new(None::<Vec<_>>).into(),
+        ]))
+        .into();
+        let mut buf = BytesMut::from(&b"+OK\r\n"[..]);
+        array.encode_to(&mut buf);
+        assert_eq!(
+            &buf[..],
+            b"+OK\r\n*3\r\n*3\r\n:10\r\n,-1.5\r\n#t\r\n$-1\r\n*-1\r\n"
+        );
+        assert_eq!(array.encoded_len(), buf.len() - 5);
+    }
+
     #[test]
     fn test_decode_resp_array() {
         let mut buf = BytesMut::new();
@@ -0,0 +1,115 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+};
+
+use bytes::BytesMut;
+
+use crate::RespEncode;
+
+use super::{frame::RespFrame, header_len, simple_string::SimpleString, write_header};
+
+/// RESP3 attribute: `|<count>\r\n<key><value>...`, auxiliary data that a
+/// server sends ahead of a reply. It is never decoded as a frame or an
+/// aggregate element of its own: it carries the frame that follows it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RespAttribute {
+    attributes: BTreeMap<SimpleString, RespFrame>,
+    value: Option<Box<RespFrame>>,
+}
+
+impl RespEncode for RespAttribute {
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self
+                .iter()
+                .map(|(key, frame)| key.encoded_len() + frame.encoded_len())
+                .sum::<usize>()
+            + self.value.as_ref().map_or(0, |value| value.encoded_len())
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write_header(buf, b'|', self.len());
+        self.iter().for_each(|(key, frame)| {
+            key.encode_to(buf);
+            frame.encode_to(buf);
+        });
+        if let Some(value) = &self.value {
+            value.encode_to(buf);
+        }
+    }
+}
+
+impl RespAttribute {
+    pub fn new() -> Self {
+        RespAttribute {
+            attributes: BTreeMap::new(),
+            value: None,
+        }
+    }
+
+    /// Attach the attribute to the element it describes.
+    pub fn with_value(mut self, value: RespFrame) -> Self {
+        self.value = Some(Box::new(value));
+        self
+    }
+
+    pub fn value(&self) -> Option<&RespFrame> {
+        self.value.as_deref()
+    }
+
+    pub fn into_parts(self) -> (BTreeMap<SimpleString, RespFrame>, Option<RespFrame>) {
+        (self.attributes, self.value.map(|value| *value))
+    }
+}
+
+impl Default for RespAttribute {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for RespAttribute {
+    type Target = BTreeMap<SimpleString, RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.attributes
+    }
+}
+
+impl DerefMut for RespAttribute {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.attributes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RespArray, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_attribute() {
+        let mut attribute = RespAttribute::new();
+        attribute.insert(SimpleString::new("ttl"), 3600.into());
+        let frame: RespFrame = attribute.into();
+        assert_eq!(frame.encode(), b"|1\r\n+ttl\r\n:3600\r\n");
+
+        let mut attribute = RespAttribute::new();
+        attribute.insert(SimpleString::new("ttl"), 3600.into());
+        let frame: RespFrame =
+            RespArray::new(Some(vec![attribute.with_value(1.into()).into(), 2.into()])).into();
+        assert_eq!(frame.encode(), b"*2\r\n|1\r\n+ttl\r\n:3600\r\n:1\r\n:2\r\n");
+        assert_eq!(frame.encoded_len(), frame.encode().len());
+    }
+
+    #[test]
+    fn test_decode_attribute() {
+        let mut buf = BytesMut::from(&b"|1\r\n$14\r\nkey-popularity\r\n,0.5\r\n:1\r\n"[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        let mut attribute = RespAttribute::new();
+        attribute.insert(SimpleString::new("key-popularity"), 0.5.into());
+        assert_eq!(frame, RespFrame::Attribute(attribute.with_value(1.into())));
+    }
+}
@@ -0,0 +1,75 @@
+use std::ops::Deref;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::{RespEncode, RespError};
+
+use super::{CRLF, CRLF_LEN};
+
+/// RESP3 big number: `(<digits>\r\n`, an integer outside the `i64` range.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BigNumber(String);
+
+impl RespEncode for BigNumber {
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 1 + CRLF_LEN
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        buf.put_u8(b'(');
+        buf.extend_from_slice(self.0.as_bytes());
+        buf.extend_from_slice(CRLF);
+    }
+}
+
+impl BigNumber {
+    /// Validate `number` as an optionally signed run of decimal digits.
+    pub fn parse(number: impl Into<String>) -> Result<Self, RespError> {
+        let number = number.into();
+        let digits = number.strip_prefix('-').unwrap_or(&number);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RespError::InvalidFrameData(number));
+        }
+        Ok(BigNumber(number))
+    }
+}
+
+impl Deref for BigNumber {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resp::frame::RespFrame, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_big_number() {
+        let frame: RespFrame = BigNumber::parse("3492890328409238509324850943850943825024385")
+            .unwrap()
+            .into();
+        assert_eq!(
+            frame.encode(),
+            b"(3492890328409238509324850943850943825024385\r\n"
+        );
+    }
+
+    #[test]
+    fn test_decode_big_number() {
+        let mut buf = BytesMut::from(&b"(-12345678901234567890\r\n(12a\r\n"[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::BigNumber(BigNumber::parse("-12345678901234567890").unwrap())
+        );
+        assert_eq!(
+            RespFrame::decode(&mut buf).unwrap_err(),
+            RespError::InvalidFrameData("12a".to_string())
+        );
+    }
+}
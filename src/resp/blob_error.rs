@@ -0,0 +1,65 @@
+use std::ops::Deref;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::RespEncode;
+
+use super::{header_len, write_header, CRLF, CRLF_LEN};
+
+/// RESP3 blob error: `!<len>\r\n<bytes>\r\n`, a binary-safe error message.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlobError(Bytes);
+
+impl RespEncode for BlobError {
+    fn encoded_len(&self) -> usize {
+        header_len(self.0.len()) + self.0.len() + CRLF_LEN
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write_header(buf, b'!', self.0.len());
+        buf.extend_from_slice(&self.0);
+        buf.extend_from_slice(CRLF);
+    }
+}
+
+impl BlobError {
+    pub fn new(message: impl Into<Bytes>) -> Self {
+        BlobError(message.into())
+    }
+}
+
+impl Deref for BlobError {
+    type Target = Bytes;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resp::frame::RespFrame, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_blob_error() {
+        let frame: RespFrame = BlobError::new("SYNTAX invalid syntax").into();
+        assert_eq!(frame.encode(), b"!21\r\nSYNTAX invalid syntax\r\n");
+    }
+
+    #[test]
+    fn test_decode_blob_error() {
+        let mut buf = BytesMut::from(&b"!21\r\nSYNTAX invalid syntax\r\n!4\r\nE\r\n\xff\r\n"[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::BlobError(BlobError::new("SYNTAX invalid syntax"))
+        );
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::BlobError(BlobError::new(&b"E\r\n\xff"[..]))
+        );
+    }
+}
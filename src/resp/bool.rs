@@ -1,42 +1,21 @@
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
-
-use super::CRLF_LEN;
+use crate::RespEncode;
 
 impl RespEncode for bool {
-    fn encode(self) -> Vec<u8> {
-        let sign = if self { 't' } else { 'f' };
-        format!("#{}\r\n", sign).into_bytes()
+    fn encoded_len(&self) -> usize {
+        b"#t\r\n".len()
     }
-}
-
-impl RespDecode for bool {
-    const PREFIX: &'static str = "#";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Bool(#), got {:?}",
-                buf
-            )));
-        }
 
-        let expect_length = bool::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-        match String::from_utf8((&data[Self::PREFIX.len()..expect_length - CRLF_LEN]).into())?
-            .as_str()
-        {
-            "t" => Ok(true),
-            "f" => Ok(false),
-            val => Err(RespError::InvalidFrameData(val.to_string())),
-        }
+    fn encode_to(&self, buf: &mut BytesMut) {
+        buf.extend_from_slice(if *self { b"#t\r\n" } else { b"#f\r\n" });
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode, RespError};
 
     use super::*;
     #[test]
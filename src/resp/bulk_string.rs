@@ -1,77 +1,48 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
-use super::{parse_aggregate_length, CRLF_LEN, DEFAULT_FRAME_SIZE};
+use super::{header_len, write_header, CRLF, CRLF_LEN};
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct BulkString(pub(crate) Option<Vec<u8>>);
+pub struct BulkString(pub(crate) Option<Bytes>);
 
 impl RespEncode for BulkString {
-    fn encode(self) -> Vec<u8> {
-        let bulk = self.0.map(|bulk_string| {
-            let len = bulk_string.len();
-            let mut result = Vec::with_capacity(len + DEFAULT_FRAME_SIZE);
-            result.extend_from_slice(format!("${}\r\n", len).as_bytes());
-            result.extend_from_slice(&bulk_string);
-            result.extend_from_slice(b"\r\n");
-            result
-        });
-        bulk.unwrap_or(b"$-1\r\n".to_vec())
-    }
-}
-
-impl RespDecode for BulkString {
-    const PREFIX: &'static str = "$";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: BulkString($), got {:?}",
-                buf
-            )));
-        }
-
-        let expect_length = BulkString::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-
-        let (end, content_length) = parse_aggregate_length(&data, Self::PREFIX.as_bytes())?;
-        if content_length < -1 {
-            return Err(RespError::InvalidFrameLength(content_length));
-        }
-        if content_length == -1 {
-            return Ok(BulkString::new(None::<Vec<_>>));
+    fn encoded_len(&self) -> usize {
+        match &self.0 {
+            Some(bulk) => header_len(bulk.len()) + bulk.len() + CRLF_LEN,
+            None => b"$-1\r\n".len(),
         }
-
-        let content_begin = end + CRLF_LEN;
-        let active_length = content_begin + content_length as usize + CRLF_LEN;
-        if !data.ends_with(b"\r\n") || active_length != expect_length {
-            return Err(RespError::InvalidFrameData(format!("{:?}", data)));
-        }
-
-        Ok(BulkString::new(Some(
-            &data[content_begin..content_begin + content_length as usize],
-        )))
     }
 
-    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let (end, length) = parse_aggregate_length(buf, Self::PREFIX.as_bytes())?;
-        if length < 0 {
-            return Ok(end + CRLF_LEN);
+    fn encode_to(&self, buf: &mut BytesMut) {
+        match &self.0 {
+            Some(bulk) => {
+                write_header(buf, b'$', bulk.len());
+                buf.extend_from_slice(bulk);
+                buf.extend_from_slice(CRLF);
+            }
+            None => buf.extend_from_slice(b"$-1\r\n"),
         }
-        Ok(length as usize + CRLF_LEN * 2 + end)
     }
 }
 
 impl BulkString {
     pub fn new(vec: Option<impl Into<Vec<u8>>>) -> Self {
-        BulkString(vec.map(|v| v.into()))
+        BulkString(vec.map(|v| Bytes::from(v.into())))
+    }
+}
+
+impl From<Bytes> for BulkString {
+    fn from(bytes: Bytes) -> Self {
+        BulkString(Some(bytes))
     }
 }
 
 impl Deref for BulkString {
-    type Target = Option<Vec<u8>>;
+    type Target = Option<Bytes>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -81,7 +52,7 @@ impl Deref for BulkString {
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode};
 
     use super::*;
 
@@ -0,0 +1,728 @@
+use std::collections::BTreeMap;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{RespEncode, RespError};
+
+use super::{
+    array::RespArray, attribute::RespAttribute, big_number::BigNumber, blob_error::BlobError,
+    bulk_string::BulkString, frame::RespFrame, map::RespMap, null::RespNull, push::RespPush,
+    set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    verbatim_string::VerbatimString, CRLF, CRLF_LEN,
+};
+
+/// Incremental RESP2/RESP3 decoder.
+///
+/// The buffer is never consumed until a whole top-level frame is available,
+/// but everything already parsed (finished elements, open aggregates, the
+/// declared length of a bulk string whose body is still arriving, how far a
+/// line has been searched for `\r\n`) is kept between calls. Feeding a frame
+/// in many small chunks therefore costs time linear in its size.
+#[derive(Debug, Default)]
+pub struct RespDecoder {
+    limits: ProtocolLimits,
+    /// Offset of the first byte not yet parsed.
+    pos: usize,
+    /// Offset where the search for the current line's `\r\n` resumes.
+    scanned: usize,
+    /// Header of a blob type (`$`, `=`, `!`) whose body is still pending.
+    blob: Option<(u8, usize)>,
+    /// Aggregates that are still waiting for elements, innermost last.
+    stack: Vec<Partial>,
+    /// Top-level attributes read so far; they decorate the next frame.
+    attributes: Vec<Node>,
+}
+
+/// Bounds on what a peer may declare, so a hostile header cannot make the
+/// server allocate or recurse without limit. Exceeding any of them is a
+/// protocol error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolLimits {
+    /// `proto-max-bulk-len`: largest accepted bulk string payload.
+    pub max_bulk_len: usize,
+    /// `proto-max-multibulk-len`: largest element count accepted in an
+    /// aggregate header.
+    pub max_multibulk_len: usize,
+    /// `proto-max-nesting-depth`: deepest nesting of aggregates.
+    pub max_depth: usize,
+    /// `client-query-buffer-limit`: most bytes buffered for a single
+    /// incomplete frame.
+    pub max_query_buffer: usize,
+}
+
+impl Default for ProtocolLimits {
+    fn default() -> Self {
+        ProtocolLimits {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_multibulk_len: i32::MAX as usize,
+            max_depth: 128,
+            max_query_buffer: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Partial {
+    kind: u8,
+    remaining: usize,
+    children: Vec<Node>,
+    /// Attributes read since the last element; they belong to the next one.
+    attributes: Vec<Node>,
+}
+
+enum Step {
+    Done(Node),
+    /// A blob header or aggregate header was read; its contents come next.
+    Opened,
+    Incomplete,
+}
+
+/// A parsed element. Byte payloads are kept as offsets into the buffer and
+/// only turned into `Bytes` slices once the whole frame has been split off.
+#[derive(Debug)]
+enum Node {
+    Frame(RespFrame),
+    Blob {
+        kind: u8,
+        start: usize,
+        end: usize,
+    },
+    Aggregate {
+        kind: u8,
+        children: Vec<Node>,
+    },
+    Attributed {
+        attribute: Box<Node>,
+        value: Box<Node>,
+    },
+}
+
+impl RespDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: ProtocolLimits) -> Self {
+        RespDecoder {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn limits(&self) -> &ProtocolLimits {
+        &self.limits
+    }
+
+    /// Apply new limits, e.g. after `CONFIG SET`. A frame being parsed is
+    /// checked against them from now on.
+    pub fn set_limits(&mut self, limits: ProtocolLimits) {
+        self.limits = limits;
+    }
+
+    /// True when no frame is partially parsed.
+    pub fn is_idle(&self) -> bool {
+        self.pos == 0 && self.blob.is_none() && self.stack.is_empty() && self.attributes.is_empty()
+    }
+
+    /// Decode the next frame from `buf`, returning `None` when more bytes are
+    /// needed. Once a frame is complete its bytes are split off `buf`.
+    ///
+    /// Between calls `buf` may only grow at the end; on error the decoder
+    /// state is undefined and the connection should be dropped.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<RespFrame>, RespError> {
+        Ok(self.decode_raw(buf)?.map(|(frame, _)| frame))
+    }
+
+    /// Like [`decode`](Self::decode), but also return the bytes the frame
+    /// was read from, exactly as the peer sent them.
+    pub fn decode_raw(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<(RespFrame, Bytes)>, RespError> {
+        loop {
+            let node = match self.blob {
+                Some((kind, len)) => {
+                    let end = self.pos + len;
+                    if buf.len() < end + CRLF_LEN {
+                        return self.incomplete(buf);
+                    }
+                    if &buf[end..end + CRLF_LEN] != CRLF {
+                        return Err(protocol_error("expected CRLF after bulk data"));
+                    }
+                    self.blob = None;
+                    let start = self.pos;
+                    self.advance(end + CRLF_LEN);
+                    Node::Blob { kind, start, end }
+                }
+                None => match self.parse_line(buf)? {
+                    Step::Done(node) => node,
+                    Step::Opened => continue,
+                    Step::Incomplete => return self.incomplete(buf),
+                },
+            };
+
+            if let Some(frame) = self.push(node, buf)? {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    fn incomplete<T>(&self, buf: &[u8]) -> Result<Option<T>, RespError> {
+        if buf.len() > self.limits.max_query_buffer {
+            return Err(protocol_error("query buffer limit exceeded"));
+        }
+        Ok(None)
+    }
+
+    /// Parse one `<type><line>\r\n`.
+    fn parse_line(&mut self, buf: &[u8]) -> Result<Step, RespError> {
+        let Some(line_end) = self.find_crlf(buf) else {
+            return Ok(Step::Incomplete);
+        };
+        // an empty line has no type byte
+        if line_end <= self.pos {
+            return Err(protocol_error("missing frame type"));
+        }
+        let kind = buf[self.pos];
+        let start = self.pos + 1;
+        let line = &buf[start..line_end];
+        let next = line_end + CRLF_LEN;
+
+        let node = match kind {
+            b'+' | b'-' | b'(' => Node::Blob {
+                kind,
+                start,
+                end: line_end,
+            },
+            b':' => Node::Frame(parse_str(line)?.parse::<i64>()?.into()),
+            b',' => Node::Frame(parse_str(line)?.parse::<f64>()?.into()),
+            b'#' => match line {
+                b"t" => Node::Frame(true.into()),
+                b"f" => Node::Frame(false.into()),
+                _ => {
+                    return Err(RespError::InvalidFrameData(
+                        String::from_utf8_lossy(line).into_owned(),
+                    ))
+                }
+            },
+            b'_' => {
+                if !line.is_empty() {
+                    return Err(RespError::InvalidFrameData(format!(
+                        "{:?}",
+                        BytesMut::from(&buf[self.pos..next])
+                    )));
+                }
+                Node::Frame(RespNull.into())
+            }
+            b'$' | b'=' | b'!' => {
+                let len = parse_len(line).ok_or_else(|| protocol_error("invalid bulk length"))?;
+                if kind == b'$' && len == -1 {
+                    Node::Frame(BulkString(None).into())
+                } else if kind == b'=' && (0..4).contains(&len) {
+                    return Err(RespError::InvalidFrameLength(len));
+                } else if len < 0 || len as usize > self.limits.max_bulk_len {
+                    return Err(protocol_error("invalid bulk length"));
+                } else {
+                    self.blob = Some((kind, len as usize));
+                    self.advance(next);
+                    return Ok(Step::Opened);
+                }
+            }
+            b'*' | b'%' | b'~' | b'>' | b'|' => {
+                let len =
+                    parse_len(line).ok_or_else(|| protocol_error("invalid multibulk length"))?;
+                if kind == b'*' && len == -1 {
+                    Node::Frame(RespArray(None).into())
+                } else if len < 0 || len as usize > self.limits.max_multibulk_len {
+                    return Err(protocol_error("invalid multibulk length"));
+                } else if len == 0 {
+                    Node::Aggregate {
+                        kind,
+                        children: Vec::new(),
+                    }
+                } else {
+                    if self.stack.len() >= self.limits.max_depth {
+                        return Err(protocol_error("too many nested aggregates"));
+                    }
+                    let remaining = match kind {
+                        b'%' | b'|' => len as usize * 2,
+                        _ => len as usize,
+                    };
+                    self.stack.push(Partial {
+                        kind,
+                        remaining,
+                        children: Vec::with_capacity(remaining.min(1024)),
+                        attributes: Vec::new(),
+                    });
+                    self.advance(next);
+                    return Ok(Step::Opened);
+                }
+            }
+            val => {
+                return Err(RespError::ProtocolError(format!(
+                    "unknown frame type '{}'",
+                    val.escape_ascii()
+                )))
+            }
+        };
+        self.advance(next);
+        Ok(Step::Done(node))
+    }
+
+    /// Attach a finished element to its parent, closing every aggregate it
+    /// completes. Returns the frame and its bytes once the top-level element
+    /// is done.
+    fn push(
+        &mut self,
+        mut node: Node,
+        buf: &mut BytesMut,
+    ) -> Result<Option<(RespFrame, Bytes)>, RespError> {
+        loop {
+            let Some(parent) = self.stack.last_mut() else {
+                // an attribute isn't a reply of its own but decorates the
+                // next one
+                if let Node::Aggregate { kind: b'|', .. } = node {
+                    self.attributes.push(node);
+                    return Ok(None);
+                }
+                let node = attach(std::mem::take(&mut self.attributes), node);
+                let frame = buf.split_to(self.pos).freeze();
+                *self = RespDecoder::with_limits(self.limits);
+                return Ok(Some((into_frame(node, &frame)?, frame)));
+            };
+            // an attribute isn't counted as an element of its parent
+            if let Node::Aggregate { kind: b'|', .. } = node {
+                parent.attributes.push(node);
+                return Ok(None);
+            }
+            node = attach(std::mem::take(&mut parent.attributes), node);
+            parent.children.push(node);
+            parent.remaining -= 1;
+            if parent.remaining > 0 {
+                return Ok(None);
+            }
+            let parent = self.stack.pop().expect("stack is not empty");
+            node = Node::Aggregate {
+                kind: parent.kind,
+                children: parent.children,
+            };
+        }
+    }
+
+    /// Find the `\r\n` ending the line at `pos`, resuming where the previous
+    /// call stopped.
+    fn find_crlf(&mut self, buf: &[u8]) -> Option<usize> {
+        let from = self.scanned.max(self.pos);
+        match buf[from..].windows(CRLF_LEN).position(|w| w == CRLF) {
+            Some(offset) => Some(from + offset),
+            None => {
+                // a trailing '\r' may be completed by the next read
+                self.scanned = buf.len().saturating_sub(1).max(self.pos);
+                None
+            }
+        }
+    }
+
+    fn advance(&mut self, pos: usize) {
+        self.pos = pos;
+        self.scanned = pos;
+    }
+}
+
+/// Wrap `node` in the attributes read before it, the first one outermost.
+fn attach(mut attributes: Vec<Node>, mut node: Node) -> Node {
+    while let Some(attribute) = attributes.pop() {
+        node = Node::Attributed {
+            attribute: Box::new(attribute),
+            value: Box::new(node),
+        };
+    }
+    node
+}
+
+fn protocol_error(reason: &str) -> RespError {
+    RespError::ProtocolError(reason.to_string())
+}
+
+fn parse_len(line: &[u8]) -> Option<isize> {
+    std::str::from_utf8(line).ok()?.parse().ok()
+}
+
+fn parse_str(line: &[u8]) -> Result<&str, RespError> {
+    std::str::from_utf8(line).map_err(|e| RespError::InvalidFrameData(e.to_string()))
+}
+
+fn into_frame(node: Node, buf: &Bytes) -> Result<RespFrame, RespError> {
+    match node {
+        Node::Frame(frame) => Ok(frame),
+        Node::Blob { kind, start, end } => {
+            let data = buf.slice(start..end);
+            Ok(match kind {
+                b'+' => SimpleString::new(data).into(),
+                b'-' => SimpleError::new(String::from_utf8(data.into())?).into(),
+                b'(' => BigNumber::parse(String::from_utf8(data.into())?)?.into(),
+                b'$' => BulkString::from(data).into(),
+                b'=' => VerbatimString::from_payload(data)?.into(),
+                _ => BlobError::new(data).into(),
+            })
+        }
+        Node::Aggregate { kind, children } => {
+            let mut frames = children.into_iter().map(|child| into_frame(child, buf));
+            Ok(match kind {
+                b'*' => RespArray::new(Some(frames.collect::<Result<Vec<_>, _>>()?)).into(),
+                b'>' => RespPush::new(frames.collect::<Result<Vec<_>, _>>()?).into(),
+                b'~' => {
+                    // sets are deduplicated and ordered by their encoding
+                    let mut members = BTreeMap::new();
+                    for frame in frames {
+                        let frame = frame?;
+                        members.insert(frame.encode(), frame);
+                    }
+                    RespSet::new(members.into_values().collect::<Vec<_>>()).into()
+                }
+                b'%' => {
+                    let mut pairs = Vec::new();
+                    while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+                        pairs.push((key?, value?));
+                    }
+                    // keep simple-string keys as they came, so the map
+                    // encodes back to the same bytes
+                    let simple = !pairs.is_empty()
+                        && pairs
+                            .iter()
+                            .all(|(key, _)| matches!(key, RespFrame::SimpleString(_)));
+                    let mut map = if simple {
+                        RespMap::with_simple_keys()
+                    } else {
+                        RespMap::new()
+                    };
+                    for (key, value) in pairs {
+                        map.insert(SimpleString::from_key_frame(key)?, value);
+                    }
+                    map.into()
+                }
+                _ => into_attribute(frames)?.into(),
+            })
+        }
+        Node::Attributed { attribute, value } => {
+            let Node::Aggregate { children, .. } = *attribute else {
+                unreachable!("only attribute aggregates are attached to elements");
+            };
+            let frames = children.into_iter().map(|child| into_frame(child, buf));
+            Ok(into_attribute(frames)?
+                .with_value(into_frame(*value, buf)?)
+                .into())
+        }
+    }
+}
+
+fn into_attribute(
+    mut frames: impl Iterator<Item = Result<RespFrame, RespError>>,
+) -> Result<RespAttribute, RespError> {
+    let mut attribute = RespAttribute::new();
+    while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+        attribute.insert(SimpleString::from_key_frame(key?)?, value?);
+    }
+    Ok(attribute)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn decode(input: &[u8]) -> Result<Option<RespFrame>, RespError> {
+        RespDecoder::new().decode(&mut BytesMut::from(input))
+    }
+
+    /// Feed `input` in `chunk` sized reads, like a socket would.
+    fn decode_chunked(input: &[u8], chunk: usize) -> Vec<RespFrame> {
+        let mut decoder = RespDecoder::new();
+        let mut buf = BytesMut::new();
+        let mut frames = Vec::new();
+        for part in input.chunks(chunk) {
+            buf.extend_from_slice(part);
+            while let Some(frame) = decoder.decode(&mut buf).unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert!(buf.is_empty());
+        assert!(decoder.is_idle());
+        frames
+    }
+
+    #[test]
+    fn test_decode_bulk_string_binary() {
+        let frame = decode(b"$6\r\n\r\n\xff\x00\r\n\r\n").unwrap().unwrap();
+        assert_eq!(frame, BulkString::new(Some(b"\r\n\xff\x00\r\n")).into());
+        assert!(decode(b"$3\r\nhello\r\n").is_err());
+    }
+
+    #[test]
+    fn test_decode_resp3_types() {
+        let frame = decode(b"(3492890328409238509324850943850943825024385\r\n");
+        assert_eq!(
+            frame.unwrap().unwrap(),
+            BigNumber::parse("3492890328409238509324850943850943825024385")
+                .unwrap()
+                .into()
+        );
+
+        let frame = decode(b"!21\r\nSYNTAX invalid syntax\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame, BlobError::new("SYNTAX invalid syntax").into());
+
+        let frame = decode(b">2\r\n+pubsub\r\n:1\r\n").unwrap().unwrap();
+        assert_eq!(
+            frame,
+            RespPush::new(vec![SimpleString::new("pubsub").into(), 1.into()]).into()
+        );
+
+        // an attribute decorates the reply after it
+        assert_eq!(decode(b"|1\r\n+ttl\r\n:10\r\n"), Ok(None));
+        let input = b"|1\r\n+ttl\r\n:10\r\n+OK\r\n";
+        let frame = decode(input).unwrap().unwrap();
+        let mut attribute = RespAttribute::new();
+        attribute.insert(SimpleString::new("ttl"), 10.into());
+        let expected: RespFrame = attribute.with_value(SimpleString::new("OK").into()).into();
+        assert_eq!(frame, expected);
+        assert_eq!(frame.encode(), input);
+        assert_eq!(decode_chunked(input, 1), vec![expected]);
+    }
+
+    #[test]
+    fn test_decode_attribute_in_aggregate() {
+        let input = b"*2\r\n|1\r\n+key\r\n+val\r\n:1\r\n:2\r\n";
+        let mut buf = BytesMut::from(&input[..]);
+        let frame = RespDecoder::new().decode(&mut buf).unwrap().unwrap();
+        assert!(buf.is_empty());
+        let mut attribute = RespAttribute::new();
+        attribute.insert(SimpleString::new("key"), SimpleString::new("val").into());
+        let expected: RespFrame =
+            RespArray::new(Some(vec![attribute.with_value(1.into()).into(), 2.into()])).into();
+        assert_eq!(frame, expected);
+        assert_eq!(frame.encode(), input);
+        assert_eq!(decode_chunked(input, 1), vec![expected]);
+
+        // consecutive attributes all wait for the next element
+        let frame = decode(b"*1\r\n|0\r\n|0\r\n:1\r\n").unwrap().unwrap();
+        assert_eq!(
+            frame,
+            RespArray::new(Some(vec![RespAttribute::new()
+                .with_value(RespAttribute::new().with_value(1.into()).into())
+                .into()]))
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_decode_redis7_hello_reply() {
+        let input =
+            b"%3\r\n$6\r\nserver\r\n$5\r\nredis\r\n$5\r\nproto\r\n:3\r\n$7\r\nmodules\r\n*0\r\n";
+        let mut map = RespMap::new();
+        map.insert(
+            SimpleString::new("server"),
+            BulkString::new(Some("redis")).into(),
+        );
+        map.insert(SimpleString::new("proto"), 3.into());
+        map.insert(
+            SimpleString::new("modules"),
+            RespArray::new(Some(vec![])).into(),
+        );
+        assert_eq!(decode(input).unwrap().unwrap(), map.into());
+    }
+
+    #[test]
+    fn test_decode_incomplete_leaves_buffer() {
+        let mut decoder = RespDecoder::new();
+        let mut buf = BytesMut::from(&b"*2\r\n$3\r\nget\r\n$3\r\nke"[..]);
+        assert_eq!(decoder.decode(&mut buf), Ok(None));
+        assert_eq!(buf.len(), 19);
+        assert!(!decoder.is_idle());
+
+        buf.extend_from_slice(b"y\r\n+OK");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap().unwrap(),
+            RespArray::new(Some(vec![
+                BulkString::new(Some("get")).into(),
+                BulkString::new(Some("key")).into(),
+            ]))
+            .into()
+        );
+        assert_eq!(&buf[..], b"+OK");
+        assert_eq!(decoder.decode(&mut buf), Ok(None));
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let input = b"*3\r\n%1\r\n+a\r\n~2\r\n#t\r\n_\r\n$-1\r\n=6\r\ntxt:hi\r\n:-7\r\n,1.5\r\n";
+        let frames = decode_chunked(input, 1);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], decode(input).unwrap().unwrap());
+        assert_eq!(frames[1..], [(-7).into(), 1.5.into()]);
+    }
+
+    #[test]
+    fn test_decode_large_frame_in_small_chunks() {
+        let value = vec![b'x'; 1024 * 1024];
+        let frame: RespFrame = RespArray::new(Some(vec![
+            BulkString::new(Some("set")).into(),
+            BulkString::new(Some("key")).into(),
+            BulkString::new(Some(value)).into(),
+        ]))
+        .into();
+        assert_eq!(decode_chunked(&frame.encode(), 1460), vec![frame]);
+    }
+
+    #[test]
+    fn test_decode_empty_line() {
+        assert_eq!(
+            decode(b"*1\r\n\r\n"),
+            Err(protocol_error("missing frame type"))
+        );
+        assert_eq!(decode(b"\r\n"), Err(protocol_error("missing frame type")));
+    }
+
+    fn decode_with(limits: ProtocolLimits, input: &[u8]) -> Result<Option<RespFrame>, RespError> {
+        RespDecoder::with_limits(limits).decode(&mut BytesMut::from(input))
+    }
+
+    #[test]
+    fn test_huge_multibulk_length() {
+        assert_eq!(
+            decode(b"*2147483648\r\n"),
+            Err(protocol_error("invalid multibulk length"))
+        );
+        // within the limit the header alone must not preallocate
+        assert_eq!(decode(b"*2147483647\r\n"), Ok(None));
+
+        let limits = ProtocolLimits {
+            max_multibulk_len: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            decode_with(limits, b"*4\r\n"),
+            Err(protocol_error("invalid multibulk length"))
+        );
+        assert_eq!(
+            decode_with(limits, b"%2\r\n"),
+            Ok(None),
+            "map length counts pairs"
+        );
+        assert_eq!(
+            decode_with(limits, b"*1\r\n~4\r\n"),
+            Err(protocol_error("invalid multibulk length"))
+        );
+    }
+
+    #[test]
+    fn test_huge_bulk_length() {
+        assert_eq!(
+            decode(b"$536870913\r\n"),
+            Err(protocol_error("invalid bulk length"))
+        );
+        assert_eq!(decode(b"$536870912\r\n"), Ok(None));
+
+        let limits = ProtocolLimits {
+            max_bulk_len: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            decode_with(limits, b"*2\r\n$4\r\nPING\r\n$5\r\n"),
+            Err(protocol_error("invalid bulk length"))
+        );
+        assert_eq!(
+            decode_with(limits, b"=5\r\n"),
+            Err(protocol_error("invalid bulk length"))
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let limits = ProtocolLimits {
+            max_depth: 4,
+            ..Default::default()
+        };
+        assert!(decode_with(limits, b"*1\r\n*1\r\n*1\r\n*1\r\n:1\r\n")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            decode_with(limits, b"*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n"),
+            Err(protocol_error("too many nested aggregates"))
+        );
+
+        let nested = b"*1\r\n".repeat(100_000);
+        assert_eq!(
+            decode(&nested),
+            Err(protocol_error("too many nested aggregates"))
+        );
+    }
+
+    #[test]
+    fn test_query_buffer_limit() {
+        let limits = ProtocolLimits {
+            max_query_buffer: 16,
+            ..Default::default()
+        };
+        // an unterminated header line
+        let mut decoder = RespDecoder::with_limits(limits);
+        let mut buf = BytesMut::from(&b"+aaaaaaaa"[..]);
+        assert_eq!(decoder.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(b"aaaaaaaaaa");
+        assert_eq!(
+            decoder.decode(&mut buf),
+            Err(protocol_error("query buffer limit exceeded"))
+        );
+
+        // a bulk body that keeps arriving
+        let mut decoder = RespDecoder::with_limits(limits);
+        let mut buf = BytesMut::from(&b"$100\r\n"[..]);
+        assert_eq!(decoder.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(&[b'x'; 16]);
+        assert_eq!(
+            decoder.decode(&mut buf),
+            Err(protocol_error("query buffer limit exceeded"))
+        );
+
+        // complete frames are fine even when the buffer holds several
+        let mut decoder = RespDecoder::with_limits(limits);
+        let mut buf = BytesMut::from(&b":1\r\n".repeat(10)[..]);
+        assert_eq!(decoder.decode(&mut buf), Ok(Some(1.into())));
+    }
+
+    proptest! {
+        #[test]
+        fn bulk_string_round_trip(payload in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let frame: RespFrame = BulkString::new(Some(payload)).into();
+            prop_assert_eq!(decode(&frame.encode()).unwrap(), Some(frame));
+        }
+
+        #[test]
+        fn array_of_bulk_strings_round_trip(
+            payloads in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..64), 0..16),
+            chunk in 1usize..64,
+        ) {
+            let frames = payloads
+                .into_iter()
+                .map(|p| BulkString::new(Some(p)).into())
+                .collect::<Vec<RespFrame>>();
+            let frame: RespFrame = RespArray::new(Some(frames)).into();
+            prop_assert_eq!(frame.encoded_len(), frame.encode().len());
+            prop_assert_eq!(decode_chunked(&frame.encode(), chunk), vec![frame]);
+        }
+
+        #[test]
+        fn truncated_bulk_string_is_incomplete(
+            payload in proptest::collection::vec(any::<u8>(), 1..128),
+            cut in 1usize..128,
+        ) {
+            let encoded = RespFrame::from(BulkString::new(Some(payload))).encode();
+            let cut = cut.min(encoded.len() - 1);
+            let mut buf = BytesMut::from(&encoded[..cut]);
+            prop_assert_eq!(RespDecoder::new().decode(&mut buf), Ok(None));
+            prop_assert_eq!(buf.len(), cut);
+        }
+    }
+}
@@ -1,45 +1,42 @@
+use std::fmt::{self, Write};
+
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
-use super::{CRLF_LEN, DEFAULT_FRAME_SIZE};
+use super::fmt_len;
 
 impl RespEncode for f64 {
-    fn encode(self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(DEFAULT_FRAME_SIZE);
-        let ret = if self.abs() > 1e+8 || self.abs() < 1e-8 {
-            format!(",{:e}\r\n", self)
-        } else {
-            format!(",{}\r\n", self)
-        };
-        result.extend_from_slice(ret.as_bytes());
-        result
+    fn encoded_len(&self) -> usize {
+        fmt_len(format_args!(",{}\r\n", DoubleDisplay(*self)))
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write!(buf, ",{}\r\n", DoubleDisplay(*self)).expect("write to BytesMut never fails");
     }
 }
 
-impl RespDecode for f64 {
-    const PREFIX: &'static str = ",";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Double(,), got {:?}",
-                buf
-            )));
-        }
+/// Very large or small values use exponent notation.
+struct DoubleDisplay(f64);
 
-        let expect_length = f64::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-        Ok(
-            String::from_utf8((&data[Self::PREFIX.len()..expect_length - CRLF_LEN]).into())?
-                .parse()?,
-        )
+impl fmt::Display for DoubleDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.abs() > 1e+8 || self.0.abs() < 1e-8 {
+            write!(f, "{:e}", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
     }
 }
 
+pub(crate) fn format_double(value: f64) -> String {
+    DoubleDisplay(value).to_string()
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode};
 
     use super::*;
     #[test]
@@ -1,12 +1,10 @@
-use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
-use tracing::info;
-
-use crate::{RespDecode, RespError};
 
+use super::double::format_double;
 use super::{
-    array::RespArray, bulk_string::BulkString, map::RespMap, null::RespNull, set::RespSet,
-    simple_error::SimpleError, simple_string::SimpleString,
+    array::RespArray, attribute::RespAttribute, big_number::BigNumber, blob_error::BlobError,
+    bulk_string::BulkString, map::RespMap, null::RespNull, push::RespPush, set::RespSet,
+    simple_error::SimpleError, simple_string::SimpleString, verbatim_string::VerbatimString,
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -22,49 +20,108 @@ pub enum RespFrame {
     Double(f64),
     Map(RespMap),
     Set(RespSet),
+    VerbatimString(VerbatimString),
+    BigNumber(BigNumber),
+    BlobError(BlobError),
+    Push(RespPush),
+    Attribute(RespAttribute),
 }
 
-impl RespDecode for RespFrame {
-    const PREFIX: &'static str = "";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let mut iter = buf.iter().peekable();
-        info!("if iter peek: {:?}", iter.peek());
-        match iter.peek() {
-            Some(b'+') => Ok(SimpleString::decode(buf)?.into()),
-            Some(b'-') => Ok(SimpleError::decode(buf)?.into()),
-            Some(b':') => Ok(i64::decode(buf)?.into()),
-            Some(b'#') => Ok(bool::decode(buf)?.into()),
-            Some(b'$') => Ok(BulkString::decode(buf)?.into()),
-            Some(b'*') => Ok(RespArray::decode(buf)?.into()),
-            Some(b'_') => Ok(RespNull::decode(buf)?.into()),
-            Some(b',') => Ok(f64::decode(buf)?.into()),
-            Some(b'%') => Ok(RespMap::decode(buf)?.into()),
-            Some(b'~') => Ok(RespSet::decode(buf)?.into()),
-            Some(val) => Err(RespError::InvalidFrameType(format!(
-                "unknown frame type: {:?}",
-                val
-            ))),
-            None => Err(RespError::NotComplete),
+impl RespFrame {
+    /// Short lowercase name of the frame type, used in logs.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RespFrame::SimpleString(_) => "simple_string",
+            RespFrame::SimpleError(_) => "error",
+            RespFrame::Integer(_) => "integer",
+            RespFrame::BulkString(BulkString(None)) => "null_bulk_string",
+            RespFrame::BulkString(_) => "bulk_string",
+            RespFrame::Array(RespArray(None)) => "null_array",
+            RespFrame::Array(_) => "array",
+            RespFrame::Null(_) => "null",
+            RespFrame::Boolean(_) => "boolean",
+            RespFrame::Double(_) => "double",
+            RespFrame::Map(_) => "map",
+            RespFrame::Set(_) => "set",
+            RespFrame::VerbatimString(_) => "verbatim_string",
+            RespFrame::BigNumber(_) => "big_number",
+            RespFrame::BlobError(_) => "blob_error",
+            RespFrame::Push(_) => "push",
+            RespFrame::Attribute(_) => "attribute",
         }
     }
 
-    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let mut iter = buf.iter().peekable();
-        match iter.peek() {
-            Some(b'+') => SimpleString::expect_length(buf),
-            Some(b'-') => SimpleError::expect_length(buf),
-            Some(b':') => i64::expect_length(buf),
-            Some(b'#') => bool::expect_length(buf),
-            Some(b'$') => BulkString::expect_length(buf),
-            Some(b'*') => RespArray::expect_length(buf),
-            Some(b'_') => RespNull::expect_length(buf),
-            Some(b',') => f64::expect_length(buf),
-            Some(b'%') => RespMap::expect_length(buf),
-            Some(b'~') => RespSet::expect_length(buf),
-            _ => Err(RespError::InvalidFrameType(format!(
-                "unknown frame type: {:?}",
-                buf
-            ))),
+    /// Rewrite RESP3-only types into their RESP2 equivalents, the way Redis
+    /// replies to a client that has not negotiated protocol 3 with HELLO.
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Array(RespArray(Some(frames))) => {
+                RespArray::new(Some(into_resp2_vec(frames))).into()
+            }
+            RespFrame::Map(map) => flatten_map(map.iter()),
+            // RESP2 has no attributes, only the element they describe is kept
+            RespFrame::Attribute(attribute) => match attribute.into_parts() {
+                (_, Some(value)) => value.into_resp2(),
+                (attributes, None) => flatten_map(attributes.iter()),
+            },
+            RespFrame::Set(set) => RespArray::new(Some(into_resp2_vec(set.to_vec()))).into(),
+            RespFrame::Push(push) => RespArray::new(Some(into_resp2_vec(push.to_vec()))).into(),
+            RespFrame::Null(_) => BulkString(None).into(),
+            RespFrame::Boolean(b) => RespFrame::Integer(b as i64),
+            RespFrame::Double(d) => BulkString::new(Some(format_double(d))).into(),
+            RespFrame::VerbatimString(s) => BulkString::from(s.data).into(),
+            RespFrame::BigNumber(n) => BulkString::new(Some(n.as_str())).into(),
+            RespFrame::BlobError(e) => SimpleError::new(String::from_utf8_lossy(&e)).into(),
+            frame => frame,
         }
     }
 }
+
+fn into_resp2_vec(frames: Vec<RespFrame>) -> Vec<RespFrame> {
+    frames.into_iter().map(RespFrame::into_resp2).collect()
+}
+
+fn flatten_map<'a>(entries: impl Iterator<Item = (&'a SimpleString, &'a RespFrame)>) -> RespFrame {
+    let frames = entries
+        .flat_map(|(key, value)| {
+            [
+                BulkString::from((**key).clone()).into(),
+                value.clone().into_resp2(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    RespArray::new(Some(frames)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_resp2() {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new("proto"), 3.into());
+        map.insert(
+            SimpleString::new("flags"),
+            RespSet::new(vec![true.into(), RespNull.into()]).into(),
+        );
+        let frame: RespFrame = map.into();
+        assert_eq!(
+            frame.into_resp2(),
+            RespArray::new(Some(vec![
+                BulkString::new(Some("flags")).into(),
+                RespArray::new(Some(vec![1.into(), BulkString(None).into()])).into(),
+                BulkString::new(Some("proto")).into(),
+                3.into(),
+            ]))
+            .into()
+        );
+
+        let frame: RespFrame = 1.5.into();
+        assert_eq!(frame.into_resp2(), BulkString::new(Some("1.5")).into());
+        let frame: RespFrame = VerbatimString::new(*b"txt", "hi").into();
+        assert_eq!(frame.into_resp2(), BulkString::new(Some("hi")).into());
+        let frame: RespFrame = RespAttribute::new().with_value(true.into()).into();
+        assert_eq!(frame.into_resp2(), 1.into());
+    }
+}
@@ -1,37 +1,27 @@
+use std::fmt::Write;
+
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
-use super::CRLF_LEN;
+use super::fmt_len;
 
 impl RespEncode for i64 {
-    fn encode(self) -> Vec<u8> {
-        format!(":{}\r\n", self).into_bytes()
+    fn encoded_len(&self) -> usize {
+        fmt_len(format_args!(":{}\r\n", self))
     }
-}
 
-impl RespDecode for i64 {
-    const PREFIX: &'static str = ":";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Integer(:), got {:?}",
-                buf
-            )));
-        }
-
-        let expect_length = i64::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-        Ok(
-            String::from_utf8((&data[Self::PREFIX.len()..expect_length - CRLF_LEN]).into())?
-                .parse()?,
-        )
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write!(buf, ":{}\r\n", self).expect("write to BytesMut never fails");
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::resp::{frame::RespFrame, simple_string::SimpleString};
+    use crate::{
+        resp::{frame::RespFrame, simple_string::SimpleString},
+        RespDecode,
+    };
 
     use super::*;
 
@@ -5,77 +5,74 @@ use std::{
 
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
 use super::{
-    frame::RespFrame, parse_aggregate_length, simple_string::SimpleString, CRLF_LEN,
-    DEFAULT_FRAME_SIZE,
+    frame::RespFrame, header_len, simple_string::SimpleString, write_header, CRLF, CRLF_LEN,
 };
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct RespMap(BTreeMap<SimpleString, RespFrame>);
+#[derive(Debug, Clone)]
+pub struct RespMap {
+    entries: BTreeMap<SimpleString, RespFrame>,
+    /// Write the keys as bulk strings, as Redis does in the maps it builds
+    /// for command replies. Only maps decoded with simple-string keys are
+    /// written back that way.
+    bulk_keys: bool,
+}
 
-impl RespEncode for RespMap {
-    fn encode(self) -> Vec<u8> {
-        let len = self.len();
-        let mut result = Vec::with_capacity(len * DEFAULT_FRAME_SIZE);
-        result.extend_from_slice(format!("%{}\r\n", len).as_bytes());
-        self.0.into_iter().for_each(|(key, frame)| {
-            result.extend_from_slice(&key.encode());
-            result.extend_from_slice(&frame.encode());
-        });
-        result
+/// Maps are equal when their entries are, however the keys are written.
+impl PartialEq for RespMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
     }
 }
 
-impl RespDecode for RespMap {
-    const PREFIX: &'static str = "%";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Map(%), got {:?}",
-                buf
-            )));
-        }
-
-        let expect_length = RespMap::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
+impl RespEncode for RespMap {
+    fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self
+                .iter()
+                .map(|(key, frame)| self.key_len(key) + frame.encoded_len())
+                .sum::<usize>()
+    }
 
-        let (end, frame_count) = parse_aggregate_length(&data, Self::PREFIX.as_bytes())?;
-        if frame_count < 0 {
-            return Err(RespError::InvalidFrameLength(frame_count));
-        }
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write_header(buf, b'%', self.len());
+        self.iter().for_each(|(key, frame)| {
+            if self.bulk_keys {
+                write_header(buf, b'$', key.len());
+                buf.extend_from_slice(key);
+                buf.extend_from_slice(CRLF);
+            } else {
+                key.encode_to(buf);
+            }
+            frame.encode_to(buf);
+        });
+    }
+}
 
-        let mut map = RespMap::new();
-        let mut tmp_buf = BytesMut::from(&data[end + CRLF_LEN..]);
-        for _ in 0..frame_count {
-            map.insert(
-                SimpleString::decode(&mut tmp_buf)?,
-                RespFrame::decode(&mut tmp_buf)?,
-            );
+impl RespMap {
+    pub fn new() -> Self {
+        RespMap {
+            entries: BTreeMap::new(),
+            bulk_keys: true,
         }
-        Ok(map)
     }
 
-    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        let (end, length) = parse_aggregate_length(buf, Self::PREFIX.as_bytes())?;
-        if length <= 0 {
-            return Ok(end + CRLF_LEN);
-        }
-        let mut cur_index = end + CRLF_LEN;
-        for _ in 0..length {
-            let length = RespFrame::expect_length(&buf[cur_index..])?;
-            cur_index += length;
-            let length = RespFrame::expect_length(&buf[cur_index..])?;
-            cur_index += length;
+    /// A map whose keys are written as simple strings.
+    pub fn with_simple_keys() -> Self {
+        RespMap {
+            entries: BTreeMap::new(),
+            bulk_keys: false,
         }
-        Ok(cur_index)
     }
-}
 
-impl RespMap {
-    pub fn new() -> Self {
-        RespMap(BTreeMap::new())
+    fn key_len(&self, key: &SimpleString) -> usize {
+        if self.bulk_keys {
+            header_len(key.len()) + key.len() + CRLF_LEN
+        } else {
+            key.encoded_len()
+        }
     }
 }
 
@@ -83,7 +80,7 @@ impl Deref for RespMap {
     type Target = BTreeMap<SimpleString, RespFrame>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.entries
     }
 }
 
@@ -94,18 +91,18 @@ impl Default for RespMap {
 }
 impl DerefMut for RespMap {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.entries
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::resp::bulk_string::BulkString;
+    use crate::{resp::bulk_string::BulkString, RespDecode};
 
     use super::*;
     #[test]
     fn test_encode_map() {
-        let mut map = RespMap::new();
+        let mut map = RespMap::with_simple_keys();
         map.insert(
             SimpleString::new("hello"),
             SimpleString::new("world").into(),
@@ -121,6 +118,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encode_map_with_bulk_keys() {
+        let mut map = RespMap::new();
+        map.insert(SimpleString::new("foo"), 1.into());
+        let frame: RespFrame = map.clone().into();
+        assert_eq!(frame.encoded_len(), frame.encode().len());
+        assert_eq!(frame.encode(), b"%1\r\n$3\r\nfoo\r\n:1\r\n");
+
+        // the key encoding doesn't take part in equality
+        let mut simple = RespMap::with_simple_keys();
+        simple.insert(SimpleString::new("foo"), 1.into());
+        assert_eq!(simple, map);
+    }
+
+    #[test]
+    fn test_decode_resp_map_with_bulk_keys() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"%2\r\n$6\r\nserver\r\n$5\r\nredis\r\n$5\r\nproto\r\n:3\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
+
+        let mut map = RespMap::new();
+        map.insert(
+            SimpleString::new("server"),
+            BulkString::new(Some("redis")).into(),
+        );
+        map.insert(SimpleString::new("proto"), 3.into());
+        assert_eq!(frame, RespFrame::Map(map));
+    }
+
     #[test]
     fn test_decode_resp_map() {
         let mut buf = BytesMut::new();
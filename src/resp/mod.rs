@@ -1,42 +1,88 @@
 mod array;
+mod attribute;
+mod big_number;
+mod blob_error;
 mod bool;
 mod bulk_string;
+mod decoder;
 mod double;
 mod frame;
 mod integer;
 mod map;
 mod null;
+mod push;
 mod set;
 mod simple_error;
 mod simple_string;
+mod verbatim_string;
 
-use bytes::BytesMut;
+use std::fmt::{self, Write};
+
+use bytes::{BufMut, BytesMut};
 use enum_dispatch::enum_dispatch;
 use thiserror::Error;
 
 pub use self::{
-    array::RespArray, bulk_string::BulkString, frame::RespFrame, map::RespMap, null::RespNull,
-    set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    array::RespArray,
+    attribute::RespAttribute,
+    big_number::BigNumber,
+    blob_error::BlobError,
+    bulk_string::BulkString,
+    decoder::{ProtocolLimits, RespDecoder},
+    frame::RespFrame,
+    map::RespMap,
+    null::RespNull,
+    push::RespPush,
+    set::RespSet,
+    simple_error::SimpleError,
+    simple_string::SimpleString,
+    verbatim_string::VerbatimString,
 };
 
 pub use crate::CRLF;
 const CRLF_LEN: usize = CRLF.len();
-const AGGREGATE_FRAME_TYPE: [&[u8]; 4] = [b"$", b"*", b"%", b"~"];
-const DEFAULT_FRAME_SIZE: usize = 16;
+
+/// Protocol spoken on a connection, negotiated with `HELLO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RespVersion {
+    #[default]
+    Resp2,
+    Resp3,
+}
 
 #[enum_dispatch]
 pub trait RespEncode {
-    fn encode(self) -> Vec<u8>;
+    /// Exact number of bytes `encode_to` writes, so callers can reserve once.
+    fn encoded_len(&self) -> usize;
+
+    /// Write the frame straight into `buf` without intermediate allocations.
+    fn encode_to(&self, buf: &mut BytesMut);
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = BytesMut::with_capacity(self.encoded_len());
+        self.encode_to(&mut buf);
+        buf.freeze().into()
+    }
 }
 
+/// One-shot decoding of a complete frame at the start of `buf`. Network code
+/// keeps a [`RespDecoder`] per connection instead, so partial input is not
+/// re-parsed on every read.
 pub trait RespDecode: Sized {
-    const PREFIX: &'static str;
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError>;
+}
 
-    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
-        find_crlf(buf, 1)
-            .map(|end| end + CRLF_LEN)
-            .ok_or(RespError::NotComplete)
+impl<T> RespDecode for T
+where
+    RespFrame: TryInto<T>,
+{
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let frame = RespDecoder::new()
+            .decode(buf)?
+            .ok_or(RespError::NotComplete)?;
+        frame
+            .try_into()
+            .map_err(|_| RespError::InvalidFrameType("unexpected frame type".to_string()))
     }
 }
 
@@ -60,34 +106,30 @@ pub enum RespError {
     NotComplete,
     #[error("Parse error")]
     ParseError,
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
 }
 
-fn find_crlf(buf: &[u8], nth_crlf: usize) -> Option<usize> {
-    let mut cur_times = 0;
-    for i in 0..buf.len() - 1 {
-        if buf[i] == b'\r' && buf[i + 1] == b'\n' {
-            cur_times += 1;
-        }
-        if cur_times == nth_crlf {
-            return Some(i);
-        }
-    }
-    None
+/// Length of `<prefix><len>\r\n`.
+fn header_len(len: usize) -> usize {
+    1 + fmt_len(format_args!("{}", len)) + CRLF_LEN
 }
 
-fn parse_aggregate_length(buf: &[u8], prefix: &[u8]) -> Result<(usize, isize), RespError> {
-    if buf.len() < 3 {
-        return Err(RespError::NotComplete);
-    }
-    if !AGGREGATE_FRAME_TYPE.contains(&prefix) {
-        return Err(RespError::InvalidFrameType(format!(
-            "frame type {} is not a aggregate type",
-            String::from_utf8_lossy(prefix)
-        )));
+fn write_header(buf: &mut BytesMut, prefix: u8, len: usize) {
+    buf.put_u8(prefix);
+    write!(buf, "{}\r\n", len).expect("write to BytesMut never fails");
+}
+
+/// Count the bytes a formatted value takes without allocating.
+fn fmt_len(args: fmt::Arguments) -> usize {
+    struct Counter(usize);
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
     }
-    let end = find_crlf(buf, 1).ok_or(RespError::NotComplete)?;
-    Ok((
-        end,
-        String::from_utf8((&buf[prefix.len()..end]).into())?.parse()?,
-    ))
+    let mut counter = Counter(0);
+    counter.write_fmt(args).expect("counting never fails");
+    counter.0
 }
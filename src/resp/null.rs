@@ -1,33 +1,17 @@
 use bytes::BytesMut;
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct RespNull;
 
 impl RespEncode for RespNull {
-    fn encode(self) -> Vec<u8> {
-        b"_\r\n".to_vec()
+    fn encoded_len(&self) -> usize {
+        b"_\r\n".len()
     }
-}
-
-impl RespDecode for RespNull {
-    const PREFIX: &'static str = "_";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: Null(_), got {:?}",
-                buf
-            )));
-        }
 
-        let expect_length = RespNull::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-
-        if !data.ends_with(b"\r\n") || data.len() != 3 {
-            return Err(RespError::InvalidFrameData(format!("{:?}", data)));
-        }
-        Ok(RespNull::new())
+    fn encode_to(&self, buf: &mut BytesMut) {
+        buf.extend_from_slice(b"_\r\n");
     }
 }
 
@@ -46,7 +30,7 @@ impl Default for RespNull {
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode, RespError};
 
     use super::*;
 
@@ -0,0 +1,71 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use crate::RespEncode;
+
+use super::{frame::RespFrame, header_len, write_header};
+
+/// RESP3 push: `><count>\r\n...`, an out-of-band message such as a pub/sub
+/// delivery. The first element names the kind of push.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RespPush(Vec<RespFrame>);
+
+impl RespEncode for RespPush {
+    fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(|f| f.encoded_len()).sum::<usize>()
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write_header(buf, b'>', self.len());
+        self.iter().for_each(|frame| frame.encode_to(buf));
+    }
+}
+
+impl RespPush {
+    pub fn new(vec: impl Into<Vec<RespFrame>>) -> Self {
+        RespPush(vec.into())
+    }
+}
+
+impl Deref for RespPush {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resp::bulk_string::BulkString, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_push() {
+        let frame: RespFrame = RespPush::new(vec![
+            BulkString::new(Some("message")).into(),
+            BulkString::new(Some("ch")).into(),
+            BulkString::new(Some("hi")).into(),
+        ])
+        .into();
+        assert_eq!(
+            frame.encode(),
+            b">3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n"
+        );
+    }
+
+    #[test]
+    fn test_decode_push() {
+        let mut buf = BytesMut::from(&b">2\r\n$7\r\nmessage\r\n:1\r\n"[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::Push(RespPush::new(vec![
+                BulkString::new(Some("message")).into(),
+                1.into()
+            ]))
+        );
+    }
+}
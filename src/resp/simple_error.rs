@@ -1,42 +1,36 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::RespEncode;
 
-use super::CRLF_LEN;
+use super::{CRLF, CRLF_LEN};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct SimpleError(String);
 
 impl RespEncode for SimpleError {
-    fn encode(self) -> Vec<u8> {
-        format!("-{}\r\n", self.0).into_bytes()
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 1 + CRLF_LEN
     }
-}
-
-impl RespDecode for SimpleError {
-    const PREFIX: &'static str = "-";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: SimpleError(-), got {:?}",
-                buf
-            )));
-        }
-        let expect_length = SimpleError::expect_length(buf)?;
 
-        let data = buf.split_to(expect_length);
-        Ok(
-            String::from_utf8((&data[Self::PREFIX.len()..expect_length - CRLF_LEN]).into())
-                .map(SimpleError::new)?,
-        )
+    fn encode_to(&self, buf: &mut BytesMut) {
+        buf.put_u8(b'-');
+        buf.extend_from_slice(self.0.as_bytes());
+        buf.extend_from_slice(CRLF);
     }
 }
 
 impl SimpleError {
+    /// Line breaks are replaced by spaces, as Redis does, so an error that
+    /// quotes client input can't end the line and inject frames.
     pub fn new(str: impl Into<String>) -> Self {
-        SimpleError(str.into())
+        let str = str.into();
+        if str.contains(['\r', '\n']) {
+            SimpleError(str.replace(['\r', '\n'], " "))
+        } else {
+            SimpleError(str)
+        }
     }
 }
 
@@ -51,7 +45,7 @@ impl Deref for SimpleError {
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode};
 
     use super::*;
 
@@ -74,4 +68,14 @@ mod tests {
             RespFrame::SimpleError(SimpleError::new("ERROR error"))
         );
     }
+
+    #[test]
+    fn test_new_strips_line_breaks() {
+        let error = SimpleError::new("ERR unknown 'x\r\n:1\r\nz'");
+        assert_eq!(error.as_str(), "ERR unknown 'x  :1  z'");
+        assert_eq!(
+            RespFrame::from(error).encode(),
+            b"-ERR unknown 'x  :1  z'\r\n"
+        );
+    }
 }
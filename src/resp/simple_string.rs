@@ -1,47 +1,51 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
+use bytes::{BufMut, Bytes, BytesMut};
 
-use crate::{RespDecode, RespEncode, RespError};
+use crate::{BulkString, RespEncode, RespError, RespFrame};
 
-use super::CRLF_LEN;
+use super::{CRLF, CRLF_LEN};
 
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
-pub struct SimpleString(String);
+pub struct SimpleString(Bytes);
 
 impl RespEncode for SimpleString {
-    fn encode(self) -> Vec<u8> {
-        format!("+{}\r\n", self.0).into_bytes()
+    fn encoded_len(&self) -> usize {
+        self.0.len() + 1 + CRLF_LEN
     }
-}
-
-impl RespDecode for SimpleString {
-    const PREFIX: &'static str = "+";
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        if !buf.starts_with(Self::PREFIX.as_bytes()) {
-            return Err(RespError::InvalidFrameType(format!(
-                "expect: SimpleString(+), got {:?}",
-                buf
-            )));
-        }
 
-        let expect_length = SimpleString::expect_length(buf)?;
-        let data = buf.split_to(expect_length);
-        Ok(
-            String::from_utf8((&data[Self::PREFIX.len()..expect_length - CRLF_LEN]).into())
-                .map(SimpleString::new)?,
-        )
+    fn encode_to(&self, buf: &mut BytesMut) {
+        buf.put_u8(b'+');
+        buf.extend_from_slice(&self.0);
+        buf.extend_from_slice(CRLF);
     }
 }
 
 impl SimpleString {
-    pub fn new(str: impl Into<String>) -> Self {
+    pub fn new(str: impl Into<Bytes>) -> Self {
         SimpleString(str.into())
     }
 }
 
+impl SimpleString {
+    /// Map and attribute keys are stored as simple strings; real servers send
+    /// them as bulk strings, so accept any string-like frame (or an integer).
+    pub fn from_key_frame(frame: RespFrame) -> Result<Self, RespError> {
+        match frame {
+            RespFrame::SimpleString(s) => Ok(s),
+            RespFrame::BulkString(BulkString(Some(bytes))) => Ok(SimpleString(bytes)),
+            RespFrame::VerbatimString(s) => Ok(SimpleString(s.data)),
+            RespFrame::Integer(i) => Ok(SimpleString::new(i.to_string())),
+            frame => Err(RespError::InvalidFrameType(format!(
+                "unsupported map key: {:?}",
+                frame
+            ))),
+        }
+    }
+}
+
 impl Deref for SimpleString {
-    type Target = String;
+    type Target = Bytes;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -51,7 +55,7 @@ impl Deref for SimpleString {
 #[cfg(test)]
 mod tests {
 
-    use crate::resp::frame::RespFrame;
+    use crate::{resp::frame::RespFrame, RespDecode};
 
     use super::*;
 
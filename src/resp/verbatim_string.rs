@@ -0,0 +1,88 @@
+use bytes::{Bytes, BytesMut};
+
+use crate::{RespEncode, RespError};
+
+use super::{header_len, write_header, CRLF, CRLF_LEN};
+
+/// RESP3 verbatim string: `=<len>\r\n<fmt>:<data>\r\n`, where `fmt` is a
+/// three byte encoding hint such as `txt` or `mkd`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VerbatimString {
+    pub(crate) format: [u8; 3],
+    pub(crate) data: Bytes,
+}
+
+impl RespEncode for VerbatimString {
+    fn encoded_len(&self) -> usize {
+        let len = self.data.len() + 4;
+        header_len(len) + len + CRLF_LEN
+    }
+
+    fn encode_to(&self, buf: &mut BytesMut) {
+        write_header(buf, b'=', self.data.len() + 4);
+        buf.extend_from_slice(&self.format);
+        buf.extend_from_slice(b":");
+        buf.extend_from_slice(&self.data);
+        buf.extend_from_slice(CRLF);
+    }
+}
+
+impl VerbatimString {
+    pub fn new(format: [u8; 3], data: impl Into<Bytes>) -> Self {
+        VerbatimString {
+            format,
+            data: data.into(),
+        }
+    }
+
+    /// Split a `<fmt>:<data>` payload.
+    pub(crate) fn from_payload(payload: Bytes) -> Result<Self, RespError> {
+        if payload.len() < 4 || payload[3] != b':' {
+            return Err(RespError::InvalidFrameData(format!("{:?}", payload)));
+        }
+        let format = [payload[0], payload[1], payload[2]];
+        Ok(VerbatimString::new(format, payload.slice(4..)))
+    }
+
+    pub fn format(&self) -> &[u8] {
+        &self.format
+    }
+
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resp::frame::RespFrame, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_verbatim_string() {
+        let frame: RespFrame = VerbatimString::new(*b"txt", "Some string").into();
+        assert_eq!(frame.encode(), b"=15\r\ntxt:Some string\r\n");
+    }
+
+    #[test]
+    fn test_decode_verbatim_string() {
+        let mut buf = BytesMut::from(&b"=15\r\ntxt:Some string\r\n=6\r\nmkd:\r\n\r\n"[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::VerbatimString(VerbatimString::new(*b"txt", "Some string"))
+        );
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::VerbatimString(VerbatimString::new(*b"mkd", "\r\n"))
+        );
+
+        let mut buf = BytesMut::from(&b"=3\r\ntxt\r\n"[..]);
+        assert_eq!(
+            RespFrame::decode(&mut buf).unwrap_err(),
+            RespError::InvalidFrameLength(3)
+        );
+    }
+}
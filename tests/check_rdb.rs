@@ -0,0 +1,72 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use bytes::Bytes;
+use simple_redis::{rdb::write_snapshot, Entry, Snapshot, Value};
+
+fn check(name: &str, data: &[u8]) -> (bool, String) {
+    let path: PathBuf = std::env::temp_dir().join(format!(
+        "simple-redis-check-{}-{}.rdb",
+        name,
+        std::process::id()
+    ));
+    fs::write(&path, data).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_simple-redis-check-rdb"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+fn sample() -> Vec<u8> {
+    let snapshot = Snapshot {
+        entries: vec![
+            Entry {
+                key: Bytes::from("s"),
+                value: Value::String(Bytes::from("v")),
+                expire_at: Some(1),
+            },
+            Entry {
+                key: Bytes::from("h"),
+                value: Value::Hash(vec![(Bytes::from("f"), Bytes::from("v"))]),
+                expire_at: None,
+            },
+        ],
+        dirty: 0,
+    };
+    write_snapshot(&snapshot, Vec::new(), true, false, 0).unwrap()
+}
+
+#[test]
+fn test_check_rdb_summarizes_valid_file() {
+    let (ok, output) = check("valid", &sample());
+    assert!(ok, "{}", output);
+    assert!(output.contains("AUX FIELD redis-ver"));
+    assert!(output.contains("\\o/ RDB looks OK! \\o/"));
+    assert!(output.contains("[info] 2 keys read"));
+    assert!(output.contains("[info] 1 already expired"));
+    assert!(output.contains("[info] 1 hash keys"));
+}
+
+#[test]
+fn test_check_rdb_reports_unsupported_and_corrupt_files() {
+    // a list, which simple-redis can't store, in a file without checksum
+    let mut data = b"REDIS0011\xfe\x00\x01\x01l\x01\x01a\xff".to_vec();
+    data.extend_from_slice(&[0; 8]);
+    let (ok, output) = check("list", &data);
+    assert!(ok, "{}", output);
+    assert!(output.contains("checksum disabled"));
+    assert!(output.contains("[info] 1 list keys (not supported, skipped when loading)"));
+    assert!(output.contains("unsupported keys: 'l'"));
+
+    let mut data = sample();
+    let len = data.len();
+    data[len - 1] ^= 0xff;
+    let (ok, output) = check("corrupt", &data);
+    assert!(!ok);
+    assert!(output.contains("--- RDB ERROR DETECTED ---"));
+    assert!(output.contains("Wrong RDB checksum"));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use anyhow::Result;
+use common::{start, Conn};
+use simple_redis::{cluster::Cluster, Backend};
+
+/// Two nodes splitting the slots, with slot 5000 moving from the first to
+/// the second. Keys: `bar` is in slot 5061, `foo` in 12182 and `key:2368`
+/// in 5000.
+fn topology(a: u16, b: u16) -> String {
+    format!(
+        "aaaa 127.0.0.1:{a}@0 master - 0 0 1 connected 0-8191 [5000->-bbbb]\n\
+         bbbb 127.0.0.1:{b}@0 master - 0 0 2 connected 8192-16383 [5000-<-aaaa]\n"
+    )
+}
+
+#[tokio::test]
+async fn keys_are_redirected_to_their_node() -> Result<()> {
+    let (a, b) = (Backend::new(), Backend::new());
+    let a_addr = start(a.clone()).await?;
+    let b_addr = start(b.clone()).await?;
+    let topology = topology(a_addr.port(), b_addr.port());
+    a.set_cluster(Cluster::parse(&topology, &["127.0.0.1"], a_addr.port())?);
+    b.set_cluster(Cluster::parse(&topology, &["127.0.0.1"], b_addr.port())?);
+    let mut a = Conn::connect(a_addr).await?;
+    let mut b = Conn::connect(b_addr).await?;
+
+    assert_eq!(
+        a.call(&["CLUSTER", "KEYSLOT", "key:2368"]).await?,
+        b":5000\r\n"
+    );
+    assert_eq!(a.call(&["SET", "bar", "1"]).await?, b"+OK\r\n");
+    let moved = format!("-MOVED 5061 127.0.0.1:{}\r\n", a_addr.port());
+    assert_eq!(b.call(&["GET", "bar"]).await?, moved.as_bytes());
+    let moved = format!("-MOVED 12182 127.0.0.1:{}\r\n", b_addr.port());
+    assert_eq!(a.call(&["SET", "foo", "1"]).await?, moved.as_bytes());
+    assert_eq!(b.call(&["SET", "foo", "1"]).await?, b"+OK\r\n");
+    // keyless commands run anywhere
+    assert_eq!(b.call(&["PING"]).await?, b"+PONG\r\n");
+
+    // a key of the migrating slot that is no longer on the source
+    let ask = format!("-ASK 5000 127.0.0.1:{}\r\n", b_addr.port());
+    assert_eq!(a.call(&["GET", "key:2368"]).await?, ask.as_bytes());
+    let moved = format!("-MOVED 5000 127.0.0.1:{}\r\n", a_addr.port());
+    assert_eq!(b.call(&["SET", "key:2368", "v"]).await?, moved.as_bytes());
+    assert_eq!(b.call(&["ASKING"]).await?, b"+OK\r\n");
+    assert_eq!(b.call(&["SET", "key:2368", "v"]).await?, b"+OK\r\n");
+    // ASKING only lasts for one command
+    assert_eq!(b.call(&["GET", "key:2368"]).await?, moved.as_bytes());
+    assert_eq!(
+        b.call(&["CLUSTER", "COUNTKEYSINSLOT", "5000"]).await?,
+        b":1\r\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::{net::SocketAddr, time::Duration};
+
+use anyhow::{bail, Result};
+use bytes::BytesMut;
+use simple_redis::{
+    network, Backend, BulkString, ProtocolLimits, RespArray, RespDecoder, RespEncode, RespFrame,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+/// Start a server on an ephemeral port and connect to it.
+#[allow(dead_code)]
+pub async fn connect() -> Result<TcpStream> {
+    let addr = start(Backend::new()).await?;
+    Ok(TcpStream::connect(addr).await?)
+}
+
+/// Serve `backend` on an ephemeral port.
+#[allow(dead_code)]
+pub async fn start(backend: Backend) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let backend = backend.clone();
+            tokio::spawn(network::stream_handler(
+                stream,
+                backend,
+                ProtocolLimits::default(),
+            ));
+        }
+    });
+    Ok(addr)
+}
+
+/// A client connection that sends commands one at a time.
+#[allow(dead_code)]
+pub struct Conn {
+    stream: TcpStream,
+    buf: BytesMut,
+    decoder: RespDecoder,
+}
+
+#[allow(dead_code)]
+impl Conn {
+    pub async fn connect(addr: SocketAddr) -> Result<Self> {
+        Ok(Conn {
+            stream: TcpStream::connect(addr).await?,
+            buf: BytesMut::new(),
+            decoder: RespDecoder::new(),
+        })
+    }
+
+    /// Send a command and return the encoded reply.
+    pub async fn call(&mut self, args: &[&str]) -> Result<Vec<u8>> {
+        let frames = args
+            .iter()
+            .map(|arg| BulkString::new(Some(*arg)).into())
+            .collect::<Vec<RespFrame>>();
+        let request = RespFrame::from(RespArray::new(Some(frames)));
+        self.stream.write_all(&request.encode()).await?;
+        self.read().await
+    }
+
+    /// Wait for the next frame, such as a pub/sub message, and return it
+    /// encoded.
+    pub async fn read(&mut self) -> Result<Vec<u8>> {
+        loop {
+            if let Some(reply) = self.decoder.decode(&mut self.buf)? {
+                return Ok(reply.encode());
+            }
+            let read = timeout(Duration::from_secs(5), self.stream.read_buf(&mut self.buf));
+            if read.await?? == 0 {
+                bail!("connection closed");
+            }
+        }
+    }
+}
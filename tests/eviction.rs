@@ -0,0 +1,57 @@
+mod common;
+
+use anyhow::Result;
+use common::{start, Conn};
+use simple_redis::Backend;
+
+#[tokio::test]
+async fn writes_are_refused_or_evict_at_maxmemory() -> Result<()> {
+    let addr = start(Backend::new()).await?;
+    let mut client = Conn::connect(addr).await?;
+    let mut subscriber = Conn::connect(addr).await?;
+
+    for i in 0..20 {
+        client
+            .call(&["set", &format!("key:{}", i), "value"])
+            .await?;
+    }
+    client
+        .call(&[
+            "config",
+            "set",
+            "maxmemory",
+            "1000",
+            "notify-keyspace-events",
+            "Ee",
+        ])
+        .await?;
+    assert_eq!(
+        client.call(&["set", "more", "value"]).await?,
+        b"-OOM command not allowed when used memory > 'maxmemory'.\r\n"
+    );
+    // reads still work
+    assert_eq!(client.call(&["get", "key:1"]).await?, b"$5\r\nvalue\r\n");
+
+    subscriber
+        .call(&["subscribe", "__keyevent@0__:evicted"])
+        .await?;
+    client
+        .call(&["config", "set", "maxmemory-policy", "allkeys-lru"])
+        .await?;
+    assert_eq!(client.call(&["set", "more", "value"]).await?, b"+OK\r\n");
+    let event = String::from_utf8(subscriber.read().await?)?;
+    assert!(event.starts_with("*3\r\n$7\r\nmessage\r\n$22\r\n__keyevent@0__:evicted\r\n"));
+    assert!(event.contains("\r\nkey:"));
+
+    let info = String::from_utf8(client.call(&["info", "stats", "memory"]).await?)?;
+    let field = |name: &str| -> u64 {
+        let start = info.find(&format!("{}:", name)).unwrap() + name.len() + 1;
+        let end = start + info[start..].find('\r').unwrap();
+        info[start..end].parse().unwrap()
+    };
+    assert!(field("evicted_keys") > 0);
+    // eviction runs before a write, which may then go a little past the limit
+    assert!(field("used_memory") <= 1100);
+    assert!(info.contains("maxmemory_policy:allkeys-lru\r\n"));
+    Ok(())
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use anyhow::Result;
+use common::{start, Conn};
+use simple_redis::Backend;
+
+#[tokio::test]
+async fn object_and_memory_report_on_keys() -> Result<()> {
+    let addr = start(Backend::new()).await?;
+    let mut client = Conn::connect(addr).await?;
+
+    client.call(&["set", "counter", "10"]).await?;
+    client.call(&["hset", "user", "name", "ada"]).await?;
+    assert_eq!(
+        client.call(&["object", "encoding", "counter"]).await?,
+        b"$3\r\nint\r\n"
+    );
+    assert_eq!(
+        client.call(&["OBJECT", "ENCODING", "user"]).await?,
+        b"$8\r\nlistpack\r\n"
+    );
+    assert_eq!(
+        client.call(&["object", "idletime", "user"]).await?,
+        b":0\r\n"
+    );
+    assert_eq!(client.call(&["object", "freq", "nokey"]).await?, b"$-1\r\n");
+
+    client
+        .call(&["config", "set", "maxmemory-policy", "allkeys-lfu"])
+        .await?;
+    assert_eq!(client.call(&["object", "freq", "user"]).await?, b":5\r\n");
+
+    let usage = String::from_utf8(client.call(&["memory", "usage", "counter"]).await?)?;
+    assert!(usage.starts_with(':'));
+    assert_eq!(
+        client.call(&["memory", "usage", "nokey"]).await?,
+        b"$-1\r\n"
+    );
+
+    // RESP2 gets the map as a flat array
+    let stats = String::from_utf8(client.call(&["memory", "stats"]).await?)?;
+    assert!(stats.contains("$10\r\nkeys.count\r\n:2\r\n"));
+    assert!(stats.contains("$13\r\ndataset.bytes\r\n:54\r\n"));
+    Ok(())
+}
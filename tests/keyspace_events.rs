@@ -0,0 +1,110 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use bytes::Bytes;
+use common::{start, Conn};
+use simple_redis::{Backend, Config, Entry, Value};
+
+/// `message`/`pmessage` pushes as a RESP2 client receives them.
+fn message(channel: &str, payload: &str) -> Vec<u8> {
+    format!(
+        "*3\r\n$7\r\nmessage\r\n${}\r\n{}\r\n${}\r\n{}\r\n",
+        channel.len(),
+        channel,
+        payload.len(),
+        payload
+    )
+    .into_bytes()
+}
+
+fn pmessage(pattern: &str, channel: &str, payload: &str) -> Vec<u8> {
+    format!(
+        "*4\r\n$8\r\npmessage\r\n${}\r\n{}\r\n${}\r\n{}\r\n${}\r\n{}\r\n",
+        pattern.len(),
+        pattern,
+        channel.len(),
+        channel,
+        payload.len(),
+        payload
+    )
+    .into_bytes()
+}
+
+#[tokio::test]
+async fn writes_and_expirations_are_published() -> Result<()> {
+    let mut config = Config::default();
+    config.set(&[("notify-keyspace-events".to_string(), "KEA".to_string())])?;
+    let backend = Backend::with_config(config);
+    let addr = start(backend.clone()).await?;
+    let mut subscriber = Conn::connect(addr).await?;
+    let mut client = Conn::connect(addr).await?;
+
+    subscriber.call(&["psubscribe", "__keyspace@0__:*"]).await?;
+    assert_eq!(
+        subscriber
+            .call(&["subscribe", "__keyevent@0__:expired"])
+            .await?,
+        b"*3\r\n$9\r\nsubscribe\r\n$22\r\n__keyevent@0__:expired\r\n:2\r\n"
+    );
+    assert!(String::from_utf8(subscriber.call(&["get", "foo"]).await?)?
+        .starts_with("-ERR Can't execute 'get'"));
+
+    client.call(&["set", "foo", "bar"]).await?;
+    client.call(&["hset", "user", "name", "ann"]).await?;
+    client.call(&["sadd", "tags", "a"]).await?;
+    // adding nothing changes nothing, so there is no event
+    client.call(&["sadd", "tags", "a"]).await?;
+    client.call(&["set", "foo", "baz"]).await?;
+    let space = "__keyspace@0__:*";
+    assert_eq!(
+        subscriber.read().await?,
+        pmessage(space, "__keyspace@0__:foo", "set")
+    );
+    assert_eq!(
+        subscriber.read().await?,
+        pmessage(space, "__keyspace@0__:user", "hset")
+    );
+    assert_eq!(
+        subscriber.read().await?,
+        pmessage(space, "__keyspace@0__:tags", "sadd")
+    );
+    assert_eq!(
+        subscriber.read().await?,
+        pmessage(space, "__keyspace@0__:foo", "set")
+    );
+
+    backend.restore_entry(Entry {
+        key: Bytes::from("session"),
+        value: Value::String(Bytes::from("x")),
+        expire_at: Some(now_ms() + 20),
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(client.call(&["get", "session"]).await?, b"$-1\r\n");
+    assert_eq!(
+        subscriber.read().await?,
+        pmessage(space, "__keyspace@0__:session", "expired")
+    );
+    assert_eq!(
+        subscriber.read().await?,
+        message("__keyevent@0__:expired", "session")
+    );
+
+    assert_eq!(
+        subscriber.call(&["unsubscribe"]).await?,
+        b"*3\r\n$11\r\nunsubscribe\r\n$22\r\n__keyevent@0__:expired\r\n:1\r\n"
+    );
+    assert_eq!(
+        client.call(&["publish", "__keyspace@0__:x", "hi"]).await?,
+        b":1\r\n"
+    );
+    Ok(())
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use common::connect;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    time::timeout,
+};
+
+fn echo(i: usize) -> (Vec<u8>, Vec<u8>) {
+    let message = format!("message-{}", i);
+    let request = format!("*2\r\n$4\r\necho\r\n${}\r\n{}\r\n", message.len(), message);
+    let reply = format!("${}\r\n{}\r\n", message.len(), message);
+    (request.into_bytes(), reply.into_bytes())
+}
+
+#[tokio::test]
+async fn pipelined_replies_keep_request_order() -> Result<()> {
+    let mut stream = connect().await?;
+    let mut requests = Vec::new();
+    let mut expected = Vec::new();
+    // enough replies to cross the write flush threshold several times
+    for i in 0..20_000 {
+        let (request, reply) = echo(i);
+        requests.extend_from_slice(&request);
+        expected.extend_from_slice(&reply);
+    }
+
+    let (mut reader, mut writer) = stream.split();
+    let write = async {
+        writer.write_all(&requests).await?;
+        writer.shutdown().await
+    };
+    let mut replies = Vec::new();
+    let read = reader.read_to_end(&mut replies);
+    let (written, read) =
+        timeout(Duration::from_secs(10), async { tokio::join!(write, read) }).await?;
+    written?;
+    read?;
+    assert_eq!(replies.len(), expected.len());
+    assert!(replies == expected, "replies are out of order");
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_then_get_in_one_batch() -> Result<()> {
+    let mut stream = connect().await?;
+    stream
+        .write_all(
+            b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n*2\r\n$3\r\nget\r\n$1\r\nk\r\nGET k\r\n",
+        )
+        .await?;
+    let expected = b"+OK\r\n$1\r\nv\r\n$1\r\nv\r\n";
+    let mut replies = vec![0; expected.len()];
+    timeout(Duration::from_secs(5), stream.read_exact(&mut replies)).await??;
+    assert_eq!(&replies, expected);
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+mod common;
+
+use anyhow::Result;
+use common::connect;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    time::timeout,
+};
+
+/// Send `request` and read until the server closes the connection.
+async fn send_and_read_all(request: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = connect().await?;
+    stream.write_all(request).await?;
+    let mut reply = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await??;
+    Ok(reply)
+}
+
+#[tokio::test]
+async fn bad_type_byte_closes_connection() -> Result<()> {
+    let reply = send_and_read_all(b"*1\r\n?4\r\nPING\r\n*1\r\n$4\r\nPING\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: unknown frame type '?'\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_line_closes_connection() -> Result<()> {
+    let reply = send_and_read_all(b"*1\r\n\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: missing frame type\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn bad_multibulk_length_closes_connection() -> Result<()> {
+    let reply = send_and_read_all(b"*abc\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: invalid multibulk length\r\n");
+
+    let reply = send_and_read_all(b"*-5\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: invalid multibulk length\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn bad_bulk_length_closes_connection() -> Result<()> {
+    let reply = send_and_read_all(b"*1\r\n$x\r\nPING\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: invalid bulk length\r\n");
+
+    let reply = send_and_read_all(b"*1\r\n$-2\r\n").await?;
+    assert_eq!(reply, b"-ERR Protocol error: invalid bulk length\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn wrong_declared_bulk_length_closes_connection() -> Result<()> {
+    let reply = send_and_read_all(b"*2\r\n$4\r\necho\r\n$2\r\nhello\r\n").await?;
+    assert_eq!(
+        reply,
+        b"-ERR Protocol error: expected CRLF after bulk data\r\n"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn replies_before_the_error_are_kept() -> Result<()> {
+    let reply = send_and_read_all(b"*2\r\n$4\r\necho\r\n$2\r\nhi\r\n*1\r\n!\r\n").await?;
+    assert_eq!(
+        reply,
+        b"$2\r\nhi\r\n-ERR Protocol error: invalid bulk length\r\n"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncated_frame_at_eof_is_dropped_silently() -> Result<()> {
+    let mut stream = connect().await?;
+    stream
+        .write_all(b"*2\r\n$4\r\necho\r\n$2\r\nhi\r\n*2\r\n$4\r\necho\r\n$5\r\nhel")
+        .await?;
+    stream.shutdown().await?;
+    let mut reply = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await??;
+    assert_eq!(reply, b"$2\r\nhi\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncated_frame_waits_for_the_rest() -> Result<()> {
+    let mut stream = connect().await?;
+    stream.write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhel").await?;
+    let mut buf = [0u8; 64];
+    assert!(
+        timeout(Duration::from_millis(100), stream.read(&mut buf))
+            .await
+            .is_err(),
+        "server must not reply to a partial frame"
+    );
+    stream.write_all(b"lo\r\n").await?;
+    let n = timeout(Duration::from_secs(5), stream.read(&mut buf)).await??;
+    assert_eq!(&buf[..n], b"$5\r\nhello\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn unbalanced_inline_quotes_close_connection() -> Result<()> {
+    let reply = send_and_read_all(b"echo \"hi\r\nPING\r\n").await?;
+    assert_eq!(
+        reply,
+        b"-ERR Protocol error: unbalanced quotes in request\r\n"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn config_set_limits_apply_to_open_connections() -> Result<()> {
+    let mut stream = connect().await?;
+    stream
+        .write_all(b"*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$18\r\nproto-max-bulk-len\r\n$3\r\n1mb\r\n")
+        .await?;
+    let mut reply = [0; 5];
+    stream.read_exact(&mut reply).await?;
+    assert_eq!(&reply, b"+OK\r\n");
+
+    stream
+        .write_all(b"*2\r\n$4\r\necho\r\n$2000000\r\n")
+        .await?;
+    let mut reply = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await??;
+    assert_eq!(reply, b"-ERR Protocol error: invalid bulk length\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn config_set_aggregate_limits_apply_to_open_connections() -> Result<()> {
+    let mut stream = connect().await?;
+    stream
+        .write_all(
+            b"*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$23\r\nproto-max-multibulk-len\r\n$1\r\n4\r\n",
+        )
+        .await?;
+    let mut reply = [0; 5];
+    stream.read_exact(&mut reply).await?;
+    assert_eq!(&reply, b"+OK\r\n");
+
+    stream
+        .write_all(b"*5\r\n$4\r\necho\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\nd\r\n")
+        .await?;
+    let mut reply = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await??;
+    assert_eq!(reply, b"-ERR Protocol error: invalid multibulk length\r\n");
+
+    let mut stream = connect().await?;
+    stream
+        .write_all(
+            b"*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$23\r\nproto-max-nesting-depth\r\n$1\r\n1\r\n",
+        )
+        .await?;
+    let mut reply = [0; 5];
+    stream.read_exact(&mut reply).await?;
+    assert_eq!(&reply, b"+OK\r\n");
+    stream
+        .write_all(b"*2\r\n$4\r\necho\r\n*1\r\n$2\r\nhi\r\n")
+        .await?;
+    let mut reply = Vec::new();
+    timeout(Duration::from_secs(5), stream.read_to_end(&mut reply)).await??;
+    assert_eq!(
+        reply,
+        b"-ERR Protocol error: too many nested aggregates\r\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use common::{start, Conn};
+use simple_redis::Backend;
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+/// Poll `GET key` on the replica until it returns `expected`.
+async fn wait_for(conn: &mut Conn, key: &str, expected: &str) -> Result<()> {
+    for _ in 0..100 {
+        let reply = conn.call(&["GET", key]).await?;
+        if reply == format!("${}\r\n{}\r\n", expected.len(), expected).as_bytes() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    bail!("{} never reached the replica", key)
+}
+
+/// Poll `INFO replication` until it reports `count` replicas.
+async fn wait_for_replicas(conn: &mut Conn, count: usize) -> Result<()> {
+    let line = format!("connected_slaves:{}\r\n", count);
+    for _ in 0..100 {
+        let info = conn.call(&["INFO", "replication"]).await?;
+        if String::from_utf8_lossy(&info).contains(&line) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    bail!("the master never had {} replicas", count)
+}
+
+#[tokio::test]
+async fn replica_syncs_and_follows_the_master() -> Result<()> {
+    let master_addr = start(Backend::new()).await?;
+    let replica_addr = start(Backend::new()).await?;
+    let mut master = Conn::connect(master_addr).await?;
+    let mut replica = Conn::connect(replica_addr).await?;
+
+    // written before the sync, so it arrives with the snapshot
+    master.call(&["SET", "before", "1"]).await?;
+    let port = master_addr.port().to_string();
+    let reply = replica.call(&["REPLICAOF", "127.0.0.1", &port]).await?;
+    assert_eq!(reply, b"+OK\r\n");
+    wait_for(&mut replica, "before", "1").await?;
+
+    // and this one with the command stream
+    master.call(&["SET", "after", "2"]).await?;
+    wait_for(&mut replica, "after", "2").await?;
+
+    let reply = replica.call(&["SET", "k", "v"]).await?;
+    assert_eq!(
+        reply,
+        b"-READONLY You can't write against a read only replica.\r\n"
+    );
+
+    let role = format!(
+        "*5\r\n$5\r\nslave\r\n$9\r\n127.0.0.1\r\n:{}\r\n$9\r\nconnected\r\n",
+        master_addr.port()
+    );
+    assert!(replica.call(&["ROLE"]).await?.starts_with(role.as_bytes()));
+    assert!(master
+        .call(&["ROLE"])
+        .await?
+        .starts_with(b"*3\r\n$6\r\nmaster\r\n"));
+
+    // promoted, the replica accepts writes again
+    replica.call(&["REPLICAOF", "NO", "ONE"]).await?;
+    let reply = replica.call(&["SET", "k", "v"]).await?;
+    assert_eq!(reply, b"+OK\r\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn replica_over_output_buffer_limit_is_dropped() -> Result<()> {
+    let master_addr = start(Backend::new()).await?;
+    let mut master = Conn::connect(master_addr).await?;
+    let reply = master
+        .call(&[
+            "CONFIG",
+            "SET",
+            "client-output-buffer-limit",
+            "replica 1mb 0 0",
+        ])
+        .await?;
+    assert_eq!(reply, b"+OK\r\n");
+
+    // a replica that never reads what it is sent
+    let mut replica = TcpStream::connect(master_addr).await?;
+    replica
+        .write_all(b"*3\r\n$5\r\nPSYNC\r\n$1\r\n?\r\n$2\r\n-1\r\n")
+        .await?;
+    wait_for_replicas(&mut master, 1).await?;
+
+    let value = "x".repeat(1 << 20);
+    for i in 0..32 {
+        master.call(&["SET", &format!("k{}", i), &value]).await?;
+    }
+    wait_for_replicas(&mut master, 0).await
+}